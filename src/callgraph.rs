@@ -0,0 +1,643 @@
+use anyhow::{bail, Context, Result};
+use gimli::UnwindSection;
+use object::{Endianness, Object, ObjectSection, ObjectSymbol, SectionKind};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::disasm::{decode_instructions, mapping_symbols, InstructionSet};
+use crate::parser::{demangle_name, get_string_attr, load_dwarf};
+use crate::types::{
+    CallGraph, CallGraphFunction, ElfSymbol, Flow, StackBound, StackRoot, VectorTable, XrefIndex,
+    XrefKind,
+};
+use crate::unwind::exception_frame_size;
+use crate::utils::arm_attribute;
+
+/// A call site from DWARF: the caller's entry address and the callee's, `None` for
+/// calls through a pointer
+type DwarfCallSite = (u64, Option<u64>);
+
+/// Build the call graph of an ELF's Thumb-2, RISC-V or Xtensa code from the calls in
+/// its instructions and `DW_TAG_call_site` entries, and compute the worst-case stack
+/// depth of every function from the frame sizes in `.debug_frame`. Data marked by `$d`
+/// mapping symbols, e.g. literal pools, is skipped.
+pub fn parse_call_graph(
+    path: &PathBuf,
+    symbols: &[ElfSymbol],
+    vector_table: &VectorTable,
+    xrefs: &XrefIndex,
+) -> Result<CallGraph> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+    let Some(instruction_set) = InstructionSet::from_architecture(obj.architecture()) else {
+        bail!(
+            "Call graphs of {:?} code are not supported",
+            obj.architecture()
+        );
+    };
+
+    let mut functions = Vec::new();
+    let mut addresses_by_name = HashMap::new();
+    for symbol in obj.symbols() {
+        if symbol.kind() != object::SymbolKind::Text || symbol.size() == 0 {
+            continue;
+        }
+        let Ok(name) = symbol.name() else {
+            continue;
+        };
+        let address = symbol.address() & !1;
+        addresses_by_name.insert(name.to_string(), address);
+        functions.push(CallGraphFunction {
+            name: demangle_name(name),
+            address,
+            size: symbol.size(),
+            frame_size: None,
+            frame_from_code: false,
+            callees: Vec::new(),
+            callers: Vec::new(),
+            indirect_calls: 0,
+            recursive: false,
+            worst_case: 0,
+            bound: StackBound::Exact,
+            deepest_callee: None,
+        });
+    }
+    functions.sort_by_key(|function| function.address);
+    // Aliases, e.g. exception handlers bound to DefaultHandler, share one body
+    functions.dedup_by_key(|function| function.address);
+    let mut graph = CallGraph {
+        functions,
+        roots: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    let code: Vec<(u64, &[u8])> = obj
+        .sections()
+        .filter(|section| section.kind() == SectionKind::Text)
+        .filter_map(|section| Some((section.address(), section.data().ok()?)))
+        .collect();
+    let cfi_frames = cfi_frame_sizes(&obj, instruction_set);
+    let mapping = mapping_symbols(symbols);
+
+    let mut callees = vec![BTreeSet::new(); graph.functions.len()];
+    for (index, function) in graph.functions.iter_mut().enumerate() {
+        let (start, end) = (function.address, function.address + function.size);
+        let Some(body) = code.iter().find_map(|(address, data)| {
+            let offset = start.checked_sub(*address)? as usize;
+            data.get(offset..offset + function.size as usize)
+        }) else {
+            continue;
+        };
+
+        let mut pushed = 0;
+        for instruction in decode_instructions(instruction_set, body, start, &mapping) {
+            match instruction.flow {
+                Flow::Call(target) => callees[index].insert(target),
+                // Branches out of the function are tail calls
+                Flow::Branch(target) | Flow::ConditionalBranch(target)
+                    if !(start..end).contains(&target) =>
+                {
+                    callees[index].insert(target)
+                }
+                Flow::IndirectCall => {
+                    function.indirect_calls += 1;
+                    true
+                }
+                _ => false,
+            };
+            pushed += instruction.stack_push;
+        }
+
+        // Without an SP-based CFA throughout, e.g. with frame pointers, the code
+        // says how far SP moves
+        function.frame_size = Some(match cfi_frames.get(&start) {
+            Some(&(size, true)) => size,
+            Some(&(size, false)) => {
+                function.frame_from_code = true;
+                size.max(pushed)
+            }
+            None => {
+                function.frame_from_code = true;
+                pushed
+            }
+        });
+    }
+
+    let call_sites = dwarf_call_sites(&obj, &addresses_by_name).unwrap_or_else(|e| {
+        graph
+            .warnings
+            .push(format!("Failed to read DWARF call sites: {}", e));
+        Vec::new()
+    });
+    let mut dwarf_indirect = vec![0; graph.functions.len()];
+    for (caller, callee) in call_sites {
+        let Some(caller) = graph.function_at(caller) else {
+            continue;
+        };
+        match callee {
+            Some(callee) => {
+                callees[caller].insert(callee);
+            }
+            None => dwarf_indirect[caller] += 1,
+        }
+    }
+
+    // Calls into the middle of a symbol are most likely data decoded as code, where
+    // mapping symbols are missing
+    for (index, targets) in callees.into_iter().enumerate() {
+        let targets: Vec<usize> = targets
+            .into_iter()
+            .filter_map(|target| graph.function_at(target))
+            .collect();
+        for callee in targets {
+            graph.functions[index].callees.push(callee);
+            graph.functions[callee].callers.push(index);
+        }
+        let function = &mut graph.functions[index];
+        function.indirect_calls = function.indirect_calls.max(dwarf_indirect[index]);
+    }
+
+    let components = mark_recursion(&mut graph);
+    let mut done = vec![false; graph.functions.len()];
+    for index in 0..graph.functions.len() {
+        worst_case(&mut graph, index, &components, &mut done);
+    }
+    // The worst case: FPU context is stacked if the interrupted code used the FPU, and
+    // the stack may need realigning. Other cores save context in software, which the
+    // handler's own frame covers.
+    let exception_frame = match instruction_set {
+        InstructionSet::Thumb => exception_frame_size(has_fpu(&obj), true) as u64,
+        _ => 0,
+    };
+    graph.roots = find_roots(&graph, vector_table, xrefs, exception_frame);
+    Ok(graph)
+}
+
+/// Whether the target has a floating-point unit, from the hard-float ABI flag or
+/// `Tag_FP_arch` in the `.ARM.attributes` build attributes
+fn has_fpu(obj: &object::File) -> bool {
+    // EF_ARM_ABI_FLOAT_HARD
+    if matches!(obj.flags(), object::FileFlags::Elf { e_flags, .. } if e_flags & 0x400 != 0) {
+        return true;
+    }
+    // Tag_FP_arch
+    arm_attribute(obj, 10).is_some_and(|fp_arch| fp_arch != 0)
+}
+
+/// Largest CFA offset of each FDE, keyed by function address, and whether the CFA
+/// stayed relative to SP, so the offset covers the whole frame
+fn cfi_frame_sizes(
+    obj: &object::File,
+    instruction_set: InstructionSet,
+) -> HashMap<u64, (u64, bool)> {
+    let mut frames = HashMap::new();
+    let Some(data) = obj
+        .section_by_name(".debug_frame")
+        .and_then(|section| section.uncompressed_data().ok())
+    else {
+        return frames;
+    };
+    let endian = if obj.is_little_endian() {
+        gimli::RunTimeEndian::Little
+    } else {
+        gimli::RunTimeEndian::Big
+    };
+
+    // DWARF number of SP: r13, x2 or a1
+    let sp = match instruction_set {
+        InstructionSet::Thumb => 13,
+        InstructionSet::Riscv(_) => 2,
+        InstructionSet::Xtensa => 1,
+    };
+
+    let mut section = gimli::DebugFrame::new(&data, endian);
+    section.set_address_size(if obj.is_64() { 8 } else { 4 });
+    let bases = gimli::BaseAddresses::default();
+    let mut context = gimli::UnwindContext::new();
+    let mut entries = section.entries(&bases);
+    while let Ok(Some(entry)) = entries.next() {
+        let gimli::CieOrFde::Fde(partial) = entry else {
+            continue;
+        };
+        let Ok(fde) = partial.parse(gimli::DebugFrame::cie_from_offset) else {
+            continue;
+        };
+        let Ok(mut rows) = fde.rows(&section, &bases, &mut context) else {
+            continue;
+        };
+
+        let (mut size, mut sp_based) = (0, true);
+        while let Ok(Some(row)) = rows.next_row() {
+            match row.cfa() {
+                gimli::CfaRule::RegisterAndOffset { register, offset } if register.0 == sp => {
+                    size = size.max(*offset as u64);
+                }
+                _ => sp_based = false,
+            }
+        }
+        frames.insert(fde.initial_address() & !1, (size, sp_based));
+    }
+    frames
+}
+
+/// Caller and callee of every `DW_TAG_call_site`. Calls from inlined code belong to the
+/// function it was inlined into.
+fn dwarf_call_sites(
+    obj: &object::File,
+    addresses_by_name: &HashMap<String, u64>,
+) -> Result<Vec<DwarfCallSite>> {
+    let dwarf = load_dwarf(obj)?;
+
+    let mut call_sites = Vec::new();
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        let low_pc = |entry: &gimli::DebuggingInformationEntry<_>| {
+            let value = entry.attr_value(gimli::DW_AT_low_pc).ok()??;
+            dwarf
+                .attr_address(&unit, value)
+                .ok()?
+                .filter(|&address| address != 0)
+        };
+
+        // Entry addresses of the subprograms enclosing the current DIE, with their depth
+        let mut scopes: Vec<(isize, u64)> = Vec::new();
+        let mut depth = 0;
+        let mut entries = unit.entries();
+        while let Some((delta, entry)) = entries.next_dfs()? {
+            depth += delta;
+            while scopes
+                .last()
+                .is_some_and(|&(scope_depth, _)| scope_depth >= depth)
+            {
+                scopes.pop();
+            }
+
+            match entry.tag() {
+                gimli::DW_TAG_subprogram => {
+                    if let Some(address) = low_pc(entry) {
+                        scopes.push((depth, address & !1));
+                    }
+                }
+                gimli::DW_TAG_call_site | gimli::DW_TAG_GNU_call_site => {
+                    let Some(&(_, caller)) = scopes.last() else {
+                        continue;
+                    };
+                    let origin = [gimli::DW_AT_call_origin, gimli::DW_AT_abstract_origin]
+                        .into_iter()
+                        .find_map(|attr_name| entry.attr_value(attr_name).ok().flatten());
+                    match origin {
+                        Some(gimli::AttributeValue::UnitRef(offset)) => {
+                            let Ok(callee) = unit.entry(offset) else {
+                                continue;
+                            };
+                            // Declarations of functions from other units carry only a name
+                            let address = low_pc(&callee).or_else(|| {
+                                [gimli::DW_AT_linkage_name, gimli::DW_AT_MIPS_linkage_name]
+                                    .into_iter()
+                                    .find_map(|attr_name| {
+                                        get_string_attr(&dwarf, &unit, &callee, attr_name)
+                                    })
+                                    .and_then(|name| addresses_by_name.get(&name).copied())
+                            });
+                            if let Some(address) = address {
+                                call_sites.push((caller, Some(address & !1)));
+                            }
+                        }
+                        Some(_) => {}
+                        None => {
+                            let indirect =
+                                [gimli::DW_AT_call_target, gimli::DW_AT_GNU_call_site_target]
+                                    .into_iter()
+                                    .any(|attr_name| {
+                                        entry.attr(attr_name).ok().flatten().is_some()
+                                    });
+                            if indirect {
+                                call_sites.push((caller, None));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(call_sites)
+}
+
+/// Flag every function on a call cycle, found as the strongly connected components of
+/// the graph (Tarjan's algorithm). Returns the component of each function.
+fn mark_recursion(graph: &mut CallGraph) -> Vec<usize> {
+    struct Tarjan<'a> {
+        graph: &'a CallGraph,
+        index: Vec<Option<usize>>,
+        low_link: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<usize>,
+        component_count: usize,
+        recursive: Vec<bool>,
+    }
+
+    impl Tarjan<'_> {
+        /// Depth-first search from `root` on an explicit stack, as call chains can be
+        /// deeper than the thread's stack
+        fn visit(&mut self, root: usize) {
+            let graph = self.graph;
+            // Each frame is a function and the position of its next callee
+            let mut work = vec![(root, 0)];
+            self.enter(root);
+            while let Some((node, position)) = work.pop() {
+                if let Some(&callee) = graph.functions[node].callees.get(position) {
+                    work.push((node, position + 1));
+                    match self.index[callee] {
+                        None => {
+                            self.enter(callee);
+                            work.push((callee, 0));
+                        }
+                        Some(index) if self.on_stack[callee] => {
+                            self.low_link[node] = self.low_link[node].min(index);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                self.finish(node);
+                if let Some(&(caller, _)) = work.last() {
+                    self.low_link[caller] = self.low_link[caller].min(self.low_link[node]);
+                }
+            }
+        }
+
+        fn enter(&mut self, node: usize) {
+            self.index[node] = Some(self.next_index);
+            self.low_link[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+        }
+
+        /// Pop the component `node` is the root of, once all its callees are visited
+        fn finish(&mut self, node: usize) {
+            if Some(self.low_link[node]) != self.index[node] {
+                return;
+            }
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                self.components[member] = self.component_count;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.component_count += 1;
+            let calls_itself = self.graph.functions[node].callees.contains(&node);
+            if component.len() > 1 || calls_itself {
+                for member in component {
+                    self.recursive[member] = true;
+                }
+            }
+        }
+    }
+
+    let count = graph.functions.len();
+    let mut tarjan = Tarjan {
+        graph,
+        index: vec![None; count],
+        low_link: vec![0; count],
+        on_stack: vec![false; count],
+        stack: Vec::new(),
+        next_index: 0,
+        components: vec![0; count],
+        component_count: 0,
+        recursive: vec![false; count],
+    };
+    for node in 0..count {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    let (components, recursive) = (tarjan.components, tarjan.recursive);
+    for (function, recursive) in graph.functions.iter_mut().zip(recursive) {
+        function.recursive = recursive;
+    }
+    components
+}
+
+/// Worst-case stack depth of every function below `root`, memoized in `done`. Calls
+/// within a cycle are not followed; the cycle makes the bound unknown instead.
+fn worst_case(graph: &mut CallGraph, root: usize, components: &[usize], done: &mut [bool]) {
+    if done[root] {
+        return;
+    }
+    done[root] = true;
+
+    // Each frame is a function and the position of its next callee; a function is
+    // finished once all callees outside its cycle are
+    let mut work = vec![(root, 0)];
+    while let Some((index, position)) = work.pop() {
+        if let Some(&callee) = graph.functions[index].callees.get(position) {
+            work.push((index, position + 1));
+            if components[callee] != components[index] && !done[callee] {
+                done[callee] = true;
+                work.push((callee, 0));
+            }
+            continue;
+        }
+
+        let function = &graph.functions[index];
+        let mut bound = if function.recursive {
+            StackBound::Unbounded
+        } else if function.indirect_calls > 0 || function.frame_size.is_none() {
+            StackBound::LowerBound
+        } else {
+            StackBound::Exact
+        };
+        let frame = function.frame_size.unwrap_or(0);
+
+        let mut deepest = None;
+        let mut deepest_size = 0;
+        for &callee in &function.callees {
+            if components[callee] == components[index] {
+                continue;
+            }
+            let callee_function = &graph.functions[callee];
+            bound = bound.max(callee_function.bound);
+            if deepest.is_none() || callee_function.worst_case > deepest_size {
+                deepest_size = callee_function.worst_case;
+                deepest = Some(callee);
+            }
+        }
+
+        let function = &mut graph.functions[index];
+        function.worst_case = frame + deepest_size;
+        function.bound = bound;
+        function.deepest_callee = deepest;
+    }
+}
+
+/// Reset and the exception and interrupt handlers from the vector table, every function
+/// whose address is taken, e.g. tasks, callbacks and executor polls, then every function
+/// nothing calls directly
+fn find_roots(
+    graph: &CallGraph,
+    vector_table: &VectorTable,
+    xrefs: &XrefIndex,
+    exception_frame: u64,
+) -> Vec<StackRoot> {
+    let mut roots: Vec<StackRoot> = Vec::new();
+    for entry in &vector_table.entries {
+        if entry.index == 0 {
+            // The initial stack pointer
+            continue;
+        }
+        let Some(function) = graph.function_at(entry.value & !1) else {
+            continue;
+        };
+        if let Some(root) = roots.iter_mut().find(|root| root.function == function) {
+            // Shared handlers, e.g. DefaultHandler for all unused interrupts
+            if !root.name.ends_with(", ...") {
+                root.name.push_str(", ...");
+            }
+            continue;
+        }
+        roots.push(StackRoot {
+            name: entry.display_name(),
+            function,
+            vector_index: Some(entry.index),
+            exception_frame: if entry.index == 1 { 0 } else { exception_frame },
+        });
+    }
+
+    // Reached through pointers from callers the graph does not know, whether or not
+    // something also calls them directly
+    let address_taken: BTreeSet<usize> = xrefs
+        .xrefs
+        .iter()
+        .filter(|xref| matches!(xref.kind, XrefKind::Address | XrefKind::Pointer))
+        .filter_map(|xref| graph.function_at(xref.to))
+        .collect();
+    for function in address_taken {
+        if roots.iter().any(|root| root.function == function) {
+            continue;
+        }
+        roots.push(StackRoot {
+            name: "Address taken".to_string(),
+            function,
+            vector_index: None,
+            exception_frame: 0,
+        });
+    }
+
+    for (index, function) in graph.functions.iter().enumerate() {
+        if function.callers.is_empty() && !roots.iter().any(|root| root.function == index) {
+            roots.push(StackRoot {
+                name: "Not called directly".to_string(),
+                function: index,
+                vector_index: None,
+                exception_frame: 0,
+            });
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A graph of functions with the given frame sizes and callees, 16 bytes apart
+    fn graph(functions: &[(u64, &[usize])]) -> CallGraph {
+        let functions = functions
+            .iter()
+            .enumerate()
+            .map(|(index, &(frame_size, callees))| CallGraphFunction {
+                name: format!("f{}", index),
+                address: 0x1000 + 16 * index as u64,
+                size: 16,
+                frame_size: Some(frame_size),
+                frame_from_code: false,
+                callees: callees.to_vec(),
+                callers: Vec::new(),
+                indirect_calls: 0,
+                recursive: false,
+                worst_case: 0,
+                bound: StackBound::Exact,
+                deepest_callee: None,
+            })
+            .collect();
+        CallGraph {
+            functions,
+            ..CallGraph::default()
+        }
+    }
+
+    fn analyze(graph: &mut CallGraph) -> Vec<usize> {
+        let components = mark_recursion(graph);
+        let mut done = vec![false; graph.functions.len()];
+        for index in 0..graph.functions.len() {
+            worst_case(graph, index, &components, &mut done);
+        }
+        components
+    }
+
+    #[test]
+    fn cycles_are_recursive() {
+        // f1 and f2 call each other, f3 calls itself
+        let mut graph = graph(&[(8, &[1]), (8, &[2]), (8, &[1]), (8, &[3]), (8, &[])]);
+        let components = analyze(&mut graph);
+
+        let recursive: Vec<bool> = graph.functions.iter().map(|f| f.recursive).collect();
+        assert_eq!(recursive, [false, true, true, true, false]);
+        assert_eq!(components[1], components[2]);
+        assert_ne!(components[0], components[1]);
+        assert_eq!(graph.functions[0].bound, StackBound::Unbounded);
+        assert_eq!(graph.functions[4].bound, StackBound::Exact);
+        // Calls within the cycle are not followed
+        assert_eq!(graph.functions[1].worst_case, 8);
+        assert_eq!(graph.functions[0].worst_case, 16);
+    }
+
+    #[test]
+    fn worst_case_follows_the_deepest_callee() {
+        // f0 calls f1 -> f2 (8 + 32 bytes) and f3 (4 bytes)
+        let mut graph = graph(&[(16, &[3, 1]), (8, &[2]), (32, &[]), (4, &[])]);
+        graph.functions[3].indirect_calls = 1;
+        analyze(&mut graph);
+
+        let f0 = &graph.functions[0];
+        assert_eq!(f0.worst_case, 56);
+        assert_eq!(f0.deepest_callee, Some(1));
+        // The indirect call in f3 leaves only a lower bound, even off the worst path
+        assert_eq!(f0.bound, StackBound::LowerBound);
+        assert_eq!(graph.functions[1].bound, StackBound::Exact);
+        assert_eq!(graph.worst_path(0), [0, 1, 2]);
+    }
+
+    #[test]
+    fn long_call_chains_do_not_overflow_the_stack() {
+        let count = 100_000;
+        let callees: Vec<Vec<usize>> = (0..count)
+            .map(|index| {
+                if index + 1 < count {
+                    vec![index + 1]
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+        let functions: Vec<(u64, &[usize])> = callees
+            .iter()
+            .map(|callees| (8, callees.as_slice()))
+            .collect();
+        let mut graph = graph(&functions);
+        analyze(&mut graph);
+
+        assert!(!graph.functions[0].recursive);
+        assert_eq!(graph.functions[0].worst_case, 8 * count as u64);
+    }
+}
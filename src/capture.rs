@@ -0,0 +1,132 @@
+use anyhow::{Context as AnyhowContext, Result};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::defmt_decoder::{rzcobs_decode, DefmtDecoder};
+use crate::types::{Capture, CaptureEntry, DefmtEncoding, DefmtInfo, DefmtLevel};
+
+/// Load a raw RTT channel dump.
+///
+/// The capture is decoded with the ELF's defmt table when the firmware uses
+/// defmt, unless `as_text` is set; otherwise it is split into text lines.
+pub fn load_capture(path: &PathBuf, defmt_info: &DefmtInfo, as_text: bool) -> Result<Capture> {
+    let data = fs::read(path).context("Failed to read capture file")?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    let capture = match defmt_info.encoding {
+        Some(encoding) if !as_text && !defmt_info.log_sites.is_empty() => {
+            let (entries, errors) = decode_defmt(&data, defmt_info, encoding);
+            Capture {
+                file_name,
+                is_defmt: true,
+                entries,
+                errors,
+            }
+        }
+        _ => Capture {
+            file_name,
+            is_defmt: false,
+            entries: decode_text(&data),
+            errors: Vec::new(),
+        },
+    };
+
+    Ok(capture)
+}
+
+fn decode_defmt(
+    data: &[u8],
+    defmt_info: &DefmtInfo,
+    encoding: DefmtEncoding,
+) -> (Vec<CaptureEntry>, Vec<String>) {
+    let decoder = DefmtDecoder::new(defmt_info);
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    match encoding {
+        DefmtEncoding::Rzcobs => {
+            // Frames are delimited by zero bytes, so one bad frame does not spoil the rest
+            let mut offset = 0;
+            for frame in data.split(|&b| b == 0) {
+                let frame_offset = offset;
+                offset += frame.len() + 1;
+                if frame.is_empty() {
+                    continue;
+                }
+
+                let decoded = rzcobs_decode(frame).and_then(|bytes| {
+                    let (frame, _) = decoder.decode_frame(&bytes)?;
+                    Ok(frame)
+                });
+                match decoded {
+                    Ok(frame) => entries.push(CaptureEntry {
+                        timestamp: frame.timestamp,
+                        level: frame.site.level,
+                        message: frame.message,
+                        site: Some(frame.site.clone()),
+                    }),
+                    Err(e) => errors.push(format!("Frame at offset {}: {}", frame_offset, e)),
+                }
+            }
+        }
+        DefmtEncoding::Raw => {
+            // Raw frames carry no delimiter; decoding cannot resume after an error
+            let mut offset = 0;
+            while offset < data.len() {
+                match decoder.decode_frame(&data[offset..]) {
+                    Ok((frame, consumed)) => {
+                        entries.push(CaptureEntry {
+                            timestamp: frame.timestamp,
+                            level: frame.site.level,
+                            message: frame.message,
+                            site: Some(frame.site.clone()),
+                        });
+                        offset += consumed;
+                    }
+                    Err(e) => {
+                        errors.push(format!(
+                            "Frame at offset {}: {} ({} bytes left undecoded)",
+                            offset,
+                            e,
+                            data.len() - offset
+                        ));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    (entries, errors)
+}
+
+/// Split a text channel into lines, picking up a log level from the first words
+fn decode_text(data: &[u8]) -> Vec<CaptureEntry> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| CaptureEntry {
+            timestamp: None,
+            level: text_level(line),
+            message: line.to_string(),
+            site: None,
+        })
+        .collect()
+}
+
+fn text_level(line: &str) -> Option<DefmtLevel> {
+    line.split_whitespace().take(3).find_map(|word| {
+        let word = word.trim_matches(|c: char| !c.is_ascii_alphabetic());
+        match word.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(DefmtLevel::Trace),
+            "DEBUG" => Some(DefmtLevel::Debug),
+            "INFO" => Some(DefmtLevel::Info),
+            "WARN" | "WARNING" => Some(DefmtLevel::Warn),
+            "ERROR" => Some(DefmtLevel::Error),
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,212 @@
+use crate::symbolize::{parse_addresses, Symbolizer};
+use crate::types::{AddressInfo, ElfSymbol, MemorySegment};
+use gpui::{prelude::*, *};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+pub struct AddressSelectEvent {
+    pub address: u64,
+}
+
+impl EventEmitter<AddressSelectEvent> for AddressLookupPanel {}
+
+/// Resolves addresses, typed or pasted from a backtrace, to section, symbol,
+/// function and inline call chain
+pub struct AddressLookupPanel {
+    /// `None` if the debug info could not be loaded, see `error`
+    symbolizer: Option<Symbolizer>,
+    error: Option<String>,
+    input: Entity<InputState>,
+    results: Vec<AddressInfo>,
+    selected_address: Option<u64>,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for AddressLookupPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl AddressLookupPanel {
+    pub fn new(
+        elf_path: &Path,
+        segments: Vec<MemorySegment>,
+        symbols: Vec<ElfSymbol>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Addresses or a pasted backtrace (0x08001234 0x0800abcd ...)")
+        });
+
+        cx.subscribe(&input, Self::on_input).detach();
+
+        let (symbolizer, error) = match Symbolizer::new(elf_path, segments, symbols) {
+            Ok(symbolizer) => (Some(symbolizer), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+
+        Self {
+            symbolizer,
+            error,
+            input,
+            results: Vec::new(),
+            selected_address: None,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn on_input(&mut self, input: Entity<InputState>, event: &InputEvent, cx: &mut Context<Self>) {
+        if let InputEvent::PressEnter { .. } = event {
+            let text = input.read(cx).text().to_string();
+            if let Some(symbolizer) = &self.symbolizer {
+                self.results = parse_addresses(&text)
+                    .into_iter()
+                    .map(|address| symbolizer.lookup(address))
+                    .collect();
+            }
+            cx.notify();
+        }
+    }
+
+    fn select_address(&mut self, address: u64, cx: &mut Context<Self>) {
+        self.selected_address = Some(address);
+        cx.emit(AddressSelectEvent { address });
+        cx.notify();
+    }
+
+    fn render_result(&self, index: usize, info: &AddressInfo, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_address == Some(info.address);
+        let last = info.frames.len().saturating_sub(1);
+
+        div()
+            .id(("address-result", index))
+            .flex()
+            .flex_col()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .cursor_pointer()
+            .rounded_sm()
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .text_sm()
+                    .font_family("monospace")
+                    .child(info.summary()),
+            )
+            .when(info.frames.is_empty(), |d| {
+                d.child(
+                    div()
+                        .pl_4()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No debug info for this address"),
+                )
+            })
+            .children(info.frames.iter().enumerate().map(|(depth, frame)| {
+                div()
+                    .flex()
+                    .gap_2()
+                    .pl_4()
+                    .text_xs()
+                    .child(
+                        div()
+                            .font_family("monospace")
+                            .font_weight(if depth == last {
+                                FontWeight::BOLD
+                            } else {
+                                FontWeight::NORMAL
+                            })
+                            .child(frame.function.clone().unwrap_or_else(|| "??".to_string())),
+                    )
+                    .child(div().text_color(cx.theme().muted_foreground).child(format!(
+                        "at {}{}",
+                        frame.location(),
+                        if depth < last { " (inlined)" } else { "" }
+                    )))
+            }))
+    }
+}
+
+impl Render for AddressLookupPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .id("address_lookup_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("Address Lookup"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("{} addresses", self.results.len())),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .px_2()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(Input::new(&self.input)),
+            )
+            .when_some(self.error.clone(), |d, error| {
+                d.child(
+                    div()
+                        .px_3()
+                        .py_2()
+                        .text_sm()
+                        .text_color(rgb(0xff4444))
+                        .child(error),
+                )
+            })
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .children(self.results.iter().enumerate().map(|(index, info)| {
+                            let address = info.address;
+                            self.render_result(index, info, cx).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |view, _event, _window, cx| {
+                                    view.select_address(address, cx);
+                                }),
+                            )
+                        })),
+                ),
+            )
+    }
+}
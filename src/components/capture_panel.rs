@@ -0,0 +1,317 @@
+use crate::types::{Capture, CaptureEntry, DefmtLevel, DefmtLogSite};
+use gpui::{prelude::*, *};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+pub struct CaptureSiteSelectEvent {
+    pub site: DefmtLogSite,
+}
+
+impl EventEmitter<CaptureSiteSelectEvent> for CapturePanel {}
+
+/// Log viewer for an offline RTT capture
+pub struct CapturePanel {
+    capture: Arc<Capture>,
+    /// Levels currently hidden by the level filter
+    hidden_levels: HashSet<DefmtLevel>,
+    selected_row: Option<usize>,
+    search_input: Entity<InputState>,
+    search_query: String,
+    focus_handle: FocusHandle,
+    /// Indices into `entries` passing the level filter and search
+    filtered: Vec<usize>,
+}
+
+impl Focusable for CapturePanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+fn level_color(level: Option<DefmtLevel>) -> Rgba {
+    match level {
+        Some(DefmtLevel::Trace) => rgb(0x5c6370),
+        Some(DefmtLevel::Debug) => rgb(0x61afef),
+        Some(DefmtLevel::Info) => rgb(0x98c379),
+        Some(DefmtLevel::Warn) => rgb(0xe5c07b),
+        Some(DefmtLevel::Error) => rgb(0xff4444),
+        None => rgb(0xabb2bf),
+    }
+}
+
+impl CapturePanel {
+    pub fn new(capture: Capture, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search_input = cx.new(|cx| InputState::new(window, cx).placeholder("Search log..."));
+
+        cx.subscribe(&search_input, Self::on_search_input).detach();
+
+        let mut panel = Self {
+            capture: Arc::new(capture),
+            hidden_levels: HashSet::new(),
+            selected_row: None,
+            search_input,
+            search_query: String::new(),
+            focus_handle: cx.focus_handle(),
+            filtered: Vec::new(),
+        };
+        panel.apply_filter();
+        panel
+    }
+
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    fn on_search_input(
+        &mut self,
+        input: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        if let InputEvent::Change = event {
+            self.search_query = input.read(cx).text().to_string();
+            self.apply_filter();
+            cx.notify();
+        }
+    }
+
+    fn toggle_level(&mut self, level: DefmtLevel, cx: &mut Context<Self>) {
+        if !self.hidden_levels.remove(&level) {
+            self.hidden_levels.insert(level);
+        }
+        self.apply_filter();
+        cx.notify();
+    }
+
+    fn apply_filter(&mut self) {
+        let query = self.search_query.to_lowercase();
+
+        self.filtered = self
+            .capture
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                entry
+                    .level
+                    .is_none_or(|level| !self.hidden_levels.contains(&level))
+            })
+            .filter(|(_, entry)| query.is_empty() || entry.message.to_lowercase().contains(&query))
+            .map(|(idx, _)| idx)
+            .collect();
+    }
+
+    fn select_entry(&mut self, row: usize, entry: &CaptureEntry, cx: &mut Context<Self>) {
+        self.selected_row = Some(row);
+        if let Some(site) = &entry.site {
+            cx.emit(CaptureSiteSelectEvent { site: site.clone() });
+        }
+        cx.notify();
+    }
+
+    fn render_level_toggle(&self, level: DefmtLevel, cx: &mut Context<Self>) -> Stateful<Div> {
+        let hidden = self.hidden_levels.contains(&level);
+
+        div()
+            .id(ElementId::Name(
+                format!("capture-level-{}", level.label()).into(),
+            ))
+            .px_2()
+            .rounded_sm()
+            .text_xs()
+            .cursor_pointer()
+            .border_1()
+            .border_color(level_color(Some(level)))
+            .when(hidden, |d| d.text_color(cx.theme().muted_foreground))
+            .when(!hidden, |d| d.text_color(level_color(Some(level))))
+            .child(format!(
+                "{} {}",
+                level.label(),
+                self.capture.level_count(level)
+            ))
+            .on_mouse_up(
+                MouseButton::Left,
+                cx.listener(move |view, _event, _window, cx| {
+                    view.toggle_level(level, cx);
+                }),
+            )
+    }
+
+    fn render_entry_row(&self, row: usize, entry: &CaptureEntry, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_row == Some(row);
+
+        div()
+            .id(ElementId::Name(format!("capture-entry-{}", row).into()))
+            .flex()
+            .items_center()
+            .w_full()
+            .px_2()
+            .gap_2()
+            .rounded_sm()
+            .when(entry.site.is_some(), |d| d.cursor_pointer())
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .w(px(90.0))
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().muted_foreground)
+                    .child(entry.timestamp.clone().unwrap_or_default()),
+            )
+            .child(
+                div()
+                    .w(px(48.0))
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(level_color(entry.level))
+                    .child(entry.level.map_or("", |level| level.label())),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .text_sm()
+                    .font_family("monospace")
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .child(entry.message.clone()),
+            )
+            .child(
+                div()
+                    .w(px(240.0))
+                    .text_xs()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(
+                        entry
+                            .site
+                            .as_ref()
+                            .and_then(|site| site.location())
+                            .unwrap_or_default(),
+                    ),
+            )
+    }
+}
+
+impl Render for CapturePanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Limit rendered rows for performance
+        const MAX_RENDERED: usize = 1000;
+        let rows_to_render = &self.filtered[..self.filtered.len().min(MAX_RENDERED)];
+        let truncated = self.filtered.len() > MAX_RENDERED;
+
+        let capture = Arc::clone(&self.capture);
+
+        div()
+            .id("capture_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "Capture {} ({})",
+                                        capture.file_name,
+                                        if capture.is_defmt { "defmt" } else { "text" }
+                                    )),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "{} of {} entries",
+                                        self.filtered.len(),
+                                        capture.entries.len()
+                                    )),
+                            ),
+                    ),
+            )
+            .child(
+                // Level filters and search
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .px_2()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .children(
+                        DefmtLevel::ALL
+                            .iter()
+                            .map(|&level| self.render_level_toggle(level, cx))
+                            .collect::<Vec<_>>(),
+                    )
+                    .child(div().flex_1().child(Input::new(&self.search_input))),
+            )
+            .when(!capture.errors.is_empty(), |d| {
+                d.child(
+                    div()
+                        .px_3()
+                        .py_1()
+                        .text_xs()
+                        .text_color(rgb(0xff4444))
+                        .child(format!(
+                            "{} frames could not be decoded: {}",
+                            capture.errors.len(),
+                            capture.errors[0]
+                        )),
+                )
+            })
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .children(rows_to_render.iter().map(|&row| {
+                            let entry = capture.entries[row].clone();
+
+                            self.render_entry_row(row, &entry, cx).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |view, _event, _window, cx| {
+                                    view.select_entry(row, &entry, cx);
+                                }),
+                            )
+                        }))
+                        .when(truncated, |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "... and {} more (filter or search to see more)",
+                                        self.filtered.len() - MAX_RENDERED
+                                    )),
+                            )
+                        }),
+                ),
+            )
+    }
+}
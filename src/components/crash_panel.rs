@@ -0,0 +1,260 @@
+use crate::components::AddressSelectEvent;
+use crate::crash::{analyze_crash, parse_crash_registers};
+use crate::symbolize::Symbolizer;
+use crate::types::{CrashFrame, CrashReport, ElfSymbol, MemorySegment, MemorySnapshot};
+use crate::unwind::Unwinder;
+use gpui::{prelude::*, *};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::path::Path;
+use std::sync::Arc;
+
+impl EventEmitter<AddressSelectEvent> for CrashPanel {}
+
+/// Decodes pasted HardFault registers and shows the unwound, symbolized backtrace
+pub struct CrashPanel {
+    /// `None` if the ELF could not be loaded, see `error`
+    analyzer: Option<(Unwinder, Symbolizer)>,
+    /// Stack memory to unwind through
+    snapshot: Option<Arc<MemorySnapshot>>,
+    error: Option<String>,
+    input: Entity<InputState>,
+    report: Option<CrashReport>,
+    selected_frame: Option<usize>,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for CrashPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl CrashPanel {
+    pub fn new(
+        elf_path: &Path,
+        segments: Vec<MemorySegment>,
+        symbols: Vec<ElfSymbol>,
+        snapshot: Option<Arc<MemorySnapshot>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input = cx.new(|cx| {
+            InputState::new(window, cx).multi_line(true).placeholder(
+                "Fault registers, e.g. pc 0x08001234 lr 0x08000f1d sp 0x20001fe0 cfsr 0x8200",
+            )
+        });
+
+        cx.subscribe(&input, Self::on_input).detach();
+
+        let analyzer = Unwinder::load(elf_path)
+            .and_then(|unwinder| Ok((unwinder, Symbolizer::new(elf_path, segments, symbols)?)));
+        let (analyzer, error) = match analyzer {
+            Ok(analyzer) => (Some(analyzer), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+
+        Self {
+            analyzer,
+            snapshot,
+            error,
+            input,
+            report: None,
+            selected_frame: None,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn on_input(&mut self, input: Entity<InputState>, event: &InputEvent, cx: &mut Context<Self>) {
+        if let InputEvent::Change = event {
+            let text = input.read(cx).text().to_string();
+            let Some((unwinder, symbolizer)) = &self.analyzer else {
+                return;
+            };
+            self.selected_frame = None;
+            self.report = None;
+            match parse_crash_registers(&text) {
+                Ok(registers) => {
+                    self.error = None;
+                    self.report = Some(analyze_crash(
+                        &registers,
+                        unwinder,
+                        symbolizer,
+                        self.snapshot.as_deref(),
+                    ));
+                }
+                Err(e) if !text.trim().is_empty() => self.error = Some(e.to_string()),
+                Err(_) => self.error = None,
+            }
+            cx.notify();
+        }
+    }
+
+    fn select_frame(&mut self, index: usize, address: u64, cx: &mut Context<Self>) {
+        self.selected_frame = Some(index);
+        cx.emit(AddressSelectEvent { address });
+        cx.notify();
+    }
+
+    fn render_frame(&self, index: usize, crash_frame: &CrashFrame, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_frame == Some(index);
+        let frame = &crash_frame.frame;
+        let info = &crash_frame.info;
+        let last = info.frames.len().saturating_sub(1);
+
+        div()
+            .id(("crash-frame", index))
+            .flex()
+            .flex_col()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .cursor_pointer()
+            .rounded_sm()
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .flex()
+                    .gap_2()
+                    .text_sm()
+                    .font_family("monospace")
+                    .child(format!("#{:<2} 0x{:08x}", index, frame.pc))
+                    .child(
+                        div()
+                            .font_weight(FontWeight::BOLD)
+                            .child(info.symbol.clone().unwrap_or_else(|| "??".to_string())),
+                    )
+                    .when(frame.exception, |d| {
+                        d.child(div().text_color(rgb(0xff4444)).child("exception"))
+                    })
+                    .children(frame.sp.map(|sp| {
+                        div()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("sp 0x{:08x}", sp))
+                    })),
+            )
+            .children(info.frames.iter().enumerate().map(|(depth, inline)| {
+                div()
+                    .pl_4()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!(
+                        "{} at {}{}",
+                        inline.function.as_deref().unwrap_or("??"),
+                        inline.location(),
+                        if depth < last { " (inlined)" } else { "" }
+                    ))
+            }))
+    }
+}
+
+impl Render for CrashPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let memory = match &self.snapshot {
+            Some(snapshot) => format!("stack from {}", snapshot.file_name),
+            None => "no RAM dump, load one with --snapshot".to_string(),
+        };
+
+        div()
+            .id("crash_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("Crash Analysis"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(memory),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .px_2()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(Input::new(&self.input).h(px(96.0))),
+            )
+            .when_some(self.error.clone(), |d, error| {
+                d.child(
+                    div()
+                        .px_3()
+                        .py_2()
+                        .text_sm()
+                        .text_color(rgb(0xff4444))
+                        .child(error),
+                )
+            })
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .when_some(self.report.as_ref(), |d, report| {
+                            d.child(
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .px_3()
+                                    .py_2()
+                                    .gap_1()
+                                    .border_b_1()
+                                    .border_color(cx.theme().border)
+                                    .children(
+                                        report
+                                            .causes
+                                            .iter()
+                                            .map(|cause| div().text_sm().child(cause.clone())),
+                                    ),
+                            )
+                            .children(report.frames.iter().enumerate().map(
+                                |(index, crash_frame)| {
+                                    let address = crash_frame.info.address;
+                                    self.render_frame(index, crash_frame, cx).on_mouse_up(
+                                        MouseButton::Left,
+                                        cx.listener(move |view, _event, _window, cx| {
+                                            view.select_frame(index, address, cx);
+                                        }),
+                                    )
+                                },
+                            ))
+                            .child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(report.stop_reason.clone()),
+                            )
+                        }),
+                ),
+            )
+    }
+}
@@ -0,0 +1,257 @@
+use crate::types::{DefmtInfo, DefmtLevel, DefmtLogSite};
+use gpui::{prelude::*, *};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+pub struct DefmtSiteSelectEvent {
+    pub site: DefmtLogSite,
+}
+
+impl EventEmitter<DefmtSiteSelectEvent> for DefmtPanel {}
+
+/// Searchable index of the defmt log sites of the firmware
+pub struct DefmtPanel {
+    defmt_info: Arc<DefmtInfo>,
+    selected_index: Option<u64>,
+    search_input: Entity<InputState>,
+    search_query: String,
+    focus_handle: FocusHandle,
+    /// Indices into `log_sites` matching the current search
+    filtered: Vec<usize>,
+}
+
+impl Focusable for DefmtPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl DefmtPanel {
+    pub fn new(defmt_info: DefmtInfo, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("Search format strings, crates, files...")
+        });
+
+        cx.subscribe(&search_input, Self::on_search_input).detach();
+
+        let mut panel = Self {
+            defmt_info: Arc::new(defmt_info),
+            selected_index: None,
+            search_input,
+            search_query: String::new(),
+            focus_handle: cx.focus_handle(),
+            filtered: Vec::new(),
+        };
+        panel.apply_filter();
+        panel
+    }
+
+    fn on_search_input(
+        &mut self,
+        input: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        if let InputEvent::Change = event {
+            self.search_query = input.read(cx).text().to_string();
+            self.apply_filter();
+            cx.notify();
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        let query = self.search_query.to_lowercase();
+
+        self.filtered = self
+            .defmt_info
+            .log_sites
+            .iter()
+            .enumerate()
+            .filter(|(_, site)| site.is_log_statement())
+            .filter(|(_, site)| {
+                query.is_empty()
+                    || site.format.to_lowercase().contains(&query)
+                    || site.crate_name.to_lowercase().contains(&query)
+                    || site.tag.to_lowercase().contains(&query)
+                    || site
+                        .file
+                        .as_ref()
+                        .is_some_and(|f| f.to_lowercase().contains(&query))
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+    }
+
+    fn select_site(&mut self, site: &DefmtLogSite, cx: &mut Context<Self>) {
+        self.selected_index = Some(site.index);
+        cx.emit(DefmtSiteSelectEvent { site: site.clone() });
+        cx.notify();
+    }
+
+    fn render_site_row(&self, site: &DefmtLogSite, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_index == Some(site.index);
+
+        let (level_label, level_color) = match site.level {
+            Some(DefmtLevel::Trace) => ("TRACE", rgb(0x5c6370)),
+            Some(DefmtLevel::Debug) => ("DEBUG", rgb(0x61afef)),
+            Some(DefmtLevel::Info) => ("INFO", rgb(0x98c379)),
+            Some(DefmtLevel::Warn) => ("WARN", rgb(0xe5c07b)),
+            Some(DefmtLevel::Error) => ("ERROR", rgb(0xff4444)),
+            None => ("PRINT", rgb(0xabb2bf)),
+        };
+
+        div()
+            .id(ElementId::Name(format!("defmt-site-{}", site.index).into()))
+            .flex()
+            .items_center()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .cursor_pointer()
+            .rounded_sm()
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .w(px(48.0))
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(level_color)
+                    .child(level_label),
+            )
+            .child(
+                div()
+                    .w(px(140.0))
+                    .text_xs()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(site.crate_name.clone()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .text_sm()
+                    .font_family("monospace")
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .child(site.format.clone()),
+            )
+            .child(
+                div()
+                    .w(px(240.0))
+                    .text_xs()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(site.location().unwrap_or_else(|| "-".to_string())),
+            )
+            .child(
+                div()
+                    .w(px(60.0))
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(
+                        site.flash_bytes
+                            .map(|bytes| format!("{} B", bytes))
+                            .unwrap_or_else(|| "-".to_string()),
+                    ),
+            )
+    }
+}
+
+impl Render for DefmtPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Limit rendered rows for performance
+        const MAX_RENDERED: usize = 500;
+        let rows_to_render = &self.filtered[..self.filtered.len().min(MAX_RENDERED)];
+        let truncated = self.filtered.len() > MAX_RENDERED;
+
+        let defmt_info = Arc::clone(&self.defmt_info);
+        let total = defmt_info
+            .log_sites
+            .iter()
+            .filter(|s| s.is_log_statement())
+            .count();
+
+        div()
+            .id("defmt_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("defmt Log Sites"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("{} of {} sites", self.filtered.len(), total)),
+                            ),
+                    ),
+            )
+            .child(
+                // Search input
+                div()
+                    .px_2()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(Input::new(&self.search_input)),
+            )
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .children(rows_to_render.iter().map(|&idx| {
+                            let site = defmt_info.log_sites[idx].clone();
+
+                            self.render_site_row(&site, cx).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |view, _event, _window, cx| {
+                                    view.select_site(&site, cx);
+                                }),
+                            )
+                        }))
+                        .when(truncated, |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "... and {} more (refine the search to see more)",
+                                        self.filtered.len() - MAX_RENDERED
+                                    )),
+                            )
+                        }),
+                ),
+            )
+    }
+}
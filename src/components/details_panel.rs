@@ -1,24 +1,42 @@
-use crate::types::{DefmtInfo, MemorySegment, RttInfo};
+use crate::types::{
+    DefmtInfo, Diagnostic, MemorySegment, MemorySnapshot, RttInfo, Severity, StackInfo,
+    StackLayout, VectorTable,
+};
 use crate::utils::format_size;
 use gpui::{prelude::*, *};
 use gpui_component::description_list::{DescriptionItem, DescriptionList};
 use gpui_component::label::Label;
 use gpui_component::scroll::ScrollbarAxis;
 use gpui_component::{ActiveTheme, StyledExt};
+use std::sync::Arc;
 
 #[derive(IntoElement)]
 pub struct DetailsPanel {
     defmt_info: DefmtInfo,
     rtt_info: RttInfo,
+    stack_info: StackInfo,
+    vector_table: VectorTable,
+    diagnostics: Vec<Diagnostic>,
     segments: Vec<MemorySegment>,
     selected_segment: Option<usize>,
     total_size: u64,
+    on_show_log_sites: Option<Box<dyn Fn(&MouseUpEvent, &mut Window, &mut App) + 'static>>,
+    /// Summary of the loaded RTT capture and the handler that toggles its viewer
+    capture: Option<(
+        String,
+        Box<dyn Fn(&MouseUpEvent, &mut Window, &mut App) + 'static>,
+    )>,
+    /// Loaded memory snapshot and the number of statics decoded from it
+    snapshot: Option<(Arc<MemorySnapshot>, usize)>,
 }
 
 impl DetailsPanel {
     pub fn new(
         defmt_info: DefmtInfo,
         rtt_info: RttInfo,
+        stack_info: StackInfo,
+        vector_table: VectorTable,
+        diagnostics: Vec<Diagnostic>,
         segments: Vec<MemorySegment>,
         selected_segment: Option<usize>,
         total_size: u64,
@@ -26,11 +44,42 @@ impl DetailsPanel {
         Self {
             defmt_info,
             rtt_info,
+            stack_info,
+            vector_table,
+            diagnostics,
             segments,
             selected_segment,
             total_size,
+            on_show_log_sites: None,
+            capture: None,
+            snapshot: None,
         }
     }
+
+    /// Handler for the button that opens the defmt log site index
+    pub fn on_show_log_sites(
+        mut self,
+        handler: impl Fn(&MouseUpEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_show_log_sites = Some(Box::new(handler));
+        self
+    }
+
+    /// Show the loaded RTT capture, with a button that toggles the log viewer
+    pub fn on_show_capture(
+        mut self,
+        summary: String,
+        handler: impl Fn(&MouseUpEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.capture = Some((summary, Box::new(handler)));
+        self
+    }
+
+    /// Show the memory snapshot the static values were read from
+    pub fn snapshot(mut self, snapshot: Arc<MemorySnapshot>, decoded_statics: usize) -> Self {
+        self.snapshot = Some((snapshot, decoded_statics));
+        self
+    }
 }
 
 impl RenderOnce for DetailsPanel {
@@ -49,7 +98,7 @@ impl RenderOnce for DetailsPanel {
 }
 
 impl DetailsPanel {
-    fn render_content(self, _cx: &App) -> impl IntoElement {
+    fn render_content(mut self, cx: &App) -> impl IntoElement {
         let mut panel = div()
             .flex()
             .flex_col()
@@ -73,6 +122,62 @@ impl DetailsPanel {
             )
             .child(general_list);
 
+        // Add lint findings
+        if !self.diagnostics.is_empty() {
+            panel = panel
+                .child(
+                    Label::new(format!("Diagnostics ({})", self.diagnostics.len()))
+                        .text_lg()
+                        .font_weight(FontWeight::BOLD)
+                        .mb_2(),
+                )
+                .child(
+                    div()
+                        .flex()
+                        .flex_col()
+                        .gap_2()
+                        .children(self.diagnostics.iter().map(|diagnostic| {
+                            let color = match diagnostic.severity {
+                                Severity::Error => rgb(0xff4444),
+                                Severity::Warning => rgb(0xe5c07b),
+                                Severity::Info => rgb(0x61afef),
+                            };
+
+                            div()
+                                .flex()
+                                .flex_col()
+                                .gap_1()
+                                .p_2()
+                                .border_l_2()
+                                .border_color(color)
+                                .child(
+                                    div()
+                                        .flex()
+                                        .gap_2()
+                                        .text_xs()
+                                        .child(
+                                            div()
+                                                .font_weight(FontWeight::BOLD)
+                                                .text_color(color)
+                                                .child(diagnostic.severity.label()),
+                                        )
+                                        .child(
+                                            div()
+                                                .font_family("monospace")
+                                                .text_color(rgb(0x888888))
+                                                .child(diagnostic.key()),
+                                        ),
+                                )
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(rgb(0xcccccc))
+                                        .child(diagnostic.message.clone()),
+                                )
+                        })),
+                );
+        }
+
         // Add defmt info section if present
         if self.defmt_info.present {
             let mut defmt_list = DescriptionList::horizontal().bordered(true).columns(1);
@@ -89,6 +194,28 @@ impl DetailsPanel {
                 );
             }
 
+            // Log sites per level, with the code the line table attributes to them
+            if !self.defmt_info.log_sites.is_empty() {
+                for (level, count, flash_bytes) in self.defmt_info.level_costs() {
+                    if count == 0 {
+                        continue;
+                    }
+                    defmt_list = defmt_list.child(
+                        DescriptionItem::new(level.label())
+                            .value(format!("{} sites, ~{}", count, format_size(flash_bytes)))
+                            .span(1),
+                    );
+                }
+
+                for (crate_name, count) in self.defmt_info.sites_per_crate().into_iter().take(5) {
+                    defmt_list = defmt_list.child(
+                        DescriptionItem::new(crate_name)
+                            .value(format!("{} sites", count))
+                            .span(1),
+                    );
+                }
+            }
+
             panel = panel
                 .child(
                     Label::new("defmt Configuration")
@@ -97,6 +224,104 @@ impl DetailsPanel {
                         .mb_2(),
                 )
                 .child(defmt_list);
+
+            let log_site_count = self
+                .defmt_info
+                .log_sites
+                .iter()
+                .filter(|s| s.is_log_statement())
+                .count();
+
+            if let Some(on_show_log_sites) =
+                self.on_show_log_sites.take().filter(|_| log_site_count > 0)
+            {
+                panel = panel.child(
+                    div()
+                        .id("show_defmt_log_sites")
+                        .mt_2()
+                        .px_3()
+                        .py_1()
+                        .border_1()
+                        .border_color(cx.theme().border)
+                        .rounded_md()
+                        .text_sm()
+                        .cursor_pointer()
+                        .hover(|d| d.bg(cx.theme().list_hover))
+                        .child(format!("Browse {} log sites", log_site_count))
+                        .on_mouse_up(MouseButton::Left, on_show_log_sites),
+                );
+            }
+        }
+
+        // Add the offline RTT capture
+        if let Some((summary, on_show_capture)) = self.capture.take() {
+            panel = panel
+                .child(
+                    Label::new("RTT Capture")
+                        .text_lg()
+                        .font_weight(FontWeight::BOLD)
+                        .mb_2(),
+                )
+                .child(div().text_sm().child(summary))
+                .child(
+                    div()
+                        .id("show_rtt_capture")
+                        .mt_2()
+                        .px_3()
+                        .py_1()
+                        .border_1()
+                        .border_color(cx.theme().border)
+                        .rounded_md()
+                        .text_sm()
+                        .cursor_pointer()
+                        .hover(|d| d.bg(cx.theme().list_hover))
+                        .child("Show / hide capture log")
+                        .on_mouse_up(MouseButton::Left, on_show_capture),
+                );
+        }
+
+        // Add the post-mortem memory snapshot
+        if let Some((snapshot, decoded_statics)) = &self.snapshot {
+            let mut snapshot_list = DescriptionList::horizontal().bordered(true).columns(1);
+
+            snapshot_list = snapshot_list
+                .child(
+                    DescriptionItem::new("File")
+                        .value(snapshot.file_name.clone())
+                        .span(1),
+                )
+                .child(
+                    DescriptionItem::new("Format")
+                        .value(snapshot.format.label())
+                        .span(1),
+                )
+                .child(
+                    DescriptionItem::new("Captured")
+                        .value(format_size(snapshot.total_size()))
+                        .span(1),
+                )
+                .child(
+                    DescriptionItem::new("Statics decoded")
+                        .value(decoded_statics.to_string())
+                        .span(1),
+                );
+
+            for region in &snapshot.regions {
+                snapshot_list = snapshot_list.child(
+                    DescriptionItem::new(format!("0x{:08x}", region.address))
+                        .value(format_size(region.data.len() as u64))
+                        .span(1),
+                );
+            }
+
+            panel = panel
+                .child(
+                    Label::new("Memory Snapshot")
+                        .text_lg()
+                        .font_weight(FontWeight::BOLD)
+                        .mb_2(),
+                )
+                .child(snapshot_list);
         }
 
         // Add RTT info section if present
@@ -165,6 +390,280 @@ impl DetailsPanel {
                         .mb_2(),
                 )
                 .child(rtt_list);
+
+            // One entry per channel with its mode and the RAM symbol it writes to
+            let up = self.rtt_info.up_buffers.iter().map(|b| ("Up", b));
+            let down = self.rtt_info.down_buffers.iter().map(|b| ("Down", b));
+            panel = panel.child(div().flex().flex_col().gap_2().children(up.chain(down).map(
+                |(direction, buffer)| {
+                    let has_issues = !buffer.issues.is_empty();
+
+                    div()
+                        .flex()
+                        .flex_col()
+                        .p_2()
+                        .border_l_2()
+                        .border_color(if has_issues {
+                            rgb(0xff4444)
+                        } else {
+                            rgb(0x61afef)
+                        })
+                        .child(
+                            div()
+                                .text_sm()
+                                .font_weight(FontWeight::BOLD)
+                                .child(format!("{} \"{}\"", direction, buffer.name)),
+                        )
+                        .child(
+                            div()
+                                .text_xs()
+                                .font_family("monospace")
+                                .text_color(rgb(0xcccccc))
+                                .child(format!(
+                                    "0x{:08x} {} ({})",
+                                    buffer.buffer_address,
+                                    buffer.buffer_symbol.as_deref().unwrap_or("?"),
+                                    format_size(buffer.size as u64)
+                                )),
+                        )
+                        .child(div().text_xs().text_color(rgb(0x888888)).child(format!(
+                            "{}, flags 0x{:x}, wr {} rd {}",
+                            buffer.mode.label(),
+                            buffer.flags,
+                            buffer.write_offset,
+                            buffer.read_offset
+                        )))
+                        .children(buffer.issues.iter().map(|issue| {
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0xff8888))
+                                .child(format!("• {}", issue))
+                        }))
+                },
+            )));
+
+            if !self.rtt_info.warnings.is_empty() {
+                panel = panel.child(
+                    div()
+                        .mt_2()
+                        .p_3()
+                        .border_1()
+                        .border_color(rgb(0xe5c07b))
+                        .rounded_md()
+                        .bg(rgb(0x2d2a1a))
+                        .children(self.rtt_info.warnings.iter().map(|warning| {
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0xe5c07b))
+                                .mb_1()
+                                .child(format!("• {}", warning))
+                        })),
+                );
+            }
+        }
+
+        // Add stack & heap headroom if the linker symbols are present
+        if self.stack_info.present {
+            let stack = &self.stack_info;
+            let mut stack_list = DescriptionList::horizontal().bordered(true).columns(1);
+
+            if let Some(layout) = &stack.layout {
+                stack_list = stack_list.child(
+                    DescriptionItem::new("Layout")
+                        .value(match layout {
+                            StackLayout::Standard => "Standard",
+                            StackLayout::FlipLink => "flip-link",
+                            StackLayout::Unknown => "Unknown",
+                        })
+                        .span(1),
+                );
+            }
+
+            if let Some(region) = &stack.ram_region {
+                stack_list = stack_list.child(
+                    DescriptionItem::new("RAM Region")
+                        .value(region.clone())
+                        .span(1),
+                );
+            }
+
+            if let Some(start) = stack.stack_start {
+                stack_list = stack_list.child(
+                    DescriptionItem::new("Initial SP")
+                        .value(format!("0x{:08x}", start))
+                        .span(1),
+                );
+            }
+
+            if let Some(limit) = stack.stack_limit {
+                stack_list = stack_list.child(
+                    DescriptionItem::new("Stack Limit")
+                        .value(format!("0x{:08x}", limit))
+                        .span(1),
+                );
+            }
+
+            stack_list = stack_list
+                .child(
+                    DescriptionItem::new("Headroom")
+                        .value(stack.headroom().map(format_size).unwrap_or_else(|| {
+                            match stack.ram_region {
+                                Some(_) => "Unknown".to_string(),
+                                None => "Unknown (select a target)".to_string(),
+                            }
+                        }))
+                        .span(1),
+                )
+                .child(
+                    DescriptionItem::new("Can Hit Statics")
+                        .value(match stack.can_collide() {
+                            Some(true) => "Yes",
+                            Some(false) => "No",
+                            None => "Unknown",
+                        })
+                        .span(1),
+                );
+
+            if let (Some(start), Some(end)) = (stack.statics_start, stack.statics_end) {
+                stack_list = stack_list.child(
+                    DescriptionItem::new("Statics")
+                        .value(format_size(end.saturating_sub(start)))
+                        .span(1),
+                );
+            }
+
+            if let Some(heap) = &stack.heap {
+                stack_list = stack_list.child(
+                    DescriptionItem::new("Heap")
+                        .value(format!("{} ({})", heap.symbol_name, format_size(heap.size)))
+                        .span(1),
+                );
+            }
+
+            panel = panel
+                .child(
+                    Label::new("Stack & Heap")
+                        .text_lg()
+                        .font_weight(FontWeight::BOLD)
+                        .mb_2(),
+                )
+                .child(stack_list);
+
+            if !stack.warnings.is_empty() {
+                panel = panel.child(
+                    div()
+                        .mt_2()
+                        .p_3()
+                        .border_1()
+                        .border_color(rgb(0xe5c07b))
+                        .rounded_md()
+                        .bg(rgb(0x2d2a1a))
+                        .children(stack.warnings.iter().map(|warning| {
+                            div()
+                                .text_xs()
+                                .text_color(rgb(0xe5c07b))
+                                .mb_1()
+                                .child(format!("• {}", warning))
+                        })),
+                );
+            }
+        }
+
+        // Add the decoded vector table
+        if self.vector_table.present {
+            let table = &self.vector_table;
+            let mut vector_list = DescriptionList::horizontal().bordered(true).columns(1);
+
+            if let (Some(section_name), Some(address)) = (&table.section_name, table.address) {
+                vector_list = vector_list.child(
+                    DescriptionItem::new("Section")
+                        .value(format!("{} @ 0x{:08x}", section_name, address))
+                        .span(1),
+                );
+            }
+
+            vector_list = vector_list
+                .child(
+                    DescriptionItem::new("Entries")
+                        .value(format!("{}", table.entries.len()))
+                        .span(1),
+                )
+                .child(
+                    DescriptionItem::new("Unhandled")
+                        .value(format!("{}", table.unhandled_count()))
+                        .span(1),
+                );
+
+            panel = panel
+                .child(
+                    Label::new("Vector Table")
+                        .text_lg()
+                        .font_weight(FontWeight::BOLD)
+                        .mb_2(),
+                )
+                .child(vector_list)
+                .child(
+                    div().flex().flex_col().children(
+                        table
+                            .entries
+                            .iter()
+                            .filter(|entry| entry.value != 0 || entry.index == 0)
+                            .map(|entry| {
+                                let has_issues = !entry.issues.is_empty();
+                                let text_color = if has_issues {
+                                    rgb(0xff8888)
+                                } else if entry.default_handler {
+                                    rgb(0x888888)
+                                } else {
+                                    rgb(0xcccccc)
+                                };
+
+                                div()
+                                    .flex()
+                                    .flex_col()
+                                    .py(px(2.0))
+                                    .child(
+                                        div()
+                                            .flex()
+                                            .gap_2()
+                                            .text_xs()
+                                            .text_color(text_color)
+                                            .child(
+                                                div()
+                                                    .w(px(28.0))
+                                                    .flex_shrink_0()
+                                                    .text_right()
+                                                    .child(format!("{}", entry.index)),
+                                            )
+                                            .child(
+                                                div()
+                                                    .w(px(120.0))
+                                                    .flex_shrink_0()
+                                                    .overflow_x_hidden()
+                                                    .text_ellipsis()
+                                                    .child(entry.display_name()),
+                                            )
+                                            .child(
+                                                div()
+                                                    .flex_1()
+                                                    .font_family("monospace")
+                                                    .overflow_x_hidden()
+                                                    .text_ellipsis()
+                                                    .child(entry.symbol.clone().unwrap_or_else(
+                                                        || format!("0x{:08x}", entry.value),
+                                                    )),
+                                            ),
+                                    )
+                                    .children(entry.issues.iter().map(|issue| {
+                                        div()
+                                            .pl(px(36.0))
+                                            .text_xs()
+                                            .text_color(rgb(0xff4444))
+                                            .child(format!("⚠ {}", issue))
+                                    }))
+                            }),
+                    ),
+                );
         }
 
         // Add selected segment details
@@ -0,0 +1,185 @@
+use crate::components::AddressSelectEvent;
+use crate::types::{Disassembly, DisassemblyLine};
+use gpui::{prelude::*, *};
+use gpui_component::ActiveTheme;
+use std::ops::Range;
+use std::sync::Arc;
+
+impl EventEmitter<AddressSelectEvent> for DisassemblyPanel {}
+
+/// Machine code of the selected function, interleaved with its source lines.
+/// Clicking an instruction with a branch target selects the target.
+pub struct DisassemblyPanel {
+    disassembly: Arc<Disassembly>,
+    /// Why the function could not be disassembled
+    error: Option<String>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for DisassemblyPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl DisassemblyPanel {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            disassembly: Arc::new(Disassembly::default()),
+            error: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    pub fn show(&mut self, disassembly: anyhow::Result<Disassembly>, cx: &mut Context<Self>) {
+        match disassembly {
+            Ok(disassembly) => {
+                self.disassembly = Arc::new(disassembly);
+                self.error = None;
+            }
+            Err(e) => {
+                self.disassembly = Arc::new(Disassembly::default());
+                self.error = Some(e.to_string());
+            }
+        }
+        self.scroll_handle.scroll_to_item(0, ScrollStrategy::Top);
+        cx.notify();
+    }
+
+    fn render_rows(
+        &mut self,
+        range: Range<usize>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Vec<Stateful<Div>> {
+        range
+            .map(|index| {
+                let row = div()
+                    .id(("disassembly-line", index))
+                    .flex()
+                    .gap_3()
+                    .px_3()
+                    .text_xs()
+                    .font_family("monospace")
+                    .whitespace_nowrap();
+
+                match &self.disassembly.lines[index] {
+                    DisassemblyLine::Source { file, line, text } => {
+                        let file_name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+                        row.bg(cx.theme().sidebar)
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{}:{}", file_name, line))
+                            .children(text.as_ref().map(|text| text.trim().to_string()))
+                    }
+                    DisassemblyLine::Instruction(instruction) => {
+                        let bytes: Vec<String> = instruction
+                            .bytes
+                            .iter()
+                            .map(|byte| format!("{:02x}", byte))
+                            .collect();
+
+                        row.child(
+                            div()
+                                .w(px(72.0))
+                                .text_color(cx.theme().muted_foreground)
+                                .child(format!("{:08x}", instruction.address)),
+                        )
+                        .child(
+                            div()
+                                .w(px(88.0))
+                                .text_color(cx.theme().muted_foreground)
+                                .child(bytes.join(" ")),
+                        )
+                        .child(
+                            div()
+                                .w(px(64.0))
+                                .font_weight(FontWeight::BOLD)
+                                .child(instruction.mnemonic.clone()),
+                        )
+                        .child(div().child(instruction.operands.clone()))
+                        .children(
+                            instruction
+                                .comment
+                                .clone()
+                                .map(|comment| div().text_color(rgb(0x98c379)).child(comment)),
+                        )
+                        .when_some(instruction.target, |d, address| {
+                            d.cursor_pointer()
+                                .hover(|d| d.bg(cx.theme().list_hover))
+                                .on_mouse_up(
+                                    MouseButton::Left,
+                                    cx.listener(move |_, _, _, cx| {
+                                        cx.emit(AddressSelectEvent { address });
+                                    }),
+                                )
+                        })
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl Render for DisassemblyPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let instructions = self
+            .disassembly
+            .lines
+            .iter()
+            .filter(|line| matches!(line, DisassemblyLine::Instruction(_)))
+            .count();
+
+        div()
+            .id("disassembly_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                                "{} ({}, {} instructions)",
+                                self.disassembly.name,
+                                self.disassembly.instruction_set,
+                                instructions
+                            )),
+                    ),
+            )
+            .when_some(self.error.clone(), |d, error| {
+                d.child(
+                    div()
+                        .flex()
+                        .flex_1()
+                        .items_center()
+                        .justify_center()
+                        .p_4()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(error),
+                )
+            })
+            .when(self.error.is_none(), |d| {
+                d.child(
+                    uniform_list(
+                        "disassembly-lines",
+                        self.disassembly.lines.len(),
+                        cx.processor(Self::render_rows),
+                    )
+                    .track_scroll(self.scroll_handle.clone())
+                    .flex_1(),
+                )
+            })
+    }
+}
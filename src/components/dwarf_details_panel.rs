@@ -1,18 +1,132 @@
-use crate::types::{DwarfSymbol, DwarfTag};
-use crate::utils::format_size;
+use crate::types::{
+    CallGraph, DecodedValue, DwarfSymbol, DwarfTag, DwarfType, EnumLayout, LayoutRow, StackBound,
+    StructLayout, VariableLocation, Xref,
+};
+use crate::utils::{format_bits, format_size, format_stack_depth};
 use gpui::{prelude::*, *};
 use gpui_component::scroll::ScrollbarAxis;
 use gpui_component::ActiveTheme;
 use gpui_component::StyledExt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Handler that jumps to the DIE at a `.debug_info` offset
+type NavigateHandler = Rc<dyn Fn(&u64, &mut Window, &mut App) + 'static>;
 
 #[derive(IntoElement)]
 pub struct DwarfDetailsPanel {
     selected_symbol: Option<DwarfSymbol>,
+    /// Value of the selected static in the loaded memory snapshot
+    snapshot_value: Option<DecodedValue>,
+    /// Value the selected static is initialized with in the ELF image
+    initial_value: Option<DecodedValue>,
+    /// Section and memory region containing the selected symbol's address
+    placement: Option<(String, Option<String>)>,
+    /// Call graph and the index of the selected function in it
+    stack_usage: Option<(Arc<CallGraph>, usize)>,
+    /// Code and data referring to the selected symbol, and the handler selecting a referrer
+    references: Option<(Vec<Xref>, NavigateHandler)>,
+    /// Called when a type reference is clicked
+    on_navigate: Option<NavigateHandler>,
+    /// Handler for the button that returns to the previously viewed DIE
+    on_back: Option<Box<dyn Fn(&MouseUpEvent, &mut Window, &mut App) + 'static>>,
+    /// Whether the disassembly is shown instead of the source, and the button's handler
+    on_toggle_disassembly: Option<(
+        bool,
+        Box<dyn Fn(&MouseUpEvent, &mut Window, &mut App) + 'static>,
+    )>,
 }
 
 impl DwarfDetailsPanel {
     pub fn new(selected_symbol: Option<DwarfSymbol>) -> Self {
-        Self { selected_symbol }
+        Self {
+            selected_symbol,
+            snapshot_value: None,
+            initial_value: None,
+            placement: None,
+            stack_usage: None,
+            references: None,
+            on_navigate: None,
+            on_back: None,
+            on_toggle_disassembly: None,
+        }
+    }
+
+    pub fn snapshot_value(mut self, value: Option<DecodedValue>) -> Self {
+        self.snapshot_value = value;
+        self
+    }
+
+    pub fn initial_value(mut self, value: Option<DecodedValue>) -> Self {
+        self.initial_value = value;
+        self
+    }
+
+    pub fn placement(mut self, section: Option<String>, region: Option<String>) -> Self {
+        self.placement = section.map(|section| (section, region));
+        self
+    }
+
+    pub fn stack_usage(mut self, call_graph: Arc<CallGraph>, function: Option<usize>) -> Self {
+        self.stack_usage = function.map(|function| (call_graph, function));
+        self
+    }
+
+    /// List the references to the selected symbol; clicking one passes its address
+    pub fn references(
+        mut self,
+        references: Vec<Xref>,
+        on_select: impl Fn(&u64, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.references = Some((references, Rc::new(on_select)));
+        self
+    }
+
+    /// Make type references clickable, passing the referenced DIE's offset
+    pub fn on_navigate(mut self, handler: impl Fn(&u64, &mut Window, &mut App) + 'static) -> Self {
+        self.on_navigate = Some(Rc::new(handler));
+        self
+    }
+
+    /// Show a back button, e.g. after following a type reference
+    pub fn on_back(
+        mut self,
+        handler: impl Fn(&MouseUpEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_back = Some(Box::new(handler));
+        self
+    }
+
+    /// Show a button switching between the function's source and its disassembly
+    pub fn on_toggle_disassembly(
+        mut self,
+        showing: bool,
+        handler: impl Fn(&MouseUpEvent, &mut Window, &mut App) + 'static,
+    ) -> Self {
+        self.on_toggle_disassembly = Some((showing, Box::new(handler)));
+        self
+    }
+
+    /// Turn `element` into a link to the DIE at `target`, if navigation is enabled
+    fn link(
+        element: Div,
+        target: Option<u64>,
+        navigate: Option<&NavigateHandler>,
+        cx: &App,
+    ) -> Div {
+        match (target, navigate) {
+            (Some(target), Some(navigate)) => {
+                let navigate = navigate.clone();
+                element
+                    .text_color(cx.theme().link)
+                    .cursor_pointer()
+                    .hover(|d| d.underline())
+                    .on_mouse_up(MouseButton::Left, move |_, window, cx| {
+                        navigate(&target, window, cx)
+                    })
+            }
+            _ => element,
+        }
     }
 
     fn tag_color(tag: &DwarfTag) -> Rgba {
@@ -23,9 +137,15 @@ impl DwarfDetailsPanel {
             DwarfTag::FormalParameter => rgb(0xd19a66),
             DwarfTag::StructureType => rgb(0x98c379),
             DwarfTag::UnionType => rgb(0x98c379),
-            DwarfTag::EnumerationType => rgb(0x56b6c2),
+            DwarfTag::EnumerationType | DwarfTag::VariantPart | DwarfTag::Variant => rgb(0x56b6c2),
             DwarfTag::Member => rgb(0xabb2bf),
-            DwarfTag::Typedef => rgb(0xe06c75),
+            DwarfTag::Typedef
+            | DwarfTag::BaseType
+            | DwarfTag::PointerType
+            | DwarfTag::ArrayType
+            | DwarfTag::ConstType
+            | DwarfTag::VolatileType
+            | DwarfTag::SubroutineType => rgb(0xe06c75),
             DwarfTag::Namespace => rgb(0x61afef),
             DwarfTag::LexicalBlock => rgb(0x5c6370),
             DwarfTag::InlinedSubroutine => rgb(0xc678dd),
@@ -48,6 +168,7 @@ impl RenderOnce for DwarfDetailsPanel {
 
 impl DwarfDetailsPanel {
     fn render_content(self, cx: &App) -> impl IntoElement {
+        let navigate = self.on_navigate;
         if let Some(symbol) = self.selected_symbol {
             let tag_color = Self::tag_color(&symbol.tag);
             let icon = symbol.tag.icon().to_string();
@@ -80,21 +201,76 @@ impl DwarfDetailsPanel {
                                 .border_color(cx.theme().border)
                                 .bg(cx.theme().sidebar)
                                 .child(
-                                    div().flex().items_center().gap_2().child(
-                                        div()
-                                            .px_2()
-                                            .py_1()
-                                            .rounded_md()
-                                            .bg(tag_color)
-                                            .text_color(rgb(0xffffff))
-                                            .text_xs()
-                                            .font_weight(FontWeight::SEMIBOLD)
-                                            .child(format!(
-                                                "{} {}",
-                                                icon,
-                                                symbol.tag.display_name()
-                                            )),
-                                    ),
+                                    div()
+                                        .flex()
+                                        .items_center()
+                                        .justify_between()
+                                        .gap_2()
+                                        .child(
+                                            div()
+                                                .px_2()
+                                                .py_1()
+                                                .rounded_md()
+                                                .bg(tag_color)
+                                                .text_color(rgb(0xffffff))
+                                                .text_xs()
+                                                .font_weight(FontWeight::SEMIBOLD)
+                                                .child(format!(
+                                                    "{} {}",
+                                                    icon,
+                                                    symbol.tag.display_name()
+                                                )),
+                                        )
+                                        .child(
+                                            div()
+                                                .flex()
+                                                .gap_2()
+                                                .when_some(
+                                                    self.on_toggle_disassembly,
+                                                    |d, (showing, on_toggle)| {
+                                                        d.child(
+                                                            div()
+                                                                .id("dwarf_details_disassembly")
+                                                                .px_2()
+                                                                .border_1()
+                                                                .border_color(cx.theme().border)
+                                                                .rounded_md()
+                                                                .text_xs()
+                                                                .cursor_pointer()
+                                                                .hover(|d| {
+                                                                    d.bg(cx.theme().list_hover)
+                                                                })
+                                                                .child(if showing {
+                                                                    "Source"
+                                                                } else {
+                                                                    "Disassembly"
+                                                                })
+                                                                .on_mouse_up(
+                                                                    MouseButton::Left,
+                                                                    on_toggle,
+                                                                ),
+                                                        )
+                                                    },
+                                                )
+                                                .when_some(self.on_back, |d, on_back| {
+                                                    d.child(
+                                                        div()
+                                                            .id("dwarf_details_back")
+                                                            .px_2()
+                                                            .border_1()
+                                                            .border_color(cx.theme().border)
+                                                            .rounded_md()
+                                                            .text_xs()
+                                                            .cursor_pointer()
+                                                            .hover(|d| d.bg(cx.theme().list_hover))
+                                                            .child("← Back")
+                                                            .on_mouse_up(
+                                                                MouseButton::Left,
+                                                                on_back,
+                                                            ),
+                                                    )
+                                                }),
+                                        ),
                                 )
                                 .child(
                                     div()
@@ -103,7 +279,26 @@ impl DwarfDetailsPanel {
                                         .font_weight(FontWeight::BOLD)
                                         .text_color(cx.theme().foreground)
                                         .child(symbol.name.clone()),
-                                ),
+                                )
+                                .when_some(symbol.address, |d, address| {
+                                    let mut location = format!("0x{:08x}", address);
+                                    if let Some(size) = symbol.size {
+                                        location.push_str(&format!(" ({})", format_size(size)));
+                                    }
+                                    if let Some((section, region)) = &self.placement {
+                                        location.push_str(&format!(" in {}", section));
+                                        if let Some(region) = region {
+                                            location.push_str(&format!(", {}", region));
+                                        }
+                                    }
+                                    d.child(
+                                        div()
+                                            .text_xs()
+                                            .font_family("monospace")
+                                            .text_color(cx.theme().muted_foreground)
+                                            .child(location),
+                                    )
+                                }),
                         )
                         .child(
                             // Attributes section - scrollable
@@ -114,6 +309,47 @@ impl DwarfDetailsPanel {
                                         .flex_col()
                                         .p_2()
                                         .gap_1()
+                                        .when_some(self.snapshot_value, |d, value| {
+                                            d.child(Self::render_value_section(
+                                                "SNAPSHOT VALUE",
+                                                &value,
+                                                cx,
+                                            ))
+                                        })
+                                        .when_some(self.initial_value, |d, value| {
+                                            d.child(Self::render_value_section(
+                                                "INITIAL VALUE",
+                                                &value,
+                                                cx,
+                                            ))
+                                        })
+                                        .when_some(symbol.type_info.as_ref(), |d, ty| {
+                                            d.child(Self::render_type(
+                                                ty,
+                                                symbol.type_offset,
+                                                navigate.as_ref(),
+                                                cx,
+                                            ))
+                                        })
+                                        .when(!symbol.locations.is_empty(), |d| {
+                                            d.child(Self::render_locations(&symbol.locations, cx))
+                                        })
+                                        .when_some(self.stack_usage, |d, (graph, function)| {
+                                            d.child(Self::render_stack_usage(&graph, function, cx))
+                                        })
+                                        .when_some(self.references, |d, (references, on_select)| {
+                                            d.child(Self::render_references(
+                                                &references,
+                                                &on_select,
+                                                cx,
+                                            ))
+                                        })
+                                        .when(
+                                            symbol.ranges.len() > 1
+                                                || (symbol.tag == DwarfTag::CompileUnit
+                                                    && !symbol.ranges.is_empty()),
+                                            |d| d.child(Self::render_ranges(&symbol.ranges, cx)),
+                                        )
                                         .when(has_attributes, |d| {
                                             d.child(
                                                 div()
@@ -125,6 +361,9 @@ impl DwarfDetailsPanel {
                                             )
                                             .children(
                                                 symbol.attributes.iter().map(|(name, value)| {
+                                                    let target = (name == "DW_AT_type")
+                                                        .then_some(symbol.type_offset)
+                                                        .flatten();
                                                     div()
                                                         .flex()
                                                         .py(px(2.0))
@@ -139,7 +378,7 @@ impl DwarfDetailsPanel {
                                                                 )
                                                                 .child(name.clone()),
                                                         )
-                                                        .child(
+                                                        .child(Self::link(
                                                             div()
                                                                 .flex_1()
                                                                 .text_xs()
@@ -148,7 +387,10 @@ impl DwarfDetailsPanel {
                                                                 .overflow_x_hidden()
                                                                 .text_ellipsis()
                                                                 .child(value.clone()),
-                                                        )
+                                                            target,
+                                                            navigate.as_ref(),
+                                                            cx,
+                                                        ))
                                                 }),
                                             )
                                         })
@@ -189,25 +431,58 @@ impl DwarfDetailsPanel {
                                             .text_xs()
                                             .font_weight(FontWeight::BOLD)
                                             .text_color(cx.theme().muted_foreground)
-                                            .child(Self::children_header_text(&symbol.tag)),
+                                            .child(match (&symbol.layout, &symbol.enum_layout) {
+                                                (Some(_), _) => "LAYOUT",
+                                                (_, Some(_)) => "VARIANTS",
+                                                _ => Self::children_header_text(&symbol.tag),
+                                            }),
                                     )
                                     .child(
                                         div()
                                             .text_xs()
                                             .text_color(cx.theme().muted_foreground)
-                                            .child(format!("{} items", symbol.children.len())),
+                                            .child(match (&symbol.layout, &symbol.enum_layout) {
+                                                (Some(layout), _) => format!(
+                                                    "{}, {} wasted",
+                                                    format_size(layout.byte_size),
+                                                    format_bits(layout.wasted_bits())
+                                                ),
+                                                (_, Some(layout)) => {
+                                                    format!("{} variants", layout.variants.len())
+                                                }
+                                                _ => format!("{} items", symbol.children.len()),
+                                            }),
                                     ),
                             )
                             .child(
                                 // Children list - scrollable
                                 div().flex_1().overflow_hidden().child(
                                     div().size_full().scrollable(ScrollbarAxis::Both).child(
-                                        div().flex().flex_col().children(
-                                            symbol
-                                                .children
-                                                .iter()
-                                                .map(|child| Self::render_child_row(child, cx)),
-                                        ),
+                                        div().flex().flex_col().map(|d| {
+                                            match (&symbol.layout, &symbol.enum_layout) {
+                                                (Some(layout), _) => d.child(Self::render_layout(
+                                                    layout,
+                                                    navigate.as_ref(),
+                                                    cx,
+                                                )),
+                                                (_, Some(layout)) => {
+                                                    d.child(Self::render_enum_layout(
+                                                        layout,
+                                                        navigate.as_ref(),
+                                                        cx,
+                                                    ))
+                                                }
+                                                _ => d.children(symbol.children.iter().map(
+                                                    |child| {
+                                                        Self::render_child_row(
+                                                            child,
+                                                            navigate.as_ref(),
+                                                            cx,
+                                                        )
+                                                    },
+                                                )),
+                                            }
+                                        }),
                                     ),
                                 ),
                             ),
@@ -229,11 +504,691 @@ impl DwarfDetailsPanel {
         }
     }
 
+    /// Value and its members in display order, with their nesting depth
+    fn flatten_value(value: &DecodedValue) -> Vec<(usize, DecodedValue)> {
+        // Large arrays of structs would otherwise produce thousands of rows
+        const MAX_ROWS: usize = 500;
+
+        fn walk(value: &DecodedValue, depth: usize, rows: &mut Vec<(usize, DecodedValue)>) {
+            if rows.len() >= MAX_ROWS {
+                return;
+            }
+            rows.push((
+                depth,
+                DecodedValue {
+                    children: Vec::new(),
+                    ..value.clone()
+                },
+            ));
+            for child in &value.children {
+                walk(child, depth + 1, rows);
+            }
+        }
+
+        let mut rows = Vec::new();
+        walk(value, 0, &mut rows);
+        rows
+    }
+
+    fn render_type(
+        ty: &DwarfType,
+        target: Option<u64>,
+        navigate: Option<&NavigateHandler>,
+        cx: &App,
+    ) -> Div {
+        let row = |syntax: &'static str, name: String| {
+            div()
+                .flex()
+                .gap_2()
+                .child(
+                    div()
+                        .w(px(40.0))
+                        .flex_shrink_0()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(syntax),
+                )
+                .child(Self::link(
+                    div()
+                        .flex_1()
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(cx.theme().foreground)
+                        .child(name),
+                    target,
+                    navigate,
+                    cx,
+                ))
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pb_2()
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(cx.theme().muted_foreground)
+                    .pb_1()
+                    .child("TYPE"),
+            )
+            .child(row("Rust", ty.rust_name()))
+            .child(row("C", ty.c_name()))
+    }
+
+    fn render_locations(locations: &[VariableLocation], cx: &App) -> Div {
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pb_2()
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(cx.theme().muted_foreground)
+                    .pb_1()
+                    .child("LOCATION"),
+            )
+            .children(locations.iter().map(|location| {
+                div()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().foreground)
+                    .child(location.summary())
+            }))
+    }
+
+    /// Frame size and worst-case stack depth from the call graph
+    fn render_stack_usage(graph: &CallGraph, index: usize, cx: &App) -> Div {
+        let function = &graph.functions[index];
+        let row = |label: &str, value: String| {
+            div()
+                .flex()
+                .gap_2()
+                .text_xs()
+                .child(
+                    div()
+                        .w(px(120.0))
+                        .flex_shrink_0()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(label.to_string()),
+                )
+                .child(
+                    div()
+                        .font_family("monospace")
+                        .text_color(cx.theme().foreground)
+                        .child(value),
+                )
+        };
+
+        let frame = match function.frame_size {
+            Some(size) if function.frame_from_code => format!("{} B (from code)", size),
+            Some(size) => format!("{} B", size),
+            None => "unknown".to_string(),
+        };
+        let mut calls = format!("{} functions", function.callees.len());
+        if function.indirect_calls > 0 {
+            calls.push_str(&format!(", {} indirect", function.indirect_calls));
+        }
+        let why = match function.bound {
+            StackBound::Exact => None,
+            StackBound::LowerBound => {
+                Some("Indirect calls or functions of unknown frame size are reachable")
+            }
+            StackBound::Unbounded => Some("Recursion is reachable"),
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pb_2()
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(cx.theme().muted_foreground)
+                    .pb_1()
+                    .child("STACK USAGE"),
+            )
+            .child(row("Frame", frame))
+            .child(row(
+                "Worst case",
+                format_stack_depth(function.worst_case, function.bound),
+            ))
+            .child(row("Calls", calls))
+            .child(row("Callers", function.callers.len().to_string()))
+            .when(function.recursive, |d| {
+                d.child(row("Recursive", "part of a call cycle".to_string()))
+            })
+            .when_some(why, |d, why| {
+                d.child(div().text_xs().text_color(rgb(0xe5c07b)).child(why))
+            })
+            .when(function.deepest_callee.is_some(), |d| {
+                d.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .pt_1()
+                        .child("Deepest call chain"),
+                )
+                .children(graph.worst_path(index).into_iter().map(|index| {
+                    let function = &graph.functions[index];
+                    div()
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(cx.theme().foreground)
+                        .overflow_x_hidden()
+                        .text_ellipsis()
+                        .child(format!(
+                            "{:>6} B  {}",
+                            function.frame_size.unwrap_or(0),
+                            function.name
+                        ))
+                }))
+            })
+    }
+
+    fn render_references(references: &[Xref], on_select: &NavigateHandler, cx: &App) -> Div {
+        /// Large tables are referenced from everywhere; listing them all helps nobody
+        const MAX_REFERENCES: usize = 100;
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pb_2()
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(cx.theme().muted_foreground)
+                    .pb_1()
+                    .child(format!("REFERENCED BY ({})", references.len())),
+            )
+            .when(references.is_empty(), |d| {
+                d.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No references found in code or data"),
+                )
+            })
+            .children(references.iter().take(MAX_REFERENCES).map(|xref| {
+                let on_select = on_select.clone();
+                let from = xref.from;
+                div()
+                    .flex()
+                    .gap_2()
+                    .text_xs()
+                    .font_family("monospace")
+                    .cursor_pointer()
+                    .hover(|d| d.bg(cx.theme().list_hover))
+                    .on_mouse_up(MouseButton::Left, move |_, window, cx| {
+                        on_select(&from, window, cx)
+                    })
+                    .child(
+                        div()
+                            .w(px(56.0))
+                            .flex_shrink_0()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(xref.kind.display_name()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_x_hidden()
+                            .text_ellipsis()
+                            .text_color(cx.theme().link)
+                            .child(xref.from_symbol.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("0x{:08x}", xref.from)),
+                    )
+            }))
+            .when(references.len() > MAX_REFERENCES, |d| {
+                d.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format!(
+                            "... and {} more",
+                            references.len() - MAX_REFERENCES
+                        )),
+                )
+            })
+    }
+
+    /// Pahole-style member list with the padding between members made visible
+    fn render_layout(layout: &StructLayout, navigate: Option<&NavigateHandler>, cx: &App) -> Div {
+        let hole_color = rgb(0xe5c07b);
+        let cell = |width: f32| {
+            div()
+                .w(px(width))
+                .flex_shrink_0()
+                .text_xs()
+                .font_family("monospace")
+                .text_color(cx.theme().muted_foreground)
+        };
+
+        let rows = layout.rows.iter().map(|row| match row {
+            LayoutRow::Member(member) => {
+                let (offset, size) = match member.bits {
+                    Some((start, bit_size)) => (
+                        format!("+{}:{}", start / 8, start % 8),
+                        format!("{} bit{}", bit_size, if bit_size == 1 { "" } else { "s" }),
+                    ),
+                    None => (format!("+{}", member.offset), format_size(member.size)),
+                };
+                div()
+                    .flex()
+                    .items_center()
+                    .w_full()
+                    .px_3()
+                    .py_1()
+                    .gap_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .hover(|d| d.bg(cx.theme().list_hover))
+                    .child(cell(60.0).child(offset))
+                    .child(
+                        div()
+                            .w(px(160.0))
+                            .flex_shrink_0()
+                            .text_sm()
+                            .font_family("monospace")
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(cx.theme().foreground)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(member.name.clone()),
+                    )
+                    .child(Self::link(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .font_family("monospace")
+                            .text_color(cx.theme().muted_foreground)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(member.type_name.clone()),
+                        member.type_offset,
+                        navigate,
+                        cx,
+                    ))
+                    .child(cell(60.0).text_right().child(size))
+                    .child(
+                        cell(60.0)
+                            .text_right()
+                            .child(format!("align {}", member.alignment)),
+                    )
+            }
+            LayoutRow::Padding {
+                bit_offset,
+                bit_size,
+                trailing,
+            } => div()
+                .flex()
+                .w_full()
+                .px_3()
+                .py_1()
+                .gap_2()
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(cell(60.0).child(format!("+{}", bit_offset / 8)))
+                .child(
+                    div()
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(hole_color)
+                        .child(if *trailing {
+                            format!("/* {} trailing padding */", format_bits(*bit_size))
+                        } else {
+                            format!("/* XXX {} hole */", format_bits(*bit_size))
+                        }),
+                ),
+        });
+
+        let member_count = layout
+            .rows
+            .iter()
+            .filter(|row| matches!(row, LayoutRow::Member(_)))
+            .count();
+        let summary = format!(
+            "size: {}, alignment: {}, members: {}, holes: {}, wasted: {}",
+            layout.byte_size,
+            layout.alignment,
+            member_count,
+            layout.hole_count(),
+            format_bits(layout.wasted_bits())
+        );
+
+        div()
+            .flex()
+            .flex_col()
+            .children(rows)
+            .child(
+                div()
+                    .px_3()
+                    .py_2()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().muted_foreground)
+                    .child(summary),
+            )
+            .when_some(layout.suggestion.as_ref(), |d, suggestion| {
+                d.child(
+                    div()
+                        .flex()
+                        .flex_col()
+                        .gap_1()
+                        .px_3()
+                        .pb_2()
+                        .child(
+                            div()
+                                .text_xs()
+                                .font_weight(FontWeight::BOLD)
+                                .text_color(cx.theme().muted_foreground)
+                                .child(format!(
+                                    "SUGGESTED ORDER ({} bytes, saves {})",
+                                    suggestion.byte_size,
+                                    format_size(layout.byte_size - suggestion.byte_size)
+                                )),
+                        )
+                        .child(
+                            div()
+                                .text_xs()
+                                .font_family("monospace")
+                                .text_color(cx.theme().foreground)
+                                .child(suggestion.order.join(", ")),
+                        ),
+                )
+            })
+    }
+
+    /// Variants with their discriminant values, payload sizes and payload fields
+    fn render_enum_layout(
+        layout: &EnumLayout,
+        navigate: Option<&NavigateHandler>,
+        cx: &App,
+    ) -> Div {
+        let warning_color = rgb(0xe5c07b);
+        let largest = layout.largest_variant();
+        let max_payload = largest.map_or(0, |variant| variant.payload_size).max(1);
+
+        let discriminant = match &layout.discriminant {
+            Some(discr) => match &discr.niche_in {
+                Some(field) => format!(
+                    "niche in {} (+{}, {})",
+                    field,
+                    discr.offset,
+                    format_size(discr.size)
+                ),
+                None => format!("tag at +{} ({})", discr.offset, format_size(discr.size)),
+            },
+            None => "no discriminant".to_string(),
+        };
+
+        // A single huge variant makes every value of the enum pay for it
+        let size_note = largest.and_then(|largest| {
+            let next = layout
+                .variants
+                .iter()
+                .filter(|variant| variant.name != largest.name)
+                .map(|variant| variant.payload_size)
+                .max()?;
+            (largest.payload_size >= 64 && largest.payload_size >= next.max(1) * 4).then(|| {
+                format!(
+                    "{} carries {} while the next largest variant needs {}; boxing its payload would shrink the enum",
+                    largest.name,
+                    format_size(largest.payload_size),
+                    format_size(next)
+                )
+            })
+        });
+
+        let variants = layout.variants.iter().map(|variant| {
+            let value = match (variant.value, &layout.discriminant) {
+                (Some(value), _) => format!("= {}", value),
+                (None, Some(discr)) if discr.niche_in.is_some() => "niche owner".to_string(),
+                (None, _) => "default".to_string(),
+            };
+            let bar_width = 80.0 * variant.payload_size as f32 / max_payload as f32;
+
+            div()
+                .flex()
+                .flex_col()
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .w_full()
+                        .px_3()
+                        .py_1()
+                        .gap_2()
+                        .hover(|d| d.bg(cx.theme().list_hover))
+                        .child(Self::link(
+                            div()
+                                .w(px(160.0))
+                                .flex_shrink_0()
+                                .text_sm()
+                                .font_family("monospace")
+                                .font_weight(FontWeight::MEDIUM)
+                                .text_color(cx.theme().foreground)
+                                .overflow_hidden()
+                                .text_ellipsis()
+                                .child(variant.name.clone()),
+                            variant.type_offset,
+                            navigate,
+                            cx,
+                        ))
+                        .child(
+                            div()
+                                .w(px(100.0))
+                                .flex_shrink_0()
+                                .text_xs()
+                                .font_family("monospace")
+                                .text_color(cx.theme().muted_foreground)
+                                .child(value),
+                        )
+                        .when(variant.payload_size > 0, |d| {
+                            d.child(
+                                div()
+                                    .w(px(80.0))
+                                    .flex_shrink_0()
+                                    .h(px(6.0))
+                                    .rounded_sm()
+                                    .bg(cx.theme().border)
+                                    .child(
+                                        div()
+                                            .h_full()
+                                            .w(px(bar_width))
+                                            .rounded_sm()
+                                            .bg(rgb(0x56b6c2)),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format_size(variant.payload_size)),
+                            )
+                        }),
+                )
+                .children(variant.payload.iter().flat_map(|payload| {
+                    payload.rows.iter().filter_map(move |row| match row {
+                        LayoutRow::Member(field) => Some(
+                            div()
+                                .flex()
+                                .gap_2()
+                                .pl(px(28.0))
+                                .pr_3()
+                                .pb_1()
+                                .text_xs()
+                                .font_family("monospace")
+                                .text_color(cx.theme().muted_foreground)
+                                .child(format!("+{}", field.offset))
+                                .child(field.name.clone())
+                                .child(Self::link(
+                                    div().child(field.type_name.clone()),
+                                    field.type_offset,
+                                    navigate,
+                                    cx,
+                                ))
+                                .child(format_size(field.size)),
+                        ),
+                        LayoutRow::Padding { .. } => None,
+                    })
+                }))
+        });
+
+        div()
+            .flex()
+            .flex_col()
+            .child(
+                div()
+                    .px_3()
+                    .py_2()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().muted_foreground)
+                    .child(match layout.byte_size {
+                        Some(size) => format!("size: {}, {}", size, discriminant),
+                        None => discriminant,
+                    }),
+            )
+            .when_some(size_note, |d, note| {
+                d.child(
+                    div()
+                        .px_3()
+                        .pb_2()
+                        .text_xs()
+                        .text_color(warning_color)
+                        .child(note),
+                )
+            })
+            .children(variants)
+    }
+
+    fn render_ranges(ranges: &[(u64, u64)], cx: &App) -> Div {
+        // LTO builds put thousands of ranges into one unit
+        const MAX_RANGES: usize = 100;
+        let total: u64 = ranges.iter().map(|(start, end)| end - start).sum();
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pb_2()
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(cx.theme().muted_foreground)
+                    .pb_1()
+                    .child(format!(
+                        "CODE RANGES ({}, {} total)",
+                        ranges.len(),
+                        format_size(total)
+                    )),
+            )
+            .children(ranges.iter().take(MAX_RANGES).map(|(start, end)| {
+                div()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().foreground)
+                    .child(format!(
+                        "0x{:08x}..0x{:08x} ({})",
+                        start,
+                        end,
+                        format_size(end - start)
+                    ))
+            }))
+            .when(ranges.len() > MAX_RANGES, |d| {
+                d.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(format!("... and {} more", ranges.len() - MAX_RANGES)),
+                )
+            })
+    }
+
+    fn render_value_section(title: &'static str, value: &DecodedValue, cx: &App) -> Div {
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .pb_2()
+            .child(
+                div()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(cx.theme().muted_foreground)
+                    .pb_1()
+                    .child(title),
+            )
+            .children(
+                Self::flatten_value(value)
+                    .iter()
+                    .map(|(depth, value)| Self::render_value_row(*depth, value, cx)),
+            )
+    }
+
+    fn render_value_row(depth: usize, value: &DecodedValue, cx: &App) -> Div {
+        div()
+            .flex()
+            .py(px(2.0))
+            .gap_2()
+            .pl(px(depth as f32 * 12.0))
+            .child(
+                div()
+                    .w(px(120.0))
+                    .flex_shrink_0()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().muted_foreground)
+                    .overflow_x_hidden()
+                    .text_ellipsis()
+                    .child(value.name.clone()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(rgb(0xe5c07b))
+                    .overflow_x_hidden()
+                    .text_ellipsis()
+                    .child(value.value.clone()),
+            )
+            .child(
+                div()
+                    .w(px(100.0))
+                    .flex_shrink_0()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().muted_foreground)
+                    .overflow_x_hidden()
+                    .text_ellipsis()
+                    .child(value.type_name.clone()),
+            )
+    }
+
     fn children_header_text(tag: &DwarfTag) -> &'static str {
         match tag {
             DwarfTag::StructureType => "STRUCT MEMBERS",
             DwarfTag::UnionType => "UNION MEMBERS",
             DwarfTag::EnumerationType => "ENUM VARIANTS",
+            DwarfTag::VariantPart => "VARIANTS",
+            DwarfTag::Variant => "PAYLOAD",
             DwarfTag::Subprogram => "PARAMETERS & LOCALS",
             DwarfTag::Namespace => "CONTENTS",
             DwarfTag::CompileUnit => "SYMBOLS",
@@ -241,7 +1196,7 @@ impl DwarfDetailsPanel {
         }
     }
 
-    fn render_child_row(child: &DwarfSymbol, cx: &App) -> Div {
+    fn render_child_row(child: &DwarfSymbol, navigate: Option<&NavigateHandler>, cx: &App) -> Div {
         let tag_color = Self::tag_color(&child.tag);
         let icon = child.tag.icon().to_string();
 
@@ -252,13 +1207,14 @@ impl DwarfDetailsPanel {
             .find(|(name, _)| name == "DW_AT_data_member_location")
             .map(|(_, v)| v.clone());
 
-        // Extract type from attributes
-        let type_info = child
-            .attributes
-            .iter()
-            .find(|(name, _)| name == "DW_AT_type")
-            .map(|(_, v)| v.clone())
-            .or_else(|| child.type_name.clone());
+        // Prefer the resolved type name over the raw DW_AT_type reference
+        let type_info = child.type_name.clone().or_else(|| {
+            child
+                .attributes
+                .iter()
+                .find(|(name, _)| name == "DW_AT_type")
+                .map(|(_, v)| v.clone())
+        });
 
         div()
             .flex()
@@ -305,7 +1261,7 @@ impl DwarfDetailsPanel {
             )
             // Type
             .when_some(type_info, |d, ti| {
-                d.child(
+                d.child(Self::link(
                     div()
                         .flex_1()
                         .text_xs()
@@ -314,7 +1270,10 @@ impl DwarfDetailsPanel {
                         .overflow_hidden()
                         .text_ellipsis()
                         .child(ti),
-                )
+                    child.type_offset,
+                    navigate,
+                    cx,
+                ))
             })
             // Size
             .when_some(child.size, |d, size| {
@@ -1,15 +1,17 @@
-use crate::types::{DwarfInfo, DwarfSymbol, DwarfTag};
-use crate::utils::format_size;
+use crate::types::{DecodedValue, DwarfInfo, DwarfSymbol, DwarfTag};
+use crate::utils::{format_size, parse_hex};
 use gpui::{prelude::*, *};
 use gpui_component::input::{Input, InputEvent, InputState};
 use gpui_component::scroll::ScrollbarAxis;
 use gpui_component::{ActiveTheme, StyledExt};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub struct DwarfSymbolSelectEvent {
     pub symbol: DwarfSymbol,
+    /// Address searched for when the symbol was picked from an address search
+    pub address: Option<u64>,
 }
 
 impl EventEmitter<DwarfSymbolSelectEvent> for DwarfTreePanel {}
@@ -23,6 +25,8 @@ struct FlatNode {
 
 pub struct DwarfTreePanel {
     dwarf_info: Arc<DwarfInfo>,
+    /// Static values from the loaded memory snapshot, keyed by DIE offset
+    snapshot_values: Arc<HashMap<u64, DecodedValue>>,
     expanded_ids: HashSet<usize>,
     selected_id: Option<usize>,
     search_input: Entity<InputState>,
@@ -41,9 +45,14 @@ impl Focusable for DwarfTreePanel {
 }
 
 impl DwarfTreePanel {
-    pub fn new(dwarf_info: DwarfInfo, window: &mut Window, cx: &mut Context<Self>) -> Self {
+    pub fn new(
+        dwarf_info: DwarfInfo,
+        snapshot_values: Arc<HashMap<u64, DecodedValue>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
         let search_input =
-            cx.new(|cx| InputState::new(window, cx).placeholder("Search symbols..."));
+            cx.new(|cx| InputState::new(window, cx).placeholder("Search symbols or 0x address..."));
 
         cx.subscribe(&search_input, Self::on_search_input).detach();
 
@@ -57,6 +66,7 @@ impl DwarfTreePanel {
 
         Self {
             dwarf_info,
+            snapshot_values,
             expanded_ids,
             selected_id: None,
             search_input,
@@ -99,8 +109,81 @@ impl DwarfTreePanel {
         self.selected_id = Some(symbol.id);
         cx.emit(DwarfSymbolSelectEvent {
             symbol: symbol.clone(),
+            address: search_address(&self.search_query),
+        });
+        cx.notify();
+    }
+
+    /// Select the symbol whose DIE is at `die_offset`, expanding all of its parents.
+    /// Returns false if no such symbol is in the tree.
+    pub fn select_by_offset(&mut self, die_offset: u64, cx: &mut Context<Self>) -> bool {
+        self.select_offset(die_offset, None, cx)
+    }
+
+    /// Select the innermost function or inlined call whose code covers `address`, or
+    /// else the static stored there
+    pub fn select_address(&mut self, address: u64, cx: &mut Context<Self>) -> bool {
+        let die_offset = self
+            .dwarf_info
+            .scopes_at(address)
+            .into_iter()
+            .rev()
+            .find(|scope| {
+                matches!(
+                    scope.tag,
+                    DwarfTag::Subprogram | DwarfTag::InlinedSubroutine
+                )
+            })
+            .or_else(|| self.dwarf_info.static_at(address))
+            .and_then(|scope| scope.die_offset);
+        die_offset.is_some_and(|offset| self.select_offset(offset, Some(address), cx))
+    }
+
+    fn select_offset(
+        &mut self,
+        die_offset: u64,
+        address: Option<u64>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let dwarf_info = Arc::clone(&self.dwarf_info);
+        let mut path = Vec::new();
+
+        let found = dwarf_info
+            .compile_units
+            .iter()
+            .any(|cu| Self::find_path_static(cu, die_offset, &mut path));
+        let Some((symbol, parents)) = path.split_last().filter(|_| found) else {
+            return false;
+        };
+
+        self.expanded_ids
+            .extend(parents.iter().map(|parent| parent.id));
+        self.cache_dirty = true;
+        self.selected_id = Some(symbol.id);
+        cx.emit(DwarfSymbolSelectEvent {
+            symbol: (*symbol).clone(),
+            address,
         });
         cx.notify();
+        true
+    }
+
+    fn find_path_static<'a>(
+        symbol: &'a DwarfSymbol,
+        die_offset: u64,
+        path: &mut Vec<&'a DwarfSymbol>,
+    ) -> bool {
+        path.push(symbol);
+        if symbol.die_offset == Some(die_offset)
+            || symbol
+                .children
+                .iter()
+                .any(|child| Self::find_path_static(child, die_offset, path))
+        {
+            return true;
+        }
+        path.pop();
+        false
     }
 
     fn rebuild_cache(&mut self) {
@@ -116,6 +199,14 @@ impl DwarfTreePanel {
             for cu in &dwarf_info.compile_units {
                 Self::collect_expanded_nodes_static(cu, 0, &expanded_ids, &mut self.cached_nodes);
             }
+        } else if let Some(address) = search_address(&search_query) {
+            // Address search - show the unit, function and blocks or inlined calls covering it
+            for (depth, symbol) in dwarf_info.scopes_at(address).into_iter().enumerate() {
+                self.cached_nodes.push(FlatNode {
+                    symbol: Arc::new(symbol.clone()),
+                    depth,
+                });
+            }
         } else {
             // Search mode - show matching nodes (limited to avoid lag)
             let query = search_query.to_lowercase();
@@ -214,9 +305,15 @@ impl DwarfTreePanel {
             DwarfTag::FormalParameter => rgb(0xd19a66),
             DwarfTag::StructureType => rgb(0x98c379),
             DwarfTag::UnionType => rgb(0x98c379),
-            DwarfTag::EnumerationType => rgb(0x56b6c2),
+            DwarfTag::EnumerationType | DwarfTag::VariantPart | DwarfTag::Variant => rgb(0x56b6c2),
             DwarfTag::Member => rgb(0xabb2bf),
-            DwarfTag::Typedef => rgb(0xe06c75),
+            DwarfTag::Typedef
+            | DwarfTag::BaseType
+            | DwarfTag::PointerType
+            | DwarfTag::ArrayType
+            | DwarfTag::ConstType
+            | DwarfTag::VolatileType
+            | DwarfTag::SubroutineType => rgb(0xe06c75),
             DwarfTag::Namespace => rgb(0x61afef),
             DwarfTag::LexicalBlock => rgb(0x5c6370),
             DwarfTag::InlinedSubroutine => rgb(0xc678dd),
@@ -280,6 +377,26 @@ impl DwarfTreePanel {
                 .child(display_name),
         );
 
+        // Snapshot value badge
+        if let Some(value) = symbol
+            .die_offset
+            .and_then(|offset| self.snapshot_values.get(&offset))
+        {
+            let display_value = if value.value.chars().count() > 32 {
+                format!("{}...", value.value.chars().take(29).collect::<String>())
+            } else {
+                value.value.clone()
+            };
+            row = row.child(
+                div()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(rgb(0xe5c07b))
+                    .mr_1()
+                    .child(display_value),
+            );
+        }
+
         // Address badge
         if let Some(addr) = symbol.address {
             row = row.child(
@@ -308,6 +425,15 @@ impl DwarfTreePanel {
     }
 }
 
+/// Address of an `0x...` search query
+fn search_address(query: &str) -> Option<u64> {
+    let query = query.trim();
+    if !query.starts_with("0x") && !query.starts_with("0X") {
+        return None;
+    }
+    parse_hex(query)
+}
+
 impl Render for DwarfTreePanel {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         // Rebuild cache if needed
@@ -317,13 +443,22 @@ impl Render for DwarfTreePanel {
 
         // Limit rendered nodes for performance
         const MAX_RENDERED: usize = 500;
-        let nodes_to_render = if self.cached_nodes.len() > MAX_RENDERED {
-            &self.cached_nodes[..MAX_RENDERED]
-        } else {
-            &self.cached_nodes[..]
-        };
+        let total = self.cached_nodes.len();
 
-        let truncated = self.cached_nodes.len() > MAX_RENDERED;
+        // Keep the selection in view, e.g. after jumping to a type deep in the tree
+        let selected_index = self.selected_id.and_then(|id| {
+            self.cached_nodes
+                .iter()
+                .position(|node| node.symbol.id == id)
+        });
+        let start = match selected_index {
+            Some(index) if index >= MAX_RENDERED => {
+                (index - MAX_RENDERED / 2).min(total - MAX_RENDERED)
+            }
+            _ => 0,
+        };
+        let end = (start + MAX_RENDERED).min(total);
+        let nodes_to_render = &self.cached_nodes[start..end];
 
         div()
             .id("dwarf_tree_panel")
@@ -372,6 +507,16 @@ impl Render for DwarfTreePanel {
                     div()
                         .size_full()
                         .scrollable(ScrollbarAxis::Vertical)
+                        .when(start > 0, |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("... {} earlier symbols", start)),
+                            )
+                        })
                         .children(nodes_to_render.iter().map(|node| {
                             let symbol = (*node.symbol).clone();
                             let symbol_id = symbol.id;
@@ -387,7 +532,7 @@ impl Render for DwarfTreePanel {
                                 }),
                             )
                         }))
-                        .when(truncated, |d| {
+                        .when(end < total, |d| {
                             d.child(
                                 div()
                                     .px_3()
@@ -396,7 +541,7 @@ impl Render for DwarfTreePanel {
                                     .text_color(cx.theme().muted_foreground)
                                     .child(format!(
                                         "... and {} more (expand folders to see more)",
-                                        self.cached_nodes.len() - MAX_RENDERED
+                                        total - end
                                     )),
                             )
                         }),
@@ -0,0 +1,454 @@
+use crate::parser::demangle_name;
+use crate::types::{ElfSymbol, MemorySegment};
+use crate::utils::{format_size, generate_color, parse_hex};
+use gpui::{prelude::*, *};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::{ActiveTheme, StyledExt};
+use std::ops::Range;
+use std::sync::Arc;
+
+const BYTES_PER_ROW: usize = 16;
+/// Matches collected per search, enough to step through without freezing on `00`
+const MAX_MATCHES: usize = 10_000;
+
+/// Hex/ASCII view of a section's file contents, annotated with its symbols
+pub struct HexViewPanel {
+    segment: MemorySegment,
+    /// File contents, `None` for NOBITS sections or when they could not be read
+    data: Option<Arc<Vec<u8>>>,
+    /// Why the contents could not be read
+    error: Option<String>,
+    /// Sized symbols inside the section, sorted by address
+    symbols: Arc<Vec<ElfSymbol>>,
+    /// Byte offset under the mouse
+    hovered: Option<usize>,
+    /// Byte range highlighted by go-to-address or search
+    highlight: Option<Range<usize>>,
+    goto_input: Entity<InputState>,
+    search_input: Entity<InputState>,
+    /// Offsets of the current search pattern
+    matches: Vec<usize>,
+    current_match: Option<usize>,
+    /// Pattern `matches` was computed for, to step through them on repeated Enter
+    last_pattern: Vec<u8>,
+    status: Option<String>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for HexViewPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl HexViewPanel {
+    pub fn new(
+        segment: MemorySegment,
+        data: anyhow::Result<Option<Vec<u8>>>,
+        symbols: &[ElfSymbol],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let goto_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Go to address (0x...)"));
+        let search_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("Search bytes (de ad be ef) or \"text\"")
+        });
+
+        cx.subscribe(&goto_input, Self::on_goto_input).detach();
+        cx.subscribe(&search_input, Self::on_search_input).detach();
+
+        let section_end = segment.address + segment.size;
+        let mut section_symbols: Vec<ElfSymbol> = symbols
+            .iter()
+            .filter(|s| s.size > 0 && !s.name.starts_with('$'))
+            .filter(|s| s.address >= segment.address && s.address < section_end)
+            .cloned()
+            .collect();
+        section_symbols.sort_by_key(|s| s.address);
+
+        let (data, error) = match data {
+            Ok(data) => (data.map(Arc::new), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+
+        Self {
+            segment,
+            data,
+            error,
+            symbols: Arc::new(section_symbols),
+            hovered: None,
+            highlight: None,
+            goto_input,
+            search_input,
+            matches: Vec::new(),
+            current_match: None,
+            last_pattern: Vec::new(),
+            status: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn on_goto_input(
+        &mut self,
+        input: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        if let InputEvent::PressEnter { .. } = event {
+            let text = input.read(cx).text().to_string();
+            self.goto_address(text.trim());
+            cx.notify();
+        }
+    }
+
+    fn on_search_input(
+        &mut self,
+        input: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        if let InputEvent::PressEnter { .. } = event {
+            let text = input.read(cx).text().to_string();
+            self.search(&text);
+            cx.notify();
+        }
+    }
+
+    fn goto_address(&mut self, text: &str) {
+        let Some(address) = parse_hex(text) else {
+            self.status = Some(format!("'{}' is not an address", text));
+            return;
+        };
+
+        if address < self.segment.address || address >= self.segment.address + self.segment.size {
+            self.status = Some(format!(
+                "0x{:08x} is outside {} (0x{:08x}..0x{:08x})",
+                address,
+                self.segment.name,
+                self.segment.address,
+                self.segment.address + self.segment.size
+            ));
+            return;
+        }
+
+        let offset = (address - self.segment.address) as usize;
+        self.status = None;
+        self.reveal(offset..offset + 1);
+    }
+
+    fn search(&mut self, text: &str) {
+        let Some(data) = self.data.clone() else {
+            return;
+        };
+        let Some(pattern) = parse_search_pattern(text) else {
+            self.matches.clear();
+            self.current_match = None;
+            self.status = None;
+            return;
+        };
+
+        // Enter on the same pattern steps to the next match
+        if pattern == self.last_pattern && !self.matches.is_empty() {
+            let next = self
+                .current_match
+                .map_or(0, |i| (i + 1) % self.matches.len());
+            self.select_match(next, pattern.len());
+            return;
+        }
+
+        self.matches = data
+            .windows(pattern.len())
+            .enumerate()
+            .filter(|(_, window)| *window == pattern.as_slice())
+            .map(|(offset, _)| offset)
+            .take(MAX_MATCHES)
+            .collect();
+        self.last_pattern = pattern;
+
+        if self.matches.is_empty() {
+            self.current_match = None;
+            self.status = Some("No matches".to_string());
+        } else {
+            self.select_match(0, self.last_pattern.len());
+        }
+    }
+
+    fn select_match(&mut self, index: usize, len: usize) {
+        self.current_match = Some(index);
+        self.status = Some(format!(
+            "Match {} of {}{}",
+            index + 1,
+            self.matches.len(),
+            if self.matches.len() == MAX_MATCHES {
+                "+"
+            } else {
+                ""
+            }
+        ));
+        let offset = self.matches[index];
+        self.reveal(offset..offset + len);
+    }
+
+    fn reveal(&mut self, range: Range<usize>) {
+        self.scroll_handle
+            .scroll_to_item(range.start / BYTES_PER_ROW, ScrollStrategy::Center);
+        self.highlight = Some(range);
+    }
+
+    fn set_hovered(&mut self, offset: usize, hovered: bool, cx: &mut Context<Self>) {
+        if hovered {
+            self.hovered = Some(offset);
+        } else if self.hovered == Some(offset) {
+            self.hovered = None;
+        }
+        cx.notify();
+    }
+
+    /// Index of the symbol containing `address`
+    fn symbol_index_at(&self, address: u64) -> Option<usize> {
+        let end = self.symbols.partition_point(|s| s.address <= address);
+        let index = end.checked_sub(1)?;
+        let symbol = &self.symbols[index];
+        (address < symbol.address + symbol.size).then_some(index)
+    }
+
+    fn render_rows(
+        &mut self,
+        range: Range<usize>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Vec<Stateful<Div>> {
+        let Some(data) = self.data.clone() else {
+            return Vec::new();
+        };
+
+        range
+            .map(|row| {
+                let row_start = row * BYTES_PER_ROW;
+                let row_end = (row_start + BYTES_PER_ROW).min(data.len());
+                let bytes = &data[row_start..row_end];
+
+                let mut hex = div().flex().gap_1();
+                let mut ascii = div().flex();
+
+                for (i, &byte) in bytes.iter().enumerate() {
+                    let offset = row_start + i;
+                    let address = self.segment.address + offset as u64;
+                    let symbol_color = self
+                        .symbol_index_at(address)
+                        .map(|index| generate_color(index).opacity(0.25));
+                    let is_highlighted = self
+                        .highlight
+                        .as_ref()
+                        .is_some_and(|range| range.contains(&offset));
+                    let is_hovered = self.hovered == Some(offset);
+
+                    let style_cell = |cell: Stateful<Div>| {
+                        cell.when_some(symbol_color, |d, color| d.bg(color))
+                            .when(is_highlighted, |d| {
+                                d.bg(cx.theme().accent)
+                                    .text_color(cx.theme().accent_foreground)
+                            })
+                            .when(is_hovered, |d| d.border_b_1().border_color(rgb(0xe5c07b)))
+                    };
+
+                    if i == BYTES_PER_ROW / 2 {
+                        hex = hex.child(div().w(px(4.0)));
+                    }
+                    hex = hex.child(
+                        style_cell(div().id(("hex-byte", offset)))
+                            .w(px(18.0))
+                            .child(format!("{:02x}", byte))
+                            .on_hover(cx.listener(move |view, hovered: &bool, _window, cx| {
+                                view.set_hovered(offset, *hovered, cx);
+                            })),
+                    );
+                    ascii = ascii.child(
+                        style_cell(div().id(("ascii-byte", offset)))
+                            .w(px(8.0))
+                            .child(if byte.is_ascii_graphic() || byte == b' ' {
+                                (byte as char).to_string()
+                            } else {
+                                ".".to_string()
+                            })
+                            .on_hover(cx.listener(move |view, hovered: &bool, _window, cx| {
+                                view.set_hovered(offset, *hovered, cx);
+                            })),
+                    );
+                }
+
+                div()
+                    .id(("hex-row", row))
+                    .flex()
+                    .gap_4()
+                    .px_3()
+                    .text_xs()
+                    .font_family("monospace")
+                    .child(
+                        div()
+                            .w(px(80.0))
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!("{:08x}", self.segment.address + row_start as u64)),
+                    )
+                    .child(hex)
+                    .child(ascii)
+            })
+            .collect()
+    }
+
+    /// Containing symbol and offset of the hovered byte
+    fn hover_readout(&self) -> Option<String> {
+        let offset = self.hovered?;
+        let byte = *self.data.as_ref()?.get(offset)?;
+        let address = self.segment.address + offset as u64;
+
+        let symbol = match self.symbol_index_at(address) {
+            Some(index) => {
+                let symbol = &self.symbols[index];
+                format!(
+                    "{}+0x{:x} ({})",
+                    demangle_name(&symbol.name),
+                    address - symbol.address,
+                    format_size(symbol.size)
+                )
+            }
+            None => "no symbol".to_string(),
+        };
+
+        Some(format!(
+            "0x{:08x}  {}+0x{:x}  0x{:02x}  {}",
+            address, self.segment.name, offset, byte, symbol
+        ))
+    }
+}
+
+/// Bytes to search for: hex pairs like `de ad be ef`, or text, forced with quotes
+fn parse_search_pattern(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    if let Some(quoted) = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return (!quoted.is_empty()).then(|| quoted.as_bytes().to_vec());
+    }
+
+    let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let is_hex = digits.len() % 2 == 0 && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if !is_hex {
+        return Some(text.as_bytes().to_vec());
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+impl Render for HexViewPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let row_count = self
+            .data
+            .as_ref()
+            .map_or(0, |data| data.len().div_ceil(BYTES_PER_ROW));
+        let readout = self.hover_readout();
+
+        div()
+            .id("hex_view_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                                "{} @ 0x{:08x} ({}, {} symbols)",
+                                self.segment.name,
+                                self.segment.address,
+                                format_size(self.segment.size),
+                                self.symbols.len()
+                            )),
+                    )
+                    .when_some(self.status.clone(), |d, status| {
+                        d.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(status),
+                        )
+                    }),
+            )
+            .when(self.data.is_some(), |d| {
+                d.child(
+                    // Go-to-address and search
+                    div()
+                        .flex()
+                        .gap_2()
+                        .px_2()
+                        .py_2()
+                        .border_b_1()
+                        .border_color(cx.theme().border)
+                        .child(div().w(px(220.0)).child(Input::new(&self.goto_input)))
+                        .child(div().flex_1().child(Input::new(&self.search_input))),
+                )
+                .child(
+                    uniform_list("hex-rows", row_count, cx.processor(Self::render_rows))
+                        .track_scroll(self.scroll_handle.clone())
+                        .flex_1(),
+                )
+                .child(
+                    // Hover readout
+                    div()
+                        .px_3()
+                        .py_1()
+                        .border_t_1()
+                        .border_color(cx.theme().border)
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(cx.theme().muted_foreground)
+                        .child(readout.unwrap_or_else(|| "Hover a byte for details".to_string())),
+                )
+            })
+            .when(self.data.is_none(), |d| {
+                d.child(
+                    div()
+                        .flex()
+                        .flex_1()
+                        .items_center()
+                        .justify_center()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(match &self.error {
+                            Some(error) => {
+                                format!("Failed to read {}: {}", self.segment.name, error)
+                            }
+                            None => format!(
+                                "{} is a NOBITS section: it takes {} of memory but has no data \
+                                 in the ELF file, and is zero-filled at startup",
+                                self.segment.name,
+                                format_size(self.segment.size)
+                            ),
+                        }),
+                )
+            })
+    }
+}
@@ -1,36 +1,111 @@
 use crate::components::symbols_panel::SymbolsTableDelegate;
 use crate::components::{
-    render_regions_panel, render_sections_panel, DetailsPanel, DwarfDetailsPanel,
-    DwarfSymbolSelectEvent, DwarfTreePanel,
+    render_regions_panel, render_sections_panel, AddressLookupPanel, AddressSelectEvent,
+    CapturePanel, CaptureSiteSelectEvent, CrashPanel, DefmtPanel, DefmtSiteSelectEvent,
+    DetailsPanel, DisassemblyPanel, DwarfDetailsPanel, DwarfSymbolSelectEvent, DwarfTreePanel,
+    HexViewPanel, PanicAuditPanel, RamAdvicePanel, SourcePanel, StackUsagePanel,
 };
+use crate::disasm::disassemble;
+use crate::lint::{run_lints, LintConfig};
 use crate::parser::{
-    get_all_targets, load_memory_layout_from_probe_rs, parse_defmt_info, parse_elf_segments,
-    parse_rtt_info,
+    demangle_name, get_all_targets, load_memory_layout_from_probe_rs, parse_defmt_info,
+    parse_elf_segments, parse_rtt_info, read_section_data,
 };
+use crate::source::PathRemap;
+use crate::stack::analyze_stack;
 use crate::types::{
-    DefmtInfo, DwarfInfo, DwarfSymbol, ElfSymbol, MemoryRegion, MemorySegment, RttInfo,
+    Analysis, CallGraph, DecodedValue, DefmtInfo, Diagnostic, DwarfInfo, DwarfSymbol, DwarfTag,
+    ElfSymbol, LineTable, MemoryRegion, MemorySegment, MemorySnapshot, PanicAudit, RamAdvice,
+    RttInfo, StackInfo, VectorTable, XrefIndex,
 };
+use crate::utils::with_dwarf_statics;
+use crate::vector_table::parse_vector_table;
 use gpui::{prelude::*, *};
 use gpui_component::resizable::{h_resizable, resizable_panel, v_resizable};
 use gpui_component::select::{SearchableVec, Select, SelectEvent, SelectState};
-use gpui_component::table::{Table, TableState};
+use gpui_component::table::{Table, TableEvent, TableState};
 use gpui_component::theme::{Theme, ThemeRegistry};
 use gpui_component::IndexPath;
 use gpui_component::TitleBar;
 use gpui_component::{v_flex, ActiveTheme, Sizable};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::PathBuf;
+use std::sync::Arc;
+
+/// List shown in the bottom panel next to the DWARF details, in place of the symbols of
+/// the selected segment. Only one is open at a time.
+enum ActivePanel {
+    AddressLookup(Entity<AddressLookupPanel>),
+    Crash(Entity<CrashPanel>),
+    StackUsage(Entity<StackUsagePanel>),
+    RamAdvice(Entity<RamAdvicePanel>),
+    PanicAudit(Entity<PanicAuditPanel>),
+    Defmt(Entity<DefmtPanel>),
+    Capture(Entity<CapturePanel>),
+}
+
+impl ActivePanel {
+    fn view(&self) -> AnyView {
+        match self {
+            ActivePanel::AddressLookup(panel) => panel.clone().into(),
+            ActivePanel::Crash(panel) => panel.clone().into(),
+            ActivePanel::StackUsage(panel) => panel.clone().into(),
+            ActivePanel::RamAdvice(panel) => panel.clone().into(),
+            ActivePanel::PanicAudit(panel) => panel.clone().into(),
+            ActivePanel::Defmt(panel) => panel.clone().into(),
+            ActivePanel::Capture(panel) => panel.clone().into(),
+        }
+    }
+}
 
 pub struct MemoryView {
     segments: Vec<MemorySegment>,
     memory_regions: Vec<MemoryRegion>,
     symbols: Vec<ElfSymbol>,
+    /// `symbols` plus statics found only in DWARF, for address lookups in the memory map
+    lookup_symbols: Vec<ElfSymbol>,
     defmt_info: DefmtInfo,
     rtt_info: RttInfo,
+    stack_info: StackInfo,
+    /// Worst-case stack depths, shown per function and ranked in the stack usage panel
+    call_graph: Arc<CallGraph>,
+    /// Who references each address, listed in the DWARF details
+    xrefs: XrefIndex,
+    /// Statics that could move to flash or skip zeroing, listed in the RAM advice panel
+    ram_advice: Arc<Vec<RamAdvice>>,
+    /// Functions pulling in panic and fmt code, listed in the panic audit panel
+    panic_audit: Arc<PanicAudit>,
+    vector_table: VectorTable,
+    interrupt_names: BTreeMap<u32, String>,
+    diagnostics: Vec<Diagnostic>,
+    lint_config: LintConfig,
     dwarf_info: DwarfInfo,
+    line_table: LineTable,
+    /// Substitutions for source paths recorded at build time
+    path_remaps: Vec<PathRemap>,
     selected_segment: Option<usize>,
     selected_dwarf_symbol: Option<DwarfSymbol>,
+    /// DIEs viewed before following type references, most recent last
+    dwarf_history: Vec<u64>,
     symbols_table: Option<Entity<TableState<SymbolsTableDelegate>>>,
+    /// Hex view of the selected segment, shown in place of its symbols table
+    hex_view: Option<Entity<HexViewPanel>>,
+    /// Disassembly of the function picked in the symbols table, shown in its place
+    symbol_disassembly: Option<Entity<DisassemblyPanel>>,
     dwarf_tree_panel: Entity<DwarfTreePanel>,
+    /// Source of the selected DWARF symbol, next to its details
+    source_panel: Option<Entity<SourcePanel>>,
+    /// Disassembly of the selected DWARF function, shown instead of its source
+    disassembly_panel: Option<Entity<DisassemblyPanel>>,
+    show_disassembly: bool,
+    active_panel: Option<ActivePanel>,
+    /// Log viewer of the capture given on the command line, kept while other panels are open
+    capture_panel: Option<Entity<CapturePanel>>,
+    snapshot: Option<Arc<MemorySnapshot>>,
+    /// Static values decoded from the snapshot, keyed by DIE offset
+    snapshot_values: Arc<HashMap<u64, DecodedValue>>,
+    /// Static values as initialized by the ELF image, keyed by DIE offset
+    initial_values: HashMap<u64, DecodedValue>,
     target_select: Entity<SelectState<SearchableVec<String>>>,
     theme_select: Entity<SelectState<SearchableVec<String>>>,
     elf_path: PathBuf,
@@ -45,17 +120,36 @@ impl Focusable for MemoryView {
 
 impl MemoryView {
     pub fn new(
-        segments: Vec<MemorySegment>,
-        memory_regions: Vec<MemoryRegion>,
-        symbols: Vec<ElfSymbol>,
-        defmt_info: DefmtInfo,
-        rtt_info: RttInfo,
-        dwarf_info: DwarfInfo,
+        analysis: Analysis,
+        lint_config: LintConfig,
+        path_remaps: Vec<PathRemap>,
         current_target: Option<String>,
         elf_path: PathBuf,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
+        let Analysis {
+            segments,
+            memory_regions,
+            symbols,
+            defmt_info,
+            rtt_info,
+            stack_info,
+            call_graph,
+            xrefs,
+            ram_advice,
+            panic_audit,
+            vector_table,
+            interrupt_names,
+            diagnostics,
+            dwarf_info,
+            line_table,
+            capture,
+            snapshot,
+            snapshot_values,
+            initial_values,
+        } = analysis;
+
         // Build target list with "None" option at the top
         let mut all_targets = vec!["(No target)".to_string()];
         all_targets.extend(get_all_targets());
@@ -78,11 +172,22 @@ impl MemoryView {
 
         // Create DWARF tree panel
         let dwarf_info_clone = dwarf_info.clone();
-        let dwarf_tree_panel = cx.new(|cx| DwarfTreePanel::new(dwarf_info_clone, window, cx));
+        let snapshot_values = Arc::new(snapshot_values);
+        let tree_values = Arc::clone(&snapshot_values);
+        let dwarf_tree_panel =
+            cx.new(|cx| DwarfTreePanel::new(dwarf_info_clone, tree_values, window, cx));
 
         cx.subscribe(&dwarf_tree_panel, Self::on_dwarf_symbol_select)
             .detach();
 
+        // Create the capture log viewer, shown until the user opens something else
+        let capture_panel =
+            capture.map(|capture| cx.new(|cx| CapturePanel::new(capture, window, cx)));
+        if let Some(capture_panel) = &capture_panel {
+            cx.subscribe(capture_panel, Self::on_capture_site_select)
+                .detach();
+        }
+
         // Create theme selector
         let theme_registry = ThemeRegistry::global(cx);
         let theme_names: Vec<String> = theme_registry
@@ -105,17 +210,42 @@ impl MemoryView {
         cx.subscribe(&theme_select, Self::on_theme_select_event)
             .detach();
 
+        let lookup_symbols = with_dwarf_statics(&symbols, &dwarf_info);
+
         Self {
             segments,
             memory_regions,
             symbols,
+            lookup_symbols,
             defmt_info,
             rtt_info,
+            stack_info,
+            call_graph: Arc::new(call_graph),
+            xrefs,
+            ram_advice: Arc::new(ram_advice),
+            panic_audit: Arc::new(panic_audit),
+            vector_table,
+            interrupt_names,
+            diagnostics,
+            lint_config,
             dwarf_info,
+            line_table,
+            path_remaps,
             selected_segment: None,
             selected_dwarf_symbol: None,
+            dwarf_history: Vec::new(),
             symbols_table: None,
+            hex_view: None,
+            symbol_disassembly: None,
             dwarf_tree_panel,
+            source_panel: None,
+            disassembly_panel: None,
+            show_disassembly: false,
+            active_panel: capture_panel.clone().map(ActivePanel::Capture),
+            capture_panel,
+            snapshot: snapshot.map(Arc::new),
+            snapshot_values,
+            initial_values,
             target_select,
             theme_select,
             elf_path,
@@ -130,12 +260,256 @@ impl MemoryView {
         cx: &mut Context<Self>,
     ) {
         // Clear ELF segment selection so DWARF details panel is shown
-        self.selected_segment = None;
-        self.symbols_table = None;
+        self.clear_segment_selection();
         self.selected_dwarf_symbol = Some(event.symbol.clone());
+        self.open_source(&event.symbol, event.address, cx);
+        if self.show_disassembly {
+            self.open_disassembly(cx);
+        }
         cx.notify();
     }
 
+    /// Show the source lines the code of `symbol` was generated from, or those of
+    /// `address` when it was found by address. Symbols without code show their declaration.
+    fn open_source(&mut self, symbol: &DwarfSymbol, address: Option<u64>, cx: &mut Context<Self>) {
+        let table = &self.line_table;
+        let code = match address.and_then(|address| table.row_at(address)) {
+            Some(row) if row.line > 0 => Some((row.file, BTreeSet::from([row.line]))),
+            _ => table.lines_in(&symbol.ranges),
+        };
+        let selection = match code {
+            Some((file, lines)) => Some((table.files[file].clone(), table.line_bytes(file), lines)),
+            None => symbol.file.clone().zip(symbol.line).map(|(path, line)| {
+                let line_bytes = table
+                    .file_index(&path)
+                    .map(|file| table.line_bytes(file))
+                    .unwrap_or_default();
+                (path, line_bytes, BTreeSet::from([line]))
+            }),
+        };
+
+        let Some((path, line_bytes, lines)) = selection else {
+            self.source_panel = None;
+            return;
+        };
+        let remaps = &self.path_remaps;
+        let source_panel = self
+            .source_panel
+            .get_or_insert_with(|| cx.new(SourcePanel::new));
+        source_panel.update(cx, |panel, cx| {
+            panel.show(&path, remaps, line_bytes, lines, cx);
+        });
+    }
+
+    /// Disassemble the selected function for the panel shown in place of its source
+    fn open_disassembly(&mut self, cx: &mut Context<Self>) {
+        let Some(symbol) = self
+            .selected_dwarf_symbol
+            .as_ref()
+            .filter(|symbol| symbol.tag == DwarfTag::Subprogram && !symbol.ranges.is_empty())
+        else {
+            self.disassembly_panel = None;
+            return;
+        };
+        let disassembly = disassemble(
+            &self.elf_path,
+            &symbol.name,
+            &symbol.ranges,
+            &self.symbols,
+            &self.line_table,
+            &self.path_remaps,
+        );
+        let disassembly_panel = self.disassembly_panel.get_or_insert_with(|| {
+            let panel = cx.new(DisassemblyPanel::new);
+            cx.subscribe(&panel, Self::on_address_select).detach();
+            panel
+        });
+        disassembly_panel.update(cx, |panel, cx| panel.show(disassembly, cx));
+    }
+
+    fn toggle_disassembly(&mut self, cx: &mut Context<Self>) {
+        self.show_disassembly = !self.show_disassembly;
+        if self.show_disassembly {
+            self.open_disassembly(cx);
+        }
+        cx.notify();
+    }
+
+    /// Follow a type reference in the details panel, remembering where we came from
+    fn navigate_to_die(&mut self, die_offset: u64, cx: &mut Context<Self>) {
+        let current = self
+            .selected_dwarf_symbol
+            .as_ref()
+            .and_then(|symbol| symbol.die_offset);
+        let found = self
+            .dwarf_tree_panel
+            .update(cx, |tree, cx| tree.select_by_offset(die_offset, cx));
+        if found {
+            self.dwarf_history.extend(current);
+            cx.notify();
+        }
+    }
+
+    fn navigate_back(&mut self, cx: &mut Context<Self>) {
+        if let Some(die_offset) = self.dwarf_history.pop() {
+            self.dwarf_tree_panel.update(cx, |tree, cx| {
+                tree.select_by_offset(die_offset, cx);
+            });
+            cx.notify();
+        }
+    }
+
+    /// Show `panel` in the bottom panel, closing the one open before and the symbols of
+    /// the selected segment
+    fn set_active_panel(&mut self, panel: Option<ActivePanel>, cx: &mut Context<Self>) {
+        if panel.is_some() {
+            self.clear_segment_selection();
+        }
+        self.active_panel = panel;
+        cx.notify();
+    }
+
+    fn clear_segment_selection(&mut self) {
+        self.selected_segment = None;
+        self.symbols_table = None;
+        self.hex_view = None;
+        self.symbol_disassembly = None;
+    }
+
+    fn toggle_defmt_panel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::Defmt(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let defmt_info = self.defmt_info.clone();
+        let defmt_panel = cx.new(|cx| DefmtPanel::new(defmt_info, window, cx));
+        cx.subscribe(&defmt_panel, Self::on_defmt_site_select)
+            .detach();
+        self.set_active_panel(Some(ActivePanel::Defmt(defmt_panel)), cx);
+    }
+
+    fn toggle_address_lookup(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::AddressLookup(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let elf_path = self.elf_path.clone();
+        let segments = self.segments.clone();
+        let symbols = self.lookup_symbols.clone();
+        let address_lookup =
+            cx.new(|cx| AddressLookupPanel::new(&elf_path, segments, symbols, window, cx));
+        cx.subscribe(&address_lookup, Self::on_address_select)
+            .detach();
+        self.set_active_panel(Some(ActivePanel::AddressLookup(address_lookup)), cx);
+    }
+
+    fn toggle_crash_panel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::Crash(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let elf_path = self.elf_path.clone();
+        let segments = self.segments.clone();
+        let symbols = self.lookup_symbols.clone();
+        let snapshot = self.snapshot.clone();
+        let crash_panel =
+            cx.new(|cx| CrashPanel::new(&elf_path, segments, symbols, snapshot, window, cx));
+        cx.subscribe(&crash_panel, Self::on_address_select).detach();
+        self.set_active_panel(Some(ActivePanel::Crash(crash_panel)), cx);
+    }
+
+    fn toggle_stack_usage_panel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::StackUsage(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let call_graph = Arc::clone(&self.call_graph);
+        let stack_usage_panel = cx.new(|cx| StackUsagePanel::new(call_graph, window, cx));
+        cx.subscribe(&stack_usage_panel, Self::on_address_select)
+            .detach();
+        self.set_active_panel(Some(ActivePanel::StackUsage(stack_usage_panel)), cx);
+    }
+
+    fn toggle_ram_advice_panel(&mut self, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::RamAdvice(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let ram_advice = Arc::clone(&self.ram_advice);
+        let ram_advice_panel = cx.new(|cx| RamAdvicePanel::new(ram_advice, cx));
+        cx.subscribe(&ram_advice_panel, Self::on_address_select)
+            .detach();
+        self.set_active_panel(Some(ActivePanel::RamAdvice(ram_advice_panel)), cx);
+    }
+
+    fn toggle_panic_audit_panel(&mut self, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::PanicAudit(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let panic_audit = Arc::clone(&self.panic_audit);
+        let panic_audit_panel = cx.new(|cx| PanicAuditPanel::new(panic_audit, cx));
+        cx.subscribe(&panic_audit_panel, Self::on_address_select)
+            .detach();
+        self.set_active_panel(Some(ActivePanel::PanicAudit(panic_audit_panel)), cx);
+    }
+
+    /// From the address lookup, a crash backtrace, the stack usage ranking, the RAM advice
+    /// or the panic audit
+    fn on_address_select<T>(
+        &mut self,
+        _: Entity<T>,
+        event: &AddressSelectEvent,
+        cx: &mut Context<Self>,
+    ) {
+        // Show the function covering the address and its source line
+        self.select_reference(event.address, cx);
+    }
+
+    /// From the references of the selected symbol: show the referring function, or the
+    /// static holding a pointer
+    fn select_reference(&mut self, address: u64, cx: &mut Context<Self>) {
+        self.dwarf_tree_panel.update(cx, |tree, cx| {
+            tree.select_address(address, cx);
+        });
+    }
+
+    fn toggle_capture_panel(&mut self, cx: &mut Context<Self>) {
+        if matches!(self.active_panel, Some(ActivePanel::Capture(_))) {
+            self.set_active_panel(None, cx);
+            return;
+        }
+        let panel = self.capture_panel.clone().map(ActivePanel::Capture);
+        self.set_active_panel(panel, cx);
+    }
+
+    fn on_capture_site_select(
+        &mut self,
+        _: Entity<CapturePanel>,
+        event: &CaptureSiteSelectEvent,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(die_offset) = event.site.die_offset {
+            self.dwarf_tree_panel.update(cx, |tree, cx| {
+                tree.select_by_offset(die_offset, cx);
+            });
+        }
+    }
+
+    fn on_defmt_site_select(
+        &mut self,
+        _: Entity<DefmtPanel>,
+        event: &DefmtSiteSelectEvent,
+        cx: &mut Context<Self>,
+    ) {
+        // Jump to the log statement in the DWARF tree, which updates the details panel
+        if let Some(die_offset) = event.site.die_offset {
+            self.dwarf_tree_panel.update(cx, |tree, cx| {
+                tree.select_by_offset(die_offset, cx);
+            });
+        }
+    }
+
     fn on_target_select_event(
         &mut self,
         _: Entity<SelectState<SearchableVec<String>>>,
@@ -188,8 +562,8 @@ impl MemoryView {
             for segment in &mut self.segments {
                 segment.conflicts.clear();
             }
-            self.selected_segment = None;
-            self.symbols_table = None;
+            self.reload_analysis();
+            self.clear_segment_selection();
             cx.notify();
             return;
         }
@@ -198,21 +572,44 @@ impl MemoryView {
             if let Ok(segments) = parse_elf_segments(&self.elf_path, Some(&memory_regions)) {
                 self.memory_regions = memory_regions;
                 self.segments = segments;
-                self.selected_segment = None;
-                self.symbols_table = None;
+                self.reload_analysis();
+                self.clear_segment_selection();
 
-                // Reload defmt and RTT info
+                // Reload defmt info
                 if let Ok(defmt_info) = parse_defmt_info(&self.elf_path) {
                     self.defmt_info = defmt_info;
                 }
-                if let Ok(rtt_info) = parse_rtt_info(&self.elf_path) {
-                    self.rtt_info = rtt_info;
-                }
                 cx.notify();
             }
         }
     }
 
+    /// Re-run the analyses that depend on the memory regions of the selected target
+    fn reload_analysis(&mut self) {
+        if let Ok(rtt_info) = parse_rtt_info(&self.elf_path, &self.symbols, &self.memory_regions) {
+            self.rtt_info = rtt_info;
+        }
+        self.stack_info = analyze_stack(&self.symbols, &self.segments, &self.memory_regions);
+        if let Ok(vector_table) = parse_vector_table(
+            &self.elf_path,
+            &self.symbols,
+            &self.segments,
+            &self.memory_regions,
+            &self.interrupt_names,
+        ) {
+            self.vector_table = vector_table;
+        }
+        if let Ok(diagnostics) = run_lints(
+            &self.elf_path,
+            &self.segments,
+            &self.memory_regions,
+            &self.vector_table,
+            &self.lint_config,
+        ) {
+            self.diagnostics = diagnostics;
+        }
+    }
+
     fn on_segment_click(
         &mut self,
         idx: usize,
@@ -222,9 +619,10 @@ impl MemoryView {
     ) {
         // Toggle the selected segment (click again to close)
         if self.selected_segment == Some(idx) {
-            self.selected_segment = None;
-            self.symbols_table = None;
+            self.clear_segment_selection();
         } else {
+            self.set_active_panel(None, cx);
+            self.clear_segment_selection();
             self.selected_segment = Some(idx);
 
             // Filter symbols for the selected segment
@@ -232,7 +630,7 @@ impl MemoryView {
                 let segment_start = segment.address;
                 let segment_end = segment.address + segment.size;
                 let filtered_symbols: Vec<ElfSymbol> = self
-                    .symbols
+                    .lookup_symbols
                     .iter()
                     .filter(|s| s.address >= segment_start && s.address < segment_end)
                     .cloned()
@@ -240,17 +638,147 @@ impl MemoryView {
 
                 // Create or update the table with the filtered symbols
                 let delegate = SymbolsTableDelegate::new(filtered_symbols);
-                self.symbols_table = Some(cx.new(|cx| {
+                let executable = segment.flags.contains('X');
+                let symbols_table = cx.new(|cx| {
                     TableState::new(delegate, window, cx)
-                        .row_selectable(false)
+                        .row_selectable(executable)
                         .col_selectable(false)
                         .sortable(true)
-                }));
+                });
+                cx.subscribe(&symbols_table, Self::on_symbol_row_select)
+                    .detach();
+                self.symbols_table = Some(symbols_table);
             }
         }
         cx.notify();
     }
 
+    /// Disassemble a function picked in the symbols table of an executable section
+    fn on_symbol_row_select(
+        &mut self,
+        table: Entity<TableState<SymbolsTableDelegate>>,
+        event: &TableEvent,
+        cx: &mut Context<Self>,
+    ) {
+        let TableEvent::SelectRow(row) = event else {
+            return;
+        };
+        let Some(symbol) = table.read(cx).delegate().symbols.get(*row).cloned() else {
+            return;
+        };
+        if symbol.size == 0 {
+            return;
+        }
+        let disassembly = disassemble(
+            &self.elf_path,
+            &demangle_name(&symbol.name),
+            &[(symbol.address, symbol.address + symbol.size)],
+            &self.symbols,
+            &self.line_table,
+            &self.path_remaps,
+        );
+        let panel = cx.new(DisassemblyPanel::new);
+        cx.subscribe(&panel, Self::on_address_select).detach();
+        panel.update(cx, |panel, cx| panel.show(disassembly, cx));
+        self.hex_view = None;
+        self.symbol_disassembly = Some(panel);
+        cx.notify();
+    }
+
+    fn toggle_hex_view(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.symbol_disassembly.take().is_some() {
+            cx.notify();
+            return;
+        }
+        if self.hex_view.take().is_none() {
+            let Some(segment) = self.selected_segment.and_then(|idx| self.segments.get(idx)) else {
+                return;
+            };
+            let data = read_section_data(&self.elf_path, &segment.name);
+            let segment = segment.clone();
+            let symbols = &self.lookup_symbols;
+            self.hex_view =
+                Some(cx.new(|cx| HexViewPanel::new(segment, data, symbols, window, cx)));
+        }
+        cx.notify();
+    }
+
+    /// Whether the selected DWARF symbol is a function with code to disassemble
+    fn selected_has_code(&self) -> bool {
+        self.selected_dwarf_symbol
+            .as_ref()
+            .is_some_and(|symbol| symbol.tag == DwarfTag::Subprogram && !symbol.ranges.is_empty())
+    }
+
+    /// Details of the selected DWARF symbol: its values in the memory snapshot and the
+    /// ELF image, where it is placed, its stack usage and who references it
+    fn dwarf_details_panel(&self, cx: &Context<Self>) -> DwarfDetailsPanel {
+        let selected_offset = self
+            .selected_dwarf_symbol
+            .as_ref()
+            .and_then(|symbol| symbol.die_offset);
+        let snapshot_value =
+            selected_offset.and_then(|offset| self.snapshot_values.get(&offset).cloned());
+        let initial_value =
+            selected_offset.and_then(|offset| self.initial_values.get(&offset).cloned());
+        let selected_address = self
+            .selected_dwarf_symbol
+            .as_ref()
+            .and_then(|symbol| symbol.address);
+        let selected_section = selected_address.and_then(|address| {
+            self.segments
+                .iter()
+                .find(|s| address >= s.address && address < s.address + s.size)
+                .map(|s| s.name.clone())
+        });
+        let selected_region = selected_address.and_then(|address| {
+            self.memory_regions
+                .iter()
+                .find(|r| address >= r.start && address < r.start + r.size)
+                .map(|r| r.name.clone())
+        });
+        let selected_function =
+            selected_address.and_then(|address| self.call_graph.function_at(address & !1));
+        let references = self.selected_dwarf_symbol.as_ref().and_then(|symbol| {
+            let address = symbol.address? & !1;
+            let size = symbol
+                .size
+                .or_else(|| symbol.ranges.first().map(|(start, end)| end - start))
+                .unwrap_or(0);
+            Some(self.xrefs.to_range(address, size).to_vec())
+        });
+
+        DwarfDetailsPanel::new(self.selected_dwarf_symbol.clone())
+            .snapshot_value(snapshot_value)
+            .initial_value(initial_value)
+            .placement(selected_section, selected_region)
+            .stack_usage(Arc::clone(&self.call_graph), selected_function)
+            .when_some(references, |panel, references| {
+                panel.references(
+                    references,
+                    cx.listener(|view, address: &u64, _, cx| {
+                        view.select_reference(*address, cx);
+                    }),
+                )
+            })
+            .on_navigate(cx.listener(|view, offset: &u64, _, cx| {
+                view.navigate_to_die(*offset, cx);
+            }))
+            .when(!self.dwarf_history.is_empty(), |panel| {
+                panel.on_back(cx.listener(|view, _: &MouseUpEvent, _, cx| {
+                    view.navigate_back(cx);
+                }))
+            })
+            .when(self.selected_has_code(), |panel| {
+                panel.on_toggle_disassembly(
+                    self.show_disassembly,
+                    cx.listener(|view, _: &MouseUpEvent, _, cx| {
+                        view.toggle_disassembly(cx);
+                    }),
+                )
+            })
+    }
+
     fn calculate_scale_factor(
         &self,
         total_size: u64,
@@ -326,6 +854,28 @@ impl MemoryView {
     }
 }
 
+/// Small outlined button that opens or closes a panel, labelled `(open, close)` by whether
+/// the panel is open
+fn panel_toggle_button(
+    id: &'static str,
+    (open_label, close_label): (&'static str, &'static str),
+    is_open: bool,
+    cx: &App,
+    on_click: impl Fn(&MouseUpEvent, &mut Window, &mut App) + 'static,
+) -> Stateful<Div> {
+    div()
+        .id(id)
+        .px_2()
+        .border_1()
+        .border_color(cx.theme().border)
+        .rounded_md()
+        .text_xs()
+        .cursor_pointer()
+        .hover(|d| d.bg(cx.theme().list_hover))
+        .child(if is_open { close_label } else { open_label })
+        .on_mouse_up(MouseButton::Left, on_click)
+}
+
 impl Render for MemoryView {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let padding = 20.0;
@@ -377,7 +927,57 @@ impl Render for MemoryView {
         );
 
         // Check if we have a bottom panel to show
-        let has_bottom_panel = self.symbols_table.is_some() || self.selected_dwarf_symbol.is_some();
+        let mut details_panel = DetailsPanel::new(
+            self.defmt_info.clone(),
+            self.rtt_info.clone(),
+            self.stack_info.clone(),
+            self.vector_table.clone(),
+            self.diagnostics.clone(),
+            self.segments.clone(),
+            selected_segment,
+            total_size,
+        )
+        .on_show_log_sites(cx.listener(|view, _: &MouseUpEvent, window, cx| {
+            view.toggle_defmt_panel(window, cx);
+        }));
+        if let Some(capture_panel) = &self.capture_panel {
+            let capture = capture_panel.read(cx).capture();
+            let summary = format!(
+                "{} ({} entries, {} errors)",
+                capture.file_name,
+                capture.entries.len(),
+                capture.errors.len()
+            );
+            details_panel = details_panel.on_show_capture(
+                summary,
+                cx.listener(|view, _: &MouseUpEvent, _window, cx| {
+                    view.toggle_capture_panel(cx);
+                }),
+            );
+        }
+        if let Some(snapshot) = &self.snapshot {
+            details_panel =
+                details_panel.snapshot(Arc::clone(snapshot), self.snapshot_values.len());
+        }
+
+        let has_code = self.selected_has_code();
+
+        // Source or disassembly shown next to the DWARF details
+        let code_panel: Option<AnyView> = match (&self.disassembly_panel, &self.source_panel) {
+            (Some(disassembly_panel), _) if self.show_disassembly && has_code => {
+                Some(disassembly_panel.clone().into())
+            }
+            (_, Some(source_panel)) => Some(source_panel.clone().into()),
+            _ => None,
+        };
+
+        // List shown next to the DWARF details: address lookup, crash analysis, stack usage,
+        // RAM advice, panic audit, defmt log sites or the capture
+        let log_panel = self.active_panel.as_ref().map(ActivePanel::view);
+
+        let has_bottom_panel = self.symbols_table.is_some()
+            || log_panel.is_some()
+            || self.selected_dwarf_symbol.is_some();
 
         // Check if we have a target selected (i.e., memory regions to show)
         let has_target = !self.memory_regions.is_empty();
@@ -395,7 +995,53 @@ impl Render for MemoryView {
                             .flex()
                             .items_center()
                             .justify_end()
+                            .gap_2()
                             .w_full()
+                            .child(panel_toggle_button(
+                                "toggle_address_lookup",
+                                ("Address lookup", "Close lookup"),
+                                matches!(self.active_panel, Some(ActivePanel::AddressLookup(_))),
+                                cx,
+                                cx.listener(|view, _: &MouseUpEvent, window, cx| {
+                                    view.toggle_address_lookup(window, cx);
+                                }),
+                            ))
+                            .child(panel_toggle_button(
+                                "toggle_crash_panel",
+                                ("Crash analysis", "Close crash"),
+                                matches!(self.active_panel, Some(ActivePanel::Crash(_))),
+                                cx,
+                                cx.listener(|view, _: &MouseUpEvent, window, cx| {
+                                    view.toggle_crash_panel(window, cx);
+                                }),
+                            ))
+                            .child(panel_toggle_button(
+                                "toggle_stack_usage_panel",
+                                ("Stack usage", "Close stack usage"),
+                                matches!(self.active_panel, Some(ActivePanel::StackUsage(_))),
+                                cx,
+                                cx.listener(|view, _: &MouseUpEvent, window, cx| {
+                                    view.toggle_stack_usage_panel(window, cx);
+                                }),
+                            ))
+                            .child(panel_toggle_button(
+                                "toggle_ram_advice_panel",
+                                ("RAM advice", "Close RAM advice"),
+                                matches!(self.active_panel, Some(ActivePanel::RamAdvice(_))),
+                                cx,
+                                cx.listener(|view, _: &MouseUpEvent, _, cx| {
+                                    view.toggle_ram_advice_panel(cx);
+                                }),
+                            ))
+                            .child(panel_toggle_button(
+                                "toggle_panic_audit_panel",
+                                ("Panic audit", "Close panic audit"),
+                                matches!(self.active_panel, Some(ActivePanel::PanicAudit(_))),
+                                cx,
+                                cx.listener(|view, _: &MouseUpEvent, _, cx| {
+                                    view.toggle_panic_audit_panel(cx);
+                                }),
+                            ))
                             .child(
                                 div()
                                     .w(px(200.0))
@@ -494,13 +1140,7 @@ impl Render for MemoryView {
                                                             padding,
                                                         ))
                                                     })
-                                                    .child(DetailsPanel::new(
-                                                        self.defmt_info.clone(),
-                                                        self.rtt_info.clone(),
-                                                        self.segments.clone(),
-                                                        selected_segment,
-                                                        total_size,
-                                                    ))
+                                                    .child(details_panel)
                                             )
                                     )
                             )
@@ -526,6 +1166,9 @@ impl Render for MemoryView {
                                             .child(
                                                 // Header
                                                 gpui::div()
+                                                    .flex()
+                                                    .items_center()
+                                                    .justify_between()
                                                     .px_3()
                                                     .py_2()
                                                     .border_b_1()
@@ -538,26 +1181,106 @@ impl Render for MemoryView {
                                                             .text_color(cx.theme().muted_foreground)
                                                             .child(format!("Symbols in {} ({} total)", segment.name, symbols_count))
                                                     )
+                                                    .child(panel_toggle_button(
+                                                        "toggle_hex_view",
+                                                        ("Hex view", "Symbols"),
+                                                        self.hex_view.is_some() || self.symbol_disassembly.is_some(),
+                                                        cx,
+                                                        cx.listener(|view, _: &MouseUpEvent, window, cx| {
+                                                            view.toggle_hex_view(window, cx);
+                                                        }),
+                                                    ))
                                             )
+                                            .map(|d| match (self.hex_view.as_ref(), self.symbol_disassembly.as_ref()) {
+                                                (Some(hex_view), _) => d.child(hex_view.clone()),
+                                                (None, Some(disassembly)) => d.child(disassembly.clone()),
+                                                (None, None) => d.child(
+                                                    Table::new(table_state).stripe(true).bordered(false)
+                                                ),
+                                            })
+                                    )
+                            )
+                        } else if let Some(log_panel) = log_panel {
+                            // Log sites, capture or address lookup, next to the details and source of the selection
+                            group.child(
+                                resizable_panel()
+                                    .size(px(400.0))
+                                    .size_range(px(400.0)..px(800.0))
+                                    .child(
+                                        h_resizable("log-h-resizable")
                                             .child(
-                                                Table::new(table_state).stripe(true).bordered(false)
+                                                resizable_panel().child(
+                                                    div()
+                                                        .size_full()
+                                                        .border_t_1()
+                                                        .border_color(cx.theme().border)
+                                                        .child(log_panel)
+                                                )
+                                            )
+                                            .when(self.selected_dwarf_symbol.is_some(), |d| {
+                                                d.child(
+                                                    resizable_panel()
+                                                        .size(px(450.0))
+                                                        .child(
+                                                            gpui_component::v_flex()
+                                                                .size_full()
+                                                                .border_t_1()
+                                                                .border_l_1()
+                                                                .border_color(cx.theme().border)
+                                                                .child(self.dwarf_details_panel(cx))
+                                                        )
+                                                )
+                                            })
+                                            .when_some(
+                                                code_panel.clone().filter(|_| self.selected_dwarf_symbol.is_some()),
+                                                |d, code_panel| {
+                                                    d.child(
+                                                        resizable_panel()
+                                                            .size(px(500.0))
+                                                            .child(
+                                                                div()
+                                                                    .size_full()
+                                                                    .border_t_1()
+                                                                    .border_l_1()
+                                                                    .border_color(cx.theme().border)
+                                                                    .child(code_panel)
+                                                            )
+                                                    )
+                                                },
                                             )
                                     )
                             )
                         } else if self.selected_dwarf_symbol.is_some() {
-                            // DWARF symbol selected - show details panel at bottom
+                            // DWARF symbol selected - show details panel at bottom, next to its source
                             group.child(
                                 resizable_panel()
                                     .size(px(400.0))
                                     .size_range(px(400.0)..px(800.0))
                                     .child(
-                                        gpui_component::v_flex()
-                                            .size_full()
-                                            .border_t_1()
-                                            .border_color(cx.theme().border)
-                                            .child(DwarfDetailsPanel::new(
-                                                self.selected_dwarf_symbol.clone(),
-                                            ))
+                                        h_resizable("dwarf-h-resizable")
+                                            .child(
+                                                resizable_panel().child(
+                                                    gpui_component::v_flex()
+                                                        .size_full()
+                                                        .border_t_1()
+                                                        .border_color(cx.theme().border)
+                                                        .child(self.dwarf_details_panel(cx))
+                                                )
+                                            )
+                                            .when_some(code_panel, |d, code_panel| {
+                                                d.child(
+                                                    resizable_panel()
+                                                        .size(px(600.0))
+                                                        .child(
+                                                            div()
+                                                                .size_full()
+                                                                .border_t_1()
+                                                                .border_l_1()
+                                                                .border_color(cx.theme().border)
+                                                                .child(code_panel)
+                                                        )
+                                                )
+                                            })
                                     )
                             )
                         } else {
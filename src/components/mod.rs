@@ -1,19 +1,39 @@
 // mod defmt_section; // No longer used - replaced with DescriptionList
 // mod rtt_section; // No longer used - replaced with DescriptionList
+mod address_lookup_panel;
+mod capture_panel;
+mod crash_panel;
+mod defmt_panel;
 mod details_panel;
+mod disassembly_panel;
 mod dwarf_details_panel;
 mod dwarf_tree_panel;
+mod hex_view_panel;
 mod memory_view;
+mod panic_audit_panel;
+mod ram_advice_panel;
 mod regions_panel;
 mod sections_panel;
+mod source_panel;
+mod stack_usage_panel;
 pub mod symbols_panel;
 // pub mod target_selector; // No longer used - replaced with gpui-component Select
 
 // pub use defmt_section::DefmtSection;
 // pub use rtt_section::RttSection;
+pub use address_lookup_panel::{AddressLookupPanel, AddressSelectEvent};
+pub use capture_panel::{CapturePanel, CaptureSiteSelectEvent};
+pub use crash_panel::CrashPanel;
+pub use defmt_panel::{DefmtPanel, DefmtSiteSelectEvent};
 pub use details_panel::DetailsPanel;
+pub use disassembly_panel::DisassemblyPanel;
 pub use dwarf_details_panel::DwarfDetailsPanel;
 pub use dwarf_tree_panel::{DwarfSymbolSelectEvent, DwarfTreePanel};
+pub use hex_view_panel::HexViewPanel;
 pub use memory_view::MemoryView;
+pub use panic_audit_panel::PanicAuditPanel;
+pub use ram_advice_panel::RamAdvicePanel;
 pub use regions_panel::render_regions_panel;
 pub use sections_panel::render_sections_panel;
+pub use source_panel::SourcePanel;
+pub use stack_usage_panel::StackUsagePanel;
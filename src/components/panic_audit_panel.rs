@@ -0,0 +1,183 @@
+use crate::components::AddressSelectEvent;
+use crate::types::{Machinery, PanicAudit};
+use crate::utils::format_size;
+use gpui::{prelude::*, *};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::sync::Arc;
+
+impl EventEmitter<AddressSelectEvent> for PanicAuditPanel {}
+
+/// Functions outside the standard library that pull in panic or fmt code
+pub struct PanicAuditPanel {
+    audit: Arc<PanicAudit>,
+    selected_row: Option<usize>,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for PanicAuditPanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl PanicAuditPanel {
+    pub fn new(audit: Arc<PanicAudit>, cx: &mut Context<Self>) -> Self {
+        Self {
+            audit,
+            selected_row: None,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn select_row(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.selected_row = Some(index);
+        cx.emit(AddressSelectEvent {
+            address: self.audit.entries[index].address,
+        });
+        cx.notify();
+    }
+
+    fn render_row(&self, index: usize, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_row == Some(index);
+        let entry = &self.audit.entries[index];
+
+        div()
+            .id(("panic-audit-row", index))
+            .flex()
+            .flex_col()
+            .w_full()
+            .px_2()
+            .py_1()
+            .cursor_pointer()
+            .rounded_sm()
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .w(px(48.0))
+                            .flex_shrink_0()
+                            .text_xs()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(match entry.machinery {
+                                Machinery::Panic => rgb(0xe06c75),
+                                Machinery::Fmt => rgb(0xe5c07b),
+                            })
+                            .child(entry.machinery.display_name()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .font_family("monospace")
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(entry.function.clone()),
+                    ),
+            )
+            .when(!entry.path.is_empty(), |d| {
+                d.child(
+                    div()
+                        .pl(px(56.0))
+                        .text_xs()
+                        .font_family("monospace")
+                        .text_color(cx.theme().muted_foreground)
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .child(format!("via {}", entry.path.join(" -> "))),
+                )
+            })
+            .children(entry.locations.iter().map(|&location| {
+                let location = &self.audit.locations[location];
+                div()
+                    .pl(px(56.0))
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(cx.theme().muted_foreground)
+                    .child(format!(
+                        "at {}:{}:{}",
+                        location.file, location.line, location.column
+                    ))
+            }))
+    }
+}
+
+impl Render for PanicAuditPanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let audit = Arc::clone(&self.audit);
+
+        div()
+            .id("panic_audit_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("Panic and fmt Audit"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "panic {} in {} functions, fmt {} in {} functions, {} locations",
+                                        format_size(audit.panic_size),
+                                        audit.panic_functions,
+                                        format_size(audit.fmt_size),
+                                        audit.fmt_functions,
+                                        audit.locations.len()
+                                    )),
+                            ),
+                    ),
+            )
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .when(audit.entries.is_empty(), |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_sm()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("No code outside the standard library reaches panic or fmt code"),
+                            )
+                        })
+                        .children((0..audit.entries.len()).map(|index| {
+                            self.render_row(index, cx).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |view, _event, _window, cx| {
+                                    view.select_row(index, cx);
+                                }),
+                            )
+                        })),
+                ),
+            )
+    }
+}
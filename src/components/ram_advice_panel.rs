@@ -0,0 +1,181 @@
+use crate::components::AddressSelectEvent;
+use crate::types::{RamAdvice, RamAdviceKind};
+use crate::utils::format_size;
+use gpui::{prelude::*, *};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::sync::Arc;
+
+impl EventEmitter<AddressSelectEvent> for RamAdvicePanel {}
+
+/// Statics that could move to flash or skip zeroing, largest savings first
+pub struct RamAdvicePanel {
+    advice: Arc<Vec<RamAdvice>>,
+    selected_row: Option<usize>,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for RamAdvicePanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl RamAdvicePanel {
+    pub fn new(advice: Arc<Vec<RamAdvice>>, cx: &mut Context<Self>) -> Self {
+        Self {
+            advice,
+            selected_row: None,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    fn select_row(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.selected_row = Some(index);
+        cx.emit(AddressSelectEvent {
+            address: self.advice[index].address,
+        });
+        cx.notify();
+    }
+
+    fn render_row(&self, index: usize, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_row == Some(index);
+        let advice = &self.advice[index];
+        let location = match (&advice.file, advice.line) {
+            (Some(file), Some(line)) => {
+                let file_name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+                format!("{}:{}", file_name, line)
+            }
+            _ => advice.section.clone(),
+        };
+
+        div()
+            .id(("ram-advice-row", index))
+            .flex()
+            .flex_col()
+            .w_full()
+            .px_2()
+            .py_1()
+            .cursor_pointer()
+            .rounded_sm()
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .child(
+                        div()
+                            .w(px(90.0))
+                            .flex_shrink_0()
+                            .text_sm()
+                            .font_family("monospace")
+                            .child(format_size(advice.size)),
+                    )
+                    .child(
+                        div()
+                            .w(px(80.0))
+                            .flex_shrink_0()
+                            .text_xs()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(match advice.kind {
+                                RamAdviceKind::ReadOnly => rgb(0x98c379),
+                                RamAdviceKind::Uninit => rgb(0xe5c07b),
+                            })
+                            .child(advice.kind.display_name()),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .font_family("monospace")
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(advice.name.clone()),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(location),
+                    ),
+            )
+            .child(
+                div()
+                    .pl(px(98.0))
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(advice.suggestion()),
+            )
+    }
+}
+
+impl Render for RamAdvicePanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let saving: u64 = self.advice.iter().map(RamAdvice::ram_saving).sum();
+
+        div()
+            .id("ram_advice_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("RAM Reduction"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!("{} could be saved", format_size(saving))),
+                            ),
+                    ),
+            )
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .when(self.advice.is_empty(), |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_sm()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("No read-only statics or large zeroed arrays in RAM"),
+                            )
+                        })
+                        .children((0..self.advice.len()).map(|index| {
+                            self.render_row(index, cx).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |view, _event, _window, cx| {
+                                    view.select_row(index, cx);
+                                }),
+                            )
+                        })),
+                ),
+            )
+    }
+}
@@ -0,0 +1,210 @@
+use crate::source::{read_source_lines, resolve_source_path, PathRemap};
+use crate::utils::format_size;
+use gpui::{prelude::*, *};
+use gpui_component::ActiveTheme;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Source file of the selected symbol, with the lines its code comes from
+/// highlighted and the bytes of code generated for each line in the gutter
+pub struct SourcePanel {
+    /// Path as recorded at build time
+    path: String,
+    /// Where the file was read from, `None` if it could not be found
+    resolved: Option<PathBuf>,
+    /// Why the resolved file could not be read
+    error: Option<String>,
+    lines: Arc<Vec<String>>,
+    highlighted: BTreeSet<u32>,
+    line_bytes: BTreeMap<u32, u64>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for SourcePanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl SourcePanel {
+    pub fn new(cx: &mut Context<Self>) -> Self {
+        Self {
+            path: String::new(),
+            resolved: None,
+            error: None,
+            lines: Arc::new(Vec::new()),
+            highlighted: BTreeSet::new(),
+            line_bytes: BTreeMap::new(),
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    /// Show `path` scrolled to the first highlighted line. The file is only read
+    /// again when it is not the one already shown.
+    pub fn show(
+        &mut self,
+        path: &str,
+        remaps: &[PathRemap],
+        line_bytes: BTreeMap<u32, u64>,
+        highlighted: BTreeSet<u32>,
+        cx: &mut Context<Self>,
+    ) {
+        if path != self.path {
+            self.path = path.to_string();
+            self.resolved = resolve_source_path(path, remaps);
+            let lines = self.resolved.as_deref().map(read_source_lines);
+            self.error = match &lines {
+                Some(Err(e)) => Some(format!("{:#}", e)),
+                _ => None,
+            };
+            self.lines = Arc::new(lines.and_then(Result::ok).unwrap_or_default());
+        }
+
+        if let Some(&first) = highlighted.first() {
+            self.scroll_handle
+                .scroll_to_item(first.saturating_sub(1) as usize, ScrollStrategy::Center);
+        }
+        self.highlighted = highlighted;
+        self.line_bytes = line_bytes;
+        cx.notify();
+    }
+
+    fn render_rows(
+        &mut self,
+        range: Range<usize>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Vec<Stateful<Div>> {
+        range
+            .map(|index| {
+                let line = index as u32 + 1;
+                let is_highlighted = self.highlighted.contains(&line);
+                let bytes = self.line_bytes.get(&line).copied();
+
+                div()
+                    .id(("source-line", index))
+                    .flex()
+                    .gap_3()
+                    .px_3()
+                    .text_xs()
+                    .font_family("monospace")
+                    .when(is_highlighted, |d| d.bg(cx.theme().accent.opacity(0.3)))
+                    .child(
+                        div()
+                            .w(px(48.0))
+                            .flex()
+                            .justify_end()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(line.to_string()),
+                    )
+                    .child(
+                        div()
+                            .w(px(56.0))
+                            .flex()
+                            .justify_end()
+                            .text_color(rgb(0xe5c07b))
+                            .children(bytes.map(format_size)),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .whitespace_nowrap()
+                            .child(self.lines[index].clone()),
+                    )
+            })
+            .collect()
+    }
+}
+
+impl Render for SourcePanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let file_name = self
+            .path
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(&self.path)
+            .to_string();
+        let total_bytes: u64 = self.line_bytes.values().sum();
+        let remapped = self
+            .resolved
+            .as_ref()
+            .filter(|resolved| resolved.to_string_lossy() != self.path);
+
+        div()
+            .id("source_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .flex()
+                    .flex_col()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(cx.theme().muted_foreground)
+                            .child(format!(
+                                "{} ({} lines highlighted, {} of code in file)",
+                                file_name,
+                                self.highlighted.len(),
+                                format_size(total_bytes)
+                            )),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(match remapped {
+                                Some(resolved) => {
+                                    format!("{} (built as {})", resolved.display(), self.path)
+                                }
+                                None => self.path.clone(),
+                            }),
+                    ),
+            )
+            .map(|d| {
+                let message = match (&self.resolved, &self.error) {
+                    (Some(_), None) => {
+                        return d.child(
+                            uniform_list(
+                                "source-lines",
+                                self.lines.len(),
+                                cx.processor(Self::render_rows),
+                            )
+                            .track_scroll(self.scroll_handle.clone())
+                            .flex_1(),
+                        );
+                    }
+                    (Some(_), Some(error)) => error.clone(),
+                    (None, _) => format!(
+                        "{} was not found on this machine. If the firmware was built \
+                         elsewhere, e.g. in a CI container, point its build path at a \
+                         local checkout with --remap-path-prefix FROM=TO",
+                        self.path
+                    ),
+                };
+                d.child(
+                    div()
+                        .flex()
+                        .flex_1()
+                        .items_center()
+                        .justify_center()
+                        .p_4()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child(message),
+                )
+            })
+    }
+}
@@ -0,0 +1,290 @@
+use crate::components::AddressSelectEvent;
+use crate::types::{CallGraph, StackBound};
+use crate::utils::format_stack_depth;
+use gpui::{prelude::*, *};
+use gpui_component::input::{Input, InputEvent, InputState};
+use gpui_component::scroll::ScrollbarAxis;
+use gpui_component::{ActiveTheme, StyledExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+impl EventEmitter<AddressSelectEvent> for StackUsagePanel {}
+
+/// A function in the ranking, either as a root or on its own
+struct StackUsageRow {
+    /// Root name for reset, handlers, address-taken and uncalled functions
+    root: Option<String>,
+    function: usize,
+    /// Worst case including the exception frame of a root
+    depth: u64,
+}
+
+/// Roots and functions ranked by worst-case stack depth
+pub struct StackUsagePanel {
+    call_graph: Arc<CallGraph>,
+    rows: Vec<StackUsageRow>,
+    selected_row: Option<usize>,
+    search_input: Entity<InputState>,
+    search_query: String,
+    /// Indices into `rows` matching the current search
+    filtered: Vec<usize>,
+    focus_handle: FocusHandle,
+}
+
+impl Focusable for StackUsagePanel {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl StackUsagePanel {
+    pub fn new(call_graph: Arc<CallGraph>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Search functions..."));
+
+        cx.subscribe(&search_input, Self::on_search_input).detach();
+
+        // Vector table roots first, then every function by its own worst case
+        let mut rows: Vec<StackUsageRow> = call_graph
+            .ranked_roots()
+            .into_iter()
+            .filter(|root| root.vector_index.is_some())
+            .map(|root| StackUsageRow {
+                root: Some(root.name.clone()),
+                function: root.function,
+                depth: call_graph.root_depth(root),
+            })
+            .collect();
+        // Other roots are labelled by why they are one, e.g. their address is taken
+        let mut labels: HashMap<usize, String> = call_graph
+            .roots
+            .iter()
+            .filter(|root| root.vector_index.is_none())
+            .map(|root| (root.function, root.name.to_lowercase()))
+            .collect();
+        let mut functions: Vec<StackUsageRow> = (0..call_graph.functions.len())
+            .map(|function| StackUsageRow {
+                root: labels.remove(&function),
+                function,
+                depth: call_graph.functions[function].worst_case,
+            })
+            .collect();
+        functions.sort_by_key(|row| std::cmp::Reverse(row.depth));
+        rows.extend(functions);
+
+        let mut panel = Self {
+            call_graph,
+            rows,
+            selected_row: None,
+            search_input,
+            search_query: String::new(),
+            filtered: Vec::new(),
+            focus_handle: cx.focus_handle(),
+        };
+        panel.apply_filter();
+        panel
+    }
+
+    fn on_search_input(
+        &mut self,
+        input: Entity<InputState>,
+        event: &InputEvent,
+        cx: &mut Context<Self>,
+    ) {
+        if let InputEvent::Change = event {
+            self.search_query = input.read(cx).text().to_string();
+            self.apply_filter();
+            cx.notify();
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        let query = self.search_query.to_lowercase();
+        let functions = &self.call_graph.functions;
+
+        self.filtered = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| {
+                query.is_empty()
+                    || functions[row.function].name.to_lowercase().contains(&query)
+                    || row
+                        .root
+                        .as_ref()
+                        .is_some_and(|root| root.to_lowercase().contains(&query))
+            })
+            .map(|(index, _)| index)
+            .collect();
+    }
+
+    fn select_row(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.selected_row = Some(index);
+        let address = self.call_graph.functions[self.rows[index].function].address;
+        cx.emit(AddressSelectEvent { address });
+        cx.notify();
+    }
+
+    fn render_row(&self, index: usize, cx: &App) -> Stateful<Div> {
+        let is_selected = self.selected_row == Some(index);
+        let row = &self.rows[index];
+        let function = &self.call_graph.functions[row.function];
+        let bound_color: Hsla = match function.bound {
+            StackBound::Exact => cx.theme().foreground,
+            StackBound::LowerBound => rgb(0xe5c07b).into(),
+            StackBound::Unbounded => rgb(0xff4444).into(),
+        };
+
+        div()
+            .id(("stack-usage-row", index))
+            .flex()
+            .items_center()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .cursor_pointer()
+            .rounded_sm()
+            .when(is_selected, |d| {
+                d.bg(cx.theme().accent)
+                    .text_color(cx.theme().accent_foreground)
+            })
+            .when(!is_selected, |d| d.hover(|d| d.bg(cx.theme().list_hover)))
+            .child(
+                div()
+                    .w(px(140.0))
+                    .flex_shrink_0()
+                    .text_sm()
+                    .font_family("monospace")
+                    .text_color(bound_color)
+                    .child(format_stack_depth(row.depth, function.bound)),
+            )
+            .child(
+                div()
+                    .w(px(160.0))
+                    .flex_shrink_0()
+                    .text_xs()
+                    .font_weight(FontWeight::BOLD)
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(row.root.clone().unwrap_or_default()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .text_sm()
+                    .font_family("monospace")
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .child(function.name.clone()),
+            )
+            .child(
+                div()
+                    .w(px(120.0))
+                    .flex_shrink_0()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(match (function.recursive, function.indirect_calls) {
+                        (true, _) => "recursive".to_string(),
+                        (false, 0) => String::new(),
+                        (false, calls) => format!("{} indirect calls", calls),
+                    }),
+            )
+    }
+}
+
+impl Render for StackUsagePanel {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        // Limit rendered rows for performance
+        const MAX_RENDERED: usize = 500;
+        let rows_to_render = &self.filtered[..self.filtered.len().min(MAX_RENDERED)];
+        let truncated = self.filtered.len() > MAX_RENDERED;
+
+        div()
+            .id("stack_usage_panel")
+            .flex()
+            .flex_col()
+            .size_full()
+            .child(
+                // Header
+                div()
+                    .px_3()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .bg(cx.theme().sidebar)
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_sm()
+                                    .font_weight(FontWeight::BOLD)
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child("Worst-case Stack Usage"),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "{} functions",
+                                        self.call_graph.functions.len()
+                                    )),
+                            ),
+                    ),
+            )
+            .child(
+                // Search input
+                div()
+                    .px_2()
+                    .py_2()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(Input::new(&self.search_input)),
+            )
+            .child(
+                div().flex_1().overflow_hidden().child(
+                    div()
+                        .size_full()
+                        .scrollable(ScrollbarAxis::Vertical)
+                        .when(self.call_graph.functions.is_empty(), |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_sm()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(
+                                        "No call graph, only Thumb-2, RISC-V and Xtensa code is analyzed",
+                                    ),
+                            )
+                        })
+                        .children(rows_to_render.iter().map(|&index| {
+                            self.render_row(index, cx).on_mouse_up(
+                                MouseButton::Left,
+                                cx.listener(move |view, _event, _window, cx| {
+                                    view.select_row(index, cx);
+                                }),
+                            )
+                        }))
+                        .when(truncated, |d| {
+                            d.child(
+                                div()
+                                    .px_3()
+                                    .py_2()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(format!(
+                                        "... and {} more (refine the search to see more)",
+                                        self.filtered.len() - MAX_RENDERED
+                                    )),
+                            )
+                        }),
+                ),
+            )
+    }
+}
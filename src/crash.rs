@@ -0,0 +1,283 @@
+use anyhow::{bail, Result};
+
+use crate::symbolize::Symbolizer;
+use crate::types::{CrashFrame, CrashRegisters, CrashReport, MemorySnapshot};
+use crate::unwind::{is_exc_return, Unwinder, LR, PC, SP};
+use crate::utils::parse_hex;
+
+/// Configurable Fault Status Register bits: MMFSR, BFSR and UFSR
+fn cfsr_cause(bit: u32) -> Option<&'static str> {
+    Some(match bit {
+        0 => "IACCVIOL: instruction fetch from a region the MPU forbids, e.g. execute-never",
+        1 => "DACCVIOL: data access violating the MPU configuration",
+        3 => "MUNSTKERR: MPU fault while unstacking on exception return",
+        4 => "MSTKERR: MPU fault while stacking on exception entry, often a stack overflow",
+        5 => "MLSPERR: MPU fault during lazy floating-point state preservation",
+        8 => "IBUSERR: bus error on instruction fetch",
+        9 => "PRECISERR: precise data bus error, caused by the instruction at the stacked PC",
+        10 => "IMPRECISERR: imprecise data bus error, caused by a write before the stacked PC",
+        11 => "UNSTKERR: bus fault while unstacking on exception return",
+        12 => "STKERR: bus fault while stacking on exception entry, often a stack overflow",
+        13 => "LSPERR: bus fault during lazy floating-point state preservation",
+        16 => "UNDEFINSTR: undefined instruction",
+        17 => "INVSTATE: invalid execution state, e.g. a branch without the Thumb bit",
+        18 => "INVPC: invalid EXC_RETURN value on exception return",
+        19 => "NOCP: coprocessor access while it is disabled, e.g. FPU code with the FPU off",
+        20 => "STKOF: stack pointer went below its stack limit register",
+        24 => "UNALIGNED: unaligned access with unaligned trapping enabled",
+        25 => "DIVBYZERO: division by zero with divide-by-zero trapping enabled",
+        _ => return None,
+    })
+}
+
+/// HardFault Status Register bits
+fn hfsr_cause(bit: u32) -> Option<&'static str> {
+    Some(match bit {
+        1 => "VECTTBL: bus fault reading the vector table on exception entry",
+        30 => "FORCED: a configurable fault escalated, its handler is disabled or could not run",
+        31 => "DEBUGEVT: a debug event such as BKPT occurred without a debugger attached",
+        _ => return None,
+    })
+}
+
+const MMARVALID: u32 = 1 << 7;
+const BFARVALID: u32 = 1 << 15;
+
+/// Registers pasted from a fault log, a debugger or a printed `ExceptionFrame`, as
+/// `name value` pairs such as `pc = 0x08001234`, `LR: 08000f1d` or `cfsr 0x8200`.
+/// Values are hex.
+pub fn parse_crash_registers(text: &str) -> Result<CrashRegisters> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+
+    let mut registers = CrashRegisters::default();
+    let mut found = false;
+    for pair in words.windows(2) {
+        let Some(value) = parse_hex(&pair[1]).and_then(|value| u32::try_from(value).ok()) else {
+            continue;
+        };
+        let slot = match pair[0].as_str() {
+            "sp" | "r13" => &mut registers.core[SP],
+            "lr" | "r14" => &mut registers.core[LR],
+            "pc" | "r15" => &mut registers.core[PC],
+            "msp" => &mut registers.msp,
+            "psp" => &mut registers.psp,
+            "xpsr" | "psr" => &mut registers.xpsr,
+            "cfsr" => &mut registers.cfsr,
+            "hfsr" => &mut registers.hfsr,
+            "mmfar" => &mut registers.mmfar,
+            "bfar" => &mut registers.bfar,
+            "exc_return" | "excreturn" => &mut registers.exc_return,
+            name => match name.strip_prefix('r').and_then(|n| n.parse::<usize>().ok()) {
+                Some(index) if index <= 12 => &mut registers.core[index],
+                _ => continue,
+            },
+        };
+        *slot = Some(value);
+        found = true;
+    }
+
+    if !found {
+        bail!("No registers found, expected pairs like `pc = 0x08001234`");
+    }
+    Ok(registers)
+}
+
+/// Readable causes from the fault status and address registers
+pub fn fault_causes(registers: &CrashRegisters) -> Vec<String> {
+    if registers.cfsr.is_none() && registers.hfsr.is_none() {
+        return vec!["No fault status registers given (CFSR, HFSR)".to_string()];
+    }
+    let cfsr = registers.cfsr.unwrap_or(0);
+    let hfsr = registers.hfsr.unwrap_or(0);
+
+    let mut causes: Vec<String> = (0..32)
+        .filter(|bit| cfsr & (1 << bit) != 0)
+        .filter_map(cfsr_cause)
+        .chain(
+            (0..32)
+                .filter(|bit| hfsr & (1 << bit) != 0)
+                .filter_map(hfsr_cause),
+        )
+        .map(str::to_string)
+        .collect();
+
+    for (valid, name, value) in [
+        (MMARVALID, "MMFAR", registers.mmfar),
+        (BFARVALID, "BFAR", registers.bfar),
+    ] {
+        if cfsr & valid != 0 {
+            causes.push(match value {
+                Some(address) => format!("Faulting data address ({}): 0x{:08x}", name, address),
+                None => format!("{} holds the faulting address but was not given", name),
+            });
+        }
+    }
+
+    if causes.is_empty() {
+        causes.push("No fault status bits set".to_string());
+    }
+    causes
+}
+
+/// Decode the fault and unwind from the faulting instruction. Registers read inside
+/// the fault handler, with `EXC_RETURN` in LR, are unwound from the handler; otherwise
+/// they describe the interrupted code and SP is the address of its exception frame.
+pub fn analyze_crash(
+    registers: &CrashRegisters,
+    unwinder: &Unwinder,
+    symbolizer: &Symbolizer,
+    memory: Option<&MemorySnapshot>,
+) -> CrashReport {
+    let in_handler = registers.core[LR].is_some_and(is_exc_return);
+    let exc_return = registers
+        .exc_return
+        .or(registers.core[LR].filter(|_| in_handler));
+    let sp = registers.core[SP].or(match exc_return {
+        Some(exc_return) if exc_return & 0x4 != 0 => registers.psp,
+        _ => registers.msp.or(registers.psp),
+    });
+
+    let mut core = registers.core;
+    core[SP] = sp;
+    let (frames, stop_reason) = match sp {
+        Some(sp) if !in_handler => {
+            match unwinder.unstack(sp, exc_return, &core, registers.xpsr, memory) {
+                Ok(core) => unwinder.unwind(core, registers.psp, memory),
+                Err(reason) => (Vec::new(), reason),
+            }
+        }
+        _ if core[PC].is_some() => unwinder.unwind(core, registers.psp, memory),
+        _ => {
+            let reason = "Neither the stacked PC nor the exception frame address (SP) was given";
+            (Vec::new(), reason.to_string())
+        }
+    };
+
+    CrashReport {
+        causes: fault_causes(registers),
+        frames: frames
+            .into_iter()
+            .map(|frame| CrashFrame {
+                frame,
+                info: symbolizer.lookup(frame.lookup_address()),
+            })
+            .collect(),
+        stop_reason,
+    }
+}
+
+/// Lines for the `--crash` output
+pub fn describe_crash(report: &CrashReport) -> Vec<String> {
+    let mut lines = vec!["Fault:".to_string()];
+    lines.extend(report.causes.iter().map(|cause| format!("  {}", cause)));
+    lines.push(String::new());
+    lines.push("Backtrace:".to_string());
+    for (index, crash_frame) in report.frames.iter().enumerate() {
+        let frame = &crash_frame.frame;
+        lines.push(format!(
+            "  #{:<2} 0x{:08x}  {}{}",
+            index,
+            frame.pc,
+            crash_frame.info.symbol.as_deref().unwrap_or("??"),
+            if frame.exception { "  (exception)" } else { "" }
+        ));
+        let last = crash_frame.info.frames.len().saturating_sub(1);
+        for (depth, inline) in crash_frame.info.frames.iter().enumerate() {
+            lines.push(format!(
+                "        {} at {}{}",
+                inline.function.as_deref().unwrap_or("??"),
+                inline.location(),
+                if depth < last { " (inlined)" } else { "" }
+            ));
+        }
+    }
+    lines.push(format!("  {}", report.stop_reason));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fault_causes, parse_crash_registers};
+    use crate::types::CrashRegisters;
+    use crate::unwind::{LR, PC, SP};
+
+    #[test]
+    fn registers_from_a_fault_log() {
+        let text = "R0 = 0x00000001\nr12: 2000_0010\nSP 0x20007fd0  LR=0xfffffff9\n\
+                    PC: 08001234 xPSR 0x61000000\nCFSR = 0x00008200  BFAR = 0x40021000";
+        let registers = parse_crash_registers(text).unwrap();
+        assert_eq!(registers.core[0], Some(1));
+        assert_eq!(registers.core[12], Some(0x2000_0010));
+        assert_eq!(registers.core[SP], Some(0x2000_7fd0));
+        assert_eq!(registers.core[LR], Some(0xffff_fff9));
+        assert_eq!(registers.core[PC], Some(0x0800_1234));
+        assert_eq!(registers.xpsr, Some(0x6100_0000));
+        assert_eq!(registers.cfsr, Some(0x8200));
+        assert_eq!(registers.bfar, Some(0x4002_1000));
+        assert_eq!(registers.hfsr, None);
+    }
+
+    #[test]
+    fn malformed_dumps_are_rejected() {
+        assert!(parse_crash_registers("").is_err());
+        assert!(parse_crash_registers("HardFault at an unknown address").is_err());
+        // Values must be hex and fit in 32 bits
+        assert!(parse_crash_registers("pc = 0xzzzz").is_err());
+        assert!(parse_crash_registers("pc = 0x1_0000_0000").is_err());
+        assert!(parse_crash_registers("r16 = 0x1").is_err());
+    }
+
+    #[test]
+    fn instruction_access_violation() {
+        let registers = CrashRegisters {
+            cfsr: Some(0x01),
+            ..CrashRegisters::default()
+        };
+        let causes = fault_causes(&registers);
+        assert_eq!(causes.len(), 1);
+        assert!(causes[0].starts_with("IACCVIOL"));
+    }
+
+    #[test]
+    fn precise_bus_error_with_address() {
+        let mut registers = CrashRegisters {
+            cfsr: Some(0x8200),
+            bfar: Some(0x4002_1000),
+            ..CrashRegisters::default()
+        };
+        let causes = fault_causes(&registers);
+        assert_eq!(causes.len(), 2);
+        assert!(causes[0].starts_with("PRECISERR"));
+        assert_eq!(causes[1], "Faulting data address (BFAR): 0x40021000");
+
+        registers.bfar = None;
+        assert_eq!(
+            fault_causes(&registers)[1],
+            "BFAR holds the faulting address but was not given"
+        );
+    }
+
+    #[test]
+    fn forced_hard_fault() {
+        // A precise bus error escalated to a HardFault
+        let registers = CrashRegisters {
+            cfsr: Some(0x0200),
+            hfsr: Some(0x4000_0000),
+            ..CrashRegisters::default()
+        };
+        let causes = fault_causes(&registers);
+        assert_eq!(causes.len(), 2);
+        assert!(causes[0].starts_with("PRECISERR"));
+        assert!(causes[1].starts_with("FORCED"));
+
+        let registers = CrashRegisters::default();
+        assert_eq!(
+            fault_causes(&registers),
+            ["No fault status registers given (CFSR, HFSR)"]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use gimli::ReaderOffset;
+use object::{Object, ObjectSection, ObjectSymbol};
+use std::collections::HashMap;
+
+use crate::parser::{get_string_attr, load_dwarf, static_address};
+use crate::source::file_path;
+use crate::types::{DefmtEncoding, DefmtLevel, DefmtLogSite};
+
+/// Name of the static every defmt logging macro expands to
+const LOG_STATEMENT_STATIC: &str = "DEFMT_LOG_STATEMENT";
+
+/// Decode the interned defmt strings of an ELF file.
+///
+/// defmt stores each string as the name of a symbol in the `.defmt` section,
+/// encoded as JSON; the symbol's address is the index sent over the wire.
+/// Source locations and flash cost come from the DWARF info when available.
+pub fn parse_log_sites(obj: &object::File) -> Result<Vec<DefmtLogSite>> {
+    let Some(defmt_section) = obj.section_by_name(".defmt") else {
+        return Ok(Vec::new());
+    };
+    let section_index = defmt_section.index();
+
+    let mut sites = Vec::new();
+
+    for symbol in obj.symbols() {
+        if symbol.section_index() != Some(section_index) {
+            continue;
+        }
+        let Ok(name) = symbol.name() else {
+            continue;
+        };
+        // Marker symbols like `_defmt_version_` are not JSON
+        let Ok(json) = serde_json::from_str::<serde_json::Value>(name) else {
+            continue;
+        };
+
+        let field = |key: &str| json.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let Some(tag) = field("tag") else {
+            continue;
+        };
+
+        sites.push(DefmtLogSite {
+            index: symbol.address(),
+            level: DefmtLevel::from_tag(&tag),
+            tag,
+            format: field("data").unwrap_or_default(),
+            crate_name: field("crate_name")
+                .or_else(|| field("package"))
+                .unwrap_or_else(|| "<unknown>".to_string()),
+            file: None,
+            line: None,
+            flash_bytes: None,
+            die_offset: None,
+        });
+    }
+
+    sites.sort_by_key(|site| site.index);
+
+    // Missing or broken debug info only costs us the source locations
+    let _ = resolve_locations(obj, &mut sites);
+
+    Ok(sites)
+}
+
+/// Wire encoding selected by the defmt crate features, recorded in a marker symbol
+pub fn parse_encoding(obj: &object::File) -> Option<DefmtEncoding> {
+    obj.symbols().find_map(|symbol| {
+        let name = symbol.name().ok()?;
+        match name.strip_prefix("_defmt_encoding_ = ")? {
+            "raw" => Some(DefmtEncoding::Raw),
+            "rzcobs" => Some(DefmtEncoding::Rzcobs),
+            _ => None,
+        }
+    })
+}
+
+/// Fill in file, line, flash cost and DIE offset from the `DEFMT_LOG_STATEMENT`
+/// statics, whose location is the address of their interned string
+fn resolve_locations(obj: &object::File, sites: &mut [DefmtLogSite]) -> Result<()> {
+    let dwarf = load_dwarf(obj)?;
+
+    let site_by_index: HashMap<u64, usize> = sites
+        .iter()
+        .enumerate()
+        .map(|(i, site)| (site.index, i))
+        .collect();
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+
+        // (site, file index, line) of every log statement in this unit
+        let mut found = Vec::new();
+
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_variable {
+                continue;
+            }
+            if get_string_attr(&dwarf, &unit, entry, gimli::DW_AT_name).as_deref()
+                != Some(LOG_STATEMENT_STATIC)
+            {
+                continue;
+            }
+
+            let Some(index) = static_address(&dwarf, &unit, entry) else {
+                continue;
+            };
+            let Some(&site_idx) = site_by_index.get(&index) else {
+                continue;
+            };
+
+            let file_index = match entry.attr_value(gimli::DW_AT_decl_file)? {
+                Some(gimli::AttributeValue::FileIndex(idx)) => Some(idx),
+                _ => None,
+            };
+            let line = entry
+                .attr_value(gimli::DW_AT_decl_line)?
+                .and_then(|attr| attr.udata_value());
+
+            let site = &mut sites[site_idx];
+            site.die_offset = entry
+                .offset()
+                .to_debug_info_offset(&unit.header)
+                .map(|offset| offset.0.into_u64());
+            site.line = line.map(|line| line as u32);
+            site.file = file_index.and_then(|idx| file_path(&dwarf, &unit, idx));
+
+            if let (Some(file_index), Some(line)) = (file_index, line) {
+                found.push((site_idx, file_index, line));
+            }
+        }
+
+        if found.is_empty() {
+            continue;
+        }
+
+        let line_bytes = code_bytes_per_line(&unit)?;
+        for (site_idx, file_index, line) in found {
+            sites[site_idx].flash_bytes = line_bytes.get(&(file_index, line)).copied();
+        }
+    }
+
+    Ok(())
+}
+
+/// Bytes of code the line table attributes to each (file index, line) of a unit
+fn code_bytes_per_line<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+) -> Result<HashMap<(u64, u64), u64>> {
+    let mut bytes = HashMap::new();
+
+    let Some(line_program) = unit.line_program.clone() else {
+        return Ok(bytes);
+    };
+
+    let mut rows = line_program.rows();
+    // (address, file index, line) of the row that covers the code up to the next row
+    let mut previous: Option<(u64, u64, u64)> = None;
+
+    while let Some((_, row)) = rows.next_row()? {
+        if let Some((address, file_index, line)) = previous.take() {
+            if line != 0 {
+                *bytes.entry((file_index, line)).or_default() +=
+                    row.address().saturating_sub(address);
+            }
+        }
+
+        if !row.end_sequence() {
+            let line = row.line().map(|line| line.get()).unwrap_or(0);
+            previous = Some((row.address(), row.file_index(), line));
+        }
+    }
+
+    Ok(bytes)
+}
@@ -0,0 +1,709 @@
+use anyhow::{bail, Context as AnyhowContext, Result};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::types::{DefmtInfo, DefmtLogSite};
+
+/// A defmt log frame decoded against the ELF's string table
+pub struct DecodedFrame<'a> {
+    pub site: &'a DefmtLogSite,
+    pub timestamp: Option<String>,
+    pub message: String,
+}
+
+/// Decodes defmt wire frames using the interned strings of one firmware image
+pub struct DefmtDecoder<'a> {
+    sites: HashMap<u64, &'a DefmtLogSite>,
+    timestamp: Option<&'a DefmtLogSite>,
+}
+
+impl<'a> DefmtDecoder<'a> {
+    pub fn new(defmt_info: &'a DefmtInfo) -> Self {
+        Self {
+            sites: defmt_info
+                .log_sites
+                .iter()
+                .map(|site| (site.index, site))
+                .collect(),
+            timestamp: defmt_info
+                .log_sites
+                .iter()
+                .find(|site| site.tag == "defmt_timestamp"),
+        }
+    }
+
+    /// Decode one unframed (raw encoding) frame from the start of `bytes`,
+    /// returning it with the number of bytes consumed
+    pub fn decode_frame(&self, bytes: &[u8]) -> Result<(DecodedFrame<'a>, usize)> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        let index = reader.u16()? as u64;
+        let site = self.site(index)?;
+
+        let timestamp = match self.timestamp {
+            Some(timestamp) => Some(self.decode_format(&mut reader, &timestamp.format)?),
+            None => None,
+        };
+        let message = self.decode_site(&mut reader, site)?;
+
+        Ok((
+            DecodedFrame {
+                site,
+                timestamp,
+                message,
+            },
+            reader.pos,
+        ))
+    }
+
+    fn site(&self, index: u64) -> Result<&'a DefmtLogSite> {
+        self.sites
+            .get(&index)
+            .copied()
+            .with_context(|| format!("Unknown defmt string index {}", index))
+    }
+
+    /// Decode the arguments of an interned string, handling derived enums
+    fn decode_site(&self, reader: &mut Reader, site: &DefmtLogSite) -> Result<String> {
+        // `#[derive(Format)]` on enums interns "A|B({=u8})|C" and sends the discriminant first
+        let variants = split_variants(&site.format);
+        if site.tag == "defmt_derived" && variants.len() > 1 {
+            let discriminant = if variants.len() > 256 {
+                reader.u16()? as usize
+            } else {
+                reader.u8()? as usize
+            };
+            let variant = variants
+                .get(discriminant)
+                .with_context(|| format!("Invalid enum discriminant {}", discriminant))?;
+            return self.decode_format(reader, variant);
+        }
+
+        self.decode_format(reader, &site.format)
+    }
+
+    /// Read the arguments of a format string and render it
+    fn decode_format(&self, reader: &mut Reader, format: &str) -> Result<String> {
+        let segments = parse_format(format)?;
+
+        // Arguments are sent once per index in ascending order. Bitfields of the
+        // same argument share one value, truncated to the bytes they cover.
+        let mut by_index: BTreeMap<usize, Vec<&ParamType>> = BTreeMap::new();
+        for segment in &segments {
+            if let Segment::Param { index, ty, .. } = segment {
+                by_index.entry(*index).or_default().push(ty);
+            }
+        }
+
+        let mut values = HashMap::new();
+        for (index, types) in by_index {
+            let value = match bitfield_bounds(&types) {
+                Some((start, end)) => {
+                    let lowest_byte = start / 8;
+                    let highest_byte = (end - 1) / 8;
+                    let mut value = 0u128;
+                    for i in 0..=(highest_byte - lowest_byte) {
+                        value |= (reader.u8()? as u128) << (8 * i);
+                    }
+                    Value::Uint(value << (8 * lowest_byte))
+                }
+                None => self.read_value(reader, types[0])?,
+            };
+            values.insert(index, value);
+        }
+
+        let mut out = String::new();
+        for segment in &segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Param { index, ty, hint } => {
+                    let value = &values[index];
+                    match ty {
+                        ParamType::BitField(start, end) => {
+                            let Value::Uint(raw) = value else {
+                                unreachable!("bitfields are read as unsigned integers");
+                            };
+                            let mask = 1u128
+                                .checked_shl(end - start)
+                                .map_or(u128::MAX, |bit| bit - 1);
+                            out.push_str(&format_uint((raw >> start) & mask, hint.as_deref()));
+                        }
+                        _ => out.push_str(&value.render(hint.as_deref())),
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn read_value(&self, reader: &mut Reader, ty: &ParamType) -> Result<Value> {
+        Ok(match ty {
+            ParamType::Bool => Value::Bool(reader.u8()? != 0),
+            ParamType::Char => {
+                let code = reader.uint(4)? as u32;
+                Value::Char(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+            }
+            ParamType::Uint(bytes) => Value::Uint(reader.uint(*bytes)?),
+            ParamType::Int(bytes) => {
+                let raw = reader.uint(*bytes)?;
+                // Sign-extend from the encoded width
+                let shift = 128 - 8 * *bytes as u32;
+                Value::Int(((raw << shift) as i128) >> shift)
+            }
+            ParamType::F32 => Value::F32(f32::from_bits(reader.uint(4)? as u32)),
+            ParamType::F64 => Value::F64(f64::from_bits(reader.uint(8)? as u64)),
+            ParamType::Usize => Value::Uint(reader.leb128()? as u128),
+            ParamType::Isize => {
+                let zigzag = reader.leb128()?;
+                Value::Int(((zigzag >> 1) as i64 ^ -((zigzag & 1) as i64)) as i128)
+            }
+            ParamType::Str => {
+                let len = reader.leb128()? as usize;
+                Value::Str(String::from_utf8_lossy(reader.take(len)?).into_owned())
+            }
+            ParamType::IStr => {
+                let index = reader.u16()? as u64;
+                Value::Str(self.site(index)?.format.clone())
+            }
+            ParamType::U8Slice => {
+                let len = reader.leb128()? as usize;
+                Value::Bytes(reader.take(len)?.to_vec())
+            }
+            ParamType::U8Array(len) => Value::Bytes(reader.take(*len)?.to_vec()),
+            ParamType::Format => Value::Formatted(self.read_nested(reader)?),
+            ParamType::FormatSlice => {
+                let len = reader.leb128()? as usize;
+                Value::List(
+                    (0..len)
+                        .map(|_| self.read_nested(reader))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            ParamType::FormatArray(len) => Value::List(
+                (0..*len)
+                    .map(|_| self.read_nested(reader))
+                    .collect::<Result<_>>()?,
+            ),
+            ParamType::FormatSequence => {
+                let mut out = String::new();
+                loop {
+                    let index = reader.u16()? as u64;
+                    if index == 0 {
+                        break;
+                    }
+                    out.push_str(&self.decode_site(reader, self.site(index)?)?);
+                }
+                Value::Formatted(out)
+            }
+            ParamType::Debug | ParamType::Display => {
+                // `Debug2Format`/`Display2Format` stream the text and end it with 0xff
+                let end = reader.bytes[reader.pos..]
+                    .iter()
+                    .position(|&b| b == 0xff)
+                    .context("Unterminated Debug2Format string")?;
+                let text = String::from_utf8_lossy(reader.take(end)?).into_owned();
+                reader.u8()?;
+                Value::Formatted(text)
+            }
+            ParamType::BitField(..) => unreachable!("bitfields are read by decode_format"),
+        })
+    }
+
+    /// A nested `Format` value: its own string index followed by its arguments
+    fn read_nested(&self, reader: &mut Reader) -> Result<String> {
+        let index = reader.u16()? as u64;
+        self.decode_site(reader, self.site(index)?)
+    }
+}
+
+/// Decode one rzCOBS frame (without the 0x00 delimiter).
+///
+/// rzCOBS is processed back to front: a byte of 0x01..=0x7f is a bitmask for the
+/// next 7 bytes (set bits are zeros that were elided), 0x80 | n means n + 7
+/// non-zero bytes followed by a zero, and 0xff means 134 non-zero bytes.
+/// The decoded frame may end with padding zeros, which the frame decoder ignores.
+pub fn rzcobs_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 2);
+    let mut data = data.iter().rev().copied();
+
+    while let Some(x) = data.next() {
+        match x {
+            0 => bail!("Unexpected zero in rzCOBS frame"),
+            0x01..=0x7f => {
+                for i in 0..7 {
+                    if x & (1 << (6 - i)) == 0 {
+                        out.push(data.next().context("Truncated rzCOBS frame")?);
+                    } else {
+                        out.push(0);
+                    }
+                }
+            }
+            0x80..=0xfe => {
+                let n = (x & 0x7f) + 7;
+                out.push(0);
+                for _ in 0..n {
+                    out.push(data.next().context("Truncated rzCOBS frame")?);
+                }
+            }
+            0xff => {
+                for _ in 0..134 {
+                    out.push(data.next().context("Truncated rzCOBS frame")?);
+                }
+            }
+        }
+    }
+
+    out.reverse();
+    Ok(out)
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, len: usize) -> Result<&'b [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .context("Frame ended unexpectedly")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(self.uint(2)? as u16)
+    }
+
+    /// Little-endian unsigned integer of `len` bytes
+    fn uint(&mut self, len: usize) -> Result<u128> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &b| (acc << 8) | b as u128))
+    }
+
+    fn leb128(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("LEB128 value is too long")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ParamType {
+    Bool,
+    Char,
+    /// Unsigned integer of the given byte width
+    Uint(usize),
+    /// Signed integer of the given byte width
+    Int(usize),
+    F32,
+    F64,
+    Usize,
+    Isize,
+    Str,
+    IStr,
+    U8Slice,
+    U8Array(usize),
+    Format,
+    FormatSlice,
+    FormatArray(usize),
+    FormatSequence,
+    Debug,
+    Display,
+    /// Bit range `start..end` of an integer argument
+    BitField(u32, u32),
+}
+
+enum Segment {
+    Literal(String),
+    Param {
+        index: usize,
+        ty: ParamType,
+        hint: Option<String>,
+    },
+}
+
+enum Value {
+    Bool(bool),
+    Char(char),
+    Uint(u128),
+    Int(i128),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Formatted(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn render(&self, hint: Option<&str>) -> String {
+        match self {
+            Value::Bool(b) => b.to_string(),
+            Value::Char(c) => c.to_string(),
+            Value::Uint(v) => format_uint(*v, hint),
+            Value::Int(v) => match hint {
+                Some(_) if *v >= 0 => format_uint(*v as u128, hint),
+                _ => v.to_string(),
+            },
+            Value::F32(v) => v.to_string(),
+            Value::F64(v) => v.to_string(),
+            Value::Str(s) if hint == Some("?") => format!("{:?}", s),
+            Value::Str(s) | Value::Formatted(s) => s.clone(),
+            Value::Bytes(bytes) if hint == Some("a") => {
+                format!("b\"{}\"", bytes.escape_ascii())
+            }
+            Value::Bytes(bytes) => {
+                let items: Vec<String> = bytes
+                    .iter()
+                    .map(|&b| format_uint(b as u128, hint))
+                    .collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::List(items) => format!("[{}]", items.join(", ")),
+        }
+    }
+}
+
+/// Render an integer with a defmt display hint
+fn format_uint(value: u128, hint: Option<&str>) -> String {
+    match hint {
+        Some("x") => format!("{:x}", value),
+        Some("#x") => format!("{:#x}", value),
+        Some("X") => format!("{:X}", value),
+        Some("#X") => format!("{:#X}", value),
+        Some("b") => format!("{:b}", value),
+        Some("#b") => format!("{:#b}", value),
+        Some("o") => format!("{:o}", value),
+        Some("#o") => format!("{:#o}", value),
+        // Timestamps in micro- or milliseconds, shown as seconds
+        Some("us") | Some("tus") => format!("{}.{:06}", value / 1_000_000, value % 1_000_000),
+        Some("ms") | Some("tms") => format!("{}.{:03}", value / 1_000, value % 1_000),
+        _ => value.to_string(),
+    }
+}
+
+/// Lowest and highest bit of the bitfields of one argument, if all its uses are bitfields
+fn bitfield_bounds(types: &[&ParamType]) -> Option<(u32, u32)> {
+    types.iter().try_fold(None, |bounds, ty| match ty {
+        ParamType::BitField(start, end) => Some(Some(match bounds {
+            Some((lo, hi)) => (u32::min(lo, *start), u32::max(hi, *end)),
+            None => (*start, *end),
+        })),
+        _ => None,
+    })?
+}
+
+/// Split a derived enum format string into its variants at top-level `|`
+fn split_variants(format: &str) -> Vec<&str> {
+    let mut variants = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in format.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '|' if depth == 0 => {
+                variants.push(&format[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    variants.push(&format[start..]);
+
+    variants
+}
+
+/// Parse a defmt format string like `x={=u8:x} y={1=0..4}` into literals and parameters
+fn parse_format(format: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut next_implicit = 0;
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut param = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => param.push(c),
+                        None => bail!("Unterminated parameter in {:?}", format),
+                    }
+                }
+
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+
+                let (spec, hint) = match param.split_once(':') {
+                    Some((spec, hint)) => (spec, Some(hint.to_string())),
+                    None => (param.as_str(), None),
+                };
+                let (index, ty) = match spec.split_once('=') {
+                    Some((index, ty)) => (index, ty),
+                    // `{}` without a type is a nested `Format` value
+                    None => (spec, "?"),
+                };
+                let index = if index.is_empty() {
+                    next_implicit += 1;
+                    next_implicit - 1
+                } else {
+                    index
+                        .parse()
+                        .with_context(|| format!("Invalid parameter index {:?}", index))?
+                };
+
+                segments.push(Segment::Param {
+                    index,
+                    ty: parse_type(ty)?,
+                    hint,
+                });
+            }
+            _ => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+
+    Ok(segments)
+}
+
+fn parse_type(ty: &str) -> Result<ParamType> {
+    let ty = ty.trim();
+
+    Ok(match ty {
+        "bool" => ParamType::Bool,
+        "char" => ParamType::Char,
+        "u8" => ParamType::Uint(1),
+        "u16" => ParamType::Uint(2),
+        "u24" => ParamType::Uint(3),
+        "u32" => ParamType::Uint(4),
+        "u64" => ParamType::Uint(8),
+        "u128" => ParamType::Uint(16),
+        "i8" => ParamType::Int(1),
+        "i16" => ParamType::Int(2),
+        "i32" => ParamType::Int(4),
+        "i64" => ParamType::Int(8),
+        "i128" => ParamType::Int(16),
+        "f32" => ParamType::F32,
+        "f64" => ParamType::F64,
+        "usize" => ParamType::Usize,
+        "isize" => ParamType::Isize,
+        "str" => ParamType::Str,
+        "istr" => ParamType::IStr,
+        "?" => ParamType::Format,
+        "[u8]" => ParamType::U8Slice,
+        "[?]" => ParamType::FormatSlice,
+        "__internal_FormatSequence" => ParamType::FormatSequence,
+        "__internal_Debug" => ParamType::Debug,
+        "__internal_Display" => ParamType::Display,
+        _ => {
+            if let Some((start, end)) = ty.split_once("..") {
+                let start: u32 = start.trim().parse().context("Invalid bitfield start")?;
+                let end: u32 = end.trim().parse().context("Invalid bitfield end")?;
+                if start >= end || end > 128 {
+                    bail!("Invalid bitfield range {}..{}", start, end);
+                }
+                return Ok(ParamType::BitField(start, end));
+            }
+
+            // Arrays: `[u8; N]` and `[?; N]`
+            let array = ty
+                .strip_prefix('[')
+                .and_then(|ty| ty.strip_suffix(']'))
+                .and_then(|ty| ty.split_once(';'));
+            match array {
+                Some((element, len)) => {
+                    let len = len.trim().parse().context("Invalid array length")?;
+                    match element.trim() {
+                        "u8" => ParamType::U8Array(len),
+                        "?" => ParamType::FormatArray(len),
+                        element => bail!("Unsupported array element type {:?}", element),
+                    }
+                }
+                None => bail!("Unsupported defmt parameter type {:?}", ty),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_format, rzcobs_decode, DefmtDecoder, Reader};
+    use crate::types::{DefmtInfo, DefmtLogSite};
+
+    fn decoder_info(sites: &[(u64, &str, &str)]) -> DefmtInfo {
+        DefmtInfo {
+            present: true,
+            sections: Vec::new(),
+            log_sites: sites
+                .iter()
+                .map(|&(index, tag, format)| DefmtLogSite {
+                    index,
+                    tag: tag.to_string(),
+                    level: None,
+                    format: format.to_string(),
+                    crate_name: "app".to_string(),
+                    file: None,
+                    line: None,
+                    flash_bytes: None,
+                    die_offset: None,
+                })
+                .collect(),
+            encoding: None,
+        }
+    }
+
+    /// The encoder of the `rzcobs` crate defmt uses, for round trips
+    fn rzcobs_encode(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (mut run, mut zeros) = (0u8, 0u8);
+        for &byte in data {
+            if run < 7 {
+                if byte == 0 {
+                    zeros |= 1 << run;
+                } else {
+                    out.push(byte);
+                }
+                run += 1;
+                if run == 7 && zeros != 0 {
+                    out.push(zeros);
+                    (run, zeros) = (0, 0);
+                }
+            } else if byte == 0 {
+                out.push((run - 7) | 0x80);
+                (run, zeros) = (0, 0);
+            } else {
+                out.push(byte);
+                run += 1;
+                if run == 134 {
+                    out.push(0xff);
+                    (run, zeros) = (0, 0);
+                }
+            }
+        }
+        match run {
+            0 => {}
+            1..=6 => out.push((zeros | 0xff << run) & 0x7f),
+            _ => out.push((run - 7) | 0x80),
+        }
+        out
+    }
+
+    #[test]
+    fn rzcobs_fills_in_elided_zeros() {
+        // 0x7a: the bitmask of the group 11 00 22, with the unused bits set
+        assert_eq!(
+            rzcobs_decode(&[0x11, 0x22, 0x7a]).unwrap(),
+            [0x11, 0x00, 0x22, 0x00, 0x00, 0x00, 0x00]
+        );
+        // 0x7c needs two non-zero bytes before it
+        assert!(rzcobs_decode(&[0x11, 0x7c]).is_err());
+        assert!(rzcobs_decode(&[0x00]).is_err());
+    }
+
+    #[test]
+    fn rzcobs_round_trip() {
+        let long_run: Vec<u8> = (1..=200).collect();
+        let frames: [&[u8]; 5] = [
+            &[0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00],
+            &[0x00; 20],
+            &[0xab; 9],
+            &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x00, 0x09],
+            &long_run,
+        ];
+        for frame in frames {
+            let decoded = rzcobs_decode(&rzcobs_encode(frame)).unwrap();
+            // Anything past the frame is padding
+            assert_eq!(&decoded[..frame.len()], frame);
+            assert!(decoded[frame.len()..].iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn leb128() {
+        let mut reader = Reader {
+            bytes: &[0xe5, 0x8e, 0x26, 0x7f],
+            pos: 0,
+        };
+        assert_eq!(reader.leb128().unwrap(), 624_485);
+        assert_eq!(reader.leb128().unwrap(), 127);
+        assert!(reader.leb128().is_err());
+    }
+
+    #[test]
+    fn format_strings() {
+        assert!(parse_format("x={=u8").is_err());
+        assert!(parse_format("{=u7}").is_err());
+        assert!(parse_format("{=4..4}").is_err());
+        assert_eq!(parse_format("{{literal}} {=u8:x}").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn decodes_frames() {
+        let info = decoder_info(&[
+            (1, "defmt_info", "x={=u8:x} y={=i16} s={=str} n={=usize}"),
+            (2, "defmt_timestamp", "{=u32:us}"),
+        ]);
+        let decoder = DefmtDecoder::new(&info);
+        let bytes = [
+            0x01, 0x00, // string index
+            0x40, 0x42, 0x0f, 0x00, // timestamp: 1_000_000 us
+            0xab, // x
+            0xfe, 0xff, // y
+            0x02, b'h', b'i', // s
+            0xac, 0x02, // n: 300 as LEB128
+            0xff, // next frame
+        ];
+        let (frame, consumed) = decoder.decode_frame(&bytes).unwrap();
+        assert_eq!(frame.site.index, 1);
+        assert_eq!(frame.timestamp.as_deref(), Some("1.000000"));
+        assert_eq!(frame.message, "x=ab y=-2 s=hi n=300");
+        assert_eq!(consumed, bytes.len() - 1);
+
+        assert!(decoder.decode_frame(&[0x07, 0x00]).is_err());
+        assert!(decoder.decode_frame(&bytes[..8]).is_err());
+    }
+
+    #[test]
+    fn decodes_derived_enums_and_bitfields() {
+        let info = decoder_info(&[
+            (3, "defmt_derived", "A|B({=u8})"),
+            (4, "defmt_info", "e={=?} lo={1=0..4:b} hi={1=4..8}"),
+        ]);
+        let decoder = DefmtDecoder::new(&info);
+        // Nested value: its string index, the variant and the payload; then the bitfield byte
+        let bytes = [0x04, 0x00, 0x03, 0x00, 0x01, 0x07, 0xa5];
+        let (frame, consumed) = decoder.decode_frame(&bytes).unwrap();
+        assert_eq!(frame.message, "e=B(7) lo=101 hi=10");
+        assert_eq!(consumed, bytes.len());
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::{bail, Context, Result};
+use object::{Architecture, Object, ObjectSection};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::disasm_riscv::RiscvState;
+use crate::source::{read_source_lines, resolve_source_path, PathRemap};
+use crate::types::{
+    DisassembledInstruction, Disassembly, DisassemblyLine, ElfSymbol, Flow, LineTable,
+};
+use crate::utils::symbolize_address;
+use crate::{disasm_riscv, disasm_thumb, disasm_xtensa};
+
+/// Instruction sets the disassembler decodes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionSet {
+    Thumb,
+    /// RV32 or RV64 by XLEN
+    Riscv(u32),
+    Xtensa,
+}
+
+impl InstructionSet {
+    /// The instruction set of an ELF machine, `None` if it is not decoded
+    pub fn from_architecture(architecture: Architecture) -> Option<Self> {
+        match architecture {
+            Architecture::Arm => Some(InstructionSet::Thumb),
+            Architecture::Riscv32 => Some(InstructionSet::Riscv(32)),
+            Architecture::Riscv64 => Some(InstructionSet::Riscv(64)),
+            Architecture::Xtensa => Some(InstructionSet::Xtensa),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InstructionSet::Thumb => "Thumb-2",
+            InstructionSet::Riscv(32) => "RV32",
+            InstructionSet::Riscv(_) => "RV64",
+            InstructionSet::Xtensa => "Xtensa",
+        }
+    }
+}
+
+/// Disassemble the code in `ranges`, interleaved with the source lines the line table
+/// attributes it to. The instruction set follows the ELF's machine; data marked by
+/// `$d` mapping symbols, e.g. literal pools, is shown as words.
+pub fn disassemble(
+    path: &PathBuf,
+    name: &str,
+    ranges: &[(u64, u64)],
+    symbols: &[ElfSymbol],
+    line_table: &LineTable,
+    remaps: &[PathRemap],
+) -> Result<Disassembly> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+    let Some(instruction_set) = InstructionSet::from_architecture(obj.architecture()) else {
+        bail!(
+            "Disassembly of {:?} code is not supported",
+            obj.architecture()
+        );
+    };
+
+    let sections: Vec<(u64, &[u8])> = obj
+        .sections()
+        .filter(|section| section.file_range().is_some())
+        .filter_map(|section| Some((section.address(), section.data().ok()?)))
+        .collect();
+    let bytes_at = |address: u64, size: u64| {
+        sections.iter().find_map(|(start, data)| {
+            let offset = address.checked_sub(*start)? as usize;
+            data.get(offset..offset.checked_add(size as usize)?)
+        })
+    };
+    let mapping = mapping_symbols(symbols);
+
+    let mut lines = Vec::new();
+    let mut sources: HashMap<usize, Option<Vec<String>>> = HashMap::new();
+    let mut last_source = None;
+    for &(start, end) in ranges {
+        let Some(code) = bytes_at(start, end.saturating_sub(start)) else {
+            bail!("No file data for 0x{:08x}..0x{:08x}", start, end);
+        };
+
+        for mut instruction in decode_instructions(instruction_set, code, start, &mapping) {
+            if let Some(row) = line_table.row_at(instruction.address) {
+                if row.line != 0 && last_source != Some((row.file, row.line)) {
+                    last_source = Some((row.file, row.line));
+                    let file = line_table.files.get(row.file).cloned().unwrap_or_default();
+                    let text = sources
+                        .entry(row.file)
+                        .or_insert_with(|| {
+                            let path = resolve_source_path(&file, remaps)?;
+                            read_source_lines(&path).ok()
+                        })
+                        .as_ref()
+                        .and_then(|source| source.get(row.line as usize - 1).cloned());
+                    lines.push(DisassemblyLine::Source {
+                        file,
+                        line: row.line,
+                        text,
+                    });
+                }
+            }
+
+            instruction.comment = annotate(&instruction, symbols, &bytes_at);
+            lines.push(DisassemblyLine::Instruction(instruction));
+        }
+    }
+
+    Ok(Disassembly {
+        name: name.to_string(),
+        instruction_set: instruction_set.name().to_string(),
+        lines,
+    })
+}
+
+/// Decode `code` loaded at `start`. Data between `$d` and the next code mapping symbol,
+/// and whatever cannot be decoded, comes out as `.word`, `.short` or `.byte`.
+pub fn decode_instructions(
+    instruction_set: InstructionSet,
+    code: &[u8],
+    start: u64,
+    mapping: &[&ElfSymbol],
+) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut it = 0;
+    let mut riscv = RiscvState::default();
+    let mut offset = 0;
+    while offset < code.len() {
+        let address = start + offset as u64;
+        let rest = &code[offset..];
+        let decoded = if in_data(mapping, address) {
+            None
+        } else {
+            match instruction_set {
+                InstructionSet::Thumb => disasm_thumb::decode(rest, address, &mut it),
+                InstructionSet::Riscv(xlen) => {
+                    disasm_riscv::decode(rest, address, xlen, &mut riscv)
+                }
+                InstructionSet::Xtensa => disasm_xtensa::decode(rest, address),
+            }
+        };
+        let instruction = decoded.unwrap_or_else(|| data_word(rest, address));
+        offset += instruction.bytes.len();
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// The mapping symbols among `symbols`, which are sorted by address
+pub fn mapping_symbols(symbols: &[ElfSymbol]) -> Vec<&ElfSymbol> {
+    symbols
+        .iter()
+        .filter(|symbol| is_mapping_symbol(&symbol.name))
+        .collect()
+}
+
+/// ARM and RISC-V mapping symbols, which mark where code and data start
+fn is_mapping_symbol(name: &str) -> bool {
+    match name.get(..2) {
+        // RISC-V code mapping symbols may carry the ISA string, e.g. `$xrv32imac`
+        Some("$x") => true,
+        Some("$a" | "$d" | "$t") => name.len() == 2 || name[2..].starts_with('.'),
+        _ => false,
+    }
+}
+
+/// Whether the last mapping symbol at or below `address` marks data, or ARM code
+/// that is not decoded
+fn in_data(mapping: &[&ElfSymbol], address: u64) -> bool {
+    let end = mapping.partition_point(|symbol| symbol.address <= address);
+    end > 0 && matches!(mapping[end - 1].name.get(..2), Some("$d" | "$a"))
+}
+
+/// A word of data, or the bytes left before the end of the range
+fn data_word(data: &[u8], address: u64) -> DisassembledInstruction {
+    let bytes = data[..data.len().min(4)].to_vec();
+    let (mnemonic, value) = match bytes.len() {
+        4 => (
+            ".word",
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        ),
+        2 | 3 => (".short", u16::from_le_bytes([bytes[0], bytes[1]]) as u32),
+        _ => (".byte", bytes[0] as u32),
+    };
+    let bytes = match mnemonic {
+        ".short" => bytes[..2].to_vec(),
+        ".byte" => bytes[..1].to_vec(),
+        _ => bytes,
+    };
+    DisassembledInstruction {
+        address,
+        bytes,
+        mnemonic: mnemonic.to_string(),
+        operands: format!("0x{:x}", value),
+        target: None,
+        literal: None,
+        comment: None,
+        flow: Flow::Next,
+        stack_push: 0,
+    }
+}
+
+/// Symbol of a branch target, or the constant a literal load reads and its symbol
+fn annotate<'a>(
+    instruction: &DisassembledInstruction,
+    symbols: &[ElfSymbol],
+    bytes_at: &impl Fn(u64, u64) -> Option<&'a [u8]>,
+) -> Option<String> {
+    if let Some(literal) = instruction.literal {
+        // A store through a LUI/AUIPC base: the location matters, not what it holds
+        if matches!(
+            instruction.mnemonic.as_str(),
+            "sb" | "sh" | "sw" | "sd" | "fsw" | "fsd"
+        ) {
+            return symbolize_address(symbols, literal).map(|symbol| format!("<{}>", symbol));
+        }
+        let size = match instruction.mnemonic.trim_end_matches(".w") {
+            "vldr" if instruction.operands.starts_with('d') => 8,
+            "ld" | "fld" | "ldrd" => 8,
+            "ldrh" | "ldrsh" | "lh" | "lhu" => 2,
+            "ldrb" | "ldrsb" | "lb" | "lbu" => 1,
+            _ => 4,
+        };
+        let bytes = bytes_at(literal, size)?;
+        let value = bytes
+            .iter()
+            .rev()
+            .fold(0u64, |value, &byte| value << 8 | byte as u64);
+        // Thumb function pointers carry the Thumb bit
+        let function = (value & 1 != 0)
+            .then(|| symbolize_address(symbols, value & !1))
+            .flatten()
+            .filter(|symbol| !symbol.contains('+'));
+        return Some(
+            match function.or_else(|| symbolize_address(symbols, value)) {
+                Some(symbol) => format!("=0x{:x} <{}>", value, symbol),
+                None => format!("=0x{:x}", value),
+            },
+        );
+    }
+
+    instruction
+        .target
+        .and_then(|target| symbolize_address(symbols, target))
+        .map(|symbol| format!("<{}>", symbol))
+}
@@ -0,0 +1,739 @@
+use crate::types::{DisassembledInstruction, Flow};
+
+const REGISTERS: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+const FLOAT_REGISTERS: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2",
+    "fa3", "fa4", "fa5", "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9",
+    "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+/// Decoder state carried between instructions
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RiscvState {
+    /// Register and value set by the previous instruction if it was a LUI or AUIPC, to
+    /// resolve the address of the following load, store, ADDI or JALR
+    upper: Option<(u32, u64)>,
+}
+
+struct Decoded {
+    mnemonic: String,
+    operands: String,
+    target: Option<u64>,
+    literal: Option<u64>,
+    flow: Flow,
+    stack_push: u64,
+}
+
+fn op(mnemonic: &str, operands: String) -> Decoded {
+    Decoded {
+        mnemonic: mnemonic.to_string(),
+        operands,
+        target: None,
+        literal: None,
+        flow: Flow::Next,
+        stack_push: 0,
+    }
+}
+
+fn x(index: u32) -> &'static str {
+    REGISTERS[index as usize & 31]
+}
+
+fn f(index: u32) -> &'static str {
+    FLOAT_REGISTERS[index as usize & 31]
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    ((value << (32 - bits)) as i32 >> (32 - bits)) as i64
+}
+
+/// Decode one RV32 or RV64 (`xlen`) instruction, including the C extension.
+/// Compressed instructions are shown as the instruction they expand to.
+pub fn decode(
+    data: &[u8],
+    address: u64,
+    xlen: u32,
+    state: &mut RiscvState,
+) -> Option<DisassembledInstruction> {
+    let low = u16::from_le_bytes([*data.first()?, *data.get(1)?]) as u32;
+    let (size, word) = if low & 3 == 3 {
+        let high = u16::from_le_bytes([*data.get(2)?, *data.get(3)?]) as u32;
+        (4, Some(low | high << 16))
+    } else {
+        (2, expand_compressed(low, xlen))
+    };
+
+    let upper = state.upper.take();
+    let decoded = match word {
+        Some(word) => decode_32(word, address, xlen, upper, state),
+        None if low == 0 => op("unimp", String::new()),
+        None => op(".short", format!("0x{:04x}", low)),
+    };
+
+    Some(DisassembledInstruction {
+        address,
+        bytes: data[..size].to_vec(),
+        mnemonic: decoded.mnemonic,
+        operands: decoded.operands,
+        target: decoded.target,
+        literal: decoded.literal,
+        comment: None,
+        flow: decoded.flow,
+        stack_push: decoded.stack_push,
+    })
+}
+
+fn csr_name(csr: u32) -> String {
+    match csr {
+        0x001 => "fflags",
+        0x002 => "frm",
+        0x003 => "fcsr",
+        0x300 => "mstatus",
+        0x301 => "misa",
+        0x302 => "medeleg",
+        0x303 => "mideleg",
+        0x304 => "mie",
+        0x305 => "mtvec",
+        0x340 => "mscratch",
+        0x341 => "mepc",
+        0x342 => "mcause",
+        0x343 => "mtval",
+        0x344 => "mip",
+        0x100 => "sstatus",
+        0x104 => "sie",
+        0x105 => "stvec",
+        0x140 => "sscratch",
+        0x141 => "sepc",
+        0x142 => "scause",
+        0x143 => "stval",
+        0x144 => "sip",
+        0x180 => "satp",
+        0xb00 => "mcycle",
+        0xb02 => "minstret",
+        0xc00 => "cycle",
+        0xc01 => "time",
+        0xc02 => "instret",
+        0xf11 => "mvendorid",
+        0xf12 => "marchid",
+        0xf13 => "mimpid",
+        0xf14 => "mhartid",
+        _ => return format!("0x{:x}", csr),
+    }
+    .to_string()
+}
+
+fn decode_32(
+    word: u32,
+    address: u64,
+    xlen: u32,
+    upper: Option<(u32, u64)>,
+    state: &mut RiscvState,
+) -> Decoded {
+    let rd = (word >> 7) & 31;
+    let funct3 = (word >> 12) & 7;
+    let rs1 = (word >> 15) & 31;
+    let rs2 = (word >> 20) & 31;
+    let funct7 = word >> 25;
+    let imm_i = sign_extend(word >> 20, 12);
+    let imm_s = sign_extend((word >> 25) << 5 | ((word >> 7) & 31), 12);
+    let unknown = || op(".word", format!("0x{:08x}", word));
+    // RV32 addresses wrap at 32 bits, so LUI of 0x80000 is 0x8000_0000 and not sign-extended
+    let address_of = |value: i64| {
+        if xlen == 32 {
+            value as u32 as u64
+        } else {
+            value as u64
+        }
+    };
+    // Address formed from a preceding LUI or AUIPC of the base register
+    let relative = |offset: i64| {
+        upper
+            .filter(|&(register, _)| register == rs1 && rs1 != 0)
+            .map(|(_, base)| address_of(base as i64 + offset))
+    };
+    let shamt_mask = if xlen == 64 { 0x3f } else { 0x1f };
+
+    match word & 0x7f {
+        0x37 => {
+            state.upper = Some((rd, address_of((word & 0xffff_f000) as i32 as i64)));
+            op("lui", format!("{}, 0x{:x}", x(rd), word >> 12))
+        }
+        0x17 => {
+            let value = address_of(address as i64 + (word & 0xffff_f000) as i32 as i64);
+            state.upper = Some((rd, value));
+            op("auipc", format!("{}, 0x{:x}", x(rd), word >> 12))
+        }
+        0x6f => {
+            let offset = sign_extend(
+                (word >> 31) << 20
+                    | ((word >> 12) & 0xff) << 12
+                    | ((word >> 20) & 1) << 11
+                    | ((word >> 21) & 0x3ff) << 1,
+                21,
+            );
+            let target = address_of(address as i64 + offset);
+            let decoded = match rd {
+                0 => op("j", format!("0x{:x}", target)),
+                1 => op("jal", format!("0x{:x}", target)),
+                _ => op("jal", format!("{}, 0x{:x}", x(rd), target)),
+            };
+            Decoded {
+                target: Some(target),
+                // JAL without a link register is a plain jump
+                flow: if rd == 0 {
+                    Flow::Branch(target)
+                } else {
+                    Flow::Call(target)
+                },
+                ..decoded
+            }
+        }
+        0x67 if funct3 == 0 => {
+            let decoded = match (rd, rs1, imm_i) {
+                (0, 1, 0) => op("ret", String::new()),
+                (0, _, 0) => op("jr", x(rs1).to_string()),
+                (0, _, _) => op("jr", format!("{}({})", imm_i, x(rs1))),
+                (1, _, 0) => op("jalr", x(rs1).to_string()),
+                (1, _, _) => op("jalr", format!("{}({})", imm_i, x(rs1))),
+                _ => op("jalr", format!("{}, {}({})", x(rd), imm_i, x(rs1))),
+            };
+            // AUIPC and JALR form the `call` and `tail` of far targets
+            let target = relative(imm_i);
+            let flow = match (rd, rs1, target) {
+                (0, 1, _) => Flow::Return,
+                (0, _, Some(target)) => Flow::Branch(target),
+                (0, _, None) => Flow::IndirectBranch,
+                (_, _, Some(target)) => Flow::Call(target),
+                (_, _, None) => Flow::IndirectCall,
+            };
+            Decoded {
+                target,
+                flow,
+                ..decoded
+            }
+        }
+        0x63 => {
+            let offset = sign_extend(
+                (word >> 31) << 12
+                    | ((word >> 7) & 1) << 11
+                    | ((word >> 25) & 0x3f) << 5
+                    | ((word >> 8) & 0xf) << 1,
+                13,
+            );
+            let target = address_of(address as i64 + offset);
+            let name = match funct3 {
+                0 => "beq",
+                1 => "bne",
+                4 => "blt",
+                5 => "bge",
+                6 => "bltu",
+                7 => "bgeu",
+                _ => return unknown(),
+            };
+            let decoded = match (funct3, rs2) {
+                (0 | 1 | 4 | 5, 0) => {
+                    let name = ["beqz", "bnez", "", "", "bltz", "bgez"][funct3 as usize];
+                    op(name, format!("{}, 0x{:x}", x(rs1), target))
+                }
+                _ => op(name, format!("{}, {}, 0x{:x}", x(rs1), x(rs2), target)),
+            };
+            Decoded {
+                target: Some(target),
+                flow: Flow::ConditionalBranch(target),
+                ..decoded
+            }
+        }
+        0x03 => {
+            let name = match funct3 {
+                0 => "lb",
+                1 => "lh",
+                2 => "lw",
+                3 if xlen == 64 => "ld",
+                4 => "lbu",
+                5 => "lhu",
+                6 if xlen == 64 => "lwu",
+                _ => return unknown(),
+            };
+            Decoded {
+                literal: relative(imm_i),
+                ..op(name, format!("{}, {}({})", x(rd), imm_i, x(rs1)))
+            }
+        }
+        0x23 => {
+            let name = match funct3 {
+                0 => "sb",
+                1 => "sh",
+                2 => "sw",
+                3 if xlen == 64 => "sd",
+                _ => return unknown(),
+            };
+            Decoded {
+                literal: relative(imm_s),
+                ..op(name, format!("{}, {}({})", x(rs2), imm_s, x(rs1)))
+            }
+        }
+        0x07 | 0x27 => {
+            let name = match (word & 0x7f, funct3) {
+                (0x07, 2) => "flw",
+                (0x07, 3) => "fld",
+                (0x27, 2) => "fsw",
+                (0x27, 3) => "fsd",
+                _ => return unknown(),
+            };
+            if word & 0x7f == 0x07 {
+                Decoded {
+                    literal: relative(imm_i),
+                    ..op(name, format!("{}, {}({})", f(rd), imm_i, x(rs1)))
+                }
+            } else {
+                Decoded {
+                    literal: relative(imm_s),
+                    ..op(name, format!("{}, {}({})", f(rs2), imm_s, x(rs1)))
+                }
+            }
+        }
+        0x13 => {
+            let shamt = (word >> 20) & shamt_mask;
+            match funct3 {
+                0 if rd == 0 && rs1 == 0 && imm_i == 0 => op("nop", String::new()),
+                0 if rs1 == 0 => op("li", format!("{}, {}", x(rd), imm_i)),
+                0 if imm_i == 0 => op("mv", format!("{}, {}", x(rd), x(rs1))),
+                0 => Decoded {
+                    target: relative(imm_i),
+                    // Frame allocation, `addi sp, sp, -N`
+                    stack_push: if rd == 2 && rs1 == 2 && imm_i < 0 {
+                        imm_i.unsigned_abs()
+                    } else {
+                        0
+                    },
+                    ..op("addi", format!("{}, {}, {}", x(rd), x(rs1), imm_i))
+                },
+                2 => op("slti", format!("{}, {}, {}", x(rd), x(rs1), imm_i)),
+                3 if imm_i == 1 => op("seqz", format!("{}, {}", x(rd), x(rs1))),
+                3 => op("sltiu", format!("{}, {}, {}", x(rd), x(rs1), imm_i)),
+                4 if imm_i == -1 => op("not", format!("{}, {}", x(rd), x(rs1))),
+                4 => op("xori", format!("{}, {}, {}", x(rd), x(rs1), imm_i)),
+                6 => op("ori", format!("{}, {}, {}", x(rd), x(rs1), imm_i)),
+                7 => op("andi", format!("{}, {}, {}", x(rd), x(rs1), imm_i)),
+                1 => op("slli", format!("{}, {}, {}", x(rd), x(rs1), shamt)),
+                _ if word & 0x4000_0000 == 0 => {
+                    op("srli", format!("{}, {}, {}", x(rd), x(rs1), shamt))
+                }
+                _ => op("srai", format!("{}, {}, {}", x(rd), x(rs1), shamt)),
+            }
+        }
+        0x1b if xlen == 64 => {
+            let shamt = (word >> 20) & 0x1f;
+            match funct3 {
+                0 if imm_i == 0 => op("sext.w", format!("{}, {}", x(rd), x(rs1))),
+                0 => op("addiw", format!("{}, {}, {}", x(rd), x(rs1), imm_i)),
+                1 => op("slliw", format!("{}, {}, {}", x(rd), x(rs1), shamt)),
+                5 if word & 0x4000_0000 == 0 => {
+                    op("srliw", format!("{}, {}, {}", x(rd), x(rs1), shamt))
+                }
+                5 => op("sraiw", format!("{}, {}, {}", x(rd), x(rs1), shamt)),
+                _ => unknown(),
+            }
+        }
+        0x33 | 0x3b => {
+            let word_op = word & 0x7f == 0x3b;
+            if word_op && xlen != 64 {
+                return unknown();
+            }
+            let name = match (funct7, funct3) {
+                (0x00, 0) => "add",
+                (0x20, 0) => "sub",
+                (0x00, 1) => "sll",
+                (0x00, 2) if !word_op => "slt",
+                (0x00, 3) if !word_op => "sltu",
+                (0x00, 4) if !word_op => "xor",
+                (0x00, 5) => "srl",
+                (0x20, 5) => "sra",
+                (0x00, 6) if !word_op => "or",
+                (0x00, 7) if !word_op => "and",
+                (0x01, 0) => "mul",
+                (0x01, 1) if !word_op => "mulh",
+                (0x01, 2) if !word_op => "mulhsu",
+                (0x01, 3) if !word_op => "mulhu",
+                (0x01, 4) => "div",
+                (0x01, 5) => "divu",
+                (0x01, 6) => "rem",
+                (0x01, 7) => "remu",
+                _ => return unknown(),
+            };
+            let name = if word_op {
+                format!("{}w", name)
+            } else {
+                name.to_string()
+            };
+            match (name.as_str(), rs1) {
+                ("sub", 0) => op("neg", format!("{}, {}", x(rd), x(rs2))),
+                ("subw", 0) => op("negw", format!("{}, {}", x(rd), x(rs2))),
+                ("sltu", 0) => op("snez", format!("{}, {}", x(rd), x(rs2))),
+                _ => op(&name, format!("{}, {}, {}", x(rd), x(rs1), x(rs2))),
+            }
+        }
+        0x2f if funct3 == 2 || (funct3 == 3 && xlen == 64) => {
+            let name = match word >> 27 {
+                0x02 => "lr",
+                0x03 => "sc",
+                0x01 => "amoswap",
+                0x00 => "amoadd",
+                0x04 => "amoxor",
+                0x0c => "amoand",
+                0x08 => "amoor",
+                0x10 => "amomin",
+                0x14 => "amomax",
+                0x18 => "amominu",
+                0x1c => "amomaxu",
+                _ => return unknown(),
+            };
+            let ordering = match (word >> 25) & 3 {
+                0 => "",
+                1 => ".rl",
+                2 => ".aq",
+                _ => ".aqrl",
+            };
+            let width = if funct3 == 2 { "w" } else { "d" };
+            let name = format!("{}.{}{}", name, width, ordering);
+            if word >> 27 == 0x02 {
+                op(&name, format!("{}, ({})", x(rd), x(rs1)))
+            } else {
+                op(&name, format!("{}, {}, ({})", x(rd), x(rs2), x(rs1)))
+            }
+        }
+        0x0f => match funct3 {
+            0 if word == 0x0ff0_000f => op("fence", String::new()),
+            0 => {
+                let set = |bits: u32| -> String {
+                    ["i", "o", "r", "w"]
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| bits & (8 >> index) != 0)
+                        .map(|(_, name)| *name)
+                        .collect()
+                };
+                op(
+                    "fence",
+                    format!("{}, {}", set((word >> 24) & 0xf), set((word >> 20) & 0xf)),
+                )
+            }
+            1 => op("fence.i", String::new()),
+            _ => unknown(),
+        },
+        0x73 => decode_system(word),
+        0x43 | 0x47 | 0x4b | 0x4f => {
+            let name = ["fmadd", "fmsub", "fnmsub", "fnmadd"][((word >> 2) & 3) as usize];
+            let format = if funct7 & 3 == 0 { "s" } else { "d" };
+            op(
+                &format!("{}.{}", name, format),
+                format!("{}, {}, {}, {}", f(rd), f(rs1), f(rs2), f(word >> 27)),
+            )
+        }
+        0x53 => decode_float(word),
+        _ => unknown(),
+    }
+}
+
+fn decode_system(word: u32) -> Decoded {
+    let rd = (word >> 7) & 31;
+    let funct3 = (word >> 12) & 7;
+    let rs1 = (word >> 15) & 31;
+    let csr = csr_name(word >> 20);
+
+    match (funct3, word) {
+        (0, 0x0000_0073) => op("ecall", String::new()),
+        (0, 0x0010_0073) => op("ebreak", String::new()),
+        (0, 0x3020_0073) => Decoded {
+            flow: Flow::Return,
+            ..op("mret", String::new())
+        },
+        (0, 0x1020_0073) => Decoded {
+            flow: Flow::Return,
+            ..op("sret", String::new())
+        },
+        (0, 0x1050_0073) => op("wfi", String::new()),
+        (0, _) => op(".word", format!("0x{:08x}", word)),
+        _ => {
+            let source = if funct3 >= 5 {
+                rs1.to_string()
+            } else {
+                x(rs1).to_string()
+            };
+            let suffix = if funct3 >= 5 { "i" } else { "" };
+            match (funct3 & 3, rd, rs1) {
+                (2, _, 0) if funct3 == 2 => op("csrr", format!("{}, {}", x(rd), csr)),
+                (1, 0, _) => op(&format!("csrw{}", suffix), format!("{}, {}", csr, source)),
+                (2, 0, _) => op(&format!("csrs{}", suffix), format!("{}, {}", csr, source)),
+                (3, 0, _) => op(&format!("csrc{}", suffix), format!("{}, {}", csr, source)),
+                (kind, _, _) => {
+                    let name = ["", "csrrw", "csrrs", "csrrc"][kind as usize];
+                    op(
+                        &format!("{}{}", name, suffix),
+                        format!("{}, {}, {}", x(rd), csr, source),
+                    )
+                }
+            }
+        }
+    }
+}
+
+fn decode_float(word: u32) -> Decoded {
+    let rd = (word >> 7) & 31;
+    let funct3 = (word >> 12) & 7;
+    let rs1 = (word >> 15) & 31;
+    let rs2 = (word >> 20) & 31;
+    let funct7 = word >> 25;
+    let format = match funct7 & 3 {
+        0 => "s",
+        1 => "d",
+        _ => return op(".word", format!("0x{:08x}", word)),
+    };
+    let integer = ["w", "wu", "l", "lu"];
+    let three = |name: &str| {
+        op(
+            &format!("{}.{}", name, format),
+            format!("{}, {}, {}", f(rd), f(rs1), f(rs2)),
+        )
+    };
+
+    match (funct7 & !3, funct3) {
+        (0x00, _) => three("fadd"),
+        (0x04, _) => three("fsub"),
+        (0x08, _) => three("fmul"),
+        (0x0c, _) => three("fdiv"),
+        (0x2c, _) => op(
+            &format!("fsqrt.{}", format),
+            format!("{}, {}", f(rd), f(rs1)),
+        ),
+        (0x10, 0..=2) if rs1 == rs2 => {
+            let name = ["fmv", "fneg", "fabs"][funct3 as usize];
+            op(
+                &format!("{}.{}", name, format),
+                format!("{}, {}", f(rd), f(rs1)),
+            )
+        }
+        (0x10, 0..=2) => three(["fsgnj", "fsgnjn", "fsgnjx"][funct3 as usize]),
+        (0x14, 0) => three("fmin"),
+        (0x14, 1) => three("fmax"),
+        (0x50, 0..=2) => op(
+            &format!("{}.{}", ["fle", "flt", "feq"][funct3 as usize], format),
+            format!("{}, {}, {}", x(rd), f(rs1), f(rs2)),
+        ),
+        (0x20, _) => {
+            let from = if format == "s" { "d" } else { "s" };
+            op(
+                &format!("fcvt.{}.{}", format, from),
+                format!("{}, {}", f(rd), f(rs1)),
+            )
+        }
+        (0x60, _) if rs2 < 4 => op(
+            &format!("fcvt.{}.{}", integer[rs2 as usize], format),
+            format!("{}, {}", x(rd), f(rs1)),
+        ),
+        (0x68, _) if rs2 < 4 => op(
+            &format!("fcvt.{}.{}", format, integer[rs2 as usize]),
+            format!("{}, {}", f(rd), x(rs1)),
+        ),
+        (0x70, 0) => {
+            let name = if format == "s" { "fmv.x.w" } else { "fmv.x.d" };
+            op(name, format!("{}, {}", x(rd), f(rs1)))
+        }
+        (0x70, 1) => op(
+            &format!("fclass.{}", format),
+            format!("{}, {}", x(rd), f(rs1)),
+        ),
+        (0x78, 0) => {
+            let name = if format == "s" { "fmv.w.x" } else { "fmv.d.x" };
+            op(name, format!("{}, {}", f(rd), x(rs1)))
+        }
+        _ => op(".word", format!("0x{:08x}", word)),
+    }
+}
+
+fn encode_i(opcode: u32, rd: u32, funct3: u32, rs1: u32, imm: i64) -> u32 {
+    ((imm as u32) & 0xfff) << 20 | rs1 << 15 | funct3 << 12 | rd << 7 | opcode
+}
+
+fn encode_s(opcode: u32, funct3: u32, rs1: u32, rs2: u32, imm: i64) -> u32 {
+    let imm = imm as u32;
+    ((imm >> 5) & 0x7f) << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12 | (imm & 31) << 7 | opcode
+}
+
+fn encode_r(opcode: u32, rd: u32, funct3: u32, rs1: u32, rs2: u32, funct7: u32) -> u32 {
+    funct7 << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12 | rd << 7 | opcode
+}
+
+fn encode_b(funct3: u32, rs1: u32, rs2: u32, offset: i64) -> u32 {
+    let imm = offset as u32;
+    ((imm >> 12) & 1) << 31
+        | ((imm >> 5) & 0x3f) << 25
+        | rs2 << 20
+        | rs1 << 15
+        | funct3 << 12
+        | ((imm >> 1) & 0xf) << 8
+        | ((imm >> 11) & 1) << 7
+        | 0x63
+}
+
+fn encode_j(rd: u32, offset: i64) -> u32 {
+    let imm = offset as u32;
+    ((imm >> 20) & 1) << 31
+        | ((imm >> 1) & 0x3ff) << 21
+        | ((imm >> 11) & 1) << 20
+        | ((imm >> 12) & 0xff) << 12
+        | rd << 7
+        | 0x6f
+}
+
+/// The 32-bit instruction a compressed one stands for
+fn expand_compressed(hw: u32, xlen: u32) -> Option<u32> {
+    let bit = |index: u32| (hw >> index) & 1;
+    let bits = |high: u32, low: u32| (hw >> low) & ((1 << (high - low + 1)) - 1);
+    let funct3 = hw >> 13;
+    let rd = bits(11, 7);
+    let rs2 = bits(6, 2);
+    // The 3-bit register fields of the common forms name x8..x15
+    let rd_short = 8 + bits(4, 2);
+    let rs1_short = 8 + bits(9, 7);
+    let imm6 = sign_extend(bit(12) << 5 | bits(6, 2), 6);
+    let offset_lw = (bits(12, 10) << 3 | bit(6) << 2 | bit(5) << 6) as i64;
+    let offset_ld = (bits(12, 10) << 3 | bits(6, 5) << 6) as i64;
+    let jump = || {
+        sign_extend(
+            bit(12) << 11
+                | bit(11) << 4
+                | bits(10, 9) << 8
+                | bit(8) << 10
+                | bit(7) << 6
+                | bit(6) << 7
+                | bits(5, 3) << 1
+                | bit(2) << 5,
+            12,
+        )
+    };
+
+    Some(match (hw & 3, funct3) {
+        (0, 0) => {
+            let offset = bits(12, 11) << 4 | bits(10, 7) << 6 | bit(6) << 2 | bit(5) << 3;
+            if offset == 0 {
+                return None;
+            }
+            encode_i(0x13, rd_short, 0, 2, offset as i64)
+        }
+        (0, 1) => encode_i(0x07, rd_short, 3, rs1_short, offset_ld),
+        (0, 2) => encode_i(0x03, rd_short, 2, rs1_short, offset_lw),
+        (0, 3) if xlen == 64 => encode_i(0x03, rd_short, 3, rs1_short, offset_ld),
+        (0, 3) => encode_i(0x07, rd_short, 2, rs1_short, offset_lw),
+        (0, 5) => encode_s(0x27, 3, rs1_short, rd_short, offset_ld),
+        (0, 6) => encode_s(0x23, 2, rs1_short, rd_short, offset_lw),
+        (0, 7) if xlen == 64 => encode_s(0x23, 3, rs1_short, rd_short, offset_ld),
+        (0, 7) => encode_s(0x27, 2, rs1_short, rd_short, offset_lw),
+        (1, 0) => encode_i(0x13, rd, 0, rd, imm6),
+        (1, 1) if xlen == 64 => encode_i(0x1b, rd, 0, rd, imm6),
+        (1, 1) => encode_j(1, jump()),
+        (1, 2) => encode_i(0x13, rd, 0, 0, imm6),
+        (1, 3) if rd == 2 => {
+            let offset = sign_extend(
+                bit(12) << 9 | bit(6) << 4 | bit(5) << 6 | bits(4, 3) << 7 | bit(2) << 5,
+                10,
+            );
+            encode_i(0x13, 2, 0, 2, offset)
+        }
+        (1, 3) => ((imm6 as u32) & 0xfffff) << 12 | rd << 7 | 0x37,
+        (1, 4) => {
+            let shamt = bit(12) << 5 | bits(6, 2);
+            match (bits(11, 10), bit(12), bits(6, 5)) {
+                (0, _, _) => encode_i(0x13, rs1_short, 5, rs1_short, shamt as i64),
+                (1, _, _) => encode_i(0x13, rs1_short, 5, rs1_short, (shamt | 0x400) as i64),
+                (2, _, _) => encode_i(0x13, rs1_short, 7, rs1_short, imm6),
+                (_, 0, operation) => {
+                    let (funct3, funct7) = [(0, 0x20), (4, 0), (6, 0), (7, 0)][operation as usize];
+                    encode_r(0x33, rs1_short, funct3, rs1_short, rd_short, funct7)
+                }
+                (_, _, 0) => encode_r(0x3b, rs1_short, 0, rs1_short, rd_short, 0x20),
+                (_, _, 1) => encode_r(0x3b, rs1_short, 0, rs1_short, rd_short, 0),
+                _ => return None,
+            }
+        }
+        (1, 5) => encode_j(0, jump()),
+        (1, 6 | 7) => {
+            let offset = sign_extend(
+                bit(12) << 8 | bits(11, 10) << 3 | bits(6, 5) << 6 | bits(4, 3) << 1 | bit(2) << 5,
+                9,
+            );
+            encode_b(funct3 - 6, rs1_short, 0, offset)
+        }
+        (2, 0) => encode_i(0x13, rd, 1, rd, (bit(12) << 5 | bits(6, 2)) as i64),
+        (2, 1) => {
+            let offset = bit(12) << 5 | bits(6, 5) << 3 | bits(4, 2) << 6;
+            encode_i(0x07, rd, 3, 2, offset as i64)
+        }
+        (2, 2) => {
+            let offset = bit(12) << 5 | bits(6, 4) << 2 | bits(3, 2) << 6;
+            encode_i(0x03, rd, 2, 2, offset as i64)
+        }
+        (2, 3) => {
+            if xlen == 64 {
+                let offset = bit(12) << 5 | bits(6, 5) << 3 | bits(4, 2) << 6;
+                encode_i(0x03, rd, 3, 2, offset as i64)
+            } else {
+                let offset = bit(12) << 5 | bits(6, 4) << 2 | bits(3, 2) << 6;
+                encode_i(0x07, rd, 2, 2, offset as i64)
+            }
+        }
+        (2, 4) => match (bit(12), rd, rs2) {
+            (0, 0, _) => return None,
+            (0, _, 0) => encode_i(0x67, 0, 0, rd, 0),
+            (0, _, _) => encode_r(0x33, rd, 0, 0, rs2, 0),
+            (_, 0, 0) => 0x0010_0073,
+            (_, _, 0) => encode_i(0x67, 1, 0, rd, 0),
+            _ => encode_r(0x33, rd, 0, rd, rs2, 0),
+        },
+        (2, 5) => encode_s(
+            0x27,
+            3,
+            2,
+            rs2,
+            (bits(12, 10) << 3 | bits(9, 7) << 6) as i64,
+        ),
+        (2, 6) => encode_s(0x23, 2, 2, rs2, (bits(12, 9) << 2 | bits(8, 7) << 6) as i64),
+        (2, 7) => {
+            if xlen == 64 {
+                encode_s(
+                    0x23,
+                    3,
+                    2,
+                    rs2,
+                    (bits(12, 10) << 3 | bits(9, 7) << 6) as i64,
+                )
+            } else {
+                encode_s(0x27, 2, 2, rs2, (bits(12, 9) << 2 | bits(8, 7) << 6) as i64)
+            }
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, RiscvState};
+
+    /// The address `lui a5, 0x80001; sw a0, -4(a5)` stores to
+    fn store_address(xlen: u32) -> Option<u64> {
+        let mut state = RiscvState::default();
+        decode(&0x8000_17b7u32.to_le_bytes(), 0x2000_0000, xlen, &mut state)?;
+        decode(&0xfea7_ae23u32.to_le_bytes(), 0x2000_0004, xlen, &mut state)?.literal
+    }
+
+    #[test]
+    fn upper_immediates_wrap_at_xlen() {
+        assert_eq!(store_address(32), Some(0x8000_0ffc));
+        assert_eq!(store_address(64), Some(0xffff_ffff_8000_0ffc));
+    }
+}
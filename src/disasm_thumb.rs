@@ -0,0 +1,1239 @@
+use crate::types::{DisassembledInstruction, Flow};
+
+const REGISTERS: [&str; 16] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc",
+];
+
+const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "", "",
+];
+
+const SHIFTS: [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+
+/// Decoded mnemonic and operands, before the IT condition is applied
+struct Decoded {
+    mnemonic: String,
+    operands: String,
+    target: Option<u64>,
+    literal: Option<u64>,
+    flow: Flow,
+    stack_push: u64,
+}
+
+fn op(mnemonic: &str, operands: String) -> Decoded {
+    Decoded {
+        mnemonic: mnemonic.to_string(),
+        operands,
+        target: None,
+        literal: None,
+        flow: Flow::Next,
+        stack_push: 0,
+    }
+}
+
+/// A branch or call to `target`, with `flow` telling which
+fn branch(mnemonic: &str, target: u64, flow: Flow) -> Decoded {
+    Decoded {
+        target: Some(target),
+        flow,
+        ..op(mnemonic, format!("0x{:x}", target))
+    }
+}
+
+/// BX, MOV or a load to PC: a return if it goes through LR
+fn jump(decoded: Decoded, rm: u32) -> Decoded {
+    Decoded {
+        flow: if rm == 14 {
+            Flow::Return
+        } else {
+            Flow::IndirectBranch
+        },
+        ..decoded
+    }
+}
+
+/// PUSH of the registers in `mask`
+fn push(mnemonic: &str, mask: u32) -> Decoded {
+    Decoded {
+        stack_push: mask.count_ones() as u64 * 4,
+        ..op(mnemonic, register_list(mask))
+    }
+}
+
+/// POP of the registers in `mask`, a return if it loads PC
+fn pop(mnemonic: &str, mask: u32) -> Decoded {
+    Decoded {
+        flow: if mask & 0x8000 != 0 {
+            Flow::Return
+        } else {
+            Flow::Next
+        },
+        ..op(mnemonic, register_list(mask))
+    }
+}
+
+fn r(index: u32) -> &'static str {
+    REGISTERS[index as usize & 15]
+}
+
+fn imm(value: i64) -> String {
+    match value {
+        -9..=9 => format!("#{}", value),
+        _ if value < 0 => format!("#-0x{:x}", -value),
+        _ => format!("#0x{:x}", value),
+    }
+}
+
+fn register_list(mask: u32) -> String {
+    let names: Vec<&str> = (0..16).filter(|i| mask & (1 << i) != 0).map(r).collect();
+    format!("{{{}}}", names.join(", "))
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    ((value << (32 - bits)) as i32 >> (32 - bits)) as i64
+}
+
+/// Decode one Thumb or Thumb-2 instruction. `it` holds the IT block state, the
+/// condition in the high nibble and the remaining mask below it, and is advanced.
+pub fn decode(data: &[u8], address: u64, it: &mut u8) -> Option<DisassembledInstruction> {
+    let halfword = |offset: usize| -> Option<u32> {
+        let bytes = data.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]) as u32)
+    };
+    let hw1 = halfword(0)?;
+    let in_it = *it & 0xf != 0;
+    let condition = CONDITIONS[(*it >> 4) as usize];
+
+    let (size, decoded) = if matches!(hw1 >> 11, 0b11101..=0b11111) {
+        let hw2 = halfword(2)?;
+        (4, decode_32(hw1, hw2, address))
+    } else {
+        (2, decode_16(hw1, address, in_it))
+    };
+
+    let mut mnemonic = decoded.mnemonic;
+    let mut flow = decoded.flow;
+    if in_it {
+        if let Flow::Branch(target) = flow {
+            flow = Flow::ConditionalBranch(target);
+        }
+        // The condition goes before a .w width suffix
+        match mnemonic.strip_suffix(".w") {
+            Some(base) => mnemonic = format!("{}{}.w", base, condition),
+            None => mnemonic.push_str(condition),
+        }
+        *it = if *it & 0x7 == 0 {
+            0
+        } else {
+            (*it & 0xe0) | ((*it << 1) & 0x1f)
+        };
+    }
+    if size == 2 && hw1 & 0xff00 == 0xbf00 && hw1 & 0xf != 0 {
+        // IT starts a new block after this instruction
+        *it = hw1 as u8;
+    }
+
+    Some(DisassembledInstruction {
+        address,
+        bytes: data[..size].to_vec(),
+        mnemonic,
+        operands: decoded.operands,
+        target: decoded.target,
+        literal: decoded.literal,
+        comment: None,
+        flow,
+        stack_push: decoded.stack_push,
+    })
+}
+
+fn decode_16(hw: u32, address: u64, in_it: bool) -> Decoded {
+    let pc = address + 4;
+    // 16-bit data processing sets the flags only outside IT blocks
+    let s = |name: &str| {
+        if in_it {
+            name.to_string()
+        } else {
+            format!("{}s", name)
+        }
+    };
+    let rd = hw & 7;
+    let rn = (hw >> 3) & 7;
+
+    match hw >> 11 {
+        0b00000..=0b00010 => {
+            let shift = (hw >> 6) & 0x1f;
+            let kind = hw >> 11;
+            if kind == 0 && shift == 0 {
+                return op(&s("mov"), format!("{}, {}", r(rd), r(rn)));
+            }
+            let shift = if shift == 0 { 32 } else { shift };
+            op(
+                &s(SHIFTS[kind as usize]),
+                format!("{}, {}, {}", r(rd), r(rn), imm(shift as i64)),
+            )
+        }
+        0b00011 => {
+            let name = if hw & 0x200 == 0 { "add" } else { "sub" };
+            let operand = if hw & 0x400 == 0 {
+                r((hw >> 6) & 7).to_string()
+            } else {
+                imm(((hw >> 6) & 7) as i64)
+            };
+            op(&s(name), format!("{}, {}, {}", r(rd), r(rn), operand))
+        }
+        0b00100..=0b00111 => {
+            let rd = (hw >> 8) & 7;
+            let value = imm((hw & 0xff) as i64);
+            match (hw >> 11) & 3 {
+                0 => op(&s("mov"), format!("{}, {}", r(rd), value)),
+                1 => op("cmp", format!("{}, {}", r(rd), value)),
+                2 => op(&s("add"), format!("{}, {}", r(rd), value)),
+                _ => op(&s("sub"), format!("{}, {}", r(rd), value)),
+            }
+        }
+        0b01000 if hw & 0x400 == 0 => {
+            const NAMES: [&str; 16] = [
+                "and", "eor", "lsl", "lsr", "asr", "adc", "sbc", "ror", "tst", "rsb", "cmp", "cmn",
+                "orr", "mul", "bic", "mvn",
+            ];
+            let opcode = (hw >> 6) & 0xf;
+            match opcode {
+                8 | 10 | 11 => op(NAMES[opcode as usize], format!("{}, {}", r(rd), r(rn))),
+                9 => op(&s("rsb"), format!("{}, {}, #0", r(rd), r(rn))),
+                13 => op(&s("mul"), format!("{}, {}, {}", r(rd), r(rn), r(rd))),
+                _ => op(&s(NAMES[opcode as usize]), format!("{}, {}", r(rd), r(rn))),
+            }
+        }
+        0b01000 => {
+            let rdn = (hw & 7) | ((hw >> 4) & 8);
+            let rm = (hw >> 3) & 0xf;
+            match (hw >> 8) & 3 {
+                0 => op("add", format!("{}, {}", r(rdn), r(rm))),
+                1 => op("cmp", format!("{}, {}", r(rdn), r(rm))),
+                2 if rdn == 15 => jump(op("mov", format!("pc, {}", r(rm))), rm),
+                2 => op("mov", format!("{}, {}", r(rdn), r(rm))),
+                _ if hw & 0x80 == 0 => jump(op("bx", r(rm).to_string()), rm),
+                _ => Decoded {
+                    flow: Flow::IndirectCall,
+                    ..op("blx", r(rm).to_string())
+                },
+            }
+        }
+        0b01001 => {
+            let offset = (hw & 0xff) * 4;
+            let literal = (pc & !3) + offset as u64;
+            Decoded {
+                literal: Some(literal),
+                ..op(
+                    "ldr",
+                    format!("{}, [pc, {}]", r((hw >> 8) & 7), imm(offset as i64)),
+                )
+            }
+        }
+        0b01010 | 0b01011 => {
+            const NAMES: [&str; 8] = [
+                "str", "strh", "strb", "ldrsb", "ldr", "ldrh", "ldrb", "ldrsh",
+            ];
+            op(
+                NAMES[((hw >> 9) & 7) as usize],
+                format!("{}, [{}, {}]", r(rd), r(rn), r((hw >> 6) & 7)),
+            )
+        }
+        0b01100..=0b10001 => {
+            let (name, scale) = match (hw >> 11, hw & 0x800 != 0) {
+                (0b01100, _) => ("str", 4),
+                (0b01101, _) => ("ldr", 4),
+                (0b01110, _) => ("strb", 1),
+                (0b01111, _) => ("ldrb", 1),
+                (_, false) => ("strh", 2),
+                (_, true) => ("ldrh", 2),
+            };
+            let offset = ((hw >> 6) & 0x1f) * scale;
+            op(
+                name,
+                format!("{}, [{}, {}]", r(rd), r(rn), imm(offset as i64)),
+            )
+        }
+        0b10010 | 0b10011 => {
+            let name = if hw & 0x800 == 0 { "str" } else { "ldr" };
+            let offset = (hw & 0xff) * 4;
+            op(
+                name,
+                format!("{}, [sp, {}]", r((hw >> 8) & 7), imm(offset as i64)),
+            )
+        }
+        0b10100 => {
+            let target = (pc & !3) + (hw & 0xff) as u64 * 4;
+            Decoded {
+                target: Some(target),
+                ..op("adr", format!("{}, 0x{:x}", r((hw >> 8) & 7), target))
+            }
+        }
+        0b10101 => op(
+            "add",
+            format!("{}, sp, {}", r((hw >> 8) & 7), imm((hw & 0xff) as i64 * 4)),
+        ),
+        0b10110 | 0b10111 => decode_misc_16(hw, pc),
+        0b11000 => op(
+            "stmia",
+            format!("{}!, {}", r((hw >> 8) & 7), register_list(hw & 0xff)),
+        ),
+        0b11001 => {
+            let rn = (hw >> 8) & 7;
+            let writeback = if hw & (1 << rn) == 0 { "!" } else { "" };
+            op(
+                "ldmia",
+                format!("{}{}, {}", r(rn), writeback, register_list(hw & 0xff)),
+            )
+        }
+        0b11010 | 0b11011 => match (hw >> 8) & 0xf {
+            0xe => op("udf", imm((hw & 0xff) as i64)),
+            0xf => op("svc", imm((hw & 0xff) as i64)),
+            condition => {
+                let target = (pc as i64 + sign_extend((hw & 0xff) << 1, 9)) as u64;
+                branch(
+                    &format!("b{}", CONDITIONS[condition as usize]),
+                    target,
+                    Flow::ConditionalBranch(target),
+                )
+            }
+        },
+        _ => {
+            let target = (pc as i64 + sign_extend((hw & 0x7ff) << 1, 12)) as u64;
+            branch("b", target, Flow::Branch(target))
+        }
+    }
+}
+
+fn decode_misc_16(hw: u32, pc: u64) -> Decoded {
+    let rd = hw & 7;
+    let rm = (hw >> 3) & 7;
+    match (hw >> 8) & 0xf {
+        0b0000 if hw & 0x80 == 0 => op("add", format!("sp, {}", imm((hw & 0x7f) as i64 * 4))),
+        0b0000 => Decoded {
+            stack_push: (hw & 0x7f) as u64 * 4,
+            ..op("sub", format!("sp, {}", imm((hw & 0x7f) as i64 * 4)))
+        },
+        0b0001 | 0b0011 | 0b1001 | 0b1011 => {
+            let name = if hw & 0x800 == 0 { "cbz" } else { "cbnz" };
+            let offset = ((hw >> 9) & 1) << 6 | ((hw >> 3) & 0x1f) << 1;
+            let target = pc + offset as u64;
+            Decoded {
+                target: Some(target),
+                flow: Flow::ConditionalBranch(target),
+                ..op(name, format!("{}, 0x{:x}", r(rd), target))
+            }
+        }
+        0b0010 => {
+            const NAMES: [&str; 4] = ["sxth", "sxtb", "uxth", "uxtb"];
+            op(
+                NAMES[((hw >> 6) & 3) as usize],
+                format!("{}, {}", r(rd), r(rm)),
+            )
+        }
+        0b0100 | 0b0101 => push("push", (hw & 0xff) | ((hw & 0x100) << 6)),
+        0b0110 if hw & 0xffe8 == 0xb660 => {
+            let name = if hw & 0x10 == 0 { "cpsie" } else { "cpsid" };
+            let flags: String = [(4, 'a'), (2, 'i'), (1, 'f')]
+                .iter()
+                .filter(|(bit, _)| hw & bit != 0)
+                .map(|(_, flag)| *flag)
+                .collect();
+            op(name, flags)
+        }
+        0b1010 => match (hw >> 6) & 3 {
+            0 => op("rev", format!("{}, {}", r(rd), r(rm))),
+            1 => op("rev16", format!("{}, {}", r(rd), r(rm))),
+            3 => op("revsh", format!("{}, {}", r(rd), r(rm))),
+            _ => op(".short", format!("0x{:04x}", hw)),
+        },
+        0b1100 | 0b1101 => pop("pop", (hw & 0xff) | ((hw & 0x100) << 7)),
+        0b1110 => op("bkpt", format!("0x{:04x}", hw & 0xff)),
+        0b1111 if hw & 0xf != 0 => {
+            let first = (hw >> 4) & 0xf;
+            let mask = hw & 0xf;
+            let mut name = "it".to_string();
+            for bit in (mask.trailing_zeros() + 1..4).rev() {
+                name.push(if (mask >> bit) & 1 == first & 1 {
+                    't'
+                } else {
+                    'e'
+                });
+            }
+            op(&name, CONDITIONS[first as usize].to_string())
+        }
+        0b1111 => match (hw >> 4) & 0xf {
+            0 => op("nop", String::new()),
+            1 => op("yield", String::new()),
+            2 => op("wfe", String::new()),
+            3 => op("wfi", String::new()),
+            4 => op("sev", String::new()),
+            _ => op(".short", format!("0x{:04x}", hw)),
+        },
+        _ => op(".short", format!("0x{:04x}", hw)),
+    }
+}
+
+fn decode_32(hw1: u32, hw2: u32, address: u64) -> Decoded {
+    let pc = address + 4;
+    let rn = hw1 & 0xf;
+    let rd = (hw2 >> 8) & 0xf;
+    let rt = (hw2 >> 12) & 0xf;
+    let rm = hw2 & 0xf;
+    let unknown = || op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2));
+
+    if hw1 & 0xfe40 == 0xe800 {
+        // Load/store multiple
+        let writeback = hw1 & 0x20 != 0;
+        let load = hw1 & 0x10 != 0;
+        let list = register_list(hw2);
+        return match ((hw1 >> 7) & 3, load) {
+            (1, true) if rn == 13 && writeback => pop("pop.w", hw2),
+            (2, false) if rn == 13 && writeback => push("push.w", hw2),
+            (1 | 2, _) => {
+                let name = match ((hw1 >> 7) & 3, load) {
+                    (1, false) => "stmia.w",
+                    (1, true) => "ldmia.w",
+                    (_, false) => "stmdb",
+                    (_, true) => "ldmdb",
+                };
+                op(
+                    name,
+                    format!("{}{}, {}", r(rn), if writeback { "!" } else { "" }, list),
+                )
+            }
+            _ => unknown(),
+        };
+    }
+
+    if hw1 & 0xfe40 == 0xe840 {
+        // Load/store dual or exclusive, table branch
+        let imm8 = (hw2 & 0xff) as i64 * 4;
+        return match ((hw1 >> 7) & 3, (hw1 >> 4) & 3) {
+            (0, 0) => op(
+                "strex",
+                format!("{}, {}, [{}, {}]", r(rd), r(rt), r(rn), imm(imm8)),
+            ),
+            (0, 1) => op("ldrex", format!("{}, [{}, {}]", r(rt), r(rn), imm(imm8))),
+            (1, 1) if (hw2 >> 4) & 0xf <= 1 => {
+                let decoded = if hw2 & 0x10 == 0 {
+                    op("tbb", format!("[{}, {}]", r(rn), r(rm)))
+                } else {
+                    op("tbh", format!("[{}, {}, lsl #1]", r(rn), r(rm)))
+                };
+                Decoded {
+                    flow: Flow::Switch,
+                    ..decoded
+                }
+            }
+            (1, 1) if (hw2 >> 4) & 0xe == 4 => {
+                let name = if hw2 & 0x10 == 0 { "ldrexb" } else { "ldrexh" };
+                op(name, format!("{}, [{}]", r(rt), r(rn)))
+            }
+            (1, 0) if (hw2 >> 4) & 0xe == 4 => {
+                let name = if hw2 & 0x10 == 0 { "strexb" } else { "strexh" };
+                op(name, format!("{}, {}, [{}]", r(rm), r(rt), r(rn)))
+            }
+            (1, 0 | 1) => unknown(),
+            (_, op2) => {
+                let name = if op2 & 1 == 0 { "strd" } else { "ldrd" };
+                let offset = if hw1 & 0x80 == 0 { -imm8 } else { imm8 };
+                let address = memory_operand(rn, offset, hw1 & 0x100 != 0, hw1 & 0x20 != 0);
+                let literal =
+                    (rn == 15 && op2 & 1 != 0).then(|| ((pc & !3) as i64 + offset) as u64);
+                Decoded {
+                    literal,
+                    ..op(name, format!("{}, {}, {}", r(rt), r(rd), address))
+                }
+            }
+        };
+    }
+
+    if hw1 & 0xfe00 == 0xea00 {
+        return decode_shifted_register(hw1, hw2);
+    }
+
+    if hw1 & 0xf800 == 0xf000 && hw2 & 0x8000 != 0 {
+        return decode_branch(hw1, hw2, pc);
+    }
+
+    if hw1 & 0xfa00 == 0xf000 {
+        return decode_modified_immediate(hw1, hw2);
+    }
+
+    if hw1 & 0xfa00 == 0xf200 {
+        return decode_plain_immediate(hw1, hw2, pc);
+    }
+
+    if hw1 & 0xfe00 == 0xf800 && hw1 & 0x70 != 0x70 {
+        let decoded = decode_load_store(hw1, hw2, pc);
+        return match decoded.mnemonic.as_str() {
+            // Loads to PC other than POP jump, e.g. through a table of addresses
+            name if rt == 15 && name.starts_with("ldr") => Decoded {
+                flow: Flow::IndirectBranch,
+                ..decoded
+            },
+            _ => decoded,
+        };
+    }
+
+    if hw1 & 0xff80 == 0xfa00 && hw2 & 0xf000 == 0xf000 {
+        // Register-controlled shifts and extends
+        let set_flags = if hw1 & 0x10 != 0 { "s" } else { "" };
+        if hw2 & 0x80 == 0 {
+            let name = format!("{}{}.w", SHIFTS[((hw1 >> 5) & 3) as usize], set_flags);
+            return op(&name, format!("{}, {}, {}", r(rd), r(rn), r(rm)));
+        }
+        let name = match (hw1 >> 4) & 0xf {
+            0 => "sxth",
+            1 => "uxth",
+            4 => "sxtb",
+            5 => "uxtb",
+            _ => return unknown(),
+        };
+        let rotation = match (hw2 >> 4) & 3 {
+            0 => String::new(),
+            rotate => format!(", ror #{}", rotate * 8),
+        };
+        return if rn == 15 {
+            op(
+                &format!("{}.w", name),
+                format!("{}, {}{}", r(rd), r(rm), rotation),
+            )
+        } else {
+            let name = format!("{}a{}", &name[..2], &name[2..]);
+            op(
+                &name,
+                format!("{}, {}, {}{}", r(rd), r(rn), r(rm), rotation),
+            )
+        };
+    }
+
+    if hw1 & 0xffc0 == 0xfa80 && hw2 & 0xf0c0 == 0xf080 {
+        // Miscellaneous operations
+        let name = match ((hw1 >> 4) & 3, (hw2 >> 4) & 3) {
+            (1, 0) => "rev.w",
+            (1, 1) => "rev16.w",
+            (1, 2) => "rbit",
+            (1, 3) => "revsh.w",
+            (3, 0) => "clz",
+            _ => return unknown(),
+        };
+        return op(name, format!("{}, {}", r(rd), r(rm)));
+    }
+
+    if hw1 & 0xff80 == 0xfb00 {
+        return match ((hw1 >> 4) & 7, (hw2 >> 4) & 3) {
+            (0, 0) if rt == 15 => op("mul", format!("{}, {}, {}", r(rd), r(rn), r(rm))),
+            (0, 0) => op("mla", format!("{}, {}, {}, {}", r(rd), r(rn), r(rm), r(rt))),
+            (0, 1) => op("mls", format!("{}, {}, {}, {}", r(rd), r(rn), r(rm), r(rt))),
+            _ => unknown(),
+        };
+    }
+
+    if hw1 & 0xff80 == 0xfb80 {
+        let long = |name: &str| op(name, format!("{}, {}, {}, {}", r(rt), r(rd), r(rn), r(rm)));
+        return match ((hw1 >> 4) & 7, (hw2 >> 4) & 0xf) {
+            (0, 0) => long("smull"),
+            (1, 0xf) => op("sdiv", format!("{}, {}, {}", r(rd), r(rn), r(rm))),
+            (2, 0) => long("umull"),
+            (3, 0xf) => op("udiv", format!("{}, {}, {}", r(rd), r(rn), r(rm))),
+            (4, 0) => long("smlal"),
+            (6, 0) => long("umlal"),
+            _ => unknown(),
+        };
+    }
+
+    if hw1 & 0xec00 == 0xec00 {
+        return decode_vfp(hw1, hw2, pc).unwrap_or_else(unknown);
+    }
+
+    unknown()
+}
+
+/// `[rn, #offset]`, `[rn, #offset]!` or `[rn], #offset`
+fn memory_operand(rn: u32, offset: i64, index: bool, writeback: bool) -> String {
+    match (index, writeback) {
+        (true, false) if offset == 0 => format!("[{}]", r(rn)),
+        (true, false) => format!("[{}, {}]", r(rn), imm(offset)),
+        (true, true) => format!("[{}, {}]!", r(rn), imm(offset)),
+        (false, _) => format!("[{}], {}", r(rn), imm(offset)),
+    }
+}
+
+fn decode_shifted_register(hw1: u32, hw2: u32) -> Decoded {
+    let rn = hw1 & 0xf;
+    let rd = (hw2 >> 8) & 0xf;
+    let rm = hw2 & 0xf;
+    let set_flags = hw1 & 0x10 != 0;
+    let kind = (hw2 >> 4) & 3;
+    let amount = ((hw2 >> 12) & 7) << 2 | ((hw2 >> 6) & 3);
+
+    let shift = match (kind, amount) {
+        (0, 0) => String::new(),
+        (3, 0) => ", rrx".to_string(),
+        (1 | 2, 0) => format!(", {} #32", SHIFTS[kind as usize]),
+        _ => format!(", {} #{}", SHIFTS[kind as usize], amount),
+    };
+    let s = if set_flags { "s" } else { "" };
+
+    let opcode = (hw1 >> 5) & 0xf;
+    let compare = match opcode {
+        0 => "tst",
+        4 => "teq",
+        8 => "cmn",
+        13 => "cmp",
+        _ => "",
+    };
+    if rd == 15 && set_flags && !compare.is_empty() {
+        return op(
+            &format!("{}.w", compare),
+            format!("{}, {}{}", r(rn), r(rm), shift),
+        );
+    }
+    if rn == 15 && opcode == 2 {
+        // MOV and immediate shifts are ORR with PC as the first operand
+        return match (kind, amount) {
+            (0, 0) => op(&format!("mov{}.w", s), format!("{}, {}", r(rd), r(rm))),
+            (3, 0) => op(&format!("rrx{}", s), format!("{}, {}", r(rd), r(rm))),
+            _ => op(
+                &format!("{}{}.w", SHIFTS[kind as usize], s),
+                format!(
+                    "{}, {}, #{}",
+                    r(rd),
+                    r(rm),
+                    if amount == 0 { 32 } else { amount }
+                ),
+            ),
+        };
+    }
+    if rn == 15 && opcode == 3 {
+        return op(
+            &format!("mvn{}.w", s),
+            format!("{}, {}{}", r(rd), r(rm), shift),
+        );
+    }
+
+    let name = match opcode {
+        0 => "and",
+        1 => "bic",
+        2 => "orr",
+        3 => "orn",
+        4 => "eor",
+        6 => "pkh",
+        8 => "add",
+        10 => "adc",
+        11 => "sbc",
+        13 => "sub",
+        14 => "rsb",
+        _ => return op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+    };
+    op(
+        &format!("{}{}.w", name, s),
+        format!("{}, {}, {}{}", r(rd), r(rn), r(rm), shift),
+    )
+}
+
+/// The modified immediate constants of Thumb-2 data processing instructions
+fn thumb_expand_imm(imm12: u32) -> u32 {
+    let imm8 = imm12 & 0xff;
+    if imm12 >> 10 == 0 {
+        match (imm12 >> 8) & 3 {
+            0 => imm8,
+            1 => imm8 << 16 | imm8,
+            2 => imm8 << 24 | imm8 << 8,
+            _ => imm8 * 0x0101_0101,
+        }
+    } else {
+        (0x80 | (imm12 & 0x7f)).rotate_right(imm12 >> 7)
+    }
+}
+
+fn decode_modified_immediate(hw1: u32, hw2: u32) -> Decoded {
+    let rn = hw1 & 0xf;
+    let rd = (hw2 >> 8) & 0xf;
+    let set_flags = hw1 & 0x10 != 0;
+    let s = if set_flags { "s" } else { "" };
+    let imm12 = ((hw1 >> 10) & 1) << 11 | ((hw2 >> 12) & 7) << 8 | (hw2 & 0xff);
+    let value = imm(thumb_expand_imm(imm12) as i64);
+
+    let opcode = (hw1 >> 5) & 0xf;
+    let compare = match opcode {
+        0 => "tst",
+        4 => "teq",
+        8 => "cmn",
+        13 => "cmp",
+        _ => "",
+    };
+    if rd == 15 && set_flags && !compare.is_empty() {
+        return op(&format!("{}.w", compare), format!("{}, {}", r(rn), value));
+    }
+    match (opcode, rn) {
+        (2, 15) => return op(&format!("mov{}.w", s), format!("{}, {}", r(rd), value)),
+        (3, 15) => return op(&format!("mvn{}", s), format!("{}, {}", r(rd), value)),
+        _ => {}
+    }
+
+    let name = match opcode {
+        0 => "and",
+        1 => "bic",
+        2 => "orr",
+        3 => "orn",
+        4 => "eor",
+        8 => "add",
+        10 => "adc",
+        11 => "sbc",
+        13 => "sub",
+        14 => "rsb",
+        _ => return op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+    };
+    Decoded {
+        stack_push: if opcode == 13 && rd == 13 && rn == 13 {
+            thumb_expand_imm(imm12) as u64
+        } else {
+            0
+        },
+        ..op(
+            &format!("{}{}.w", name, s),
+            format!("{}, {}, {}", r(rd), r(rn), value),
+        )
+    }
+}
+
+fn decode_plain_immediate(hw1: u32, hw2: u32, pc: u64) -> Decoded {
+    let rn = hw1 & 0xf;
+    let rd = (hw2 >> 8) & 0xf;
+    let imm12 = ((hw1 >> 10) & 1) << 11 | ((hw2 >> 12) & 7) << 8 | (hw2 & 0xff);
+    let imm16 = (hw1 & 0xf) << 12 | imm12;
+    let lsb = ((hw2 >> 12) & 7) << 2 | ((hw2 >> 6) & 3);
+    let bits = hw2 & 0x1f;
+
+    match (hw1 >> 4) & 0x1f {
+        0b00000 | 0b01010 if rn == 15 => {
+            let base = (pc & !3) as i64;
+            let target = if hw1 & 0x0020 == 0 {
+                base + imm12 as i64
+            } else {
+                base - imm12 as i64
+            } as u64;
+            Decoded {
+                target: Some(target),
+                ..op("adr.w", format!("{}, 0x{:x}", r(rd), target))
+            }
+        }
+        0b00000 => op(
+            "addw",
+            format!("{}, {}, {}", r(rd), r(rn), imm(imm12 as i64)),
+        ),
+        0b01010 => Decoded {
+            stack_push: if rd == 13 && rn == 13 {
+                imm12 as u64
+            } else {
+                0
+            },
+            ..op(
+                "subw",
+                format!("{}, {}, {}", r(rd), r(rn), imm(imm12 as i64)),
+            )
+        },
+        0b00100 => op("movw", format!("{}, {}", r(rd), imm(imm16 as i64))),
+        0b01100 => op("movt", format!("{}, {}", r(rd), imm(imm16 as i64))),
+        0b10100 => op(
+            "sbfx",
+            format!("{}, {}, #{}, #{}", r(rd), r(rn), lsb, bits + 1),
+        ),
+        0b11100 => op(
+            "ubfx",
+            format!("{}, {}, #{}, #{}", r(rd), r(rn), lsb, bits + 1),
+        ),
+        // An msb below the lsb is UNPREDICTABLE, but turns up in data decoded as code
+        0b10110 => match bits.checked_sub(lsb) {
+            Some(width) if rn == 15 => op("bfc", format!("{}, #{}, #{}", r(rd), lsb, width + 1)),
+            Some(width) => op(
+                "bfi",
+                format!("{}, {}, #{}, #{}", r(rd), r(rn), lsb, width + 1),
+            ),
+            None => op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+        },
+        0b10000 | 0b10010 => op("ssat", format!("{}, #{}, {}", r(rd), bits + 1, r(rn))),
+        0b11000 | 0b11010 => op("usat", format!("{}, #{}, {}", r(rd), bits, r(rn))),
+        _ => op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+    }
+}
+
+fn special_register(sysm: u32) -> String {
+    match sysm {
+        0 => "apsr",
+        1 => "iapsr",
+        2 => "eapsr",
+        3 => "xpsr",
+        5 => "ipsr",
+        6 => "epsr",
+        7 => "iepsr",
+        8 => "msp",
+        9 => "psp",
+        10 => "msplim",
+        11 => "psplim",
+        16 => "primask",
+        17 => "basepri",
+        18 => "basepri_max",
+        19 => "faultmask",
+        20 => "control",
+        _ => return format!("sysm{}", sysm),
+    }
+    .to_string()
+}
+
+fn decode_branch(hw1: u32, hw2: u32, pc: u64) -> Decoded {
+    let s = (hw1 >> 10) & 1;
+    let j1 = (hw2 >> 13) & 1;
+    let j2 = (hw2 >> 11) & 1;
+    let imm11 = hw2 & 0x7ff;
+    let long_offset = || {
+        let i1 = !(j1 ^ s) & 1;
+        let i2 = !(j2 ^ s) & 1;
+        sign_extend(
+            s << 24 | i1 << 23 | i2 << 22 | (hw1 & 0x3ff) << 12 | imm11 << 1,
+            25,
+        )
+    };
+
+    match (hw2 >> 12) & 5 {
+        5 => {
+            let target = (pc as i64 + long_offset()) as u64;
+            branch("bl", target, Flow::Call(target))
+        }
+        // BLX to ARM code, relative to the word-aligned PC
+        4 => {
+            let target = ((pc & !3) as i64 + long_offset()) as u64;
+            branch("blx", target, Flow::Call(target))
+        }
+        1 => {
+            let target = (pc as i64 + long_offset()) as u64;
+            branch("b.w", target, Flow::Branch(target))
+        }
+        _ if (hw1 >> 6) & 0xe != 0xe => {
+            let offset = s << 20 | j2 << 19 | j1 << 18 | (hw1 & 0x3f) << 12 | imm11 << 1;
+            let name = format!("b{}.w", CONDITIONS[((hw1 >> 6) & 0xf) as usize]);
+            let target = (pc as i64 + sign_extend(offset, 21)) as u64;
+            branch(&name, target, Flow::ConditionalBranch(target))
+        }
+        _ => match (hw1 >> 4) & 0x7f {
+            0x38 | 0x39 => op(
+                "msr",
+                format!("{}, {}", special_register(hw2 & 0xff), r(hw1 & 0xf)),
+            ),
+            0x3a => match hw2 & 0xff {
+                0 => op("nop.w", String::new()),
+                1 => op("yield.w", String::new()),
+                2 => op("wfe.w", String::new()),
+                3 => op("wfi.w", String::new()),
+                4 => op("sev.w", String::new()),
+                _ => op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+            },
+            0x3b => match (hw2 >> 4) & 0xf {
+                2 => op("clrex", String::new()),
+                4 => op("dsb", barrier_option(hw2 & 0xf)),
+                5 => op("dmb", barrier_option(hw2 & 0xf)),
+                6 => op("isb", barrier_option(hw2 & 0xf)),
+                _ => op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+            },
+            0x3e | 0x3f => op(
+                "mrs",
+                format!("{}, {}", r((hw2 >> 8) & 0xf), special_register(hw2 & 0xff)),
+            ),
+            0x7f if hw2 & 0x7000 == 0x2000 => {
+                op("udf.w", imm(((hw1 & 0xf) << 12 | (hw2 & 0xfff)) as i64))
+            }
+            _ => op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2)),
+        },
+    }
+}
+
+fn barrier_option(option: u32) -> String {
+    match option {
+        0xf => "sy".to_string(),
+        0xe => "st".to_string(),
+        0xb => "ish".to_string(),
+        0xa => "ishst".to_string(),
+        0x7 => "nsh".to_string(),
+        0x3 => "osh".to_string(),
+        _ => format!("#{}", option),
+    }
+}
+
+fn decode_load_store(hw1: u32, hw2: u32, pc: u64) -> Decoded {
+    let rn = hw1 & 0xf;
+    let rt = (hw2 >> 12) & 0xf;
+    let load = hw1 & 0x10 != 0;
+    let signed = hw1 & 0x100 != 0;
+    let size = (hw1 >> 5) & 3;
+    let suffix = ["b", "h", "", ""][size as usize];
+
+    let name = match (load, signed) {
+        // Byte and halfword loads to PC are preload hints
+        (true, _) if rt == 15 && size < 2 => {
+            if signed {
+                "pli".to_string()
+            } else {
+                "pld".to_string()
+            }
+        }
+        (true, true) => format!("ldrs{}", suffix),
+        (true, false) => format!("ldr{}", suffix),
+        (false, _) => format!("str{}", suffix),
+    };
+    let hint = name.starts_with("pl");
+    let with_rt = |address: String| {
+        if hint {
+            address
+        } else {
+            format!("{}, {}", r(rt), address)
+        }
+    };
+
+    if rn == 15 && load {
+        let offset = (hw2 & 0xfff) as i64;
+        let offset = if hw1 & 0x80 == 0 { -offset } else { offset };
+        return Decoded {
+            literal: Some(((pc & !3) as i64 + offset) as u64),
+            ..op(
+                &format!("{}.w", name),
+                with_rt(format!("[pc, {}]", imm(offset))),
+            )
+        };
+    }
+    if hw1 & 0x80 != 0 {
+        let offset = (hw2 & 0xfff) as i64;
+        let address = memory_operand(rn, offset, true, false);
+        return op(&format!("{}.w", name), with_rt(address));
+    }
+    if hw2 & 0x800 != 0 {
+        let offset = (hw2 & 0xff) as i64;
+        let offset = if hw2 & 0x200 == 0 { -offset } else { offset };
+        let (index, writeback) = (hw2 & 0x400 != 0, hw2 & 0x100 != 0);
+        if hw2 & 0xf00 == 0xe00 {
+            // Unprivileged access
+            let address = memory_operand(rn, offset, true, false);
+            return op(&format!("{}t", name), with_rt(address));
+        }
+        if rn == 13 && !index && writeback && offset == 4 && load && size == 2 {
+            return pop("pop.w", 1 << rt);
+        }
+        if rn == 13 && index && writeback && offset == -4 && !load && size == 2 {
+            return push("push.w", 1 << rt);
+        }
+        return op(&name, with_rt(memory_operand(rn, offset, index, writeback)));
+    }
+    if hw2 & 0xfc0 == 0 {
+        let rm = hw2 & 0xf;
+        let shift = match (hw2 >> 4) & 3 {
+            0 => String::new(),
+            amount => format!(", lsl #{}", amount),
+        };
+        return op(
+            &format!("{}.w", name),
+            with_rt(format!("[{}, {}{}]", r(rn), r(rm), shift)),
+        );
+    }
+    op(".inst.w", format!("0x{:04x}{:04x}", hw1, hw2))
+}
+
+fn decode_vfp(hw1: u32, hw2: u32, pc: u64) -> Option<Decoded> {
+    // Only the floating-point extension, coprocessors 10 and 11
+    if hw2 & 0x0e00 != 0x0a00 {
+        return None;
+    }
+    let double = hw2 & 0x100 != 0;
+    let rn = hw1 & 0xf;
+    let d = (hw1 >> 6) & 1;
+    let vd = (hw2 >> 12) & 0xf;
+    let n = (hw2 >> 7) & 1;
+    let vn = hw1 & 0xf;
+    let m = (hw2 >> 5) & 1;
+    let vm = hw2 & 0xf;
+    let register = |high: u32, low_bit: u32| {
+        if double {
+            format!("d{}", low_bit << 4 | high)
+        } else {
+            format!("s{}", high << 1 | low_bit)
+        }
+    };
+    let (dd, dn, dm) = (register(vd, d), register(vn, n), register(vm, m));
+
+    if hw1 & 0xfe00 == 0xec00 && hw1 & 0x0120 != 0 {
+        let load = hw1 & 0x10 != 0;
+        let add = hw1 & 0x80 != 0;
+        let imm8 = hw2 & 0xff;
+        let words = if double { imm8 / 2 } else { imm8 };
+        if hw1 & 0x0120 == 0x0100 {
+            // VLDR/VSTR
+            let offset = imm8 as i64 * 4;
+            let offset = if add { offset } else { -offset };
+            let name = if load { "vldr" } else { "vstr" };
+            let literal = (rn == 15).then(|| ((pc & !3) as i64 + offset) as u64);
+            return Some(Decoded {
+                literal,
+                ..op(
+                    name,
+                    format!("{}, {}", dd, memory_operand(rn, offset, true, false)),
+                )
+            });
+        }
+        let first = if double { d << 4 | vd } else { vd << 1 | d };
+        let prefix = if double { "d" } else { "s" };
+        let list: Vec<String> = (first..first + words)
+            .map(|index| format!("{}{}", prefix, index))
+            .collect();
+        let list = format!("{{{}}}", list.join(", "));
+        return Some(match (load, add, rn == 13 && hw1 & 0x20 != 0) {
+            (true, true, true) => op("vpop", list),
+            (false, false, true) => Decoded {
+                stack_push: imm8 as u64 * 4,
+                ..op("vpush", list)
+            },
+            _ => {
+                let name = match (load, add) {
+                    (true, true) => "vldmia",
+                    (true, false) => "vldmdb",
+                    (false, true) => "vstmia",
+                    (false, false) => "vstmdb",
+                };
+                let writeback = if hw1 & 0x20 != 0 { "!" } else { "" };
+                op(name, format!("{}{}, {}", r(rn), writeback, list))
+            }
+        });
+    }
+
+    if hw1 & 0xff00 != 0xee00 {
+        return None;
+    }
+    if hw2 & 0x10 != 0 {
+        // Transfers between core and floating-point registers
+        let rt = r((hw2 >> 12) & 0xf);
+        return match (hw1 & 0xff, hw2 & 0x100 != 0) {
+            (0xf1, false) if vd == 15 => Some(op("vmrs", "APSR_nzcv, fpscr".to_string())),
+            (0xf1, false) => Some(op("vmrs", format!("{}, fpscr", rt))),
+            (0xe1, false) => Some(op("vmsr", format!("fpscr, {}", rt))),
+            (opcode, false) if opcode & 0xe0 == 0 => {
+                let sn = format!("s{}", vn << 1 | n);
+                Some(if hw1 & 0x10 != 0 {
+                    op("vmov", format!("{}, {}", rt, sn))
+                } else {
+                    op("vmov", format!("{}, {}", sn, rt))
+                })
+            }
+            _ => None,
+        };
+    }
+
+    let size = if double { ".f64" } else { ".f32" };
+    let three = |name: &str| {
+        Some(op(
+            &format!("{}{}", name, size),
+            format!("{}, {}, {}", dd, dn, dm),
+        ))
+    };
+    let negate = hw2 & 0x40 != 0;
+    match (hw1 >> 4) & 0xb {
+        0b0000 => three(if negate { "vmls" } else { "vmla" }),
+        0b0010 if !negate => three("vmul"),
+        0b0010 => three("vnmul"),
+        0b0011 => three(if negate { "vsub" } else { "vadd" }),
+        0b1000 if !negate => three("vdiv"),
+        0b1011 if !negate => {
+            let value = (hw1 & 0xf) << 4 | (hw2 & 0xf);
+            Some(op(
+                &format!("vmov{}", size),
+                format!("{}, #{}", dd, vfp_expand_imm(value)),
+            ))
+        }
+        0b1011 => {
+            let two = |name: &str, operand: String| Some(op(name, format!("{}, {}", dd, operand)));
+            match (hw1 & 0xf, hw2 & 0x80 != 0) {
+                (0, false) => two(&format!("vmov{}", size), dm),
+                (0, true) => two(&format!("vabs{}", size), dm),
+                (1, false) => two(&format!("vneg{}", size), dm),
+                (1, true) => two(&format!("vsqrt{}", size), dm),
+                (4, quiet) => two(&format!("vcmp{}{}", if quiet { "e" } else { "" }, size), dm),
+                (5, quiet) => two(
+                    &format!("vcmp{}{}", if quiet { "e" } else { "" }, size),
+                    "#0.0".to_string(),
+                ),
+                (7, true) => {
+                    let (to, from) = if double {
+                        (".f32", ".f64")
+                    } else {
+                        (".f64", ".f32")
+                    };
+                    let dd = if double {
+                        format!("s{}", vd << 1 | d)
+                    } else {
+                        format!("d{}", d << 4 | vd)
+                    };
+                    Some(op(
+                        &format!("vcvt{}{}", to, from),
+                        format!("{}, {}", dd, dm),
+                    ))
+                }
+                (8, signed) => {
+                    let from = if signed { ".s32" } else { ".u32" };
+                    Some(op(
+                        &format!("vcvt{}{}", size, from),
+                        format!("{}, s{}", dd, vm << 1 | m),
+                    ))
+                }
+                (12 | 13, _) => {
+                    let to = if hw1 & 1 != 0 { ".s32" } else { ".u32" };
+                    let round = if hw2 & 0x80 == 0 { "r" } else { "" };
+                    Some(op(
+                        &format!("vcvt{}{}{}", round, to, size),
+                        format!("s{}, {}", vd << 1 | d, dm),
+                    ))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Value of an 8-bit VFP immediate, as written in VMOV
+fn vfp_expand_imm(value: u32) -> f32 {
+    let sign = if value & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((value >> 4) & 7) as i32;
+    let exponent = if exponent & 4 != 0 {
+        exponent - 8
+    } else {
+        exponent
+    } + 1;
+    let mantissa = 1.0 + (value & 0xf) as f32 / 16.0;
+    sign * mantissa * 2f32.powi(exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode;
+    use crate::types::Flow;
+
+    /// Disassemble `bytes` loaded at 0 into one `mnemonic operands` line per instruction,
+    /// with immediates in decimal like llvm-objdump prints them
+    fn disassemble(bytes: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut it = 0;
+        let mut offset = 0;
+        while let Some(instruction) = decode(&bytes[offset..], offset as u64, &mut it) {
+            offset += instruction.bytes.len();
+            let text = format!("{} {}", instruction.mnemonic, instruction.operands);
+            lines.push(decimal(text.trim_end()));
+        }
+        lines
+    }
+
+    fn decimal(text: &str) -> String {
+        let mut parts = text.split("#0x");
+        let mut result = parts.next().unwrap_or_default().to_string();
+        for part in parts {
+            let digits = part
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .unwrap_or(part.len());
+            let value = u64::from_str_radix(&part[..digits], 16).unwrap();
+            result.push_str(&format!("#{}{}", value, &part[digits..]));
+        }
+        result
+    }
+
+    #[test]
+    fn matches_llvm_objdump() {
+        // llvm-objdump -d --triple=thumbv7em-none-eabihf --mattr=+vfp4, branch targets
+        // without their symbols
+        let cases: &[(&[u8], &str)] = &[
+            (&[0xb0, 0xb5], "push {r4, r5, r7, lr}"),
+            (&[0x84, 0xb0], "sub sp, #16"),
+            (&[0x6f, 0xf3, 0x0b, 0x10], "bfc r0, #4, #8"),
+            (&[0x62, 0xf3, 0xc7, 0x01], "bfi r1, r2, #3, #5"),
+            (&[0x41, 0xf3, 0x86, 0x00], "sbfx r0, r1, #2, #7"),
+            (&[0xc4, 0xf3, 0x0f, 0x03], "ubfx r3, r4, #0, #16"),
+            (&[0x2d, 0xe9, 0xf0, 0x41], "push.w {r4, r5, r6, r7, r8, lr}"),
+            (&[0xbd, 0xe8, 0xf0, 0x81], "pop.w {r4, r5, r6, r7, r8, pc}"),
+            (&[0x2d, 0xed, 0x04, 0x8b], "vpush {d8, d9}"),
+            (&[0x02, 0x48], "ldr r0, [pc, #8]"),
+            (&[0xd2, 0xf8, 0x24, 0x11], "ldr.w r1, [r2, #292]"),
+            (&[0x6d, 0xe9, 0x02, 0x01], "strd r0, r1, [sp, #-8]!"),
+            (&[0x41, 0xf2, 0x34, 0x20], "movw r0, #4660"),
+            (&[0xc2, 0xf2, 0x00, 0x00], "movt r0, #8192"),
+            (&[0xdf, 0xe8, 0x01, 0xf0], "tbb [pc, r1]"),
+            (&[0xad, 0xf5, 0x80, 0x6d], "sub.w sp, sp, #1024"),
+            (&[0xad, 0xf2, 0x04, 0x1d], "subw sp, sp, #260"),
+            (&[0x70, 0x47], "bx lr"),
+            (&[0x98, 0x47], "blx r3"),
+            (&[0x01, 0xeb, 0xc2, 0x00], "add.w r0, r1, r2, lsl #3"),
+            (&[0x30, 0xee, 0x81, 0x0a], "vadd.f32 s0, s1, s2"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(disassemble(bytes), [*expected], "{:02x?}", bytes);
+        }
+    }
+
+    #[test]
+    fn branches_and_it_blocks() {
+        let bytes = [
+            0x00, 0xf0, 0x80, 0xf8, // bl
+            0x02, 0xb1, // cbz
+            0x00, 0xbf, 0x0c, 0xbf, 0x01, 0x20, 0x00, 0x20, // nop; ite eq; mov; mov
+        ];
+        assert_eq!(
+            disassemble(&bytes),
+            [
+                "bl 0x104",
+                "cbz r2, 0x8",
+                "nop",
+                "ite eq",
+                "moveq r0, #1",
+                "movne r0, #0"
+            ]
+        );
+    }
+
+    #[test]
+    fn flow_and_stack_pushes() {
+        let decode_at_0 = |bytes: &[u8]| decode(bytes, 0, &mut 0).unwrap();
+        let flows = [
+            (&[0x00, 0xf0, 0x80, 0xf8][..], Flow::Call(0x104)),
+            (&[0x98, 0x47], Flow::IndirectCall),
+            (&[0x70, 0x47], Flow::Return),
+            (&[0xbd, 0xe8, 0xf0, 0x81], Flow::Return),
+            (&[0x5d, 0xf8, 0x04, 0xfb], Flow::Return),
+            (&[0x50, 0xf8, 0x21, 0xf0], Flow::IndirectBranch),
+            (&[0xdf, 0xe8, 0x01, 0xf0], Flow::Switch),
+            (&[0x02, 0xb1], Flow::ConditionalBranch(0x4)),
+        ];
+        for (bytes, flow) in flows {
+            assert_eq!(decode_at_0(bytes).flow, flow, "{:02x?}", bytes);
+        }
+
+        let pushes = [
+            (&[0xb0, 0xb5][..], 16),
+            (&[0x84, 0xb0], 16),
+            (&[0x2d, 0xe9, 0xf0, 0x41], 24),
+            (&[0x4d, 0xf8, 0x04, 0x7d], 4),
+            (&[0x2d, 0xed, 0x04, 0x8b], 16),
+            (&[0xad, 0xf5, 0x80, 0x6d], 1024),
+            (&[0xad, 0xf2, 0x04, 0x1d], 260),
+            (&[0xbd, 0xe8, 0xf0, 0x81], 0),
+        ];
+        for (bytes, size) in pushes {
+            assert_eq!(decode_at_0(bytes).stack_push, size, "{:02x?}", bytes);
+        }
+    }
+
+    #[test]
+    fn bitfield_with_msb_below_lsb() {
+        // bfi r1, r2 with lsb 3 and msb 1, UNPREDICTABLE
+        assert_eq!(
+            disassemble(&[0x62, 0xf3, 0xc1, 0x01]),
+            [".inst.w 0xf36201c1"]
+        );
+    }
+}
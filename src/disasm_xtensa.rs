@@ -0,0 +1,390 @@
+use crate::types::{DisassembledInstruction, Flow};
+
+/// Constants of the branch-immediate instructions, indexed by the `r` field
+const B4CONST: [i32; 16] = [-1, 1, 2, 3, 4, 5, 6, 7, 8, 10, 12, 16, 32, 64, 128, 256];
+const B4CONSTU: [u32; 16] = [
+    32768, 65536, 2, 3, 4, 5, 6, 7, 8, 10, 12, 16, 32, 64, 128, 256,
+];
+
+struct Decoded {
+    mnemonic: String,
+    operands: String,
+    target: Option<u64>,
+    literal: Option<u64>,
+    flow: Flow,
+    stack_push: u64,
+}
+
+fn op(mnemonic: &str, operands: String) -> Decoded {
+    Decoded {
+        mnemonic: mnemonic.to_string(),
+        operands,
+        target: None,
+        literal: None,
+        flow: Flow::Next,
+        stack_push: 0,
+    }
+}
+
+fn branch(mnemonic: &str, operands: String, target: u64) -> Decoded {
+    Decoded {
+        target: Some(target),
+        flow: Flow::ConditionalBranch(target),
+        ..op(mnemonic, format!("{}, 0x{:x}", operands, target))
+    }
+}
+
+/// Returns from calls and exceptions
+fn ret(mnemonic: &str, operands: String) -> Decoded {
+    Decoded {
+        flow: Flow::Return,
+        ..op(mnemonic, operands)
+    }
+}
+
+/// ADDI or ADDMI, which allocates a frame when it moves SP (`a1`) down
+fn add_immediate(mnemonic: &str, t: u32, s: u32, imm: i64) -> Decoded {
+    Decoded {
+        stack_push: if t == 1 && s == 1 && imm < 0 {
+            imm.unsigned_abs()
+        } else {
+            0
+        },
+        ..op(mnemonic, format!("a{}, a{}, {}", t, s, imm))
+    }
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    ((value << (32 - bits)) as i32 >> (32 - bits)) as i64
+}
+
+fn special_register(sr: u32) -> String {
+    match sr {
+        0 => "lbeg",
+        1 => "lend",
+        2 => "lcount",
+        3 => "sar",
+        5 => "litbase",
+        72 => "windowbase",
+        73 => "windowstart",
+        177 => "epc1",
+        226 => "interrupt",
+        228 => "intenable",
+        230 => "ps",
+        231 => "vecbase",
+        232 => "exccause",
+        234 => "ccount",
+        235 => "prid",
+        238 => "excvaddr",
+        240 => "ccompare0",
+        241 => "ccompare1",
+        242 => "ccompare2",
+        244 => "misc0",
+        245 => "misc1",
+        _ => return sr.to_string(),
+    }
+    .to_string()
+}
+
+/// Decode one Xtensa instruction of the core ISA or the code density option
+pub fn decode(data: &[u8], address: u64) -> Option<DisassembledInstruction> {
+    let op0 = *data.first()? as u32 & 0xf;
+    // Narrow instructions of the code density option are two bytes long
+    let size = if (8..=13).contains(&op0) { 2 } else { 3 };
+    let bytes = data.get(..size)?;
+    let word = bytes.iter().enumerate().fold(0u32, |word, (index, &byte)| {
+        word | (byte as u32) << (8 * index)
+    });
+
+    let decoded = if size == 2 {
+        decode_narrow(word, address)
+    } else {
+        decode_24(word, address)
+    };
+
+    Some(DisassembledInstruction {
+        address,
+        bytes: bytes.to_vec(),
+        mnemonic: decoded.mnemonic,
+        operands: decoded.operands,
+        target: decoded.target,
+        literal: decoded.literal,
+        comment: None,
+        flow: decoded.flow,
+        stack_push: decoded.stack_push,
+    })
+}
+
+fn decode_narrow(word: u32, address: u64) -> Decoded {
+    let t = (word >> 4) & 0xf;
+    let s = (word >> 8) & 0xf;
+    let r = (word >> 12) & 0xf;
+
+    match word & 0xf {
+        8 => op("l32i.n", format!("a{}, a{}, {}", t, s, r << 2)),
+        9 => op("s32i.n", format!("a{}, a{}, {}", t, s, r << 2)),
+        10 => op("add.n", format!("a{}, a{}, a{}", r, s, t)),
+        11 => {
+            let imm = if t == 0 { -1 } else { t as i32 };
+            op("addi.n", format!("a{}, a{}, {}", r, s, imm))
+        }
+        12 if t & 8 == 0 => {
+            let imm = ((t & 7) << 4 | r) as i32;
+            let imm = if imm >= 96 { imm - 128 } else { imm };
+            op("movi.n", format!("a{}, {}", s, imm))
+        }
+        12 => {
+            let target = address + 4 + ((t & 3) << 4 | r) as u64;
+            let name = if t & 4 == 0 { "beqz.n" } else { "bnez.n" };
+            branch(name, format!("a{}", s), target)
+        }
+        13 => match (r, t) {
+            (0, _) => op("mov.n", format!("a{}, a{}", t, s)),
+            (15, 0) => ret("ret.n", String::new()),
+            (15, 1) => ret("retw.n", String::new()),
+            (15, 2) => op("break.n", s.to_string()),
+            (15, 3) => op("nop.n", String::new()),
+            (15, 6) => op("ill.n", String::new()),
+            _ => op(".short", format!("0x{:04x}", word)),
+        },
+        _ => op(".short", format!("0x{:04x}", word)),
+    }
+}
+
+fn decode_24(word: u32, address: u64) -> Decoded {
+    let t = (word >> 4) & 0xf;
+    let s = (word >> 8) & 0xf;
+    let r = (word >> 12) & 0xf;
+    let op1 = (word >> 16) & 0xf;
+    let op2 = (word >> 20) & 0xf;
+    let imm8 = word >> 16;
+    let unknown = || op(".byte", format!("0x{:06x}", word));
+    let rrr = |name: &str| op(name, format!("a{}, a{}, a{}", r, s, t));
+    let branch_imm8 = || (address as i64 + 4 + sign_extend(imm8, 8)) as u64;
+
+    match word & 0xf {
+        0 => match (op1, op2) {
+            (0, 0) => decode_st0(word),
+            (0, 1) => rrr("and"),
+            (0, 2) if s == t => op("mov", format!("a{}, a{}", r, s)),
+            (0, 2) => rrr("or"),
+            (0, 3) => rrr("xor"),
+            (0, 4) => match r {
+                0 => op("ssr", format!("a{}", s)),
+                1 => op("ssl", format!("a{}", s)),
+                2 => op("ssa8l", format!("a{}", s)),
+                3 => op("ssa8b", format!("a{}", s)),
+                4 => op("ssai", (s | (t & 1) << 4).to_string()),
+                14 => op("nsa", format!("a{}, a{}", t, s)),
+                15 => op("nsau", format!("a{}, a{}", t, s)),
+                _ => unknown(),
+            },
+            (0, 6) if s == 0 => op("neg", format!("a{}, a{}", r, t)),
+            (0, 6) if s == 1 => op("abs", format!("a{}, a{}", r, t)),
+            (0, 8..=15) => rrr([
+                "add", "addx2", "addx4", "addx8", "sub", "subx2", "subx4", "subx8",
+            ][op2 as usize - 8]),
+            (1, 0 | 1) => op(
+                "slli",
+                format!("a{}, a{}, {}", r, s, 32 - ((op2 & 1) << 4 | t)),
+            ),
+            (1, 2 | 3) => op("srai", format!("a{}, a{}, {}", r, t, (op2 & 1) << 4 | s)),
+            (1, 4) => op("srli", format!("a{}, a{}, {}", r, t, s)),
+            (1, 6) => op("xsr", format!("a{}, {}", t, special_register(r << 4 | s))),
+            (1, 8) => rrr("src"),
+            (1, 9) => op("srl", format!("a{}, a{}", r, t)),
+            (1, 10) => op("sll", format!("a{}, a{}", r, s)),
+            (1, 11) => op("sra", format!("a{}, a{}", r, t)),
+            (1, 12) => rrr("mul16u"),
+            (1, 13) => rrr("mul16s"),
+            (2, 8) => rrr("mull"),
+            (2, 10) => rrr("muluh"),
+            (2, 11) => rrr("mulsh"),
+            (2, 12) => rrr("quou"),
+            (2, 13) => rrr("quos"),
+            (2, 14) => rrr("remu"),
+            (2, 15) => rrr("rems"),
+            (3, 0) => op("rsr", format!("a{}, {}", t, special_register(r << 4 | s))),
+            (3, 1) => op("wsr", format!("a{}, {}", t, special_register(r << 4 | s))),
+            (3, 2) => op("sext", format!("a{}, a{}, {}", r, s, t + 7)),
+            (3, 3) => op("clamps", format!("a{}, a{}, {}", r, s, t + 7)),
+            (3, 4..=11) => rrr([
+                "min", "max", "minu", "maxu", "moveqz", "movnez", "movltz", "movgez",
+            ][op2 as usize - 4]),
+            (4 | 5, _) => op(
+                "extui",
+                format!("a{}, a{}, {}, {}", r, t, (op1 & 1) << 4 | s, op2 + 1),
+            ),
+            _ => unknown(),
+        },
+        1 => {
+            // L32R loads from below the next word-aligned address
+            let offset = ((0xffff_0000 | (word >> 8)) as i32 as i64) << 2;
+            let literal = (((address + 3) & !3) as i64 + offset) as u64;
+            Decoded {
+                literal: Some(literal),
+                ..op("l32r", format!("a{}, 0x{:x}", t, literal))
+            }
+        }
+        2 => match r {
+            0 => op("l8ui", format!("a{}, a{}, {}", t, s, imm8)),
+            1 => op("l16ui", format!("a{}, a{}, {}", t, s, imm8 << 1)),
+            2 => op("l32i", format!("a{}, a{}, {}", t, s, imm8 << 2)),
+            4 => op("s8i", format!("a{}, a{}, {}", t, s, imm8)),
+            5 => op("s16i", format!("a{}, a{}, {}", t, s, imm8 << 1)),
+            6 => op("s32i", format!("a{}, a{}, {}", t, s, imm8 << 2)),
+            9 => op("l16si", format!("a{}, a{}, {}", t, s, imm8 << 1)),
+            10 => op(
+                "movi",
+                format!("a{}, {}", t, sign_extend(s << 8 | imm8, 12)),
+            ),
+            11 => op("l32ai", format!("a{}, a{}, {}", t, s, imm8 << 2)),
+            12 => add_immediate("addi", t, s, sign_extend(imm8, 8)),
+            13 => add_immediate("addmi", t, s, sign_extend(imm8, 8) << 8),
+            14 => op("s32c1i", format!("a{}, a{}, {}", t, s, imm8 << 2)),
+            15 => op("s32ri", format!("a{}, a{}, {}", t, s, imm8 << 2)),
+            _ => unknown(),
+        },
+        3 => match r {
+            0 => op("lsi", format!("f{}, a{}, {}", t, s, imm8 << 2)),
+            4 => op("ssi", format!("f{}, a{}, {}", t, s, imm8 << 2)),
+            8 => op("lsiu", format!("f{}, a{}, {}", t, s, imm8 << 2)),
+            12 => op("ssiu", format!("f{}, a{}, {}", t, s, imm8 << 2)),
+            _ => unknown(),
+        },
+        5 => {
+            let offset = sign_extend(word >> 6, 18) << 2;
+            let target = ((address & !3) as i64 + offset + 4) as u64;
+            let name = ["call0", "call4", "call8", "call12"][(t & 3) as usize];
+            Decoded {
+                target: Some(target),
+                flow: Flow::Call(target),
+                ..op(name, format!("0x{:x}", target))
+            }
+        }
+        6 => decode_si(word, address, branch_imm8()),
+        7 => {
+            let target = branch_imm8();
+            match r {
+                6 | 7 | 14 | 15 => {
+                    let name = if r < 8 { "bbci" } else { "bbsi" };
+                    branch(name, format!("a{}, {}", s, (r & 1) << 4 | t), target)
+                }
+                _ => {
+                    let name = [
+                        "bnone", "beq", "blt", "bltu", "ball", "bbc", "", "", "bany", "bne", "bge",
+                        "bgeu", "bnall", "bbs",
+                    ][r as usize];
+                    branch(name, format!("a{}, a{}", s, t), target)
+                }
+            }
+        }
+        _ => unknown(),
+    }
+}
+
+/// Jumps, returns, calls through registers and the other `ST0` instructions
+fn decode_st0(word: u32) -> Decoded {
+    let t = (word >> 4) & 0xf;
+    let s = (word >> 8) & 0xf;
+    let r = (word >> 12) & 0xf;
+    let (m, n) = (t >> 2, t & 3);
+
+    match r {
+        0 => match (m, n) {
+            (0, 0) => op("ill", String::new()),
+            (2, 0) => ret("ret", String::new()),
+            (2, 1) => ret("retw", String::new()),
+            (2, 2) => Decoded {
+                flow: Flow::IndirectBranch,
+                ..op("jx", format!("a{}", s))
+            },
+            (3, _) => Decoded {
+                flow: Flow::IndirectCall,
+                ..op(
+                    ["callx0", "callx4", "callx8", "callx12"][n as usize],
+                    format!("a{}", s),
+                )
+            },
+            _ => op(".byte", format!("0x{:06x}", word)),
+        },
+        1 => op("movsp", format!("a{}, a{}", t, s)),
+        2 => match t {
+            0 => op("isync", String::new()),
+            1 => op("rsync", String::new()),
+            2 => op("esync", String::new()),
+            3 => op("dsync", String::new()),
+            8 => op("excw", String::new()),
+            12 => op("memw", String::new()),
+            13 => op("extw", String::new()),
+            15 => op("nop", String::new()),
+            _ => op(".byte", format!("0x{:06x}", word)),
+        },
+        3 if t == 0 && s == 0 => ret("rfe", String::new()),
+        3 if t == 0 && s == 2 => ret("rfde", String::new()),
+        3 if t == 0 && s == 4 => ret("rfwo", String::new()),
+        3 if t == 0 && s == 5 => ret("rfwu", String::new()),
+        3 if t == 1 => ret("rfi", s.to_string()),
+        4 => op("break", format!("{}, {}", s, t)),
+        5 if s == 0 => op("syscall", String::new()),
+        5 if s == 1 => op("simcall", String::new()),
+        6 => op("rsil", format!("a{}, {}", t, s)),
+        7 => op("waiti", s.to_string()),
+        _ => op(".byte", format!("0x{:06x}", word)),
+    }
+}
+
+/// J, the compare-with-zero and compare-with-immediate branches, ENTRY and loops
+fn decode_si(word: u32, address: u64, target_imm8: u64) -> Decoded {
+    let s = (word >> 8) & 0xf;
+    let r = (word >> 12) & 0xf;
+    let n = (word >> 4) & 3;
+    let m = (word >> 6) & 3;
+
+    match n {
+        0 => {
+            let target = (address as i64 + 4 + sign_extend(word >> 6, 18)) as u64;
+            Decoded {
+                target: Some(target),
+                flow: Flow::Branch(target),
+                ..op("j", format!("0x{:x}", target))
+            }
+        }
+        1 => {
+            let target = (address as i64 + 4 + sign_extend(word >> 12, 12)) as u64;
+            let name = ["beqz", "bnez", "bltz", "bgez"][m as usize];
+            branch(name, format!("a{}", s), target)
+        }
+        2 => {
+            let name = ["beqi", "bnei", "blti", "bgei"][m as usize];
+            branch(
+                name,
+                format!("a{}, {}", s, B4CONST[r as usize]),
+                target_imm8,
+            )
+        }
+        _ => match (m, r) {
+            // The windowed ABI allocates the whole frame on entry
+            (0, _) => Decoded {
+                stack_push: ((word >> 12) << 3) as u64,
+                ..op("entry", format!("a{}, {}", s, (word >> 12) << 3))
+            },
+            (1, 0) => branch("bf", format!("b{}", s), target_imm8),
+            (1, 1) => branch("bt", format!("b{}", s), target_imm8),
+            (1, 8) => branch("loop", format!("a{}", s), target_imm8),
+            (1, 9) => branch("loopnez", format!("a{}", s), target_imm8),
+            (1, 10) => branch("loopgtz", format!("a{}", s), target_imm8),
+            (2, _) => branch(
+                "bltui",
+                format!("a{}, {}", s, B4CONSTU[r as usize]),
+                target_imm8,
+            ),
+            (3, _) => branch(
+                "bgeui",
+                format!("a{}, {}", s, B4CONSTU[r as usize]),
+                target_imm8,
+            ),
+            _ => op(".byte", format!("0x{:06x}", word)),
+        },
+    }
+}
@@ -0,0 +1,593 @@
+use crate::parser::{get_string_attr, global_offset, resolve_type};
+use crate::types::{
+    EnumDiscriminant, EnumLayout, EnumVariant, LayoutMember, LayoutRow, LayoutSuggestion,
+    StructLayout,
+};
+use crate::values::{
+    bitfield_start, for_each_child, member_offset, type_ref, type_size, udata_attr,
+};
+
+/// Nesting limit for types, guards against malformed or cyclic type references
+const MAX_DEPTH: usize = 16;
+/// Largest natural alignment assumed for scalars without `DW_AT_alignment`
+const MAX_ALIGNMENT: u64 = 8;
+
+/// Members and padding of the struct or union at `offset`, pahole style.
+/// Rust enums (structs with a `DW_TAG_variant_part`) and declarations have no fixed layout.
+pub fn struct_layout<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    rust_syntax: bool,
+) -> Option<StructLayout> {
+    let entry = unit.entry(offset).ok()?;
+    let is_union = entry.tag() == gimli::DW_TAG_union_type;
+    let byte_size = udata_attr(&entry, gimli::DW_AT_byte_size)?;
+
+    let mut members = Vec::new();
+    let mut has_variants = false;
+    for_each_child(unit, offset, |child| match child.tag() {
+        gimli::DW_TAG_member => {
+            // Static members of C++ classes are declarations without storage
+            let declaration = child.attr_value(gimli::DW_AT_declaration).ok().flatten();
+            if declaration.is_none() {
+                members.push(layout_member(dwarf, unit, child, rust_syntax));
+            }
+        }
+        gimli::DW_TAG_variant_part => has_variants = true,
+        _ => {}
+    });
+    if has_variants {
+        return None;
+    }
+
+    // Rust reorders fields, so DWARF order is not memory order
+    members.sort_by_key(start_bit);
+    let alignment = udata_attr(&entry, gimli::DW_AT_alignment)
+        .unwrap_or_else(|| members.iter().map(|m| m.alignment).max().unwrap_or(1));
+
+    let suggestion = (!rust_syntax && !is_union)
+        .then(|| suggest_order(&members, alignment))
+        .flatten()
+        .filter(|suggestion| suggestion.byte_size < byte_size);
+
+    let mut rows = Vec::new();
+    let mut cursor = 0;
+    for member in members {
+        let start = start_bit(&member);
+        if start > cursor {
+            rows.push(LayoutRow::Padding {
+                bit_offset: cursor,
+                bit_size: start - cursor,
+                trailing: false,
+            });
+        }
+        cursor = cursor.max(start + bit_len(&member));
+        rows.push(LayoutRow::Member(member));
+    }
+    if byte_size * 8 > cursor {
+        rows.push(LayoutRow::Padding {
+            bit_offset: cursor,
+            bit_size: byte_size * 8 - cursor,
+            trailing: true,
+        });
+    }
+
+    Some(StructLayout {
+        byte_size,
+        alignment,
+        is_union,
+        rows,
+        suggestion,
+    })
+}
+
+/// Variants of the enum at `offset`: the enumerators of a C-like enum, or the
+/// `DW_TAG_variant`s of a Rust enum with data along with their payload layouts
+pub fn enum_layout<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    rust_syntax: bool,
+) -> Option<EnumLayout> {
+    let entry = unit.entry(offset).ok()?;
+    let byte_size = type_size(unit, offset);
+
+    if entry.tag() == gimli::DW_TAG_enumeration_type {
+        let mut variants = Vec::new();
+        for_each_child(unit, offset, |enumerator| {
+            if enumerator.tag() == gimli::DW_TAG_enumerator {
+                variants.push(EnumVariant {
+                    name: get_string_attr(dwarf, unit, enumerator, gimli::DW_AT_name)
+                        .unwrap_or_else(|| "<anonymous>".to_string()),
+                    value: const_attr(enumerator, gimli::DW_AT_const_value),
+                    payload_size: 0,
+                    type_offset: None,
+                    payload: None,
+                });
+            }
+        });
+        return Some(EnumLayout {
+            byte_size,
+            discriminant: byte_size.map(|size| EnumDiscriminant {
+                offset: 0,
+                size,
+                niche_in: None,
+            }),
+            variants,
+        });
+    }
+
+    let mut variant_part = None;
+    for_each_child(unit, offset, |child| {
+        if variant_part.is_none() && child.tag() == gimli::DW_TAG_variant_part {
+            variant_part = Some(child.offset());
+        }
+    });
+    let variant_part = variant_part?;
+    let part_entry = unit.entry(variant_part).ok()?;
+
+    let mut variants = Vec::new();
+    for_each_child(unit, variant_part, |variant| {
+        if variant.tag() != gimli::DW_TAG_variant {
+            return;
+        }
+        // Each variant wraps one member, named after the variant, whose type is the payload
+        let mut payload_member = None;
+        for_each_child(unit, variant.offset(), |member| {
+            if payload_member.is_none() && member.tag() == gimli::DW_TAG_member {
+                payload_member = Some((
+                    get_string_attr(dwarf, unit, member, gimli::DW_AT_name),
+                    type_ref(member),
+                ));
+            }
+        });
+        let (name, payload_type) = payload_member.unwrap_or((None, None));
+        let payload = payload_type.and_then(|ty| struct_layout(dwarf, unit, ty, rust_syntax));
+        let payload_size = payload.as_ref().map_or(0, |layout| {
+            layout
+                .rows
+                .iter()
+                .map(|row| match row {
+                    LayoutRow::Member(member) => member.bits.map_or(member.size, |(_, b)| b / 8),
+                    LayoutRow::Padding { .. } => 0,
+                })
+                .sum()
+        });
+
+        variants.push(EnumVariant {
+            name: name.unwrap_or_else(|| "<anonymous>".to_string()),
+            value: const_attr(variant, gimli::DW_AT_discr_value),
+            payload_size,
+            type_offset: payload_type.and_then(|ty| global_offset(unit, ty)),
+            payload,
+        });
+    });
+
+    let discriminant = match part_entry.attr_value(gimli::DW_AT_discr) {
+        Ok(Some(gimli::AttributeValue::UnitRef(discr))) => unit.entry(discr).ok().map(|member| {
+            let offset = member_offset(unit, &member);
+            let size = type_ref(&member)
+                .and_then(|ty| type_size(unit, ty))
+                .unwrap_or(0);
+            EnumDiscriminant {
+                offset,
+                size,
+                niche_in: niche_field(&variants, offset),
+            }
+        }),
+        _ => None,
+    };
+
+    Some(EnumLayout {
+        byte_size,
+        discriminant,
+        variants,
+    })
+}
+
+/// The field a niche-encoded discriminant lives in, e.g. `Some.__0`. Only the
+/// variant owning the niche has no discriminant value.
+fn niche_field(variants: &[EnumVariant], offset: u64) -> Option<String> {
+    let owner = variants.iter().find(|variant| variant.value.is_none())?;
+    let field = owner.payload.as_ref().and_then(|payload| {
+        payload.rows.iter().find_map(|row| match row {
+            LayoutRow::Member(member)
+                if offset >= member.offset && offset < member.offset + member.size.max(1) =>
+            {
+                Some(member.name.clone())
+            }
+            _ => None,
+        })
+    });
+    Some(match field {
+        Some(field) => format!("{}.{}", owner.name, field),
+        None => owner.name.clone(),
+    })
+}
+
+/// A constant attribute as a signed value, whatever form it is encoded in
+fn const_attr<R: gimli::Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+    attr_name: gimli::DwAt,
+) -> Option<i128> {
+    match entry.attr_value(attr_name).ok()?? {
+        gimli::AttributeValue::Sdata(value) => Some(value as i128),
+        attr => attr.udata_value().map(|value| value as i128),
+    }
+}
+
+fn layout_member<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    member: &gimli::DebuggingInformationEntry<R>,
+    rust_syntax: bool,
+) -> LayoutMember {
+    let member_type = type_ref(member);
+    let offset = member_offset(unit, member);
+    let bits = udata_attr(member, gimli::DW_AT_bit_size).and_then(|bit_size| {
+        let start = bitfield_start(unit, member, member_type?, offset, bit_size)?;
+        Some((start, bit_size))
+    });
+
+    LayoutMember {
+        name: get_string_attr(dwarf, unit, member, gimli::DW_AT_name)
+            .unwrap_or_else(|| "<anonymous>".to_string()),
+        type_name: member_type
+            .map(|ty| resolve_type(dwarf, unit, ty).display(rust_syntax))
+            .unwrap_or_else(|| "<unknown>".to_string()),
+        type_offset: member_type.and_then(|ty| global_offset(unit, ty)),
+        // DWARF 4 bitfields may only carry a bit offset
+        offset: bits.map_or(offset, |(start, _)| start / 8),
+        size: member_type.and_then(|ty| type_size(unit, ty)).unwrap_or(0),
+        alignment: member_type.map_or(1, |ty| type_alignment(unit, ty, 0)),
+        bits,
+    }
+}
+
+fn start_bit(member: &LayoutMember) -> u64 {
+    member.bits.map_or(member.offset * 8, |(start, _)| start)
+}
+
+fn bit_len(member: &LayoutMember) -> u64 {
+    member.bits.map_or(member.size * 8, |(_, len)| len)
+}
+
+/// Alignment of a type, from `DW_AT_alignment` or the natural alignment of its scalars
+fn type_alignment<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    depth: usize,
+) -> u64 {
+    if depth > MAX_DEPTH {
+        return 1;
+    }
+    let Ok(entry) = unit.entry(offset) else {
+        return 1;
+    };
+    if let Some(alignment) = udata_attr(&entry, gimli::DW_AT_alignment) {
+        return alignment;
+    }
+
+    match entry.tag() {
+        gimli::DW_TAG_base_type
+        | gimli::DW_TAG_enumeration_type
+        | gimli::DW_TAG_pointer_type
+        | gimli::DW_TAG_reference_type
+        | gimli::DW_TAG_rvalue_reference_type => type_size(unit, offset)
+            .filter(|size| size.is_power_of_two())
+            .map_or(1, |size| size.min(MAX_ALIGNMENT)),
+        gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type | gimli::DW_TAG_class_type => {
+            let mut alignment = 1;
+            for_each_child(unit, offset, |member| {
+                if member.tag() == gimli::DW_TAG_member {
+                    if let Some(ty) = type_ref(member) {
+                        alignment = alignment.max(type_alignment(unit, ty, depth + 1));
+                    }
+                }
+            });
+            alignment
+        }
+        gimli::DW_TAG_array_type
+        | gimli::DW_TAG_typedef
+        | gimli::DW_TAG_const_type
+        | gimli::DW_TAG_volatile_type
+        | gimli::DW_TAG_atomic_type
+        | gimli::DW_TAG_restrict_type => {
+            type_ref(&entry).map_or(1, |ty| type_alignment(unit, ty, depth + 1))
+        }
+        _ => 1,
+    }
+}
+
+/// Members sorted by decreasing alignment, which leaves no holes between them.
+/// Packed structs and bitfields are left alone, reordering those changes more than padding.
+fn suggest_order(members: &[LayoutMember], alignment: u64) -> Option<LayoutSuggestion> {
+    let packed = members
+        .iter()
+        .any(|m| m.alignment == 0 || m.offset % m.alignment != 0);
+    if packed || members.iter().any(|m| m.bits.is_some()) {
+        return None;
+    }
+
+    let mut order: Vec<&LayoutMember> = members.iter().collect();
+    order.sort_by(|a, b| b.alignment.cmp(&a.alignment).then(b.size.cmp(&a.size)));
+
+    let end = order
+        .iter()
+        .fold(0, |end: u64, m| end.next_multiple_of(m.alignment) + m.size);
+
+    Some(LayoutSuggestion {
+        order: order.iter().map(|m| m.name.clone()).collect(),
+        byte_size: end.next_multiple_of(alignment.max(1)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{enum_layout, struct_layout};
+    use crate::types::{LayoutRow, StructLayout};
+    use gimli::write::{AttributeValue, DwarfUnit, EndianVec, Sections, UnitEntryId};
+    use gimli::{EndianSlice, LittleEndian};
+
+    type Attributes<'a> = &'a [(gimli::DwAt, AttributeValue)];
+
+    fn add(
+        dwarf: &mut DwarfUnit,
+        parent: UnitEntryId,
+        tag: gimli::DwTag,
+        name: &str,
+        attrs: Attributes,
+    ) -> UnitEntryId {
+        let id = dwarf.unit.add(parent, tag);
+        let entry = dwarf.unit.get_mut(id);
+        if !name.is_empty() {
+            entry.set(gimli::DW_AT_name, AttributeValue::String(name.into()));
+        }
+        for (attr, value) in attrs.iter().cloned() {
+            entry.set(attr, value);
+        }
+        id
+    }
+
+    fn base_type(dwarf: &mut DwarfUnit, name: &str, size: u64) -> UnitEntryId {
+        let root = dwarf.unit.root();
+        add(
+            dwarf,
+            root,
+            gimli::DW_TAG_base_type,
+            name,
+            &[(gimli::DW_AT_byte_size, AttributeValue::Udata(size))],
+        )
+    }
+
+    /// A member of type `ty` at byte `offset`
+    fn member(ty: UnitEntryId, offset: u64) -> Vec<(gimli::DwAt, AttributeValue)> {
+        vec![
+            (gimli::DW_AT_type, AttributeValue::UnitRef(ty)),
+            (
+                gimli::DW_AT_data_member_location,
+                AttributeValue::Udata(offset),
+            ),
+        ]
+    }
+
+    /// Write `dwarf` and lay out the type named `name` in it
+    fn layout<T>(
+        dwarf: &mut DwarfUnit,
+        name: &str,
+        layout: impl Fn(
+            &gimli::Dwarf<EndianSlice<LittleEndian>>,
+            &gimli::Unit<EndianSlice<LittleEndian>>,
+            gimli::UnitOffset,
+        ) -> Option<T>,
+    ) -> T {
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let dwarf = gimli::Dwarf::load(|id| -> gimli::Result<_> {
+            let data = sections.get(id).map_or(&[][..], |section| section.slice());
+            Ok(EndianSlice::new(data, LittleEndian))
+        })
+        .unwrap();
+        let unit = dwarf.unit(dwarf.units().next().unwrap().unwrap()).unwrap();
+
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            let entry_name = entry
+                .attr_value(gimli::DW_AT_name)
+                .unwrap()
+                .and_then(|name| dwarf.attr_string(&unit, name).ok());
+            if entry_name.is_some_and(|entry_name| entry_name.slice() == name.as_bytes()) {
+                return layout(&dwarf, &unit, entry.offset()).unwrap();
+            }
+        }
+        panic!("no {} in the unit", name);
+    }
+
+    fn unit() -> DwarfUnit {
+        DwarfUnit::new(gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        })
+    }
+
+    /// Members as (name, bit offset, bit size) and padding as ("", bit offset, bit size)
+    fn rows(layout: &StructLayout) -> Vec<(&str, u64, u64)> {
+        layout
+            .rows
+            .iter()
+            .map(|row| match row {
+                LayoutRow::Member(member) => {
+                    let (start, len) = member.bits.unwrap_or((member.offset * 8, member.size * 8));
+                    (member.name.as_str(), start, len)
+                }
+                LayoutRow::Padding {
+                    bit_offset,
+                    bit_size,
+                    ..
+                } => ("", *bit_offset, *bit_size),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn struct_padding_and_suggested_order() {
+        // struct S { char a; int b; char c; };
+        let mut dwarf = unit();
+        let char_type = base_type(&mut dwarf, "char", 1);
+        let int_type = base_type(&mut dwarf, "int", 4);
+        let root = dwarf.unit.root();
+        let size = [(gimli::DW_AT_byte_size, AttributeValue::Udata(12))];
+        let s = add(&mut dwarf, root, gimli::DW_TAG_structure_type, "S", &size);
+        add(
+            &mut dwarf,
+            s,
+            gimli::DW_TAG_member,
+            "a",
+            &member(char_type, 0),
+        );
+        add(
+            &mut dwarf,
+            s,
+            gimli::DW_TAG_member,
+            "b",
+            &member(int_type, 4),
+        );
+        add(
+            &mut dwarf,
+            s,
+            gimli::DW_TAG_member,
+            "c",
+            &member(char_type, 8),
+        );
+
+        let layout = layout(&mut dwarf, "S", |dwarf, unit, offset| {
+            struct_layout(dwarf, unit, offset, false)
+        });
+        assert_eq!(layout.alignment, 4);
+        assert_eq!(
+            rows(&layout),
+            [
+                ("a", 0, 8),
+                ("", 8, 24),
+                ("b", 32, 32),
+                ("c", 64, 8),
+                ("", 72, 24)
+            ]
+        );
+        assert!(matches!(
+            layout.rows.last(),
+            Some(LayoutRow::Padding { trailing: true, .. })
+        ));
+        let suggestion = layout.suggestion.unwrap();
+        assert_eq!(suggestion.order, ["b", "a", "c"]);
+        assert_eq!(suggestion.byte_size, 8);
+    }
+
+    #[test]
+    fn bitfields() {
+        // struct Flags { unsigned x : 3; unsigned y : 5; };
+        let mut dwarf = unit();
+        let unsigned = base_type(&mut dwarf, "unsigned int", 4);
+        let root = dwarf.unit.root();
+        let size = [(gimli::DW_AT_byte_size, AttributeValue::Udata(4))];
+        let flags = add(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_structure_type,
+            "Flags",
+            &size,
+        );
+        for (name, start, len) in [("x", 0, 3), ("y", 3, 5)] {
+            let attrs = [
+                (gimli::DW_AT_type, AttributeValue::UnitRef(unsigned)),
+                (gimli::DW_AT_bit_size, AttributeValue::Udata(len)),
+                (gimli::DW_AT_data_bit_offset, AttributeValue::Udata(start)),
+            ];
+            add(&mut dwarf, flags, gimli::DW_TAG_member, name, &attrs);
+        }
+
+        let layout = layout(&mut dwarf, "Flags", |dwarf, unit, offset| {
+            struct_layout(dwarf, unit, offset, false)
+        });
+        assert_eq!(rows(&layout), [("x", 0, 3), ("y", 3, 5), ("", 8, 24)]);
+        // Reordering bitfields is not suggested
+        assert!(layout.suggestion.is_none());
+    }
+
+    #[test]
+    fn niche_discriminant() {
+        // Option<NonZeroU32>: None is 0, Some owns the niche in its payload
+        let mut dwarf = unit();
+        let u32_type = base_type(&mut dwarf, "u32", 4);
+        let root = dwarf.unit.root();
+        let size = || [(gimli::DW_AT_byte_size, AttributeValue::Udata(4))];
+        let option = add(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_structure_type,
+            "Option",
+            &size(),
+        );
+        let none = add(
+            &mut dwarf,
+            option,
+            gimli::DW_TAG_structure_type,
+            "None",
+            &size(),
+        );
+        let some = add(
+            &mut dwarf,
+            option,
+            gimli::DW_TAG_structure_type,
+            "Some",
+            &size(),
+        );
+        add(
+            &mut dwarf,
+            some,
+            gimli::DW_TAG_member,
+            "__0",
+            &member(u32_type, 0),
+        );
+
+        let part = add(&mut dwarf, option, gimli::DW_TAG_variant_part, "", &[]);
+        let tag = add(
+            &mut dwarf,
+            part,
+            gimli::DW_TAG_member,
+            "",
+            &member(u32_type, 0),
+        );
+        dwarf
+            .unit
+            .get_mut(part)
+            .set(gimli::DW_AT_discr, AttributeValue::UnitRef(tag));
+        let none_value = [(gimli::DW_AT_discr_value, AttributeValue::Udata(0))];
+        for (name, payload, attrs) in [("None", none, &none_value[..]), ("Some", some, &[])] {
+            let variant = add(&mut dwarf, part, gimli::DW_TAG_variant, "", attrs);
+            add(
+                &mut dwarf,
+                variant,
+                gimli::DW_TAG_member,
+                name,
+                &member(payload, 0),
+            );
+        }
+
+        let layout = layout(&mut dwarf, "Option", |dwarf, unit, offset| {
+            enum_layout(dwarf, unit, offset, true)
+        });
+        assert_eq!(layout.byte_size, Some(4));
+        let discriminant = layout.discriminant.unwrap();
+        assert_eq!((discriminant.offset, discriminant.size), (0, 4));
+        assert_eq!(discriminant.niche_in.as_deref(), Some("Some.__0"));
+        let variants: Vec<(&str, Option<i128>, u64)> = layout
+            .variants
+            .iter()
+            .map(|v| (v.name.as_str(), v.value, v.payload_size))
+            .collect();
+        assert_eq!(variants, [("None", Some(0), 0), ("Some", None, 4)]);
+    }
+}
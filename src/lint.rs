@@ -0,0 +1,535 @@
+use anyhow::{Context as AnyhowContext, Result};
+use object::read::elf::{ElfFile, FileHeader, ProgramHeader, SectionHeader};
+use object::{elf, Endianness};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::types::{Diagnostic, MemoryKind, MemoryRegion, MemorySegment, Severity, VectorTable};
+
+/// Everything a lint rule can look at
+pub struct LintContext<'a> {
+    pub segments: &'a [MemorySegment],
+    pub memory_regions: &'a [MemoryRegion],
+    pub vector_table: &'a VectorTable,
+    /// (section name, VMA, LMA) of every allocated section with file data
+    pub load_addresses: Vec<(String, u64, u64)>,
+}
+
+impl LintContext<'_> {
+    fn regions(&self, kind: MemoryKind) -> impl Iterator<Item = &MemoryRegion> {
+        self.memory_regions.iter().filter(move |r| r.kind == kind)
+    }
+}
+
+/// A finding produced by a rule: the subject it is about and a message
+type Finding = (String, String);
+
+pub struct LintRule {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub default_severity: Severity,
+    check: fn(&LintContext) -> Vec<Finding>,
+}
+
+pub const RULES: &[LintRule] = &[
+    LintRule {
+        id: "vector-table-at-boot",
+        description: "Vector table starts at the beginning of boot memory",
+        default_severity: Severity::Warning,
+        check: check_vector_table_at_boot,
+    },
+    LintRule {
+        id: "vector-table-alignment",
+        description: "Vector table is aligned as required by VTOR",
+        default_severity: Severity::Error,
+        check: check_vector_table_alignment,
+    },
+    LintRule {
+        id: "initial-sp-in-ram",
+        description: "Initial stack pointer points into RAM",
+        default_severity: Severity::Error,
+        check: check_initial_sp_in_ram,
+    },
+    LintRule {
+        id: "reset-in-executable",
+        description: "Reset vector points into an executable section",
+        default_severity: Severity::Error,
+        check: check_reset_in_executable,
+    },
+    LintRule {
+        id: "data-lma-in-flash",
+        description: "Initialized writable data is loaded from flash",
+        default_severity: Severity::Error,
+        check: check_data_lma_in_flash,
+    },
+    LintRule {
+        id: "writable-in-nvm",
+        description: "No writable (SHF_WRITE) sections are placed in flash",
+        default_severity: Severity::Error,
+        check: check_writable_in_nvm,
+    },
+];
+
+/// Per-rule severity overrides and suppressed findings.
+///
+/// The config file is line based:
+///
+/// ```text
+/// # Lower a rule's severity, or turn it off entirely
+/// vector-table-at-boot = info
+/// writable-in-nvm = off
+/// # Suppress a single finding by its key (rule:subject)
+/// suppress = data-lma-in-flash:.ramfunc
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LintConfig {
+    /// `None` disables the rule
+    pub severities: HashMap<String, Option<Severity>>,
+    pub suppressed: HashSet<String>,
+}
+
+impl LintConfig {
+    pub fn load(path: &PathBuf) -> Result<Self> {
+        let text = fs::read_to_string(path).context("Failed to read lint config")?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = LintConfig::default();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                anyhow::bail!("Line {}: expected `key = value`", line_no + 1);
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            if key == "suppress" {
+                config.suppressed.insert(value.to_string());
+                continue;
+            }
+
+            if !RULES.iter().any(|rule| rule.id == key) {
+                anyhow::bail!("Line {}: unknown lint rule '{}'", line_no + 1, key);
+            }
+
+            let severity = match value {
+                "off" => None,
+                "info" => Some(Severity::Info),
+                "warning" => Some(Severity::Warning),
+                "error" => Some(Severity::Error),
+                _ => anyhow::bail!("Line {}: unknown severity '{}'", line_no + 1, value),
+            };
+            config.severities.insert(key.to_string(), severity);
+        }
+
+        Ok(config)
+    }
+}
+
+/// Run all enabled lint rules against the ELF file at `path`
+pub fn run_lints(
+    path: &PathBuf,
+    segments: &[MemorySegment],
+    memory_regions: &[MemoryRegion],
+    vector_table: &VectorTable,
+    config: &LintConfig,
+) -> Result<Vec<Diagnostic>> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+    let load_addresses = match &obj {
+        object::File::Elf32(elf) => section_load_addresses(elf),
+        object::File::Elf64(elf) => section_load_addresses(elf),
+        _ => Vec::new(),
+    };
+
+    let ctx = LintContext {
+        segments,
+        memory_regions,
+        vector_table,
+        load_addresses,
+    };
+
+    Ok(check(&ctx, config))
+}
+
+/// Run all enabled lint rules against `ctx`, most severe findings first
+fn check(ctx: &LintContext, config: &LintConfig) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for rule in RULES {
+        let severity = match config.severities.get(rule.id) {
+            Some(Some(severity)) => *severity,
+            Some(None) => continue,
+            None => rule.default_severity,
+        };
+
+        for (subject, message) in (rule.check)(ctx) {
+            let diagnostic = Diagnostic {
+                rule: rule.id.to_string(),
+                severity,
+                subject,
+                message,
+            };
+            if !config.suppressed.contains(&diagnostic.key()) {
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|d| std::cmp::Reverse(d.severity));
+
+    diagnostics
+}
+
+/// Map each allocated section with file data to its load address through the program headers
+fn section_load_addresses<Elf: FileHeader<Endian = Endianness>>(
+    elf: &ElfFile<Elf>,
+) -> Vec<(String, u64, u64)> {
+    let endian = elf.endian();
+    let program_headers = elf.elf_program_headers();
+    let sections = elf.elf_section_table();
+
+    let mut load_addresses = Vec::new();
+
+    for section in sections.iter() {
+        let flags: u64 = section.sh_flags(endian).into();
+        if flags & u64::from(elf::SHF_ALLOC) == 0 || section.sh_type(endian) == elf::SHT_NOBITS {
+            continue;
+        }
+
+        let offset: u64 = section.sh_offset(endian).into();
+        let vma: u64 = section.sh_addr(endian).into();
+
+        let Some(segment) = program_headers.iter().find(|ph| {
+            let p_offset: u64 = ph.p_offset(endian).into();
+            let p_filesz: u64 = ph.p_filesz(endian).into();
+            ph.p_type(endian) == elf::PT_LOAD && offset >= p_offset && offset < p_offset + p_filesz
+        }) else {
+            continue;
+        };
+
+        let p_offset: u64 = segment.p_offset(endian).into();
+        let p_paddr: u64 = segment.p_paddr(endian).into();
+        let lma = p_paddr + (offset - p_offset);
+
+        let name = sections
+            .section_name(endian, section)
+            .ok()
+            .and_then(|name| std::str::from_utf8(name).ok())
+            .unwrap_or("<unnamed>")
+            .to_string();
+
+        load_addresses.push((name, vma, lma));
+    }
+
+    load_addresses
+}
+
+fn check_vector_table_at_boot(ctx: &LintContext) -> Vec<Finding> {
+    let (Some(address), Some(section)) = (
+        ctx.vector_table.address,
+        ctx.vector_table.section_name.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    // Boot memory is assumed to be the lowest flash region
+    match ctx.regions(MemoryKind::Flash).min_by_key(|r| r.start) {
+        Some(boot) if boot.start != address => vec![(
+            section.clone(),
+            format!(
+                "Vector table at 0x{:08x} is not at the start of {} (0x{:08x})",
+                address, boot.name, boot.start
+            ),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+fn check_vector_table_alignment(ctx: &LintContext) -> Vec<Finding> {
+    let (Some(address), Some(section)) = (
+        ctx.vector_table.address,
+        ctx.vector_table.section_name.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    // VTOR requires alignment to the table size rounded up to a power of two, at least 128 bytes
+    let table_size = (ctx.vector_table.entries.len() as u64 * 4).max(128);
+    let alignment = table_size.next_power_of_two();
+
+    if address % alignment != 0 {
+        vec![(
+            section.clone(),
+            format!(
+                "Vector table at 0x{:08x} must be aligned to {} bytes for VTOR",
+                address, alignment
+            ),
+        )]
+    } else {
+        Vec::new()
+    }
+}
+
+fn check_initial_sp_in_ram(ctx: &LintContext) -> Vec<Finding> {
+    let Some(initial_sp) = ctx.vector_table.entries.first().map(|e| e.value) else {
+        return Vec::new();
+    };
+
+    let mut ram = ctx.regions(MemoryKind::Ram).peekable();
+    if ram.peek().is_none() {
+        return Vec::new();
+    }
+
+    // The stack pointer is decremented before the first push, so it may equal the end of RAM
+    if ram.any(|r| r.contains(initial_sp.saturating_sub(1), 1)) {
+        Vec::new()
+    } else {
+        vec![(
+            "Initial SP".to_string(),
+            format!(
+                "Initial SP 0x{:08x} is not inside any RAM region",
+                initial_sp
+            ),
+        )]
+    }
+}
+
+fn check_reset_in_executable(ctx: &LintContext) -> Vec<Finding> {
+    let Some(reset) = ctx.vector_table.entries.get(1) else {
+        return Vec::new();
+    };
+
+    let target = reset.value & !1;
+    let in_executable = ctx
+        .segments
+        .iter()
+        .any(|s| s.flags.ends_with('X') && target >= s.address && target < s.address + s.size);
+
+    if in_executable {
+        Vec::new()
+    } else {
+        vec![(
+            "Reset".to_string(),
+            format!(
+                "Reset vector 0x{:08x} does not point into an executable section",
+                reset.value
+            ),
+        )]
+    }
+}
+
+fn check_data_lma_in_flash(ctx: &LintContext) -> Vec<Finding> {
+    let flash: Vec<&MemoryRegion> = ctx.regions(MemoryKind::Flash).collect();
+    if flash.is_empty() {
+        return Vec::new();
+    }
+
+    ctx.segments
+        .iter()
+        .filter(|s| s.is_load && s.flags.contains('W'))
+        .filter_map(|segment| {
+            let (_, _, lma) = ctx
+                .load_addresses
+                .iter()
+                .find(|(name, vma, _)| *name == segment.name && *vma == segment.address)?;
+
+            if flash.iter().any(|r| r.contains(*lma, segment.size)) {
+                None
+            } else {
+                Some((
+                    segment.name.clone(),
+                    format!(
+                        "{} is loaded from 0x{:08x}, which is not in flash; its initial values are lost at reset",
+                        segment.name, lma
+                    ),
+                ))
+            }
+        })
+        .collect()
+}
+
+fn check_writable_in_nvm(ctx: &LintContext) -> Vec<Finding> {
+    let flash: Vec<&MemoryRegion> = ctx.regions(MemoryKind::Flash).collect();
+
+    ctx.segments
+        .iter()
+        .filter(|s| s.flags.contains('W'))
+        .filter_map(|segment| {
+            let region = flash
+                .iter()
+                .find(|r| r.overlaps(segment.address, segment.size))?;
+            Some((
+                segment.name.clone(),
+                format!(
+                    "Writable section {} at 0x{:08x} is placed in {}",
+                    segment.name, segment.address, region.name
+                ),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, LintConfig, LintContext};
+    use crate::types::{MemoryKind, MemoryRegion, MemorySegment, VectorEntry, VectorTable};
+
+    /// A well-formed Cortex-M image: vector table at the start of flash, code after
+    /// it, `.data` copied from flash into RAM
+    struct Image {
+        segments: Vec<MemorySegment>,
+        memory_regions: Vec<MemoryRegion>,
+        vector_table: VectorTable,
+        load_addresses: Vec<(String, u64, u64)>,
+    }
+
+    fn segment(name: &str, address: u64, size: u64, flags: &str) -> MemorySegment {
+        MemorySegment {
+            name: name.to_string(),
+            address,
+            size,
+            flags: flags.to_string(),
+            is_load: true,
+            conflicts: Vec::new(),
+        }
+    }
+
+    fn entry(index: usize, name: &str, value: u64) -> VectorEntry {
+        VectorEntry {
+            index,
+            name: name.to_string(),
+            irq_name: None,
+            value,
+            symbol: None,
+            default_handler: false,
+            issues: Vec::new(),
+        }
+    }
+
+    impl Image {
+        fn new() -> Self {
+            Image {
+                segments: vec![
+                    segment(".vector_table", 0x0800_0000, 0x400, "R--"),
+                    segment(".text", 0x0800_0400, 0x1000, "R-X"),
+                    segment(".data", 0x2000_0000, 0x100, "RW-"),
+                ],
+                memory_regions: vec![
+                    MemoryRegion {
+                        name: "FLASH".to_string(),
+                        start: 0x0800_0000,
+                        size: 0x10000,
+                        kind: MemoryKind::Flash,
+                    },
+                    MemoryRegion {
+                        name: "RAM".to_string(),
+                        start: 0x2000_0000,
+                        size: 0x5000,
+                        kind: MemoryKind::Ram,
+                    },
+                ],
+                vector_table: VectorTable {
+                    present: true,
+                    section_name: Some(".vector_table".to_string()),
+                    address: Some(0x0800_0000),
+                    entries: vec![
+                        entry(0, "Initial SP", 0x2000_5000),
+                        entry(1, "Reset", 0x0800_0401),
+                    ],
+                },
+                load_addresses: vec![
+                    (".vector_table".to_string(), 0x0800_0000, 0x0800_0000),
+                    (".text".to_string(), 0x0800_0400, 0x0800_0400),
+                    (".data".to_string(), 0x2000_0000, 0x0800_1400),
+                ],
+            }
+        }
+
+        /// Suppression keys of the findings left over with `config`
+        fn lint(&self, config: &str) -> Vec<String> {
+            let ctx = LintContext {
+                segments: &self.segments,
+                memory_regions: &self.memory_regions,
+                vector_table: &self.vector_table,
+                load_addresses: self.load_addresses.clone(),
+            };
+            check(&ctx, &LintConfig::parse(config).unwrap())
+                .iter()
+                .map(|diagnostic| diagnostic.key())
+                .collect()
+        }
+
+        /// Expect exactly the finding `key`, silenced by suppressing it
+        fn assert_fires(&self, key: &str) {
+            assert_eq!(self.lint(""), [key]);
+            assert!(self.lint(&format!("suppress = {}", key)).is_empty());
+        }
+    }
+
+    #[test]
+    fn well_formed_image_is_clean() {
+        assert!(Image::new().lint("").is_empty());
+    }
+
+    #[test]
+    fn vector_table_at_boot() {
+        let mut image = Image::new();
+        image.vector_table.address = Some(0x0800_0800);
+        image.segments[0].address = 0x0800_0800;
+        image.assert_fires("vector-table-at-boot:.vector_table");
+        assert!(image.lint("vector-table-at-boot = off").is_empty());
+    }
+
+    #[test]
+    fn vector_table_alignment() {
+        let mut image = Image::new();
+        image.memory_regions[0].start = 0x0800_0040;
+        image.vector_table.address = Some(0x0800_0040);
+        image.assert_fires("vector-table-alignment:.vector_table");
+    }
+
+    #[test]
+    fn initial_sp_in_ram() {
+        let mut image = Image::new();
+        image.vector_table.entries[0].value = 0x2000_5004;
+        image.assert_fires("initial-sp-in-ram:Initial SP");
+    }
+
+    #[test]
+    fn reset_in_executable() {
+        let mut image = Image::new();
+        image.vector_table.entries[1].value = 0x0800_0001;
+        image.assert_fires("reset-in-executable:Reset");
+    }
+
+    #[test]
+    fn data_lma_in_flash() {
+        let mut image = Image::new();
+        image.load_addresses[2].2 = 0x2000_0000;
+        image.assert_fires("data-lma-in-flash:.data");
+    }
+
+    #[test]
+    fn writable_in_nvm() {
+        let mut image = Image::new();
+        image
+            .segments
+            .push(segment(".ramfunc", 0x0800_2000, 0x40, "RWX"));
+        image.assert_fires("writable-in-nvm:.ramfunc");
+    }
+
+    #[test]
+    fn config_rejects_unknown_rules_and_severities() {
+        assert!(LintConfig::parse("no-such-rule = error").is_err());
+        assert!(LintConfig::parse("writable-in-nvm = fatal").is_err());
+        assert!(LintConfig::parse("writable-in-nvm").is_err());
+    }
+}
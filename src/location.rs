@@ -0,0 +1,307 @@
+use object::Architecture;
+
+use crate::types::VariableLocation;
+
+/// Where a local variable or parameter lives, one entry per PC range of its
+/// location list. An empty list means the compiler kept no location at all.
+pub fn variable_locations<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+    arch: Architecture,
+    frame_base: Option<&str>,
+) -> Vec<VariableLocation> {
+    let optimized_out = || {
+        vec![VariableLocation {
+            pc_range: None,
+            description: "optimized out".to_string(),
+        }]
+    };
+
+    let Ok(Some(value)) = entry.attr_value(gimli::DW_AT_location) else {
+        if entry
+            .attr_value(gimli::DW_AT_const_value)
+            .ok()
+            .flatten()
+            .is_some()
+        {
+            return vec![VariableLocation {
+                pc_range: None,
+                description: "constant (DW_AT_const_value)".to_string(),
+            }];
+        }
+        return optimized_out();
+    };
+
+    if let gimli::AttributeValue::Exprloc(expr) = value {
+        return vec![VariableLocation {
+            pc_range: None,
+            description: describe_expression(dwarf, unit, expr, arch, frame_base),
+        }];
+    }
+
+    let Ok(Some(mut entries)) = dwarf.attr_locations(unit, value) else {
+        return optimized_out();
+    };
+    let mut locations = Vec::new();
+    while let Ok(Some(entry)) = entries.next() {
+        locations.push(VariableLocation {
+            pc_range: Some((entry.range.begin, entry.range.end)),
+            description: describe_expression(dwarf, unit, entry.data, arch, frame_base),
+        });
+    }
+
+    if locations.is_empty() {
+        optimized_out()
+    } else {
+        locations
+    }
+}
+
+/// The frame base of a subprogram, which `DW_OP_fbreg` offsets are relative to
+pub fn frame_base<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+    arch: Architecture,
+) -> Option<String> {
+    let gimli::AttributeValue::Exprloc(expr) = entry.attr_value(gimli::DW_AT_frame_base).ok()??
+    else {
+        return None;
+    };
+
+    match expr.operations(unit.encoding()).next().ok()?? {
+        gimli::Operation::Register { register } => Some(register_name(arch, register)),
+        gimli::Operation::CallFrameCFA => Some("cfa".to_string()),
+        _ => None,
+    }
+}
+
+/// One location expression in words, e.g. `r4`, `[sp+12]` or `optimized out`
+fn describe_expression<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    expr: gimli::Expression<R>,
+    arch: Architecture,
+    frame_base: Option<&str>,
+) -> String {
+    let mut operations = expr.operations(unit.encoding());
+    let mut ops = Vec::new();
+    loop {
+        match operations.next() {
+            Ok(Some(op)) => ops.push(op),
+            Ok(None) => break,
+            Err(_) => return "invalid expression".to_string(),
+        }
+    }
+
+    if ops.is_empty() {
+        return "optimized out".to_string();
+    }
+
+    // Split composite locations at DW_OP_piece into their parts
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for (i, op) in ops.iter().enumerate() {
+        if let gimli::Operation::Piece { size_in_bits, .. } = op {
+            let part = describe_ops(dwarf, unit, &ops[start..i], arch, frame_base);
+            let size = if size_in_bits % 8 == 0 {
+                format!("{} bytes", size_in_bits / 8)
+            } else {
+                format!("{} bits", size_in_bits)
+            };
+            pieces.push(format!("{} ({})", part, size));
+            start = i + 1;
+        }
+    }
+    if start == 0 {
+        return describe_ops(dwarf, unit, &ops, arch, frame_base);
+    }
+    if start < ops.len() {
+        pieces.push(describe_ops(dwarf, unit, &ops[start..], arch, frame_base));
+    }
+    pieces.join(", ")
+}
+
+/// A piece of a location expression, matched against the shapes compilers emit
+fn describe_ops<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    ops: &[gimli::Operation<R>],
+    arch: Architecture,
+    frame_base: Option<&str>,
+) -> String {
+    use gimli::Operation as Op;
+
+    let offset = |base: String, offset: i64| match offset {
+        0 => base,
+        offset if offset < 0 => format!("{}-{}", base, offset.unsigned_abs()),
+        offset => format!("{}+{}", base, offset),
+    };
+    let fb = || frame_base.unwrap_or("fb").to_string();
+
+    match ops {
+        [] => "optimized out".to_string(),
+        [Op::Register { register }] => register_name(arch, *register),
+        [Op::RegisterOffset {
+            register,
+            offset: o,
+            ..
+        }] => format!("[{}]", offset(register_name(arch, *register), *o)),
+        [Op::RegisterOffset {
+            register,
+            offset: o,
+            ..
+        }, Op::StackValue] => offset(register_name(arch, *register), *o),
+        [Op::FrameOffset { offset: o }] => format!("[{}]", offset(fb(), *o)),
+        [Op::FrameOffset { offset: o }, Op::StackValue] => offset(fb(), *o),
+        [Op::Address { address }] => format!("static 0x{:08x}", address),
+        [Op::AddressIndex { index }] => match dwarf.address(unit, *index) {
+            Ok(address) => format!("static 0x{:08x}", address),
+            Err(_) => "static (unresolved address)".to_string(),
+        },
+        [Op::UnsignedConstant { value }, Op::StackValue] => format!("constant {}", value),
+        [Op::SignedConstant { value }, Op::StackValue] => format!("constant {}", value),
+        [Op::ImplicitValue { data }] => {
+            let bytes: Vec<String> = data
+                .to_slice()
+                .map(|data| data.iter().map(|b| format!("{:02x}", b)).collect())
+                .unwrap_or_default();
+            format!("constant [{}]", bytes.join(" "))
+        }
+        [Op::ImplicitPointer { .. }] => "pointer to an optimized-out value".to_string(),
+        [Op::EntryValue { expression }, rest @ ..]
+            if rest.is_empty() || matches!(rest, [Op::StackValue]) =>
+        {
+            let inner = describe_expression(
+                dwarf,
+                unit,
+                gimli::Expression(expression.clone()),
+                arch,
+                frame_base,
+            );
+            format!("value of {} on entry", inner)
+        }
+        _ => "computed (complex expression)".to_string(),
+    }
+}
+
+/// Conventional name of a DWARF register number on the given architecture
+pub fn register_name(arch: Architecture, register: gimli::Register) -> String {
+    const RISCV_ABI: [&str; 32] = [
+        "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+        "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+        "t5", "t6",
+    ];
+    let n = register.0;
+
+    match arch {
+        Architecture::Arm => match n {
+            0..=12 => format!("r{}", n),
+            13 => "sp".to_string(),
+            14 => "lr".to_string(),
+            15 => "pc".to_string(),
+            64..=95 => format!("s{}", n - 64),
+            256..=287 => format!("d{}", n - 256),
+            _ => format!("reg{}", n),
+        },
+        Architecture::Riscv32 | Architecture::Riscv64 => match n {
+            0..=31 => RISCV_ABI[n as usize].to_string(),
+            32..=63 => format!("f{}", n - 32),
+            _ => format!("reg{}", n),
+        },
+        Architecture::Xtensa => match n {
+            0..=15 => format!("a{}", n),
+            _ => format!("reg{}", n),
+        },
+        _ => format!("reg{}", n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gimli::write::{DwarfUnit, EndianVec, Sections};
+    use gimli::{EndianSlice, LittleEndian};
+
+    /// Describe a raw location expression in an empty 32-bit unit
+    fn describe(arch: Architecture, expression: &[u8], frame_base: Option<&str>) -> String {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        DwarfUnit::new(encoding).write(&mut sections).unwrap();
+        let dwarf = gimli::Dwarf::load(|id| -> gimli::Result<_> {
+            let data = sections.get(id).map_or(&[][..], |section| section.slice());
+            Ok(EndianSlice::new(data, LittleEndian))
+        })
+        .unwrap();
+        let header = dwarf.units().next().unwrap().unwrap();
+        let unit = dwarf.unit(header).unwrap();
+        let expression = gimli::Expression(EndianSlice::new(expression, LittleEndian));
+        describe_expression(&dwarf, &unit, expression, arch, frame_base)
+    }
+
+    #[test]
+    fn registers() {
+        let reg = |n: u8| gimli::DW_OP_reg0.0 + n;
+        assert_eq!(describe(Architecture::Arm, &[reg(4)], None), "r4");
+        assert_eq!(describe(Architecture::Arm, &[reg(13)], None), "sp");
+        assert_eq!(describe(Architecture::Riscv32, &[reg(10)], None), "a0");
+        // DW_OP_regx 64: s0
+        let regx = [gimli::DW_OP_regx.0, 64];
+        assert_eq!(describe(Architecture::Arm, &regx, None), "s0");
+        // sp + 12, as a memory location and as a value
+        let breg = [gimli::DW_OP_breg13.0, 12];
+        assert_eq!(describe(Architecture::Arm, &breg, None), "[sp+12]");
+        let value = [gimli::DW_OP_breg13.0, 12, gimli::DW_OP_stack_value.0];
+        assert_eq!(describe(Architecture::Arm, &value, None), "sp+12");
+    }
+
+    #[test]
+    fn frame_base_offsets() {
+        // DW_OP_fbreg -8, SLEB128
+        let fbreg = [gimli::DW_OP_fbreg.0, 0x78];
+        assert_eq!(describe(Architecture::Arm, &fbreg, Some("r7")), "[r7-8]");
+        assert_eq!(describe(Architecture::Arm, &fbreg, None), "[fb-8]");
+        let fbreg = [gimli::DW_OP_fbreg.0, 0];
+        assert_eq!(describe(Architecture::Arm, &fbreg, Some("cfa")), "[cfa]");
+    }
+
+    #[test]
+    fn static_addresses() {
+        let addr = [gimli::DW_OP_addr.0, 0x10, 0x00, 0x00, 0x20];
+        assert_eq!(
+            describe(Architecture::Arm, &addr, None),
+            "static 0x20000010"
+        );
+        assert_eq!(describe(Architecture::Arm, &[], None), "optimized out");
+    }
+
+    #[test]
+    fn pieces() {
+        // r0 for the low 4 bytes, r1 for the high 4 bytes
+        let pieces = [
+            gimli::DW_OP_reg0.0,
+            gimli::DW_OP_piece.0,
+            4,
+            gimli::DW_OP_reg1.0,
+            gimli::DW_OP_piece.0,
+            4,
+        ];
+        assert_eq!(
+            describe(Architecture::Arm, &pieces, None),
+            "r0 (4 bytes), r1 (4 bytes)"
+        );
+        // An empty piece is optimized out, a trailing part has no size
+        let pieces = [gimli::DW_OP_piece.0, 2, gimli::DW_OP_reg5.0];
+        assert_eq!(
+            describe(Architecture::Arm, &pieces, None),
+            "optimized out (2 bytes), r5"
+        );
+        // DW_OP_bit_piece 3 bits at offset 0
+        let bits = [gimli::DW_OP_reg2.0, gimli::DW_OP_bit_piece.0, 3, 0];
+        assert_eq!(describe(Architecture::Arm, &bits, None), "r2 (3 bits)");
+    }
+}
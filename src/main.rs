@@ -1,21 +1,62 @@
+mod callgraph;
+mod capture;
 mod components;
+mod crash;
+mod defmt;
+mod defmt_decoder;
+mod disasm;
+mod disasm_riscv;
+mod disasm_thumb;
+mod disasm_xtensa;
+mod layout;
+mod lint;
+mod location;
+mod panics;
 mod parser;
+mod ram;
+mod report;
+mod snapshot;
+mod source;
+mod stack;
+mod symbolize;
 mod types;
+mod unwind;
 mod utils;
+mod values;
+mod vector_table;
+mod xref;
 
 use anyhow::{Context as AnyhowContext, Result};
+use callgraph::parse_call_graph;
+use capture::load_capture;
 use clap::Parser;
 use components::MemoryView;
+use crash::{analyze_crash, describe_crash, parse_crash_registers};
 use gpui::*;
 use gpui_component::theme::{Theme, ThemeRegistry};
 use gpui_component::{Root, TitleBar};
 use gpui_component_assets::Assets;
+use lint::{run_lints, LintConfig};
+use panics::audit_panics;
 use parser::{
     load_memory_layout_from_probe_rs, parse_defmt_info, parse_dwarf_info, parse_elf_segments,
     parse_elf_symbols, parse_rtt_info,
 };
+use ram::analyze_ram_usage;
+use report::HeadlessReport;
+use snapshot::{load_elf_image, load_snapshot};
+use source::{parse_line_table, PathRemap};
+use stack::analyze_stack;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::path::PathBuf;
+use symbolize::{parse_addresses, Symbolizer};
+use types::{Analysis, Severity};
+use unwind::Unwinder;
+use utils::parse_number;
+use values::parse_static_values;
+use vector_table::{parse_svd_interrupts, parse_vector_table};
+use xref::{describe_xrefs, parse_xrefs};
 
 /// A DWARF debug symbol browser for ELF files
 #[derive(Parser, Debug)]
@@ -27,10 +68,67 @@ struct Args {
     /// Target chip for memory layout (e.g., STM32F407VGTx)
     #[arg(short, long)]
     target: Option<String>,
+
+    /// CMSIS-SVD file used to name device interrupts in the vector table
+    #[arg(long)]
+    svd: Option<PathBuf>,
+
+    /// Lint configuration file with rule severities and suppressed findings
+    #[arg(long)]
+    lint_config: Option<PathBuf>,
+
+    /// Raw RTT channel dump to decode with the ELF's defmt table
+    #[arg(long)]
+    capture: Option<PathBuf>,
+
+    /// Show the capture as plain text even if the firmware uses defmt
+    #[arg(long, requires = "capture")]
+    capture_text: bool,
+
+    /// Memory dump to read static values from (ELF core, probe-rs coredump or raw binary)
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+
+    /// Load address of a raw binary snapshot, decimal or 0x-prefixed hex (e.g. 0x20000000)
+    #[arg(long, requires = "snapshot", value_parser = parse_address)]
+    snapshot_base: Option<u64>,
+
+    /// Read sources built under FROM from TO instead, e.g. /builds/fw=/home/me/fw (repeatable)
+    #[arg(long = "remap-path-prefix", value_name = "FROM=TO")]
+    remap_path_prefix: Vec<PathRemap>,
+
+    /// Resolve hex addresses, e.g. from a backtrace or fault log, to their symbol, function
+    /// and inlined calls, then exit. Reads the addresses from stdin when none are given.
+    #[arg(long, value_name = "ADDRESS", num_args = 0..)]
+    addr2line: Option<Vec<String>>,
+
+    /// List the instructions and data that reference the named symbols (calls, jumps,
+    /// loads, address uses and pointers in data), then exit
+    #[arg(long, value_name = "SYMBOL", num_args = 1..)]
+    xrefs: Option<Vec<String>>,
+
+    /// Registers captured at a HardFault (`-` for stdin): decode the fault status and print
+    /// a backtrace, then exit. Give a RAM dump of the stack with --snapshot to unwind past
+    /// the faulting function.
+    #[arg(long, value_name = "FILE")]
+    crash: Option<PathBuf>,
+
+    /// Print an analysis report to stdout instead of opening the GUI
+    #[arg(long)]
+    headless: bool,
 }
 
 actions!(mason, [Quit]);
 
+fn parse_address(value: &str) -> Result<u64, String> {
+    parse_number(value).ok_or_else(|| {
+        format!(
+            "invalid address '{}', expected a decimal or 0x-prefixed hex number",
+            value
+        )
+    })
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
@@ -68,8 +166,117 @@ fn main() -> Result<()> {
     let symbols = parse_elf_symbols(&elf_path).context("Failed to parse ELF symbols")?;
     eprintln!("Found {} symbols in ELF file", symbols.len());
 
+    if let Some(addresses) = args.addr2line {
+        let text = if addresses.is_empty() {
+            std::io::read_to_string(std::io::stdin()).context("Failed to read addresses")?
+        } else {
+            addresses.join(" ")
+        };
+        let symbolizer = Symbolizer::new(&elf_path, segments, symbols)?;
+        for address in parse_addresses(&text) {
+            for line in symbolizer.describe(address) {
+                println!("{}", line);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(names) = args.xrefs {
+        let index = parse_xrefs(&elf_path, &symbols).context("Failed to index references")?;
+        for name in names {
+            for line in describe_xrefs(&index, &symbols, &name) {
+                println!("{}", line);
+            }
+        }
+        return Ok(());
+    }
+
+    let snapshot = match args.snapshot {
+        Some(ref path) => {
+            Some(load_snapshot(path, args.snapshot_base).context("Failed to load snapshot")?)
+        }
+        None => None,
+    };
+
+    if let Some(ref crash) = args.crash {
+        let text = if crash.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())
+        } else {
+            std::fs::read_to_string(crash)
+        }
+        .context("Failed to read crash registers")?;
+        let registers = parse_crash_registers(&text)?;
+        let unwinder = Unwinder::load(&elf_path)?;
+        let symbolizer = Symbolizer::new(&elf_path, segments, symbols)?;
+        let report = analyze_crash(&registers, &unwinder, &symbolizer, snapshot.as_ref());
+        for line in describe_crash(&report) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    let stack_info = analyze_stack(&symbols, &segments, &memory_regions);
+
+    let interrupt_names = match args.svd {
+        Some(ref svd) => parse_svd_interrupts(svd).context("Failed to load SVD file")?,
+        None => BTreeMap::new(),
+    };
+    let vector_table = parse_vector_table(
+        &elf_path,
+        &symbols,
+        &segments,
+        &memory_regions,
+        &interrupt_names,
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to parse vector table: {}", e);
+        types::VectorTable::default()
+    });
+
+    let xrefs = parse_xrefs(&elf_path, &symbols).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to index references: {}", e);
+        types::XrefIndex::default()
+    });
+    // Functions whose address is taken are roots of the call graph
+    let call_graph =
+        parse_call_graph(&elf_path, &symbols, &vector_table, &xrefs).unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to build call graph: {}", e);
+            types::CallGraph::default()
+        });
+    for warning in &call_graph.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let lint_config = match args.lint_config {
+        Some(ref path) => LintConfig::load(path).context("Failed to load lint config")?,
+        None => LintConfig::default(),
+    };
+    let diagnostics = run_lints(
+        &elf_path,
+        &segments,
+        &memory_regions,
+        &vector_table,
+        &lint_config,
+    )
+    .context("Failed to run lints")?;
+
     let defmt_info = parse_defmt_info(&elf_path).context("Failed to parse defmt info")?;
-    let rtt_info = parse_rtt_info(&elf_path).context("Failed to parse RTT info")?;
+    let rtt_info =
+        parse_rtt_info(&elf_path, &symbols, &memory_regions).context("Failed to parse RTT info")?;
+
+    let capture = match args.capture {
+        Some(ref path) => Some(
+            load_capture(path, &defmt_info, args.capture_text).context("Failed to load capture")?,
+        ),
+        None => None,
+    };
+
+    let snapshot_values = match snapshot {
+        Some(ref snapshot) => parse_static_values(&elf_path, snapshot, &symbols)
+            .context("Failed to decode static values")?,
+        None => HashMap::new(),
+    };
+
     let dwarf_info = parse_dwarf_info(&elf_path).unwrap_or_else(|e| {
         eprintln!("Warning: Failed to parse DWARF info: {}", e);
         types::DwarfInfo::default()
@@ -80,6 +287,72 @@ fn main() -> Result<()> {
         dwarf_info.total_symbols
     );
 
+    let ram_advice = analyze_ram_usage(&segments, &symbols, &dwarf_info, &xrefs);
+    let panic_audit = audit_panics(&elf_path, &call_graph, &xrefs).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to audit panics: {}", e);
+        types::PanicAudit::default()
+    });
+
+    if args.headless {
+        HeadlessReport {
+            segments: &segments,
+            memory_regions: &memory_regions,
+            defmt_info: &defmt_info,
+            rtt_info: &rtt_info,
+            stack_info: &stack_info,
+            call_graph: &call_graph,
+            vector_table: &vector_table,
+            diagnostics: &diagnostics,
+            capture: capture.as_ref(),
+            snapshot: snapshot.as_ref(),
+            snapshot_values: &snapshot_values,
+            ram_advice: &ram_advice,
+            panic_audit: &panic_audit,
+        }
+        .print();
+
+        // Fail CI pipelines on error-level findings
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let line_table = parse_line_table(&elf_path).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to parse line table: {}", e);
+        types::LineTable::default()
+    });
+    let path_remaps = args.remap_path_prefix;
+
+    let initial_values = load_elf_image(&elf_path)
+        .and_then(|image| parse_static_values(&elf_path, &image, &symbols))
+        .unwrap_or_else(|e| {
+            eprintln!("Warning: Failed to decode initial values: {}", e);
+            HashMap::new()
+        });
+
+    let analysis = Analysis {
+        segments,
+        memory_regions,
+        symbols,
+        defmt_info,
+        rtt_info,
+        stack_info,
+        call_graph,
+        xrefs,
+        ram_advice,
+        panic_audit,
+        vector_table,
+        interrupt_names,
+        diagnostics,
+        dwarf_info,
+        line_table,
+        capture,
+        snapshot,
+        snapshot_values,
+        initial_values,
+    };
+
     Application::new()
         .with_assets(Assets)
         .run(move |cx: &mut App| {
@@ -125,12 +398,9 @@ fn main() -> Result<()> {
                 let window = cx.open_window(window_options, |window, cx| {
                     let view = cx.new(|cx| {
                         MemoryView::new(
-                            segments.clone(),
-                            memory_regions.clone(),
-                            symbols.clone(),
-                            defmt_info.clone(),
-                            rtt_info.clone(),
-                            dwarf_info.clone(),
+                            analysis.clone(),
+                            lint_config.clone(),
+                            path_remaps.clone(),
                             current_target.clone(),
                             elf_path.clone(),
                             window,
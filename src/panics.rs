@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use object::{Object, ObjectSection, ObjectSymbol, SectionFlags};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::parser::demangle_name;
+use crate::types::{
+    CallGraph, Machinery, PanicAudit, PanicAuditEntry, PanicLocation, XrefIndex, XrefKind,
+};
+
+/// Call chains are cut after this many functions
+const MAX_PATH: usize = 8;
+
+struct Function {
+    name: String,
+    address: u64,
+    size: u64,
+}
+
+/// Find the functions outside `core`, `alloc` and `std` that can reach panic or
+/// `core::fmt` code, through direct calls from the call graph and the cross-references,
+/// including function addresses taken, e.g. `Display` impls put into `fmt::Arguments`.
+/// Panic locations are read from the `core::panic::Location`s in read-only data.
+pub fn audit_panics(
+    path: &PathBuf,
+    call_graph: &CallGraph,
+    xrefs: &XrefIndex,
+) -> Result<PanicAudit> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+    let mut functions: Vec<Function> = obj
+        .symbols()
+        .filter(|symbol| symbol.kind() == object::SymbolKind::Text && symbol.size() > 0)
+        .filter_map(|symbol| {
+            Some(Function {
+                name: demangle_name(symbol.name().ok()?),
+                address: symbol.address() & !1,
+                size: symbol.size(),
+            })
+        })
+        .collect();
+    functions.sort_by_key(|function| function.address);
+    functions.dedup_by_key(|function| function.address);
+    let starting_at = |address: u64| {
+        functions
+            .binary_search_by_key(&address, |function| function.address)
+            .ok()
+    };
+    let containing = |address: u64| {
+        let index = functions
+            .partition_point(|function| function.address <= address)
+            .checked_sub(1)?;
+        (address < functions[index].address + functions[index].size).then_some(index)
+    };
+
+    let mut callees: Vec<Vec<usize>> = vec![Vec::new(); functions.len()];
+    for function in &call_graph.functions {
+        let Some(from) = starting_at(function.address) else {
+            continue;
+        };
+        callees[from].extend(
+            function
+                .callees
+                .iter()
+                .filter_map(|&callee| starting_at(call_graph.functions[callee].address)),
+        );
+    }
+    for xref in &xrefs.xrefs {
+        if !matches!(
+            xref.kind,
+            XrefKind::Call | XrefKind::Jump | XrefKind::Address
+        ) {
+            continue;
+        }
+        if let (Some(from), Some(to)) = (containing(xref.from), starting_at(xref.to)) {
+            callees[from].push(to);
+        }
+    }
+    for list in &mut callees {
+        list.sort_unstable();
+        list.dedup();
+    }
+    let mut callers: Vec<Vec<usize>> = vec![Vec::new(); functions.len()];
+    for (from, list) in callees.iter().enumerate() {
+        for &to in list {
+            callers[to].push(from);
+        }
+    }
+
+    let locations = panic_locations(&obj);
+    let pointer_size = if obj.is_64() { 8 } else { 4 };
+    let mut locations_by_function: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (index, location) in locations.iter().enumerate() {
+        for xref in xrefs.to_range(location.address, 2 * pointer_size + 8) {
+            if let Some(function) = containing(xref.from) {
+                let list = locations_by_function.entry(function).or_default();
+                if !list.contains(&index) {
+                    list.push(index);
+                }
+            }
+        }
+    }
+
+    let mut audit = PanicAudit::default();
+    for function in &functions {
+        match machinery(&function.name) {
+            Some(Machinery::Panic) => {
+                audit.panic_functions += 1;
+                audit.panic_size += function.size;
+            }
+            Some(Machinery::Fmt) => {
+                audit.fmt_functions += 1;
+                audit.fmt_size += function.size;
+            }
+            None => {}
+        }
+    }
+
+    for kind in [Machinery::Panic, Machinery::Fmt] {
+        // Everything that can end up in the machinery, found walking up from it
+        let mut reaches = vec![false; functions.len()];
+        let mut queue: VecDeque<usize> = (0..functions.len())
+            .filter(|&index| machinery(&functions[index].name) == Some(kind))
+            .collect();
+        for &index in &queue {
+            reaches[index] = true;
+        }
+        while let Some(index) = queue.pop_front() {
+            for &caller in &callers[index] {
+                if !reaches[caller] {
+                    reaches[caller] = true;
+                    queue.push_back(caller);
+                }
+            }
+        }
+
+        // Report where user code crosses into library code or the machinery itself
+        for (index, function) in functions.iter().enumerate() {
+            let crosses = callees[index].iter().any(|&callee| {
+                reaches[callee]
+                    && (is_library(&functions[callee].name)
+                        || machinery(&functions[callee].name) == Some(kind))
+            });
+            if !crosses || is_library(&function.name) || machinery(&function.name) == Some(kind) {
+                continue;
+            }
+            let path = shortest_path(index, &callees, &reaches, |callee| {
+                machinery(&functions[callee].name) == Some(kind)
+            })
+            .into_iter()
+            .map(|callee| functions[callee].name.clone())
+            .collect();
+            audit.entries.push(PanicAuditEntry {
+                function: function.name.clone(),
+                address: function.address,
+                machinery: kind,
+                path,
+                locations: match kind {
+                    Machinery::Panic => locations_by_function
+                        .get(&index)
+                        .cloned()
+                        .unwrap_or_default(),
+                    Machinery::Fmt => Vec::new(),
+                },
+            });
+        }
+    }
+    audit
+        .entries
+        .sort_by(|a, b| (a.machinery, &a.function).cmp(&(b.machinery, &b.function)));
+    audit.locations = locations;
+    Ok(audit)
+}
+
+/// The panic or fmt machinery a function belongs to, by name
+fn machinery(name: &str) -> Option<Machinery> {
+    if name == "rust_begin_unwind"
+        || name.starts_with("core::panicking::")
+        || name.starts_with("std::panicking::")
+    {
+        Some(Machinery::Panic)
+    } else if name.starts_with("core::fmt::")
+        // `<T as core::fmt::Debug>::fmt` only for library types, not the firmware's own impls
+        || (name.contains(" as core::fmt::") && is_library(name))
+    {
+        Some(Machinery::Fmt)
+    } else {
+        None
+    }
+}
+
+/// Code from the standard library crates, as opposed to the firmware and its dependencies
+fn is_library(name: &str) -> bool {
+    // `<T as Trait>::method` belongs to the crate of `T`
+    let (path, is_impl) = match name.strip_prefix('<') {
+        Some(rest) => (rest.split(" as ").next().unwrap_or(rest), true),
+        None => (name, false),
+    };
+    let path = path
+        .trim_start_matches(['&', '*', '[', '('])
+        .trim_start_matches("mut ")
+        .trim_start_matches("const ");
+    match path.split_once("::") {
+        Some((krate, _)) => matches!(krate, "core" | "alloc" | "std" | "compiler_builtins"),
+        // Impls on primitives like `u32`, but plain names are C or `#[no_mangle]` functions
+        None => is_impl,
+    }
+}
+
+/// Functions from `start` to the nearest one matching `is_target`, walking only through
+/// functions that reach it
+fn shortest_path(
+    start: usize,
+    callees: &[Vec<usize>],
+    reaches: &[bool],
+    is_target: impl Fn(usize) -> bool,
+) -> Vec<usize> {
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(index) = queue.pop_front() {
+        if index != start && is_target(index) {
+            let mut path = vec![index];
+            while let Some(&before) = previous.get(path.last().unwrap()) {
+                if before == start {
+                    break;
+                }
+                path.push(before);
+            }
+            path.reverse();
+            path.truncate(MAX_PATH);
+            return path;
+        }
+        for &callee in &callees[index] {
+            if reaches[callee] && callee != start && !previous.contains_key(&callee) {
+                previous.insert(callee, index);
+                queue.push_back(callee);
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// `core::panic::Location { file: &str, line: u32, col: u32 }` values in read-only data,
+/// recognized by a pointer and length to a `.rs` path followed by plausible line and column
+fn panic_locations(obj: &object::File) -> Vec<PanicLocation> {
+    let pointer_size = if obj.is_64() { 8 } else { 4 };
+    let sections: Vec<(u64, &[u8], bool)> = obj
+        .sections()
+        .filter_map(|section| {
+            let SectionFlags::Elf { sh_flags } = section.flags() else {
+                return None;
+            };
+            // SHF_ALLOC, and neither SHF_WRITE nor SHF_EXECINSTR
+            let read_only = sh_flags & 0x7 == 0x2;
+            section.file_range()?;
+            (sh_flags & 0x2 != 0).then(|| (section.address(), section.data().ok(), read_only))
+        })
+        .filter_map(|(address, data, read_only)| Some((address, data?, read_only)))
+        .collect();
+    let word = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .rev()
+            .fold(0u64, |value, &byte| value << 8 | byte as u64)
+    };
+    let string_at = |address: u64, length: u64| {
+        sections.iter().find_map(|&(start, data, _)| {
+            let offset = address.checked_sub(start)? as usize;
+            let bytes = data.get(offset..offset.checked_add(length as usize)?)?;
+            std::str::from_utf8(bytes).ok()
+        })
+    };
+
+    let record = 2 * pointer_size + 8;
+    let mut locations = Vec::new();
+    for &(start, data, read_only) in &sections {
+        if !read_only {
+            continue;
+        }
+        let first = (start.next_multiple_of(pointer_size as u64) - start) as usize;
+        let mut offset = first;
+        while offset + record <= data.len() {
+            let bytes = &data[offset..offset + record];
+            let pointer = word(&bytes[..pointer_size]);
+            let length = word(&bytes[pointer_size..2 * pointer_size]);
+            let line = word(&bytes[2 * pointer_size..2 * pointer_size + 4]) as u32;
+            let column = word(&bytes[2 * pointer_size + 4..]) as u32;
+            let file = (1..=4096)
+                .contains(&length)
+                .then(|| string_at(pointer, length))
+                .flatten()
+                .filter(|file| file.ends_with(".rs") && !file.contains(char::is_control));
+            match file {
+                Some(file)
+                    if (1..=10_000_000).contains(&line) && (1..=100_000).contains(&column) =>
+                {
+                    locations.push(PanicLocation {
+                        address: start + offset as u64,
+                        file: file.to_string(),
+                        line,
+                        column,
+                    });
+                    offset += record;
+                }
+                _ => offset += pointer_size,
+            }
+        }
+    }
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::machinery;
+    use crate::types::Machinery;
+
+    #[test]
+    fn fmt_impls_belong_to_their_self_type() {
+        assert_eq!(machinery("core::fmt::write"), Some(Machinery::Fmt));
+        assert_eq!(
+            machinery("<u32 as core::fmt::Display>::fmt"),
+            Some(Machinery::Fmt)
+        );
+        assert_eq!(
+            machinery("<&core::panic::Location as core::fmt::Debug>::fmt"),
+            Some(Machinery::Fmt)
+        );
+        assert_eq!(machinery("<app::Error as core::fmt::Debug>::fmt"), None);
+    }
+}
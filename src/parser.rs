@@ -4,10 +4,25 @@ use probe_rs::config::MemoryRegion as ProbeRsMemoryRegion;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::layout::{enum_layout, struct_layout};
+use crate::location::{self, variable_locations};
+use crate::source::file_path;
 use crate::types::{
-    DefmtInfo, DwarfInfo, DwarfSymbol, DwarfTag, ElfSymbol, MemoryKind, MemoryRegion,
-    MemorySegment, RttBufferDesc, RttInfo,
+    DefmtInfo, DwarfInfo, DwarfSymbol, DwarfTag, DwarfType, ElfSymbol, MemoryKind, MemoryRegion,
+    MemorySegment, RttBufferDesc, RttInfo, RttMode,
 };
+use crate::values::{array_bounds, type_ref, type_size};
+
+/// Nesting limit when resolving types, guards against cyclic type references
+const MAX_TYPE_DEPTH: usize = 16;
+
+/// What every DIE of a compile unit is parsed with
+#[derive(Clone, Copy)]
+struct UnitContext {
+    arch: object::Architecture,
+    /// Render types in Rust rather than C syntax
+    rust_syntax: bool,
+}
 
 pub fn get_all_targets() -> Vec<String> {
     let mut targets: Vec<String> = probe_rs::config::families()
@@ -113,13 +128,26 @@ pub fn parse_defmt_info(path: &PathBuf) -> Result<DefmtInfo> {
         }
     }
 
+    let log_sites = crate::defmt::parse_log_sites(&obj)?;
+    let encoding = crate::defmt::parse_encoding(&obj);
+
     Ok(DefmtInfo {
         present: !defmt_sections.is_empty(),
         sections: defmt_sections,
+        log_sites,
+        encoding,
     })
 }
 
-pub fn parse_rtt_info(path: &PathBuf) -> Result<RttInfo> {
+/// Find the RTT control block and decode its buffer descriptors.
+///
+/// Buffers are checked against `memory_regions` when a target is selected,
+/// otherwise against the writable sections of the ELF file.
+pub fn parse_rtt_info(
+    path: &PathBuf,
+    symbols: &[ElfSymbol],
+    memory_regions: &[MemoryRegion],
+) -> Result<RttInfo> {
     let data = fs::read(path).context("Failed to read ELF file")?;
     let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
 
@@ -144,20 +172,42 @@ pub fn parse_rtt_info(path: &PathBuf) -> Result<RttInfo> {
                         if let Ok(section_data) = section.data() {
                             let offset = (address - section_addr) as usize;
                             if offset < section_data.len() {
-                                rtt_data = Some(&section_data[offset..]);
+                                // Don't read past the control block into neighbouring data
+                                let end = if size > 0 {
+                                    (offset + size as usize).min(section_data.len())
+                                } else {
+                                    section_data.len()
+                                };
+                                rtt_data = Some(&section_data[offset..end]);
                             }
                         }
                         break;
                     }
                 }
 
+                let mut warnings = Vec::new();
+
                 // Parse RTT control block structure if we have data
-                let (max_up, max_down, up_buffers, down_buffers) = if let Some(rtt_bytes) = rtt_data
-                {
-                    decode_rtt_control_block(rtt_bytes, ptr_size, obj.endianness())
-                } else {
-                    (None, None, Vec::new(), Vec::new())
-                };
+                let (max_up, max_down, mut up_buffers, mut down_buffers) =
+                    if let Some(rtt_bytes) = rtt_data {
+                        decode_rtt_control_block(
+                            rtt_bytes,
+                            ptr_size,
+                            obj.endianness(),
+                            &|address| read_c_string(&obj, address),
+                            &mut warnings,
+                        )
+                    } else {
+                        warnings.push(
+                            "Control block is zero-initialized; buffers are set up at runtime"
+                                .to_string(),
+                        );
+                        (None, None, Vec::new(), Vec::new())
+                    };
+
+                for buffer in up_buffers.iter_mut().chain(down_buffers.iter_mut()) {
+                    validate_rtt_buffer(buffer, &obj, symbols, memory_regions);
+                }
 
                 return Ok(RttInfo {
                     present: true,
@@ -168,6 +218,7 @@ pub fn parse_rtt_info(path: &PathBuf) -> Result<RttInfo> {
                     max_down_buffers: max_down,
                     up_buffers,
                     down_buffers,
+                    warnings,
                 });
             }
         }
@@ -182,6 +233,7 @@ pub fn parse_rtt_info(path: &PathBuf) -> Result<RttInfo> {
         max_down_buffers: None,
         up_buffers: Vec::new(),
         down_buffers: Vec::new(),
+        warnings: Vec::new(),
     })
 }
 
@@ -189,6 +241,8 @@ fn decode_rtt_control_block(
     data: &[u8],
     ptr_size: usize,
     endian: Endianness,
+    read_string: &dyn Fn(u64) -> Option<String>,
+    warnings: &mut Vec<String>,
 ) -> (
     Option<u32>,
     Option<u32>,
@@ -255,6 +309,10 @@ fn decode_rtt_control_block(
         })
     };
 
+    if !data.starts_with(b"SEGGER RTT") {
+        warnings.push("Control block ID is not \"SEGGER RTT\" in the ELF image".to_string());
+    }
+
     let max_up = read_u32(16);
     let max_down = read_u32(20);
 
@@ -271,47 +329,66 @@ fn decode_rtt_control_block(
     // unsigned int Flags;     // offset 2*ptr_size + 12
     let buffer_desc_size = 2 * ptr_size + 16;
 
+    let decode_buffer = |offset: usize, fallback_name: String| -> Option<RttBufferDesc> {
+        let name_addr = read_ptr(offset)?;
+        let buffer_addr = read_ptr(offset + ptr_size)?;
+        let buffer_size = read_u32(offset + 2 * ptr_size)?;
+        let write_offset = read_u32(offset + 2 * ptr_size + 4)?;
+        let read_offset = read_u32(offset + 2 * ptr_size + 8)?;
+        let flags = read_u32(offset + 2 * ptr_size + 12)?;
+
+        // Unused descriptors are left zeroed
+        if buffer_addr == 0 || buffer_size == 0 {
+            return None;
+        }
+
+        let name = match name_addr {
+            0 => None,
+            addr => read_string(addr),
+        };
+
+        Some(RttBufferDesc {
+            name: name.unwrap_or(fallback_name),
+            buffer_address: buffer_addr,
+            size: buffer_size,
+            buffer_symbol: None,
+            flags,
+            mode: RttMode::from_flags(flags),
+            write_offset,
+            read_offset,
+            issues: Vec::new(),
+        })
+    };
+
     if let (Some(max_up_count), Some(max_down_count)) = (max_up, max_down) {
         let up_buffers_offset = 24;
 
-        for i in 0..max_up_count.min(16) {
-            // Limit to reasonable number
-            let offset = up_buffers_offset + (i as usize * buffer_desc_size);
-            if offset + buffer_desc_size > data.len() {
-                break;
-            }
+        // The descriptor arrays are sized by the firmware; a count that does not fit
+        // the control block means the image is corrupt or the symbol size is wrong
+        let available = (data.len() - up_buffers_offset) / buffer_desc_size;
+        let claimed = max_up_count as usize + max_down_count as usize;
+        if claimed > available {
+            warnings.push(format!(
+                "Control block claims {} up and {} down buffers, but only {} descriptors fit",
+                max_up_count, max_down_count, available
+            ));
+        }
 
-            if let (Some(buffer_addr), Some(buffer_size)) =
-                (read_ptr(offset + ptr_size), read_u32(offset + 2 * ptr_size))
-            {
-                if buffer_addr != 0 && buffer_size > 0 {
-                    up_buffers.push(RttBufferDesc {
-                        name: format!("Up {}", i),
-                        buffer_address: buffer_addr,
-                        size: buffer_size,
-                    });
-                }
+        let up_count = (max_up_count as usize).min(available);
+        for i in 0..up_count {
+            let offset = up_buffers_offset + i * buffer_desc_size;
+            if let Some(buffer) = decode_buffer(offset, format!("Up {}", i)) {
+                up_buffers.push(buffer);
             }
         }
 
         let down_buffers_offset = up_buffers_offset + (max_up_count as usize * buffer_desc_size);
+        let down_count = (max_down_count as usize).min(available.saturating_sub(up_count));
 
-        for i in 0..max_down_count.min(16) {
-            let offset = down_buffers_offset + (i as usize * buffer_desc_size);
-            if offset + buffer_desc_size > data.len() {
-                break;
-            }
-
-            if let (Some(buffer_addr), Some(buffer_size)) =
-                (read_ptr(offset + ptr_size), read_u32(offset + 2 * ptr_size))
-            {
-                if buffer_addr != 0 && buffer_size > 0 {
-                    down_buffers.push(RttBufferDesc {
-                        name: format!("Down {}", i),
-                        buffer_address: buffer_addr,
-                        size: buffer_size,
-                    });
-                }
+        for i in 0..down_count {
+            let offset = down_buffers_offset + i * buffer_desc_size;
+            if let Some(buffer) = decode_buffer(offset, format!("Down {}", i)) {
+                down_buffers.push(buffer);
             }
         }
     }
@@ -319,6 +396,68 @@ fn decode_rtt_control_block(
     (max_up, max_down, up_buffers, down_buffers)
 }
 
+/// Read a NUL-terminated string from the initialized data of the ELF file
+fn read_c_string(obj: &object::File, address: u64) -> Option<String> {
+    let section = obj
+        .sections()
+        .find(|s| address >= s.address() && address < s.address() + s.size())?;
+    let data = section.data().ok()?;
+    let bytes = data.get((address - section.address()) as usize..)?;
+    let end = bytes.iter().take(256).position(|&b| b == 0)?;
+    std::str::from_utf8(&bytes[..end]).ok().map(str::to_string)
+}
+
+/// Check that an RTT buffer points at a RAM symbol and its initial offsets are sane
+fn validate_rtt_buffer(
+    buffer: &mut RttBufferDesc,
+    obj: &object::File,
+    symbols: &[ElfSymbol],
+    memory_regions: &[MemoryRegion],
+) {
+    buffer.buffer_symbol = crate::utils::symbolize_address(symbols, buffer.buffer_address);
+    if buffer.buffer_symbol.is_none() {
+        buffer
+            .issues
+            .push("Buffer address does not resolve to a symbol".to_string());
+    }
+
+    let size = buffer.size as u64;
+    let in_ram = if memory_regions.is_empty() {
+        obj.sections().any(|s| {
+            matches!(
+                s.kind(),
+                object::SectionKind::Data | object::SectionKind::UninitializedData
+            ) && buffer.buffer_address >= s.address()
+                && buffer.buffer_address + size <= s.address() + s.size()
+        })
+    } else {
+        memory_regions
+            .iter()
+            .any(|r| r.kind == MemoryKind::Ram && r.contains(buffer.buffer_address, size))
+    };
+    if !in_ram {
+        buffer.issues.push(format!(
+            "Buffer 0x{:08x}..0x{:08x} is not in RAM",
+            buffer.buffer_address,
+            buffer.buffer_address + size
+        ));
+    }
+
+    if buffer.write_offset >= buffer.size || buffer.read_offset >= buffer.size {
+        buffer.issues.push(format!(
+            "Initial offsets (write {}, read {}) are outside the buffer",
+            buffer.write_offset, buffer.read_offset
+        ));
+    }
+
+    if let RttMode::Invalid(mode) = buffer.mode {
+        buffer.issues.push(format!(
+            "Flags 0x{:x} select invalid mode {}",
+            buffer.flags, mode
+        ));
+    }
+}
+
 pub fn parse_elf_segments(
     path: &PathBuf,
     memory_regions: Option<&[MemoryRegion]>,
@@ -445,12 +584,19 @@ pub fn parse_elf_symbols(path: &PathBuf) -> Result<Vec<ElfSymbol>> {
 
     let mut symbols = Vec::new();
 
+    let is_arm = obj.architecture() == object::Architecture::Arm;
+
     for symbol in obj.symbols() {
         // Only include symbols with valid names and addresses
         if let Ok(name) = symbol.name() {
-            let address = symbol.address();
+            let mut address = symbol.address();
             let size = symbol.size();
 
+            // Thumb function symbols carry the Thumb bit in their value
+            if is_arm && symbol.kind() == object::SymbolKind::Text {
+                address &= !1;
+            }
+
             // Skip symbols with zero address or empty names
             if address > 0 && !name.is_empty() {
                 symbols.push(ElfSymbol {
@@ -468,52 +614,71 @@ pub fn parse_elf_symbols(path: &PathBuf) -> Result<Vec<ElfSymbol>> {
     Ok(symbols)
 }
 
-pub fn parse_dwarf_info(path: &PathBuf) -> Result<DwarfInfo> {
-    use gimli::RunTimeEndian;
-    use object::{Object, ObjectSection};
-
+/// File contents of a section, or `None` for NOBITS sections like `.bss`
+pub fn read_section_data(path: &PathBuf, section_name: &str) -> Result<Option<Vec<u8>>> {
     let data = fs::read(path).context("Failed to read ELF file")?;
     let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
 
-    // Determine endianness
+    let section = obj
+        .section_by_name(section_name)
+        .context(format!("Section '{}' not found", section_name))?;
+
+    if section.file_range().is_none() {
+        return Ok(None);
+    }
+
+    let data = section.data().context("Failed to read section data")?;
+    Ok(Some(data.to_vec()))
+}
+
+/// The DWARF sections of an ELF, decompressed and empty where missing
+pub fn load_dwarf(
+    obj: &object::File,
+) -> Result<gimli::Dwarf<gimli::EndianRcSlice<gimli::RunTimeEndian>>> {
     let endian = if obj.is_little_endian() {
-        RunTimeEndian::Little
+        gimli::RunTimeEndian::Little
     } else {
-        RunTimeEndian::Big
+        gimli::RunTimeEndian::Big
     };
+    let sections = gimli::DwarfSections::load(|id| -> Result<_, gimli::Error> {
+        Ok(obj
+            .section_by_name(id.name())
+            .and_then(|section| section.uncompressed_data().ok())
+            .unwrap_or_default())
+    })?;
+    Ok(sections
+        .borrow(|section| gimli::EndianRcSlice::new(std::rc::Rc::from(&section[..]), endian)))
+}
 
-    // Load DWARF sections
-    let load_section = |id: gimli::SectionId| -> Result<std::borrow::Cow<[u8]>, gimli::Error> {
-        match obj.section_by_name(id.name()) {
-            Some(section) => Ok(section
-                .uncompressed_data()
-                .unwrap_or(std::borrow::Cow::Borrowed(&[][..]))),
-            None => Ok(std::borrow::Cow::Borrowed(&[][..])),
-        }
-    };
-
-    // Load all sections
-    let dwarf_cow = gimli::Dwarf::load(&load_section)?;
-
-    // Borrow the sections for parsing
-    let dwarf = dwarf_cow.borrow(|section| gimli::EndianSlice::new(&*section, endian));
+pub fn parse_dwarf_info(path: &PathBuf) -> Result<DwarfInfo> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+    let dwarf = load_dwarf(&obj)?;
 
     let mut compile_units = Vec::new();
     let mut total_symbols = 0;
     let mut id_counter = 0;
 
-    // Iterate over compilation units
-    let mut units = dwarf.units();
-    while let Some(header) = units.next()? {
-        let unit = dwarf.unit(header)?;
+    // Parse all unit headers up front, DIEs may refer to DIEs in other units
+    let mut units = Vec::new();
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        units.push(dwarf.unit(header)?);
+    }
 
+    // Iterate over compilation units
+    for unit in &units {
         // Get the compilation unit DIE
         let mut entries = unit.entries();
 
         if let Some((_, entry)) = entries.next_dfs()? {
             if entry.tag() == gimli::DW_TAG_compile_unit {
+                let context = UnitContext {
+                    arch: obj.architecture(),
+                    rust_syntax: uses_rust_syntax(unit),
+                };
                 let (cu_symbol, cu_count) =
-                    parse_compile_unit(&dwarf, &unit, entry, &mut id_counter)?;
+                    parse_compile_unit(&dwarf, &units, unit, entry, context, &mut id_counter)?;
                 total_symbols += cu_count;
                 compile_units.push(cu_symbol);
             }
@@ -529,8 +694,10 @@ pub fn parse_dwarf_info(path: &PathBuf) -> Result<DwarfInfo> {
 
 fn parse_compile_unit<R: gimli::Reader>(
     dwarf: &gimli::Dwarf<R>,
+    units: &[gimli::Unit<R>],
     unit: &gimli::Unit<R>,
     entry: &gimli::DebuggingInformationEntry<R>,
+    context: UnitContext,
     id_counter: &mut usize,
 ) -> Result<(DwarfSymbol, usize)> {
     let mut symbol_count = 1;
@@ -546,6 +713,9 @@ fn parse_compile_unit<R: gimli::Reader>(
         .map(|dir| format!("{}/{}", dir, name))
         .or(Some(name.clone()));
 
+    // All code of the unit, which is rarely one contiguous block
+    let ranges = die_ranges(dwarf, unit, entry);
+
     let id = *id_counter;
     *id_counter += 1;
 
@@ -558,7 +728,7 @@ fn parse_compile_unit<R: gimli::Reader>(
     let mut child_iter = root.children();
     while let Some(child_node) = child_iter.next()? {
         if let Some((child_symbol, count)) =
-            parse_die_recursive(dwarf, unit, child_node, id_counter)?
+            parse_die_recursive(dwarf, units, unit, child_node, context, None, id_counter)?
         {
             symbol_count += count;
             children.push(child_symbol);
@@ -571,7 +741,13 @@ fn parse_compile_unit<R: gimli::Reader>(
             DwarfTag::Subprogram => 0,
             DwarfTag::Variable => 1,
             DwarfTag::StructureType | DwarfTag::UnionType | DwarfTag::EnumerationType => 2,
-            DwarfTag::Typedef => 3,
+            DwarfTag::Typedef
+            | DwarfTag::BaseType
+            | DwarfTag::PointerType
+            | DwarfTag::ArrayType
+            | DwarfTag::ConstType
+            | DwarfTag::VolatileType
+            | DwarfTag::SubroutineType => 3,
             DwarfTag::Namespace => 4,
             _ => 5,
         };
@@ -593,16 +769,23 @@ fn parse_compile_unit<R: gimli::Reader>(
     Ok((
         DwarfSymbol {
             id,
+            die_offset: die_offset(unit, entry),
             name: name.clone(),
             tag: DwarfTag::CompileUnit,
-            address: None,
-            size: None,
+            address: ranges.first().map(|&(start, _)| start),
+            size: (!ranges.is_empty()).then(|| code_bytes(&ranges)),
+            ranges,
             file,
             line: None,
             column: None,
             type_name: None,
+            type_offset: None,
             children,
             attributes: Vec::new(),
+            locations: Vec::new(),
+            type_info: None,
+            layout: None,
+            enum_layout: None,
         },
         symbol_count,
     ))
@@ -611,8 +794,11 @@ fn parse_compile_unit<R: gimli::Reader>(
 /// Recursively parse a DIE and all its children
 fn parse_die_recursive<R: gimli::Reader>(
     dwarf: &gimli::Dwarf<R>,
+    units: &[gimli::Unit<R>],
     unit: &gimli::Unit<R>,
     node: gimli::EntriesTreeNode<R>,
+    context: UnitContext,
+    frame_base: Option<&str>,
     id_counter: &mut usize,
 ) -> Result<Option<(DwarfSymbol, usize)>> {
     let entry = node.entry();
@@ -630,8 +816,18 @@ fn parse_die_recursive<R: gimli::Reader>(
         gimli::DW_TAG_enumeration_type => DwarfTag::EnumerationType,
         gimli::DW_TAG_member => DwarfTag::Member,
         gimli::DW_TAG_typedef => DwarfTag::Typedef,
+        gimli::DW_TAG_base_type => DwarfTag::BaseType,
+        gimli::DW_TAG_pointer_type
+        | gimli::DW_TAG_reference_type
+        | gimli::DW_TAG_rvalue_reference_type => DwarfTag::PointerType,
+        gimli::DW_TAG_array_type => DwarfTag::ArrayType,
+        gimli::DW_TAG_const_type => DwarfTag::ConstType,
+        gimli::DW_TAG_volatile_type => DwarfTag::VolatileType,
+        gimli::DW_TAG_subroutine_type => DwarfTag::SubroutineType,
         gimli::DW_TAG_namespace => DwarfTag::Namespace,
         gimli::DW_TAG_enumerator => DwarfTag::Member, // Treat enum variants as members
+        gimli::DW_TAG_variant_part => DwarfTag::VariantPart,
+        gimli::DW_TAG_variant => DwarfTag::Variant,
         _ => {
             // Skip this DIE but still process its children (they might be interesting)
             let mut child_iter = node.children();
@@ -645,30 +841,119 @@ fn parse_die_recursive<R: gimli::Reader>(
     let id = *id_counter;
     *id_counter += 1;
 
+    let offset = die_offset(unit, entry);
+
+    // Inlined and out-of-line instances and definitions of declared functions leave
+    // their name, type and source location to the DIEs they refer to
+    let dies: Vec<_> = std::iter::once((unit, entry.clone()))
+        .chain(origin_chain(units, unit, entry))
+        .collect();
+
     // Get name (with demangling)
-    let raw_name = get_string_attr(dwarf, unit, entry, gimli::DW_AT_name);
-    let linkage_name = get_string_attr(dwarf, unit, entry, gimli::DW_AT_linkage_name);
+    let raw_name = dies
+        .iter()
+        .find_map(|(unit, die)| get_string_attr(dwarf, unit, die, gimli::DW_AT_name));
+    let linkage_name = dies
+        .iter()
+        .find_map(|(unit, die)| get_string_attr(dwarf, unit, die, gimli::DW_AT_linkage_name));
+
+    // Type DIEs and functions describe a type themselves, everything else refers to one
+    let describes_type = matches!(
+        dwarf_tag,
+        DwarfTag::Subprogram
+            | DwarfTag::StructureType
+            | DwarfTag::UnionType
+            | DwarfTag::EnumerationType
+            | DwarfTag::Typedef
+            | DwarfTag::BaseType
+            | DwarfTag::PointerType
+            | DwarfTag::ArrayType
+            | DwarfTag::ConstType
+            | DwarfTag::VolatileType
+            | DwarfTag::SubroutineType
+    );
+    let type_target = dies
+        .iter()
+        .find_map(|(unit, die)| Some((*unit, type_ref(die)?)));
+    let referenced_type = type_target.map(|(unit, target)| resolve_type(dwarf, unit, target));
+    // The abstract instance of a function has its full signature
+    let signature = match dwarf_tag {
+        DwarfTag::Subprogram | DwarfTag::InlinedSubroutine => dies.last(),
+        _ => dies.first(),
+    };
+    let type_info = match signature {
+        Some((unit, die)) if describes_type || die.tag() == gimli::DW_TAG_subprogram => {
+            Some(resolve_type(dwarf, unit, die.offset()))
+        }
+        _ => referenced_type.clone(),
+    };
+    let type_name = referenced_type.map(|ty| ty.display(context.rust_syntax));
+    let type_offset = type_target.and_then(|(unit, target)| global_offset(unit, target));
 
-    let name = linkage_name
+    let mut name = linkage_name
         .or(raw_name)
         .map(|n| demangle_name(&n))
-        .unwrap_or_else(|| match dwarf_tag {
-            DwarfTag::LexicalBlock => "<block>".to_string(),
-            DwarfTag::InlinedSubroutine => "<inlined>".to_string(),
+        .unwrap_or_else(|| match (&dwarf_tag, &type_info) {
+            (DwarfTag::LexicalBlock, _) => "<block>".to_string(),
+            (DwarfTag::InlinedSubroutine, _) => "<inlined>".to_string(),
+            (DwarfTag::VariantPart, _) => "<variants>".to_string(),
+            // Unnamed pointer, array and qualifier types are named by what they are
+            (tag, Some(ty)) if describes_type && *tag != DwarfTag::Subprogram => {
+                ty.display(context.rust_syntax)
+            }
             _ => "<anonymous>".to_string(),
         });
 
-    // Get address
-    let address = get_address_attr(unit, entry, gimli::DW_AT_low_pc);
+    // Code of functions, blocks and inlined calls, possibly split into several ranges
+    let ranges = match dwarf_tag {
+        DwarfTag::Subprogram | DwarfTag::LexicalBlock | DwarfTag::InlinedSubroutine => {
+            die_ranges(dwarf, unit, entry)
+        }
+        _ => Vec::new(),
+    };
+
+    // Get address, for statics from their location expression
+    let address = get_address_attr(unit, entry, gimli::DW_AT_low_pc)
+        .or_else(|| ranges.first().map(|&(start, _)| start))
+        .or_else(|| {
+            (dwarf_tag == DwarfTag::Variable)
+                .then(|| static_address(dwarf, unit, entry))
+                .flatten()
+        });
 
-    // Get size (from high_pc - low_pc or byte_size)
-    let size = get_size(unit, entry);
+    // Get size (from high_pc - low_pc, the ranges or byte_size, for variables from their type)
+    let size = get_size(unit, entry)
+        .or_else(|| (!ranges.is_empty()).then(|| code_bytes(&ranges)))
+        .or_else(|| {
+            (dwarf_tag == DwarfTag::Variable && address.is_some())
+                .then(|| type_size(type_target?.0, type_target?.1))
+                .flatten()
+        });
 
-    // Get file/line info
-    let (file, line, column) = get_file_line_info(dwarf, unit, entry);
+    // Get file/line info, for inlined functions where they were inlined
+    let call_site = (dwarf_tag == DwarfTag::InlinedSubroutine)
+        .then(|| get_file_line_info(dwarf, unit, entry, true))
+        .filter(|(file, line, _)| file.is_some() || line.is_some());
+    let (file, line, column) = call_site
+        .or_else(|| {
+            dies.iter()
+                .map(|(unit, die)| get_file_line_info(dwarf, unit, die, false))
+                .find(|(file, line, _)| file.is_some() || line.is_some())
+        })
+        .unwrap_or_default();
 
-    // Get type name
-    let type_name = get_type_name(dwarf, unit, entry);
+    let layout = match dwarf_tag {
+        DwarfTag::StructureType | DwarfTag::UnionType => {
+            struct_layout(dwarf, unit, entry.offset(), context.rust_syntax)
+        }
+        _ => None,
+    };
+    let enum_layout = match dwarf_tag {
+        DwarfTag::StructureType | DwarfTag::EnumerationType if layout.is_none() => {
+            enum_layout(dwarf, unit, entry.offset(), context.rust_syntax)
+        }
+        _ => None,
+    };
 
     // Build attributes list - capture ALL DWARF attributes without exception
     let mut attributes = Vec::new();
@@ -683,39 +968,176 @@ fn parse_die_recursive<R: gimli::Reader>(
         attributes.push((attr_name.to_string(), attr_value));
     }
 
+    // Followed by what the origin or specification adds
+    for (origin_unit, origin) in &dies[1..] {
+        let mut attrs = origin.attrs();
+        while let Ok(Some(attr)) = attrs.next() {
+            let attr_name = attr.name().static_string().unwrap_or("Unknown");
+            if attr.name() == gimli::DW_AT_sibling
+                || attributes.iter().any(|(name, _)| name == attr_name)
+            {
+                continue;
+            }
+            let attr_value = format_attr_value(dwarf, origin_unit, &attr)
+                .unwrap_or_else(|| format!("{:?}", attr.value()));
+            attributes.push((attr_name.to_string(), attr_value));
+        }
+    }
+
+    // Where locals and parameters live; statics already have their address
+    let locations = match dwarf_tag {
+        DwarfTag::FormalParameter | DwarfTag::Variable if address.is_none() => {
+            variable_locations(dwarf, unit, entry, context.arch, frame_base)
+        }
+        _ => Vec::new(),
+    };
+
+    // Children of a function address their locals relative to its frame base
+    let own_frame_base = match dwarf_tag {
+        DwarfTag::Subprogram => location::frame_base(unit, entry, context.arch),
+        _ => None,
+    };
+    let frame_base = own_frame_base.as_deref().or(frame_base);
+
     // Parse children recursively
     let mut children = Vec::new();
     let mut symbol_count = 1;
 
     let mut child_iter = node.children();
     while let Some(child_node) = child_iter.next()? {
-        if let Some((child_symbol, count)) =
-            parse_die_recursive(dwarf, unit, child_node, id_counter)?
-        {
+        if let Some((child_symbol, count)) = parse_die_recursive(
+            dwarf, units, unit, child_node, context, frame_base, id_counter,
+        )? {
             symbol_count += count;
             children.push(child_symbol);
         }
     }
 
+    // Variants are unnamed, the member holding their payload carries the name
+    if dwarf_tag == DwarfTag::Variant {
+        if let Some(member) = children.iter().find(|c| c.tag == DwarfTag::Member) {
+            name = member.name.clone();
+        }
+    }
+
     Ok(Some((
         DwarfSymbol {
             id,
+            die_offset: offset,
             name,
             tag: dwarf_tag,
             address,
             size,
+            ranges,
             file,
             line,
             column,
             type_name,
+            type_offset,
             children,
             attributes,
+            locations,
+            type_info,
+            layout,
+            enum_layout,
         },
         symbol_count,
     )))
 }
 
-fn get_string_attr<R: gimli::Reader>(
+/// Code ranges of a DIE from `low_pc`/`high_pc` or `DW_AT_ranges` (`.debug_ranges`
+/// or `.debug_rnglists`), sorted with overlapping ranges merged
+fn die_ranges<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> Vec<(u64, u64)> {
+    let mut ranges = Vec::new();
+    if let Ok(mut iter) = dwarf.die_ranges(unit, entry) {
+        while let Ok(Some(range)) = iter.next() {
+            if range.end > range.begin {
+                ranges.push((range.begin, range.end));
+            }
+        }
+    }
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn code_bytes(ranges: &[(u64, u64)]) -> u64 {
+    ranges.iter().map(|(start, end)| end - start).sum()
+}
+
+/// Depth limit when following abstract origins and specifications
+const MAX_ORIGIN_DEPTH: usize = 4;
+
+/// The DIEs `entry` refers to through `DW_AT_abstract_origin` or `DW_AT_specification`,
+/// nearest first, each with the unit it lives in
+fn origin_chain<'u, R: gimli::Reader>(
+    units: &'u [gimli::Unit<R>],
+    unit: &'u gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> Vec<(
+    &'u gimli::Unit<R>,
+    gimli::DebuggingInformationEntry<'u, 'u, R>,
+)> {
+    let mut chain = Vec::new();
+    let mut current = (unit, entry.clone());
+
+    while chain.len() < MAX_ORIGIN_DEPTH {
+        let (unit, entry) = &current;
+        let reference = [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification]
+            .into_iter()
+            .find_map(|attr_name| entry.attr_value(attr_name).ok().flatten());
+        let target = match reference {
+            Some(gimli::AttributeValue::UnitRef(offset)) => Some((*unit, offset)),
+            // Across units, e.g. functions inlined from another crate under LTO
+            Some(gimli::AttributeValue::DebugInfoRef(offset)) => units.iter().find_map(|unit| {
+                offset
+                    .to_unit_offset(&unit.header)
+                    .map(|offset| (unit, offset))
+            }),
+            _ => None,
+        };
+        let Some(next) = target.and_then(|(unit, offset)| Some((unit, unit.entry(offset).ok()?)))
+        else {
+            break;
+        };
+        chain.push(next.clone());
+        current = next;
+    }
+
+    chain
+}
+
+/// Global `.debug_info` offset of a DIE, used to link other views to the tree
+pub fn die_offset<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> Option<u64> {
+    global_offset(unit, entry.offset())
+}
+
+/// Global `.debug_info` offset of a unit-relative offset
+pub fn global_offset<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+) -> Option<u64> {
+    use gimli::ReaderOffset;
+    offset
+        .to_debug_info_offset(&unit.header)
+        .map(|offset| offset.0.into_u64())
+}
+
+pub fn get_string_attr<R: gimli::Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &gimli::Unit<R>,
     entry: &gimli::DebuggingInformationEntry<R>,
@@ -727,6 +1149,37 @@ fn get_string_attr<R: gimli::Reader>(
     Some(cow_str.into_owned())
 }
 
+/// Address of a static from its `DW_AT_location`, for the simple expressions
+/// compilers emit for fixed addresses: `DW_OP_addr`/`DW_OP_addrx`, optionally
+/// followed by constant offsets
+pub fn static_address<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> Option<u64> {
+    let gimli::AttributeValue::Exprloc(expr) = entry.attr_value(gimli::DW_AT_location).ok()??
+    else {
+        return None;
+    };
+
+    let mut operations = expr.operations(unit.encoding());
+    let mut address = match operations.next().ok()?? {
+        gimli::Operation::Address { address } => address,
+        gimli::Operation::AddressIndex { index } => dwarf.address(unit, index).ok()?,
+        _ => return None,
+    };
+    while let Some(operation) = operations.next().ok()? {
+        match operation {
+            gimli::Operation::PlusConstant { value } => address = address.wrapping_add(value),
+            gimli::Operation::Nop => {}
+            // Anything else depends on registers or memory
+            _ => return None,
+        }
+    }
+
+    Some(address)
+}
+
 fn get_address_attr<R: gimli::Reader>(
     _unit: &gimli::Unit<R>,
     entry: &gimli::DebuggingInformationEntry<R>,
@@ -779,37 +1232,39 @@ fn get_size<R: gimli::Reader>(
     }
 }
 
+/// Source location from the `DW_AT_decl_*` attributes, or `DW_AT_call_*` for the
+/// call site of an inlined function
 fn get_file_line_info<R: gimli::Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &gimli::Unit<R>,
     entry: &gimli::DebuggingInformationEntry<R>,
+    call_site: bool,
 ) -> (Option<String>, Option<u32>, Option<u32>) {
+    let (file_attr, line_attr, column_attr) = if call_site {
+        (
+            gimli::DW_AT_call_file,
+            gimli::DW_AT_call_line,
+            gimli::DW_AT_call_column,
+        )
+    } else {
+        (
+            gimli::DW_AT_decl_file,
+            gimli::DW_AT_decl_line,
+            gimli::DW_AT_decl_column,
+        )
+    };
+
     let file = entry
-        .attr_value(gimli::DW_AT_decl_file)
+        .attr_value(file_attr)
         .ok()
         .flatten()
         .and_then(|attr| match attr {
-            gimli::AttributeValue::FileIndex(idx) => {
-                if let Some(ref line_program) = unit.line_program {
-                    let header = line_program.header();
-                    if idx > 0 {
-                        header.file(idx).and_then(|file_entry| {
-                            let raw_str = dwarf.attr_string(unit, file_entry.path_name()).ok()?;
-                            let cow_str = raw_str.to_string_lossy().ok()?;
-                            Some(cow_str.into_owned())
-                        })
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            }
+            gimli::AttributeValue::FileIndex(idx) => file_path(dwarf, unit, idx),
             _ => None,
         });
 
     let line = entry
-        .attr_value(gimli::DW_AT_decl_line)
+        .attr_value(line_attr)
         .ok()
         .flatten()
         .and_then(|attr| match attr {
@@ -821,7 +1276,7 @@ fn get_file_line_info<R: gimli::Reader>(
         });
 
     let column = entry
-        .attr_value(gimli::DW_AT_decl_column)
+        .attr_value(column_attr)
         .ok()
         .flatten()
         .and_then(|attr| match attr {
@@ -834,30 +1289,143 @@ fn get_file_line_info<R: gimli::Reader>(
     (file, line, column)
 }
 
-fn get_type_name<R: gimli::Reader>(
+/// Whether a unit's types read best in Rust syntax: everything but the C family
+pub fn uses_rust_syntax<R: gimli::Reader>(unit: &gimli::Unit<R>) -> bool {
+    let Ok(mut entries) = unit.entries_tree(None) else {
+        return true;
+    };
+    let Ok(root) = entries.root() else {
+        return true;
+    };
+    let Ok(Some(gimli::AttributeValue::Language(language))) =
+        root.entry().attr_value(gimli::DW_AT_language)
+    else {
+        return true;
+    };
+
+    !matches!(
+        language,
+        gimli::DW_LANG_C
+            | gimli::DW_LANG_C89
+            | gimli::DW_LANG_C99
+            | gimli::DW_LANG_C11
+            | gimli::DW_LANG_C17
+            | gimli::DW_LANG_C_plus_plus
+            | gimli::DW_LANG_C_plus_plus_03
+            | gimli::DW_LANG_C_plus_plus_11
+            | gimli::DW_LANG_C_plus_plus_14
+            | gimli::DW_LANG_ObjC
+            | gimli::DW_LANG_ObjC_plus_plus
+    )
+}
+
+/// The type at `offset`, followed through pointers, arrays, qualifiers and typedefs
+pub fn resolve_type<R: gimli::Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &gimli::Unit<R>,
-    entry: &gimli::DebuggingInformationEntry<R>,
-) -> Option<String> {
-    // Follow DW_AT_type reference to get type name
-    let type_offset = entry
-        .attr_value(gimli::DW_AT_type)
-        .ok()
-        .flatten()
-        .and_then(|attr| match attr {
-            gimli::AttributeValue::UnitRef(offset) => Some(offset),
-            _ => None,
-        })?;
+    offset: gimli::UnitOffset<R::Offset>,
+) -> DwarfType {
+    resolve_type_at(dwarf, unit, offset, 0)
+}
 
-    let mut tree = unit.entries_tree(Some(type_offset)).ok()?;
-    let root = tree.root().ok()?;
-    let type_entry = root.entry();
+fn resolve_type_at<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    depth: usize,
+) -> DwarfType {
+    if depth > MAX_TYPE_DEPTH {
+        return DwarfType::Unknown;
+    }
+    let Ok(entry) = unit.entry(offset) else {
+        return DwarfType::Unknown;
+    };
+    let name = get_string_attr(dwarf, unit, &entry, gimli::DW_AT_name);
+    let target = || match type_ref(&entry) {
+        Some(target) => Box::new(resolve_type_at(dwarf, unit, target, depth + 1)),
+        None => Box::new(DwarfType::Void),
+    };
+    let pointer = |sigil| DwarfType::Pointer {
+        sigil,
+        name: name.clone(),
+        pointee: target(),
+    };
+    let qualified = |qualifier| DwarfType::Qualified {
+        qualifier,
+        inner: target(),
+    };
 
-    // Get the type's name
-    get_string_attr(dwarf, unit, type_entry, gimli::DW_AT_name)
+    match entry.tag() {
+        gimli::DW_TAG_base_type | gimli::DW_TAG_unspecified_type => DwarfType::Base {
+            name: name.unwrap_or_else(|| "?".to_string()),
+        },
+        gimli::DW_TAG_structure_type => DwarfType::Named {
+            keyword: "struct",
+            name,
+        },
+        gimli::DW_TAG_class_type => DwarfType::Named {
+            keyword: "class",
+            name,
+        },
+        gimli::DW_TAG_union_type => DwarfType::Named {
+            keyword: "union",
+            name,
+        },
+        gimli::DW_TAG_enumeration_type => DwarfType::Named {
+            keyword: "enum",
+            name,
+        },
+        gimli::DW_TAG_typedef => DwarfType::Typedef {
+            name: name.clone().unwrap_or_default(),
+            target: target(),
+        },
+        gimli::DW_TAG_pointer_type => pointer("*"),
+        gimli::DW_TAG_reference_type => pointer("&"),
+        gimli::DW_TAG_rvalue_reference_type => pointer("&&"),
+        gimli::DW_TAG_const_type => qualified("const"),
+        gimli::DW_TAG_volatile_type => qualified("volatile"),
+        gimli::DW_TAG_restrict_type => qualified("restrict"),
+        gimli::DW_TAG_atomic_type => qualified("_Atomic"),
+        gimli::DW_TAG_array_type => DwarfType::Array {
+            element: target(),
+            dims: array_bounds(unit, offset),
+        },
+        // A function's own DIE has the same shape as its type
+        gimli::DW_TAG_subroutine_type | gimli::DW_TAG_subprogram => {
+            let mut params = Vec::new();
+            let mut variadic = false;
+            if let Ok(mut tree) = unit.entries_tree(Some(offset)) {
+                if let Ok(root) = tree.root() {
+                    let mut children = root.children();
+                    while let Ok(Some(child)) = children.next() {
+                        let child = child.entry();
+                        match child.tag() {
+                            gimli::DW_TAG_formal_parameter => params.push(match type_ref(child) {
+                                Some(param) => resolve_type_at(dwarf, unit, param, depth + 1),
+                                None => DwarfType::Unknown,
+                            }),
+                            gimli::DW_TAG_unspecified_parameters => variadic = true,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            DwarfType::Subroutine {
+                ret: target(),
+                params,
+                variadic,
+            }
+        }
+        _ => DwarfType::Unknown,
+    }
 }
 
-fn demangle_name(name: &str) -> String {
+pub fn demangle_name(name: &str) -> String {
+    // Plain names like `x` or `i` would demangle as C++ builtin types
+    if !(name.starts_with("_Z") || name.starts_with("__Z") || name.starts_with("_R")) {
+        return name.to_string();
+    }
+
     // Try Rust demangling
     for lang in [
         gimli::DW_LANG_Rust,
@@ -873,6 +1441,52 @@ fn demangle_name(name: &str) -> String {
     name.to_string()
 }
 
+/// Location expression as DWARF operations, e.g. `DW_OP_addr 0x20000010`.
+/// `None` if it uses operations without a short textual form.
+fn format_expression<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    expr: &gimli::Expression<R>,
+) -> Option<String> {
+    use gimli::ReaderOffset;
+    let mut ops = Vec::new();
+    let mut operations = expr.clone().operations(unit.encoding());
+    while let Some(operation) = operations.next().ok()? {
+        let op = match operation {
+            gimli::Operation::Address { address } => format!("DW_OP_addr 0x{:08x}", address),
+            gimli::Operation::AddressIndex { index } => match dwarf.address(unit, index) {
+                Ok(address) => format!("DW_OP_addrx 0x{:08x}", address),
+                Err(_) => format!("DW_OP_addrx #{}", index.0.into_u64()),
+            },
+            gimli::Operation::PlusConstant { value } => format!("DW_OP_plus_uconst 0x{:x}", value),
+            gimli::Operation::UnsignedConstant { value } => format!("DW_OP_constu {}", value),
+            gimli::Operation::SignedConstant { value } => format!("DW_OP_consts {}", value),
+            gimli::Operation::Register { register } => format!("DW_OP_reg{}", register.0),
+            gimli::Operation::RegisterOffset {
+                register, offset, ..
+            } => format!("DW_OP_breg{} {:+}", register.0, offset),
+            gimli::Operation::FrameOffset { offset } => format!("DW_OP_fbreg {:+}", offset),
+            gimli::Operation::CallFrameCFA => "DW_OP_call_frame_cfa".to_string(),
+            gimli::Operation::Deref { .. } => "DW_OP_deref".to_string(),
+            gimli::Operation::StackValue => "DW_OP_stack_value".to_string(),
+            gimli::Operation::Plus => "DW_OP_plus".to_string(),
+            gimli::Operation::Minus => "DW_OP_minus".to_string(),
+            gimli::Operation::Piece {
+                size_in_bits,
+                bit_offset: None,
+            } if size_in_bits % 8 == 0 => format!("DW_OP_piece {}", size_in_bits / 8),
+            gimli::Operation::Piece { size_in_bits, .. } => {
+                format!("DW_OP_bit_piece {}", size_in_bits)
+            }
+            gimli::Operation::Nop => "DW_OP_nop".to_string(),
+            _ => return None,
+        };
+        ops.push(op);
+    }
+
+    (!ops.is_empty()).then(|| ops.join("; "))
+}
+
 /// Format a DWARF attribute value to a human-readable string
 fn format_attr_value<R: gimli::Reader>(
     dwarf: &gimli::Dwarf<R>,
@@ -907,7 +1521,10 @@ fn format_attr_value<R: gimli::Reader>(
         gimli::AttributeValue::Sdata(val) => Some(val.to_string()),
         gimli::AttributeValue::Udata(val) => Some(val.to_string()),
         gimli::AttributeValue::Exprloc(expr) => {
-            // Format DWARF expression
+            // Disassemble the expression, falling back to raw bytes
+            if let Some(ops) = format_expression(dwarf, unit, &expr) {
+                return Some(ops);
+            }
             let bytes: Vec<String> = expr
                 .0
                 .to_slice()
@@ -1059,20 +1676,8 @@ fn format_attr_value<R: gimli::Reader>(
             Some(format!("{}", ord.static_string().unwrap_or("?")))
         }
         gimli::AttributeValue::FileIndex(idx) => {
-            // Resolve file index to actual filename
-            if let Some(ref line_program) = unit.line_program {
-                let header = line_program.header();
-                if idx > 0 {
-                    if let Some(file_entry) = header.file(idx) {
-                        if let Ok(raw_str) = dwarf.attr_string(unit, file_entry.path_name()) {
-                            if let Ok(s) = raw_str.to_string_lossy() {
-                                return Some(s.into_owned());
-                            }
-                        }
-                    }
-                }
-            }
-            Some(format!("file[{}]", idx))
+            // Resolve file index to the full path of the file
+            Some(file_path(dwarf, unit, idx).unwrap_or_else(|| format!("file[{}]", idx)))
         }
         gimli::AttributeValue::DwoId(id) => Some(format!("dwo_id 0x{:016x}", id.0)),
         _ => None, // Skip unknown attribute types
@@ -0,0 +1,89 @@
+use crate::parser::demangle_name;
+use crate::types::{
+    DwarfInfo, DwarfType, ElfSymbol, MemorySegment, RamAdvice, RamAdviceKind, XrefIndex, XrefKind,
+};
+use crate::utils::with_dwarf_statics;
+
+/// Zero-initialized arrays smaller than this are not worth a linker section of their own
+const MIN_UNINIT_SIZE: u64 = 256;
+
+/// Statics in writable sections (`SHF_WRITE`) that code only ever reads, and large
+/// zero-initialized arrays that could skip clearing in `.uninit`. Largest savings first.
+///
+/// Reads and writes come from the cross-references, so a static whose address escapes,
+/// e.g. to a function or into a pointer in data, is never reported as read-only.
+pub fn analyze_ram_usage(
+    segments: &[MemorySegment],
+    symbols: &[ElfSymbol],
+    dwarf_info: &DwarfInfo,
+    xrefs: &XrefIndex,
+) -> Vec<RamAdvice> {
+    let mut advice = Vec::new();
+    let mut statics = with_dwarf_statics(symbols, dwarf_info);
+    statics.retain(|symbol| symbol.size > 0 && !symbol.name.starts_with('$'));
+    statics.dedup_by_key(|symbol| symbol.address);
+
+    for symbol in statics {
+        let Some(segment) = segments.iter().find(|segment| {
+            symbol.address >= segment.address && symbol.address < segment.address + segment.size
+        }) else {
+            continue;
+        };
+        // Flags are "R", then "W" or "-", then "X" or "-"
+        if segment.flags != "RW-" {
+            continue;
+        }
+
+        let variable = dwarf_info
+            .static_at(symbol.address)
+            .filter(|variable| variable.address == Some(symbol.address));
+        let accesses = xrefs.to_range(symbol.address, symbol.size);
+        let zeroed = !segment.is_load
+            && !segment.name.contains("uninit")
+            && !segment.name.contains("noinit");
+        let is_array = variable
+            .and_then(|variable| variable.type_info.as_ref())
+            .is_some_and(is_array);
+
+        let read_only =
+            !accesses.is_empty() && accesses.iter().all(|xref| xref.kind == XrefKind::Read);
+
+        let kind = if read_only {
+            RamAdviceKind::ReadOnly
+        } else if zeroed && is_array && symbol.size >= MIN_UNINIT_SIZE {
+            RamAdviceKind::Uninit
+        } else {
+            continue;
+        };
+
+        advice.push(RamAdvice {
+            name: variable
+                .map(|variable| variable.name.clone())
+                .unwrap_or_else(|| demangle_name(&symbol.name)),
+            address: symbol.address,
+            size: symbol.size,
+            section: segment.name.clone(),
+            file: variable.and_then(|variable| variable.file.clone()),
+            line: variable.and_then(|variable| variable.line),
+            kind,
+            accesses: accesses.len(),
+        });
+    }
+
+    advice.sort_by_key(|advice| {
+        (
+            std::cmp::Reverse(advice.ram_saving()),
+            std::cmp::Reverse(advice.size),
+        )
+    });
+    advice
+}
+
+fn is_array(ty: &DwarfType) -> bool {
+    match ty {
+        DwarfType::Array { .. } => true,
+        DwarfType::Typedef { target, .. } => is_array(target),
+        DwarfType::Qualified { inner, .. } => is_array(inner),
+        _ => false,
+    }
+}
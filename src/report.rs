@@ -0,0 +1,447 @@
+use crate::types::{
+    CallGraph, Capture, DecodedValue, DefmtInfo, Diagnostic, MemoryRegion, MemorySegment,
+    MemorySnapshot, PanicAudit, RamAdvice, RttInfo, StackInfo, StackLayout, VectorTable,
+};
+use crate::utils::{format_size, format_stack_depth};
+use std::collections::HashMap;
+
+/// Plain-text analysis report printed by `--headless`
+pub struct HeadlessReport<'a> {
+    pub segments: &'a [MemorySegment],
+    pub memory_regions: &'a [MemoryRegion],
+    pub defmt_info: &'a DefmtInfo,
+    pub rtt_info: &'a RttInfo,
+    pub stack_info: &'a StackInfo,
+    pub call_graph: &'a CallGraph,
+    pub vector_table: &'a VectorTable,
+    pub diagnostics: &'a [Diagnostic],
+    pub capture: Option<&'a Capture>,
+    pub snapshot: Option<&'a MemorySnapshot>,
+    /// Decoded statics from the snapshot, keyed by DIE offset
+    pub snapshot_values: &'a HashMap<u64, DecodedValue>,
+    /// Statics that could move to flash or skip zeroing
+    pub ram_advice: &'a [RamAdvice],
+    /// Functions pulling in panic and `core::fmt` code
+    pub panic_audit: &'a PanicAudit,
+}
+
+impl HeadlessReport<'_> {
+    pub fn print(&self) {
+        self.print_sections();
+        self.print_regions();
+        self.print_defmt();
+        self.print_rtt();
+        self.print_stack();
+        self.print_stack_usage();
+        self.print_ram_advice();
+        self.print_panic_audit();
+        self.print_vector_table();
+        self.print_diagnostics();
+        self.print_capture();
+        self.print_snapshot();
+    }
+
+    fn print_sections(&self) {
+        println!("ELF Sections");
+        for segment in self.segments {
+            println!(
+                "  {:<24} 0x{:08x}  {:>10}  {}",
+                segment.name,
+                segment.address,
+                format_size(segment.size),
+                segment.flags
+            );
+            for conflict in &segment.conflicts {
+                println!("    ! {}", conflict);
+            }
+        }
+        println!();
+    }
+
+    fn print_regions(&self) {
+        if self.memory_regions.is_empty() {
+            return;
+        }
+
+        println!("Memory Regions");
+        for region in self.memory_regions {
+            println!(
+                "  {:<24} 0x{:08x}  {:>10}  {:?}",
+                region.name,
+                region.start,
+                format_size(region.size),
+                region.kind
+            );
+        }
+        println!();
+    }
+
+    fn print_defmt(&self) {
+        if !self.defmt_info.present {
+            return;
+        }
+
+        println!("defmt");
+        for (section_name, section_size) in &self.defmt_info.sections {
+            println!("  {:<24} {:>10}", section_name, format_size(*section_size));
+        }
+        for (level, count, flash_bytes) in self.defmt_info.level_costs() {
+            if count > 0 {
+                println!(
+                    "  {:<24} {:>4} sites  ~{}",
+                    level.label(),
+                    count,
+                    format_size(flash_bytes)
+                );
+            }
+        }
+        for (crate_name, count) in self.defmt_info.sites_per_crate() {
+            println!("  {:<24} {:>4} sites", crate_name, count);
+        }
+        println!();
+
+        let sites: Vec<_> = self
+            .defmt_info
+            .log_sites
+            .iter()
+            .filter(|site| site.is_log_statement())
+            .collect();
+        if sites.is_empty() {
+            return;
+        }
+
+        println!("defmt Log Sites ({})", sites.len());
+        for site in sites {
+            println!(
+                "  {:>5} {:<5} {:<20} {:?}",
+                site.index,
+                site.level.map_or("print", |level| level.label()),
+                site.crate_name,
+                site.format
+            );
+            if let Some(location) = site.location() {
+                println!("        {}", location);
+            }
+        }
+        println!();
+    }
+
+    fn print_rtt(&self) {
+        if !self.rtt_info.present {
+            return;
+        }
+
+        println!("RTT");
+        if let (Some(name), Some(address)) = (&self.rtt_info.symbol_name, self.rtt_info.address) {
+            println!("  Control block            {} @ 0x{:08x}", name, address);
+        }
+        for warning in &self.rtt_info.warnings {
+            println!("    ! {}", warning);
+        }
+        let up = self.rtt_info.up_buffers.iter().map(|b| ("up", b));
+        let down = self.rtt_info.down_buffers.iter().map(|b| ("down", b));
+        for (direction, buffer) in up.chain(down) {
+            println!(
+                "  {:<4} {:<19} 0x{:08x}  {:>10}  {}  {}",
+                direction,
+                buffer.name,
+                buffer.buffer_address,
+                format_size(buffer.size as u64),
+                buffer.mode.label(),
+                buffer.buffer_symbol.as_deref().unwrap_or("?")
+            );
+            for issue in &buffer.issues {
+                println!("    ! {}", issue);
+            }
+        }
+        println!();
+    }
+
+    fn print_stack(&self) {
+        let stack = self.stack_info;
+        if !stack.present {
+            return;
+        }
+
+        println!("Stack & Heap");
+        if let Some(layout) = &stack.layout {
+            let layout = match layout {
+                StackLayout::Standard => "Standard (stack above statics)",
+                StackLayout::FlipLink => "flip-link (stack below statics)",
+                StackLayout::Unknown => "unknown (no statics in the stack's RAM)",
+            };
+            println!("  Layout                   {}", layout);
+        }
+        if let Some(region) = &stack.ram_region {
+            println!("  RAM region               {}", region);
+        }
+        if let Some(start) = stack.stack_start {
+            println!("  Initial SP               0x{:08x}", start);
+        }
+        if let Some(limit) = stack.stack_limit {
+            println!("  Stack limit              0x{:08x}", limit);
+        }
+        if let (Some(start), Some(end)) = (stack.statics_start, stack.statics_end) {
+            println!(
+                "  Statics                  0x{:08x}..0x{:08x} ({})",
+                start,
+                end,
+                format_size(end.saturating_sub(start))
+            );
+        }
+        match stack.headroom() {
+            Some(headroom) => println!("  Stack headroom           {}", format_size(headroom)),
+            None if stack.ram_region.is_none() => {
+                println!("  Stack headroom           unknown (select a target)")
+            }
+            None => println!("  Stack headroom           unknown"),
+        }
+        println!(
+            "  Can collide with statics {}",
+            match stack.can_collide() {
+                Some(true) => "yes",
+                Some(false) => "no",
+                None => "unknown",
+            }
+        );
+        if let Some(heap) = &stack.heap {
+            println!(
+                "  Heap                     {} @ 0x{:08x} ({})",
+                heap.symbol_name,
+                heap.address,
+                format_size(heap.size)
+            );
+        }
+        for warning in &stack.warnings {
+            println!("    ! {}", warning);
+        }
+        println!();
+    }
+
+    fn print_stack_usage(&self) {
+        let graph = self.call_graph;
+        if graph.roots.is_empty() {
+            return;
+        }
+
+        println!("Worst-case Stack Usage");
+        let headroom = self.stack_info.headroom();
+        // Functions reached through pointers or not at all can be many, show the deepest
+        let (handlers, uncalled): (Vec<_>, Vec<_>) = graph
+            .ranked_roots()
+            .into_iter()
+            .partition(|root| root.vector_index.is_some());
+        let hidden = uncalled.len().saturating_sub(10);
+        for root in handlers.into_iter().chain(uncalled.into_iter().take(10)) {
+            let function = &graph.functions[root.function];
+            let depth = graph.root_depth(root);
+            println!(
+                "  {:<24} {:>20}  {}",
+                root.name,
+                format_stack_depth(depth, function.bound),
+                function.name
+            );
+            let path: Vec<&str> = graph
+                .worst_path(root.function)
+                .into_iter()
+                .skip(1)
+                .map(|index| graph.functions[index].name.as_str())
+                .collect();
+            if !path.is_empty() {
+                println!("    via {}", path.join(" -> "));
+            }
+            if headroom.is_some_and(|headroom| depth > headroom) {
+                println!("    ! exceeds the stack headroom");
+            }
+        }
+        if hidden > 0 {
+            println!(
+                "  ... {} more functions whose address is taken or not called directly",
+                hidden
+            );
+        }
+        println!();
+    }
+
+    fn print_ram_advice(&self) {
+        if self.ram_advice.is_empty() {
+            return;
+        }
+
+        let saving: u64 = self.ram_advice.iter().map(RamAdvice::ram_saving).sum();
+        println!("RAM Reduction ({} could be saved)", format_size(saving));
+        for advice in self.ram_advice {
+            println!(
+                "  {:<32} {:>10}  {:<10} {}",
+                advice.name,
+                format_size(advice.size),
+                advice.section,
+                advice.kind.display_name()
+            );
+            if let (Some(file), Some(line)) = (&advice.file, advice.line) {
+                println!("    {}:{}", file, line);
+            }
+            println!("    {}", advice.suggestion());
+        }
+        println!();
+    }
+
+    fn print_panic_audit(&self) {
+        let audit = self.panic_audit;
+        if audit.panic_functions == 0 && audit.fmt_functions == 0 {
+            return;
+        }
+
+        println!("Panic and fmt Audit");
+        println!(
+            "  panic: {} functions, {}; fmt: {} functions, {}; {} panic locations",
+            audit.panic_functions,
+            format_size(audit.panic_size),
+            audit.fmt_functions,
+            format_size(audit.fmt_size),
+            audit.locations.len()
+        );
+        for entry in &audit.entries {
+            println!("  {:<6} {}", entry.machinery.display_name(), entry.function);
+            if !entry.path.is_empty() {
+                println!("    via {}", entry.path.join(" -> "));
+            }
+            for &index in &entry.locations {
+                let location = &audit.locations[index];
+                println!(
+                    "    at {}:{}:{}",
+                    location.file, location.line, location.column
+                );
+            }
+        }
+        println!();
+    }
+
+    fn print_vector_table(&self) {
+        let table = self.vector_table;
+        if !table.present {
+            return;
+        }
+
+        println!(
+            "Vector Table ({} @ 0x{:08x}, {} entries, {} unhandled)",
+            table.section_name.as_deref().unwrap_or("?"),
+            table.address.unwrap_or(0),
+            table.entries.len(),
+            table.unhandled_count()
+        );
+        for entry in &table.entries {
+            if entry.value == 0 && entry.index > 0 {
+                continue;
+            }
+            println!(
+                "  {:>3} {:<24} 0x{:08x}  {}{}",
+                entry.index,
+                entry.display_name(),
+                entry.value,
+                entry.symbol.as_deref().unwrap_or("?"),
+                if entry.default_handler {
+                    " (default)"
+                } else {
+                    ""
+                }
+            );
+            for issue in &entry.issues {
+                println!("    ! {}", issue);
+            }
+        }
+        println!();
+    }
+
+    fn print_diagnostics(&self) {
+        println!("Diagnostics ({})", self.diagnostics.len());
+        for diagnostic in self.diagnostics {
+            println!(
+                "  {:<8} {}: {}",
+                diagnostic.severity.label(),
+                diagnostic.key(),
+                diagnostic.message
+            );
+        }
+        println!();
+    }
+
+    fn print_capture(&self) {
+        let Some(capture) = self.capture else {
+            return;
+        };
+
+        println!(
+            "Capture {} ({} {}, {} errors)",
+            capture.file_name,
+            capture.entries.len(),
+            if capture.is_defmt { "frames" } else { "lines" },
+            capture.errors.len()
+        );
+        for entry in &capture.entries {
+            println!(
+                "  {:>12} {:<5} {}",
+                entry.timestamp.as_deref().unwrap_or(""),
+                entry.level.map_or("", |level| level.label()),
+                entry.message
+            );
+            if let Some(location) = entry.site.as_ref().and_then(|site| site.location()) {
+                println!("{:>21}{}", "", location);
+            }
+        }
+        for error in &capture.errors {
+            println!("  ! {}", error);
+        }
+        println!();
+    }
+
+    fn print_snapshot(&self) {
+        let Some(snapshot) = self.snapshot else {
+            return;
+        };
+
+        println!(
+            "Snapshot {} ({}, {})",
+            snapshot.file_name,
+            snapshot.format.label(),
+            format_size(snapshot.total_size())
+        );
+        for region in &snapshot.regions {
+            println!(
+                "  0x{:08x}..0x{:08x}  {:>10}",
+                region.address,
+                region.address + region.data.len() as u64,
+                format_size(region.data.len() as u64)
+            );
+        }
+
+        let mut values: Vec<&DecodedValue> = self.snapshot_values.values().collect();
+        values.sort_by_key(|value| value.address);
+        println!("  {} statics", values.len());
+        for value in values {
+            print_value(value, 2);
+        }
+        println!();
+    }
+}
+
+/// Print a decoded value and its members, indented by nesting depth
+fn print_value(value: &DecodedValue, indent: usize) {
+    // Deeper levels are already summarized in their parent's value
+    const MAX_INDENT: usize = 8;
+
+    println!(
+        "{:indent$}0x{:08x} {}: {} = {}",
+        "",
+        value.address,
+        value.name,
+        value.type_name,
+        value.value,
+        indent = indent
+    );
+    if indent < MAX_INDENT {
+        for child in &value.children {
+            print_value(child, indent + 2);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{bail, Context as AnyhowContext, Result};
+use object::{Object, ObjectKind, ObjectSection, ObjectSegment, SectionKind};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::types::{MemorySnapshot, SnapshotFormat, SnapshotRegion};
+
+/// Load a memory dump for post-mortem inspection.
+///
+/// With a `base` address the file is taken as a raw binary dump starting there.
+/// Otherwise it must be an ELF core file or a probe-rs coredump.
+pub fn load_snapshot(path: &PathBuf, base: Option<u64>) -> Result<MemorySnapshot> {
+    let data = fs::read(path).context("Failed to read snapshot file")?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+
+    let (format, mut regions) = if let Some(base) = base {
+        (
+            SnapshotFormat::Raw,
+            vec![SnapshotRegion {
+                address: base,
+                data,
+            }],
+        )
+    } else if data.starts_with(b"\x7fELF") {
+        (SnapshotFormat::ElfCore, elf_core_regions(&data)?)
+    } else {
+        let dump = probe_rs::CoreDump::load(path).map_err(|e| {
+            anyhow::anyhow!(
+                "Not an ELF core or probe-rs coredump ({}); pass --snapshot-base for raw dumps",
+                e
+            )
+        })?;
+        let regions = dump
+            .data
+            .into_iter()
+            .map(|(range, data)| SnapshotRegion {
+                address: range.start,
+                data,
+            })
+            .collect();
+        (SnapshotFormat::ProbeRsCoreDump, regions)
+    };
+
+    regions.retain(|region| !region.data.is_empty());
+    regions.sort_by_key(|region| region.address);
+
+    Ok(MemorySnapshot {
+        file_name,
+        format,
+        regions,
+    })
+}
+
+/// Memory as the firmware image initializes it: the contents of `.data`, `.rodata`
+/// and the other allocated sections that carry data in the ELF file
+pub fn load_elf_image(path: &PathBuf) -> Result<MemorySnapshot> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+    let mut regions = Vec::new();
+    for section in obj.sections() {
+        let initialized = matches!(
+            section.kind(),
+            SectionKind::Text
+                | SectionKind::Data
+                | SectionKind::ReadOnlyData
+                | SectionKind::ReadOnlyDataWithRel
+                | SectionKind::ReadOnlyString
+        );
+        if !initialized {
+            continue;
+        }
+        let Ok(section_data) = section.data() else {
+            continue;
+        };
+        if section_data.is_empty() {
+            continue;
+        }
+        regions.push(SnapshotRegion {
+            address: section.address(),
+            data: section_data.to_vec(),
+        });
+    }
+    regions.sort_by_key(|region| region.address);
+
+    Ok(MemorySnapshot {
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string()),
+        format: SnapshotFormat::ElfImage,
+        regions,
+    })
+}
+
+/// Memory captured in the `PT_LOAD` segments of an ELF core file
+fn elf_core_regions(data: &[u8]) -> Result<Vec<SnapshotRegion>> {
+    let obj = object::File::parse(data).context("Failed to parse ELF core file")?;
+    if obj.kind() != ObjectKind::Core {
+        bail!("ELF file is not a core dump");
+    }
+
+    let mut regions = Vec::new();
+    for segment in obj.segments() {
+        let Ok(data) = segment.data() else {
+            continue;
+        };
+        regions.push(SnapshotRegion {
+            address: segment.address(),
+            data: data.to_vec(),
+        });
+    }
+
+    Ok(regions)
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::parser::load_dwarf;
+use crate::types::{LineRow, LineTable};
+
+/// A `FROM=TO` source path substitution. Paths recorded at build time that start
+/// with `FROM`, e.g. a CI container's checkout, are looked up under `TO` instead.
+#[derive(Clone, Debug)]
+pub struct PathRemap {
+    pub from: String,
+    pub to: String,
+}
+
+impl FromStr for PathRemap {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (from, to) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected FROM=TO, got '{}'", value))?;
+        if from.is_empty() {
+            return Err(format!("empty prefix in '{}'", value));
+        }
+        Ok(Self {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+}
+
+/// Decode the line number program of every unit into one table sorted by address
+pub fn parse_line_table(path: &PathBuf) -> Result<LineTable> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+    let dwarf = load_dwarf(&obj)?;
+
+    let mut files = Vec::new();
+    let mut file_ids: HashMap<String, usize> = HashMap::new();
+    let mut sequences: Vec<Vec<LineRow>> = Vec::new();
+
+    let mut headers = dwarf.units();
+    while let Some(header) = headers.next()? {
+        let unit = dwarf.unit(header)?;
+        let Some(program) = unit.line_program.clone() else {
+            continue;
+        };
+
+        // File indices are per unit, paths are shared between units
+        let mut unit_files: HashMap<u64, usize> = HashMap::new();
+        let mut sequence = Vec::new();
+        let mut rows = program.rows();
+        while let Some((_, row)) = rows.next_row()? {
+            if row.end_sequence() {
+                finish_sequence(std::mem::take(&mut sequence), row.address(), &mut sequences);
+                continue;
+            }
+
+            let file = *unit_files.entry(row.file_index()).or_insert_with(|| {
+                let path = file_path(&dwarf, &unit, row.file_index())
+                    .unwrap_or_else(|| "<unknown>".to_string());
+                *file_ids.entry(path.clone()).or_insert_with(|| {
+                    files.push(path);
+                    files.len() - 1
+                })
+            });
+
+            sequence.push(LineRow {
+                address: row.address(),
+                size: 0,
+                file,
+                line: row.line().map_or(0, |line| line.get() as u32),
+                column: match row.column() {
+                    gimli::ColumnType::LeftEdge => 0,
+                    gimli::ColumnType::Column(column) => column.get() as u32,
+                },
+            });
+        }
+    }
+
+    sequences.sort_by_key(|sequence| sequence[0].address);
+    Ok(LineTable {
+        files,
+        rows: sequences.into_iter().flatten().collect(),
+    })
+}
+
+/// Give each row of a sequence its size and keep the ones that cover code.
+/// Sequences of functions removed by the linker are relocated to address 0.
+fn finish_sequence(mut sequence: Vec<LineRow>, end: u64, sequences: &mut Vec<Vec<LineRow>>) {
+    if sequence.first().is_none_or(|row| row.address == 0) {
+        return;
+    }
+    let next_addresses: Vec<u64> = sequence
+        .iter()
+        .skip(1)
+        .map(|row| row.address)
+        .chain(std::iter::once(end))
+        .collect();
+    for (row, next) in sequence.iter_mut().zip(next_addresses) {
+        row.size = next.saturating_sub(row.address);
+    }
+    sequence.retain(|row| row.size > 0);
+    if !sequence.is_empty() {
+        sequences.push(sequence);
+    }
+}
+
+/// Full path of a line program file entry: its directory and name joined onto the
+/// unit's `DW_AT_comp_dir`, unless either is already absolute
+pub fn file_path<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+    file_index: u64,
+) -> Option<String> {
+    let header = unit.line_program.as_ref()?.header();
+    let file = header.file(file_index)?;
+
+    let attr_to_string = |attr| -> Option<String> {
+        let raw = dwarf.attr_string(unit, attr).ok()?;
+        Some(raw.to_string_lossy().ok()?.into_owned())
+    };
+
+    let mut path = unit
+        .comp_dir
+        .as_ref()
+        .and_then(|dir| dir.to_string_lossy().ok())
+        .map(|dir| dir.into_owned())
+        .unwrap_or_default();
+    if let Some(directory) = file.directory(header) {
+        join_path(&mut path, &attr_to_string(directory)?);
+    }
+    join_path(&mut path, &attr_to_string(file.path_name())?);
+    Some(path)
+}
+
+/// Append a component to a path from the build machine, which may use either separator
+fn join_path(path: &mut String, component: &str) {
+    let bytes = component.as_bytes();
+    let absolute = component.starts_with(['/', '\\']) || bytes.get(1) == Some(&b':');
+    if absolute || path.is_empty() {
+        *path = component.to_string();
+    } else if !component.is_empty() {
+        if !path.ends_with(['/', '\\']) {
+            path.push('/');
+        }
+        path.push_str(component);
+    }
+}
+
+/// Where a source path recorded at build time can be read here: under the longest
+/// matching remapped prefix, or else at the recorded path itself
+pub fn resolve_source_path(path: &str, remaps: &[PathRemap]) -> Option<PathBuf> {
+    let mut remaps: Vec<&PathRemap> = remaps
+        .iter()
+        .filter(|remap| path.starts_with(remap.from.as_str()))
+        .collect();
+    remaps.sort_by_key(|remap| std::cmp::Reverse(remap.from.len()));
+
+    remaps
+        .into_iter()
+        .map(|remap| {
+            let rest = path[remap.from.len()..].replace('\\', "/");
+            Path::new(&remap.to).join(rest.trim_start_matches('/'))
+        })
+        .chain(std::iter::once(PathBuf::from(path)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Lines of a source file, tolerating encodings other than UTF-8
+pub fn read_source_lines(path: &Path) -> Result<Vec<String>> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(String::from_utf8_lossy(&data)
+        .lines()
+        .map(|line| line.replace('\t', "    "))
+        .collect())
+}
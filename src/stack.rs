@@ -0,0 +1,271 @@
+use crate::types::{
+    ElfSymbol, HeapInfo, MemoryKind, MemoryRegion, MemorySegment, StackInfo, StackLayout,
+};
+use crate::utils::format_size;
+
+/// Initial stack pointer (cortex-m-rt, riscv-rt, CMSIS startup files)
+const STACK_START_SYMBOLS: &[&str] = &["_stack_start", "__stack_start", "__StackTop"];
+/// Explicit lower stack bound, if the linker script defines one
+const STACK_END_SYMBOLS: &[&str] = &["_stack_end", "__StackLimit"];
+const STATICS_START_SYMBOLS: &[&str] = &["__sdata", "_sdata", "__sbss", "_sbss"];
+const STATICS_END_SYMBOLS: &[&str] = &["__euninit", "__ebss", "_ebss", "__edata", "_edata"];
+/// Statics commonly used as `embedded-alloc` heap memory
+const HEAP_STATIC_NAMES: &[&str] = &["HEAP_MEM", "HEAP", "HEAP_AREA", "HEAP_MEMORY"];
+
+/// Below this much headroom the stack is reported as nearly exhausted
+const LOW_HEADROOM_THRESHOLD: u64 = 1024;
+
+/// Derive stack and heap headroom from linker symbols.
+///
+/// Handles both the default layout (stack at the end of RAM growing towards
+/// the statics) and flip-link's layout (stack below the statics). The lower
+/// bound of a flip-link stack is the start of RAM, which is only known when a
+/// target has been selected. Without statics symbols, the writable sections in
+/// the stack's RAM region stand in for them; with neither the layout is unknown.
+pub fn analyze_stack(
+    symbols: &[ElfSymbol],
+    segments: &[MemorySegment],
+    memory_regions: &[MemoryRegion],
+) -> StackInfo {
+    let lookup = |names: &[&str]| -> Option<u64> {
+        names
+            .iter()
+            .find_map(|name| symbols.iter().find(|s| s.name == *name).map(|s| s.address))
+    };
+
+    let Some(stack_start) = lookup(STACK_START_SYMBOLS) else {
+        return StackInfo {
+            present: false,
+            layout: None,
+            stack_start: None,
+            stack_limit: None,
+            statics_start: None,
+            statics_end: None,
+            ram_region: None,
+            heap: None,
+            warnings: Vec::new(),
+        };
+    };
+
+    let ram_region = memory_regions
+        .iter()
+        .filter(|r| r.kind == MemoryKind::Ram)
+        .find(|r| r.contains(stack_start.saturating_sub(1), 1));
+
+    // Prefer the linker symbols, fall back to the writable sections. Statics in
+    // another RAM region cannot collide with the stack, and which region a section
+    // is in is only known with a target.
+    let in_stack_ram = |address: u64| ram_region.is_none_or(|r| r.contains(address, 0));
+    let ram_segments: Vec<&MemorySegment> = segments
+        .iter()
+        .filter(|s| s.flags.contains('W'))
+        .filter(|s| ram_region.is_some_and(|r| r.contains(s.address, s.size)))
+        .collect();
+    let statics_start = STATICS_START_SYMBOLS
+        .iter()
+        .filter_map(|name| lookup(&[*name]))
+        .filter(|address| in_stack_ram(*address))
+        .min()
+        .or_else(|| ram_segments.iter().map(|s| s.address).min());
+    let mut statics_end = STATICS_END_SYMBOLS
+        .iter()
+        .filter_map(|name| lookup(&[*name]))
+        .filter(|address| in_stack_ram(*address))
+        .max()
+        .or_else(|| ram_segments.iter().map(|s| s.address + s.size).max());
+
+    let heap = find_heap(symbols, &lookup);
+
+    // A linker-placed heap (riscv-rt) sits between the statics and the stack
+    if let (Some(heap), Some(end)) = (&heap, statics_end) {
+        if heap.address >= end && heap.address < stack_start {
+            statics_end = Some(heap.address + heap.size);
+        }
+    }
+
+    let layout = match statics_start {
+        Some(start) if stack_start <= start => StackLayout::FlipLink,
+        Some(_) => StackLayout::Standard,
+        None => StackLayout::Unknown,
+    };
+
+    let explicit_limit = lookup(STACK_END_SYMBOLS).filter(|limit| *limit < stack_start);
+
+    let stack_limit = match layout {
+        StackLayout::Standard => match (explicit_limit, statics_end) {
+            (Some(limit), Some(end)) => Some(limit.max(end)),
+            (limit, end) => limit.or(end),
+        },
+        StackLayout::FlipLink => explicit_limit.or(ram_region.map(|r| r.start)),
+        StackLayout::Unknown => explicit_limit,
+    };
+
+    let mut warnings = Vec::new();
+
+    if !memory_regions.is_empty() && ram_region.is_none() {
+        warnings.push(format!(
+            "Initial stack pointer 0x{:08x} is not inside a RAM region",
+            stack_start
+        ));
+    }
+
+    if stack_start % 8 != 0 {
+        warnings.push(format!(
+            "Initial stack pointer 0x{:08x} is not 8-byte aligned",
+            stack_start
+        ));
+    }
+
+    match (&layout, ram_region) {
+        (StackLayout::Standard, _) => warnings.push(
+            "Stack overflow will silently corrupt .bss/.data (consider flip-link)".to_string(),
+        ),
+        (StackLayout::Unknown, Some(region)) => warnings.push(format!(
+            "Stack layout unknown: no statics found in {}",
+            region.name
+        )),
+        (StackLayout::Unknown, None) => warnings.push(
+            "Stack layout unknown: no linker symbols for the statics (select a target)".to_string(),
+        ),
+        (StackLayout::FlipLink, _) => {}
+    }
+
+    if let Some(limit) = stack_limit {
+        if limit >= stack_start {
+            warnings
+                .push("No stack space left: statics reach the initial stack pointer".to_string());
+        } else if stack_start - limit < LOW_HEADROOM_THRESHOLD {
+            warnings.push(format!(
+                "Only {} of stack headroom",
+                format_size(stack_start - limit)
+            ));
+        }
+    }
+
+    StackInfo {
+        present: true,
+        layout: Some(layout),
+        stack_start: Some(stack_start),
+        stack_limit,
+        statics_start,
+        statics_end,
+        ram_region: ram_region.map(|r| r.name.clone()),
+        heap,
+        warnings,
+    }
+}
+
+fn find_heap(symbols: &[ElfSymbol], lookup: &impl Fn(&[&str]) -> Option<u64>) -> Option<HeapInfo> {
+    // riscv-rt reserves the heap in the linker script: `_sheap` .. `_eheap`,
+    // with `_heap_size` as an absolute symbol
+    if let Some(start) = lookup(&["_sheap", "__sheap"]) {
+        let size = lookup(&["_eheap", "__eheap"])
+            .map(|end| end.saturating_sub(start))
+            .or_else(|| lookup(&["_heap_size"]));
+        if let Some(size) = size.filter(|size| *size > 0) {
+            return Some(HeapInfo {
+                symbol_name: "_sheap".to_string(),
+                address: start,
+                size,
+            });
+        }
+    }
+
+    // embedded-alloc: a `static mut HEAP_MEM: [MaybeUninit<u8>; N]` handed to `Heap::init`.
+    // The allocator struct itself is also often called `HEAP`, so ignore tiny symbols.
+    symbols
+        .iter()
+        .filter(|s| s.size > 64)
+        .filter(|s| {
+            let demangled = format!("{:#}", rustc_demangle::demangle(&s.name));
+            let tail = demangled.rsplit("::").next().unwrap_or(&demangled);
+            HEAP_STATIC_NAMES.contains(&tail)
+        })
+        .max_by_key(|s| s.size)
+        .map(|s| HeapInfo {
+            symbol_name: format!("{:#}", rustc_demangle::demangle(&s.name)),
+            address: s.address,
+            size: s.size,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(symbols: &[(&str, u64)]) -> Vec<ElfSymbol> {
+        symbols
+            .iter()
+            .map(|&(name, address)| ElfSymbol {
+                name: name.to_string(),
+                address,
+                size: 0,
+            })
+            .collect()
+    }
+
+    /// 64 KiB of RAM at 0x2000_0000
+    fn ram() -> Vec<MemoryRegion> {
+        vec![MemoryRegion {
+            name: "RAM".to_string(),
+            start: 0x2000_0000,
+            size: 0x1_0000,
+            kind: MemoryKind::Ram,
+        }]
+    }
+
+    #[test]
+    fn stack_above_the_statics() {
+        let symbols = symbols(&[
+            ("_stack_start", 0x2001_0000),
+            ("__sdata", 0x2000_0000),
+            ("__ebss", 0x2000_0400),
+        ]);
+        let stack = analyze_stack(&symbols, &[], &ram());
+
+        assert_eq!(stack.layout, Some(StackLayout::Standard));
+        assert_eq!(stack.stack_limit, Some(0x2000_0400));
+        assert_eq!(stack.headroom(), Some(0xfc00));
+        assert_eq!(stack.can_collide(), Some(true));
+        assert_eq!(stack.ram_region.as_deref(), Some("RAM"));
+        assert!(stack.warnings.iter().any(|w| w.contains("flip-link")));
+    }
+
+    #[test]
+    fn flip_link_stack_below_the_statics() {
+        let symbols = symbols(&[
+            ("_stack_start", 0x2000_8000),
+            ("__sdata", 0x2000_8000),
+            ("__ebss", 0x2000_9000),
+        ]);
+        let stack = analyze_stack(&symbols, &[], &ram());
+
+        assert_eq!(stack.layout, Some(StackLayout::FlipLink));
+        // The stack runs down to the start of RAM
+        assert_eq!(stack.stack_limit, Some(0x2000_0000));
+        assert_eq!(stack.headroom(), Some(0x8000));
+        assert_eq!(stack.can_collide(), Some(false));
+        assert!(stack.warnings.is_empty());
+
+        // Without a target the start of RAM is unknown
+        let stack = analyze_stack(&symbols, &[], &[]);
+        assert_eq!(stack.layout, Some(StackLayout::FlipLink));
+        assert_eq!(stack.stack_limit, None);
+    }
+
+    #[test]
+    fn explicit_stack_end_and_missing_stack() {
+        let symbols_with_end = symbols(&[
+            ("_stack_start", 0x2001_0000),
+            ("_stack_end", 0x2000_f000),
+            ("__sdata", 0x2000_0000),
+            ("__ebss", 0x2000_0400),
+        ]);
+        let stack = analyze_stack(&symbols_with_end, &[], &ram());
+        assert_eq!(stack.stack_limit, Some(0x2000_f000));
+
+        let stack = analyze_stack(&symbols(&[("__sdata", 0x2000_0000)]), &[], &ram());
+        assert!(!stack.present);
+        assert_eq!(stack.layout, None);
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Context, Result};
+use object::{Architecture, Object};
+use std::fs;
+use std::path::Path;
+
+use crate::types::{AddressInfo, ElfSymbol, InlineFrame, MemorySegment};
+use crate::utils::{parse_hex, symbolize_address};
+
+/// Resolves addresses to their section, ELF symbol and inline call chain
+pub struct Symbolizer {
+    loader: addr2line::Loader,
+    segments: Vec<MemorySegment>,
+    /// Sorted by address, as returned by `parse_elf_symbols`
+    symbols: Vec<ElfSymbol>,
+    /// Return addresses and function pointers into Thumb code have bit 0 set
+    thumb: bool,
+}
+
+impl Symbolizer {
+    pub fn new(path: &Path, segments: Vec<MemorySegment>, symbols: Vec<ElfSymbol>) -> Result<Self> {
+        let data = fs::read(path).context("Failed to read ELF file")?;
+        let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+        let loader = addr2line::Loader::new(path)
+            .map_err(|e| anyhow!("Failed to load debug info: {}", e))?;
+
+        Ok(Self {
+            loader,
+            segments,
+            symbols,
+            thumb: obj.architecture() == Architecture::Arm,
+        })
+    }
+
+    pub fn lookup(&self, address: u64) -> AddressInfo {
+        let segment = self
+            .segments
+            .iter()
+            .find(|s| address >= s.address && address < s.address + s.size);
+        // Only code addresses carry the Thumb bit, data may live at odd addresses
+        let address = match segment {
+            Some(segment) if self.thumb && segment.flags.ends_with('X') => address & !1,
+            _ => address,
+        };
+        let section = segment.map(|s| s.name.clone());
+
+        let mut frames = Vec::new();
+        if let Ok(mut iter) = self.loader.find_frames(address) {
+            while let Ok(Some(frame)) = iter.next() {
+                let function = frame
+                    .function
+                    .as_ref()
+                    .and_then(|name| name.demangle().ok())
+                    .map(|name| name.into_owned());
+                let location = frame.location.as_ref();
+                frames.push(InlineFrame {
+                    function,
+                    file: location.and_then(|l| l.file).map(str::to_string),
+                    line: location.and_then(|l| l.line),
+                    column: location.and_then(|l| l.column),
+                });
+            }
+        }
+
+        AddressInfo {
+            address,
+            section,
+            symbol: symbolize_address(&self.symbols, address),
+            frames,
+        }
+    }
+
+    /// Lines for the `--addr2line` output: a summary, then one line per inline frame
+    pub fn describe(&self, address: u64) -> Vec<String> {
+        let info = self.lookup(address);
+        let mut lines = vec![info.summary()];
+        for (depth, frame) in info.frames.iter().enumerate() {
+            let inlined = if depth + 1 < info.frames.len() {
+                " (inlined)"
+            } else {
+                ""
+            };
+            lines.push(format!(
+                "    {} at {}{}",
+                frame.function.as_deref().unwrap_or("??"),
+                frame.location(),
+                inlined
+            ));
+        }
+        lines
+    }
+}
+
+/// Addresses in pasted text such as a backtrace or fault log, in order: `0x`-prefixed
+/// hex like `0x0800_1234`, or bare 8-digit hex words like `08001234`
+pub fn parse_addresses(text: &str) -> Vec<u64> {
+    text.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| {
+            word.starts_with("0x") || word.starts_with("0X") || word.replace('_', "").len() == 8
+        })
+        .filter_map(parse_hex)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_addresses;
+
+    #[test]
+    fn addresses_in_a_backtrace() {
+        let text = "0: 0x08001234 - main\n1: 0x0800_0abc @ lr=0X20000000\nPC 08004000";
+        assert_eq!(
+            parse_addresses(text),
+            [0x0800_1234, 0x0800_0abc, 0x2000_0000, 0x0800_4000]
+        );
+    }
+
+    #[test]
+    fn decimal_and_malformed_words_are_skipped() {
+        // Decimal numbers and words that are not 8 hex digits are not addresses
+        assert_eq!(parse_addresses("line 4096, frame 12"), Vec::<u64>::new());
+        assert_eq!(parse_addresses("0x 0xzz 0x08001234g"), Vec::<u64>::new());
+        assert_eq!(
+            parse_addresses("0800123g deadbeefcafe 1234567"),
+            Vec::<u64>::new()
+        );
+        assert_eq!(parse_addresses("word deadbeef"), [0xdead_beef]);
+        assert_eq!(parse_addresses(""), Vec::<u64>::new());
+    }
+}
@@ -33,13 +33,168 @@ pub struct ElfSymbol {
 pub struct DefmtInfo {
     pub present: bool,
     pub sections: Vec<(String, u64)>, // (section_name, size)
+    pub log_sites: Vec<DefmtLogSite>,
+    /// Wire encoding from the `_defmt_encoding_` marker symbol
+    pub encoding: Option<DefmtEncoding>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefmtEncoding {
+    Raw,
+    Rzcobs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DefmtLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl DefmtLevel {
+    pub const ALL: [DefmtLevel; 5] = [
+        DefmtLevel::Trace,
+        DefmtLevel::Debug,
+        DefmtLevel::Info,
+        DefmtLevel::Warn,
+        DefmtLevel::Error,
+    ];
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "defmt_trace" => Some(DefmtLevel::Trace),
+            "defmt_debug" => Some(DefmtLevel::Debug),
+            "defmt_info" => Some(DefmtLevel::Info),
+            "defmt_warn" => Some(DefmtLevel::Warn),
+            "defmt_error" => Some(DefmtLevel::Error),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DefmtLevel::Trace => "trace",
+            DefmtLevel::Debug => "debug",
+            DefmtLevel::Info => "info",
+            DefmtLevel::Warn => "warn",
+            DefmtLevel::Error => "error",
+        }
+    }
+}
+
+/// One interned defmt string, decoded from its JSON symbol name
+#[derive(Clone, Debug)]
+pub struct DefmtLogSite {
+    /// Index of the string in the defmt table (the symbol's address)
+    pub index: u64,
+    /// Raw defmt tag, e.g. `defmt_info`, `defmt_println` or `defmt_write`
+    pub tag: String,
+    /// Log level, `None` for `println!` and non-logging strings
+    pub level: Option<DefmtLevel>,
+    pub format: String,
+    pub crate_name: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// Code bytes the line table attributes to the log statement's line
+    pub flash_bytes: Option<u64>,
+    /// `.debug_info` offset of the `DEFMT_LOG_STATEMENT` static
+    pub die_offset: Option<u64>,
+}
+
+impl DefmtLogSite {
+    /// Whether the string comes from a logging macro rather than a `Format` impl
+    pub fn is_log_statement(&self) -> bool {
+        self.level.is_some() || self.tag == "defmt_println"
+    }
+
+    pub fn location(&self) -> Option<String> {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => Some(format!("{}:{}", file, line)),
+            (Some(file), None) => Some(file.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl DefmtInfo {
+    /// (site count, flash bytes) per log level
+    pub fn level_costs(&self) -> Vec<(DefmtLevel, usize, u64)> {
+        DefmtLevel::ALL
+            .iter()
+            .map(|&level| {
+                let sites = self.log_sites.iter().filter(|s| s.level == Some(level));
+                let (count, bytes) = sites.fold((0, 0), |(count, bytes), site| {
+                    (count + 1, bytes + site.flash_bytes.unwrap_or(0))
+                });
+                (level, count, bytes)
+            })
+            .collect()
+    }
+
+    /// Number of log sites per crate, most first
+    pub fn sites_per_crate(&self) -> Vec<(String, usize)> {
+        let mut counts: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+        for site in self.log_sites.iter().filter(|s| s.is_log_statement()) {
+            *counts.entry(site.crate_name.as_str()).or_default() += 1;
+        }
+
+        let mut counts: Vec<(String, usize)> = counts
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+/// Operating mode from the low bits of an RTT buffer's `Flags`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RttMode {
+    /// Drop the whole write if it does not fit
+    NoBlockSkip,
+    /// Write as much as fits, drop the rest
+    NoBlockTrim,
+    /// Wait for the host to read
+    BlockIfFull,
+    Invalid(u32),
+}
+
+impl RttMode {
+    pub fn from_flags(flags: u32) -> Self {
+        match flags & 0x3 {
+            0 => RttMode::NoBlockSkip,
+            1 => RttMode::NoBlockTrim,
+            2 => RttMode::BlockIfFull,
+            mode => RttMode::Invalid(mode),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RttMode::NoBlockSkip => "non-blocking (skip)",
+            RttMode::NoBlockTrim => "non-blocking (trim)",
+            RttMode::BlockIfFull => "blocking",
+            RttMode::Invalid(_) => "invalid",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct RttBufferDesc {
+    /// Channel name from `sName`, or "Up n"/"Down n" if it has none
     pub name: String,
     pub buffer_address: u64,
     pub size: u32,
+    /// Symbol the buffer pointer resolves to
+    pub buffer_symbol: Option<String>,
+    pub flags: u32,
+    pub mode: RttMode,
+    /// Initial write and read offsets
+    pub write_offset: u32,
+    pub read_offset: u32,
+    pub issues: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -52,6 +207,236 @@ pub struct RttInfo {
     pub max_down_buffers: Option<u32>,
     pub up_buffers: Vec<RttBufferDesc>,
     pub down_buffers: Vec<RttBufferDesc>,
+    /// Problems with the control block itself
+    pub warnings: Vec<String>,
+}
+
+/// One line of an offline RTT capture
+#[derive(Clone, Debug)]
+pub struct CaptureEntry {
+    /// Device timestamp, formatted with the firmware's defmt timestamp format
+    pub timestamp: Option<String>,
+    pub level: Option<DefmtLevel>,
+    pub message: String,
+    /// Log site the frame was decoded from, for defmt captures
+    pub site: Option<DefmtLogSite>,
+}
+
+/// A raw RTT channel dump, decoded with the ELF's defmt table or as plain text
+#[derive(Clone, Debug)]
+pub struct Capture {
+    pub file_name: String,
+    pub is_defmt: bool,
+    pub entries: Vec<CaptureEntry>,
+    /// Frames that could not be decoded
+    pub errors: Vec<String>,
+}
+
+impl Capture {
+    pub fn level_count(&self, level: DefmtLevel) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.level == Some(level))
+            .count()
+    }
+}
+
+/// File format a memory snapshot was read from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnapshotFormat {
+    /// Raw binary dump, loaded at a user-given base address
+    Raw,
+    ProbeRsCoreDump,
+    ElfCore,
+    /// Initialized sections of the firmware ELF itself
+    ElfImage,
+}
+
+impl SnapshotFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnapshotFormat::Raw => "raw binary",
+            SnapshotFormat::ProbeRsCoreDump => "probe-rs coredump",
+            SnapshotFormat::ElfCore => "ELF core",
+            SnapshotFormat::ElfImage => "ELF image",
+        }
+    }
+}
+
+/// A contiguous block of target memory captured in a snapshot
+#[derive(Clone, Debug)]
+pub struct SnapshotRegion {
+    pub address: u64,
+    pub data: Vec<u8>,
+}
+
+/// Target memory captured after the fact, e.g. a RAM dump sent over UART
+#[derive(Clone, Debug)]
+pub struct MemorySnapshot {
+    pub file_name: String,
+    pub format: SnapshotFormat,
+    /// Captured regions, sorted by address
+    pub regions: Vec<SnapshotRegion>,
+}
+
+impl MemorySnapshot {
+    /// Bytes at `address..address + len`, if a single region covers all of them
+    pub fn read(&self, address: u64, len: u64) -> Option<&[u8]> {
+        let region = self.regions.iter().find(|region| {
+            address >= region.address && address - region.address < region.data.len() as u64
+        })?;
+        let start = (address - region.address) as usize;
+        region.data.get(start..start.checked_add(len as usize)?)
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.regions
+            .iter()
+            .map(|region| region.data.len() as u64)
+            .sum()
+    }
+}
+
+/// A value read from memory and decoded through its DWARF type
+#[derive(Clone, Debug)]
+pub struct DecodedValue {
+    /// Variable, member or element name (`[3]` for array elements)
+    pub name: String,
+    pub type_name: String,
+    pub address: u64,
+    /// Formatted value; a short summary for structs and arrays
+    pub value: String,
+    pub children: Vec<DecodedValue>,
+}
+
+/// How the stack is placed relative to the statics in RAM
+#[derive(Clone, Debug, PartialEq)]
+pub enum StackLayout {
+    /// Stack at the top of RAM, growing down towards `.bss`/`.data`
+    Standard,
+    /// flip-link: stack below the statics, growing towards the start of RAM
+    FlipLink,
+    /// No statics found in the stack's RAM region, e.g. without linker symbols or a
+    /// target
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub struct HeapInfo {
+    pub symbol_name: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+/// Stack and heap headroom derived from cortex-m-rt / riscv-rt linker symbols
+#[derive(Clone, Debug)]
+pub struct StackInfo {
+    pub present: bool,
+    pub layout: Option<StackLayout>,
+    /// Initial stack pointer (highest stack address)
+    pub stack_start: Option<u64>,
+    /// Lowest address the stack can grow to before hitting something else
+    pub stack_limit: Option<u64>,
+    /// Start and end of the statics (`.data`, `.bss`, `.uninit`) in the stack's RAM region
+    pub statics_start: Option<u64>,
+    pub statics_end: Option<u64>,
+    pub ram_region: Option<String>,
+    pub heap: Option<HeapInfo>,
+    pub warnings: Vec<String>,
+}
+
+impl StackInfo {
+    /// Bytes the stack can grow before it reaches `stack_limit`
+    pub fn headroom(&self) -> Option<u64> {
+        match (self.stack_start, self.stack_limit) {
+            (Some(start), Some(limit)) => Some(start.saturating_sub(limit)),
+            _ => None,
+        }
+    }
+
+    /// Whether a stack overflow runs into `.bss`/`.data` instead of faulting, `None`
+    /// if the layout is unknown
+    pub fn can_collide(&self) -> Option<bool> {
+        match self.layout {
+            Some(StackLayout::Standard) => Some(true),
+            Some(StackLayout::FlipLink) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// One slot of the Cortex-M vector table
+#[derive(Clone, Debug)]
+pub struct VectorEntry {
+    pub index: usize,
+    /// Exception name ("Reset", "HardFault", ...) or "IRQ n"
+    pub name: String,
+    /// Interrupt name from the SVD file, for device interrupts
+    pub irq_name: Option<String>,
+    /// Raw word stored in the table
+    pub value: u64,
+    /// Symbol the entry points to, as `name` or `name+0xoff`
+    pub symbol: Option<String>,
+    pub default_handler: bool,
+    pub issues: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VectorTable {
+    pub present: bool,
+    pub section_name: Option<String>,
+    pub address: Option<u64>,
+    pub entries: Vec<VectorEntry>,
+}
+
+impl VectorEntry {
+    pub fn display_name(&self) -> String {
+        match &self.irq_name {
+            Some(irq_name) => format!("{} {}", self.name, irq_name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl VectorTable {
+    /// Interrupts and exceptions that fall through to the default handler
+    pub fn unhandled_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.default_handler).count()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A finding of the firmware lint pass
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub rule: String,
+    pub severity: Severity,
+    /// Section or vector the finding is about
+    pub subject: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Key used to suppress this finding in the lint config
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.rule, self.subject)
+    }
 }
 
 /// Represents a DWARF debug symbol with hierarchical structure
@@ -59,6 +444,8 @@ pub struct RttInfo {
 pub struct DwarfSymbol {
     /// Unique identifier for this symbol
     pub id: usize,
+    /// Offset of the DIE in `.debug_info`
+    pub die_offset: Option<u64>,
     /// Symbol name (demangled if possible)
     pub name: String,
     /// The DWARF tag type (function, variable, struct, etc.)
@@ -67,6 +454,8 @@ pub struct DwarfSymbol {
     pub address: Option<u64>,
     /// Size in bytes if known
     pub size: Option<u64>,
+    /// Code ranges `[start, end)` of a function, block or compile unit, sorted by address
+    pub ranges: Vec<(u64, u64)>,
     /// Source file path
     pub file: Option<String>,
     /// Line number in source file
@@ -75,10 +464,318 @@ pub struct DwarfSymbol {
     pub column: Option<u32>,
     /// Type information (for variables, parameters, etc.)
     pub type_name: Option<String>,
+    /// Offset in `.debug_info` of the DIE that `type_name` refers to
+    pub type_offset: Option<u64>,
     /// Child symbols (nested scopes, members, parameters, etc.)
     pub children: Vec<DwarfSymbol>,
     /// Additional attributes for display
     pub attributes: Vec<(String, String)>,
+    /// Where a local or parameter lives, per PC range (empty for everything else)
+    pub locations: Vec<VariableLocation>,
+    /// Full type of a variable, parameter, member or function, or the type a type DIE describes
+    pub type_info: Option<DwarfType>,
+    /// Member offsets and padding, for structs and unions
+    pub layout: Option<StructLayout>,
+    /// Variants and discriminant, for enums
+    pub enum_layout: Option<EnumLayout>,
+}
+
+/// Location of a local variable or parameter over a range of program counters
+#[derive(Clone, Debug)]
+pub struct VariableLocation {
+    /// PC range the location is valid for, `None` for the whole scope
+    pub pc_range: Option<(u64, u64)>,
+    /// Where the value is, e.g. "r4", "[sp+12]" or "optimized out"
+    pub description: String,
+}
+
+impl VariableLocation {
+    pub fn summary(&self) -> String {
+        match self.pc_range {
+            Some((start, end)) => format!("{} in 0x{:08x}..0x{:08x}", self.description, start, end),
+            None => self.description.clone(),
+        }
+    }
+}
+
+/// Byte layout of a struct or union, with the padding between its members
+#[derive(Clone, Debug)]
+pub struct StructLayout {
+    pub byte_size: u64,
+    pub alignment: u64,
+    pub is_union: bool,
+    /// Members and padding in memory order
+    pub rows: Vec<LayoutRow>,
+    /// Member order with less padding, for C structs where reordering saves bytes
+    pub suggestion: Option<LayoutSuggestion>,
+}
+
+impl StructLayout {
+    /// Total padding in bits, including trailing padding
+    pub fn wasted_bits(&self) -> u64 {
+        self.rows
+            .iter()
+            .map(|row| match row {
+                LayoutRow::Padding { bit_size, .. } => *bit_size,
+                LayoutRow::Member(_) => 0,
+            })
+            .sum()
+    }
+
+    pub fn hole_count(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|row| {
+                matches!(
+                    row,
+                    LayoutRow::Padding {
+                        trailing: false,
+                        ..
+                    }
+                )
+            })
+            .count()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum LayoutRow {
+    Member(LayoutMember),
+    /// Unused bits between members, or after the last one when `trailing`
+    Padding {
+        bit_offset: u64,
+        bit_size: u64,
+        trailing: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutMember {
+    pub name: String,
+    pub type_name: String,
+    /// Offset in `.debug_info` of the member's type DIE
+    pub type_offset: Option<u64>,
+    pub offset: u64,
+    pub size: u64,
+    pub alignment: u64,
+    /// Bit offset from the start of the struct and width, for bitfields
+    pub bits: Option<(u64, u64)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutSuggestion {
+    /// Member names in the suggested order
+    pub order: Vec<String>,
+    pub byte_size: u64,
+}
+
+/// Variants of a C-like or Rust enum and where its discriminant is stored
+#[derive(Clone, Debug)]
+pub struct EnumLayout {
+    pub byte_size: Option<u64>,
+    pub discriminant: Option<EnumDiscriminant>,
+    pub variants: Vec<EnumVariant>,
+}
+
+impl EnumLayout {
+    /// The variant with the most payload, which sets the size of the enum
+    pub fn largest_variant(&self) -> Option<&EnumVariant> {
+        self.variants
+            .iter()
+            .filter(|variant| variant.payload_size > 0)
+            .max_by_key(|variant| variant.payload_size)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnumDiscriminant {
+    pub offset: u64,
+    pub size: u64,
+    /// Field whose invalid values encode the other variants, for niche-encoded enums
+    pub niche_in: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnumVariant {
+    pub name: String,
+    /// Discriminant value, `None` for the variant that owns the niche
+    pub value: Option<i128>,
+    /// Bytes used by the variant's fields
+    pub payload_size: u64,
+    /// Offset in `.debug_info` of the payload struct
+    pub type_offset: Option<u64>,
+    /// Field layout of the payload, for Rust enums with data
+    pub payload: Option<StructLayout>,
+}
+
+/// A type resolved through its whole `DW_AT_type` chain
+#[derive(Clone, Debug, PartialEq)]
+pub enum DwarfType {
+    Void,
+    Base {
+        name: String,
+    },
+    /// Struct, union, enum or class, referred to by name
+    Named {
+        keyword: &'static str,
+        name: Option<String>,
+    },
+    Typedef {
+        name: String,
+        target: Box<DwarfType>,
+    },
+    /// Pointer (`*`), reference (`&`) or rvalue reference (`&&`)
+    Pointer {
+        sigil: &'static str,
+        /// Name the compiler gave the pointer type, as rustc does for `*const u8`
+        name: Option<String>,
+        pointee: Box<DwarfType>,
+    },
+    /// `const`, `volatile`, `restrict` or `_Atomic`
+    Qualified {
+        qualifier: &'static str,
+        inner: Box<DwarfType>,
+    },
+    Array {
+        element: Box<DwarfType>,
+        /// Element counts, outermost first; `None` if unbounded
+        dims: Vec<Option<u64>>,
+    },
+    Subroutine {
+        ret: Box<DwarfType>,
+        params: Vec<DwarfType>,
+        variadic: bool,
+    },
+    /// Past the nesting limit, or a DIE that is not a type
+    Unknown,
+}
+
+impl DwarfType {
+    /// Type in the syntax of the compile unit's language
+    pub fn display(&self, rust: bool) -> String {
+        if rust {
+            self.rust_name()
+        } else {
+            self.c_name()
+        }
+    }
+
+    /// Rust syntax, e.g. `*const u8`, `[u32; 16]` or `fn(u32) -> bool`
+    pub fn rust_name(&self) -> String {
+        match self {
+            DwarfType::Void => "()".to_string(),
+            DwarfType::Base { name } | DwarfType::Typedef { name, .. } => name.clone(),
+            DwarfType::Named { keyword, name } => name
+                .clone()
+                .unwrap_or_else(|| format!("<anonymous {}>", keyword)),
+            DwarfType::Pointer {
+                name: Some(name), ..
+            } => name.clone(),
+            DwarfType::Pointer { sigil, pointee, .. } => match (*sigil, pointee.as_ref()) {
+                (
+                    "*",
+                    DwarfType::Qualified {
+                        qualifier: "const",
+                        inner,
+                    },
+                ) => format!("*const {}", inner.rust_name()),
+                ("*", pointee) => format!("*mut {}", pointee.rust_name()),
+                (sigil, pointee) => format!("{}{}", sigil, pointee.rust_name()),
+            },
+            // Rust has no qualifiers outside of pointers
+            DwarfType::Qualified { inner, .. } => inner.rust_name(),
+            DwarfType::Array { element, dims } => {
+                dims.iter()
+                    .rev()
+                    .fold(element.rust_name(), |inner, count| match count {
+                        Some(count) => format!("[{}; {}]", inner, count),
+                        None => format!("[{}]", inner),
+                    })
+            }
+            DwarfType::Subroutine {
+                ret,
+                params,
+                variadic,
+            } => {
+                let mut params: Vec<String> = params.iter().map(|p| p.rust_name()).collect();
+                if *variadic {
+                    params.push("...".to_string());
+                }
+                match ret.as_ref() {
+                    DwarfType::Void => format!("fn({})", params.join(", ")),
+                    ret => format!("fn({}) -> {}", params.join(", "), ret.rust_name()),
+                }
+            }
+            DwarfType::Unknown => "?".to_string(),
+        }
+    }
+
+    /// C syntax, e.g. `const char *`, `uint8_t [16]` or `void (*)(int)`
+    pub fn c_name(&self) -> String {
+        self.c_declarator("")
+    }
+
+    /// The type wrapped around a declarator, inside out as C declarations read
+    fn c_declarator(&self, declarator: &str) -> String {
+        let with = |base: String| {
+            if declarator.is_empty() {
+                base
+            } else {
+                format!("{} {}", base, declarator)
+            }
+        };
+
+        match self {
+            DwarfType::Void => with("void".to_string()),
+            DwarfType::Base { name } | DwarfType::Typedef { name, .. } => with(name.clone()),
+            DwarfType::Named { keyword, name } => with(format!(
+                "{} {}",
+                keyword,
+                name.as_deref().unwrap_or("<anonymous>")
+            )),
+            DwarfType::Pointer { sigil, pointee, .. } => {
+                let inner = format!("{}{}", sigil, declarator);
+                match pointee.as_ref() {
+                    DwarfType::Array { .. } | DwarfType::Subroutine { .. } => {
+                        pointee.c_declarator(&format!("({})", inner))
+                    }
+                    pointee => pointee.c_declarator(&inner),
+                }
+            }
+            // A qualified pointer puts the qualifier after the `*`
+            DwarfType::Qualified { qualifier, inner } => match inner.as_ref() {
+                DwarfType::Pointer { .. } => {
+                    inner.c_declarator(format!("{} {}", qualifier, declarator).trim_end())
+                }
+                inner => format!("{} {}", qualifier, inner.c_declarator(declarator)),
+            },
+            DwarfType::Array { element, dims } => {
+                let bounds: String = dims
+                    .iter()
+                    .map(|count| match count {
+                        Some(count) => format!("[{}]", count),
+                        None => "[]".to_string(),
+                    })
+                    .collect();
+                element.c_declarator(&format!("{}{}", declarator, bounds))
+            }
+            DwarfType::Subroutine {
+                ret,
+                params,
+                variadic,
+            } => {
+                let mut params: Vec<String> = params.iter().map(|p| p.c_name()).collect();
+                if *variadic {
+                    params.push("...".to_string());
+                }
+                if params.is_empty() {
+                    params.push("void".to_string());
+                }
+                ret.c_declarator(&format!("{}({})", declarator, params.join(", ")))
+            }
+            DwarfType::Unknown => with("?".to_string()),
+        }
+    }
 }
 
 /// DWARF tag types we care about displaying
@@ -95,7 +792,16 @@ pub enum DwarfTag {
     EnumerationType,
     Member,
     Typedef,
+    BaseType,
+    PointerType,
+    ArrayType,
+    ConstType,
+    VolatileType,
+    SubroutineType,
     Namespace,
+    /// The variants of a Rust enum with data
+    VariantPart,
+    Variant,
     Other(String),
 }
 
@@ -113,7 +819,15 @@ impl DwarfTag {
             DwarfTag::EnumerationType => "Enum",
             DwarfTag::Member => "Member",
             DwarfTag::Typedef => "Typedef",
+            DwarfTag::BaseType => "Base Type",
+            DwarfTag::PointerType => "Pointer",
+            DwarfTag::ArrayType => "Array",
+            DwarfTag::ConstType => "Const",
+            DwarfTag::VolatileType => "Volatile",
+            DwarfTag::SubroutineType => "Function Type",
             DwarfTag::Namespace => "Namespace",
+            DwarfTag::VariantPart => "Variant Part",
+            DwarfTag::Variant => "Variant",
             DwarfTag::Other(s) => s.as_str(),
         }
     }
@@ -131,7 +845,15 @@ impl DwarfTag {
             DwarfTag::EnumerationType => "▤",
             DwarfTag::Member => "•",
             DwarfTag::Typedef => "≡",
+            DwarfTag::BaseType => "#",
+            DwarfTag::PointerType => "*",
+            DwarfTag::ArrayType => "[ ]",
+            DwarfTag::ConstType => "c",
+            DwarfTag::VolatileType => "v",
+            DwarfTag::SubroutineType => "ƒ*",
             DwarfTag::Namespace => ":::",
+            DwarfTag::VariantPart => "⋁",
+            DwarfTag::Variant => "◦",
             DwarfTag::Other(_) => "?",
         }
     }
@@ -155,6 +877,69 @@ impl Default for DwarfInfo {
     }
 }
 
+impl DwarfInfo {
+    /// Compile unit, function and nested blocks or inlined calls whose code covers
+    /// `address`, outermost first. Empty if no code is there.
+    pub fn scopes_at(&self, address: u64) -> Vec<&DwarfSymbol> {
+        fn walk<'a>(
+            symbols: &'a [DwarfSymbol],
+            address: u64,
+            path: &mut Vec<&'a DwarfSymbol>,
+        ) -> bool {
+            for symbol in symbols {
+                if symbol.contains_address(address) {
+                    path.push(symbol);
+                    walk(&symbol.children, address, path);
+                    return true;
+                }
+                // Namespaces, types and units without ranges still hold functions
+                if symbol.ranges.is_empty() && !symbol.children.is_empty() {
+                    let depth = path.len();
+                    path.push(symbol);
+                    if walk(&symbol.children, address, path) {
+                        return true;
+                    }
+                    path.truncate(depth);
+                }
+            }
+            false
+        }
+
+        let mut path = Vec::new();
+        walk(&self.compile_units, address, &mut path);
+        // Only scopes with code of their own describe the address
+        path.retain(|symbol| !symbol.ranges.is_empty());
+        path
+    }
+
+    /// The static variable whose storage covers `address`
+    pub fn static_at(&self, address: u64) -> Option<&DwarfSymbol> {
+        fn walk(symbols: &[DwarfSymbol], address: u64) -> Option<&DwarfSymbol> {
+            symbols.iter().find_map(|symbol| {
+                let covers = symbol.tag == DwarfTag::Variable
+                    && symbol.address.is_some_and(|start| {
+                        address >= start && address < start + symbol.size.unwrap_or(0).max(1)
+                    });
+                if covers {
+                    Some(symbol)
+                } else {
+                    walk(&symbol.children, address)
+                }
+            })
+        }
+
+        walk(&self.compile_units, address)
+    }
+}
+
+impl DwarfSymbol {
+    pub fn contains_address(&self, address: u64) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| address >= start && address < end)
+    }
+}
+
 impl MemoryRegion {
     pub fn contains(&self, address: u64, size: u64) -> bool {
         let end = address + size;
@@ -168,3 +953,630 @@ impl MemoryRegion {
         !(end <= self.start || address >= region_end)
     }
 }
+
+/// Decoded `.debug_line` programs of all units
+#[derive(Clone, Debug, Default)]
+pub struct LineTable {
+    /// Source paths as recorded at build time, joined onto the unit's `DW_AT_comp_dir`
+    pub files: Vec<String>,
+    /// Rows of all sequences, sorted by address
+    pub rows: Vec<LineRow>,
+}
+
+/// Code generated for one source position
+#[derive(Clone, Debug)]
+pub struct LineRow {
+    pub address: u64,
+    /// Bytes of code up to the next row
+    pub size: u64,
+    /// Index into `LineTable::files`
+    pub file: usize,
+    /// Line number, 0 for compiler-generated code
+    pub line: u32,
+    pub column: u32,
+}
+
+impl LineTable {
+    /// Row whose code covers `address`
+    pub fn row_at(&self, address: u64) -> Option<&LineRow> {
+        let index = self
+            .rows
+            .partition_point(|row| row.address <= address)
+            .checked_sub(1)?;
+        let row = &self.rows[index];
+        (address < row.address + row.size).then_some(row)
+    }
+
+    /// Rows starting within any of `ranges`
+    pub fn rows_in<'a>(&'a self, ranges: &'a [(u64, u64)]) -> impl Iterator<Item = &'a LineRow> {
+        ranges.iter().flat_map(move |&(start, end)| {
+            let first = self.rows.partition_point(|row| row.address < start);
+            self.rows[first..]
+                .iter()
+                .take_while(move |row| row.address < end)
+        })
+    }
+
+    /// Lines of `ranges` in the file most of their code comes from, along with that
+    /// file. The rest is usually code inlined from other files.
+    pub fn lines_in(
+        &self,
+        ranges: &[(u64, u64)],
+    ) -> Option<(usize, std::collections::BTreeSet<u32>)> {
+        let mut file_bytes: std::collections::BTreeMap<usize, u64> =
+            std::collections::BTreeMap::new();
+        for row in self.rows_in(ranges).filter(|row| row.line > 0) {
+            *file_bytes.entry(row.file).or_default() += row.size;
+        }
+        let (file, _) = file_bytes
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+        let lines = self
+            .rows_in(ranges)
+            .filter(|row| row.file == file && row.line > 0)
+            .map(|row| row.line)
+            .collect();
+        Some((file, lines))
+    }
+
+    /// Bytes of code generated for each line of `file`
+    pub fn line_bytes(&self, file: usize) -> std::collections::BTreeMap<u32, u64> {
+        let mut bytes = std::collections::BTreeMap::new();
+        for row in self
+            .rows
+            .iter()
+            .filter(|row| row.file == file && row.line > 0)
+        {
+            *bytes.entry(row.line).or_default() += row.size;
+        }
+        bytes
+    }
+
+    pub fn file_index(&self, path: &str) -> Option<usize> {
+        self.files.iter().position(|file| file == path)
+    }
+}
+
+/// What is at an address: its section, ELF symbol and the chain of inlined calls
+#[derive(Clone, Debug)]
+pub struct AddressInfo {
+    pub address: u64,
+    pub section: Option<String>,
+    /// Containing ELF symbol as `name` or `name+0xoff`
+    pub symbol: Option<String>,
+    /// Innermost first; the last frame is the function the code belongs to
+    pub frames: Vec<InlineFrame>,
+}
+
+impl AddressInfo {
+    /// The DWARF function containing the address, after undoing inlining
+    pub fn function(&self) -> Option<&str> {
+        self.frames.last()?.function.as_deref()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "0x{:08x}  {}  {}",
+            self.address,
+            self.section.as_deref().unwrap_or("(no section)"),
+            self.symbol.as_deref().unwrap_or("??")
+        )
+    }
+}
+
+/// One function of an inline call chain, with the source position inside it
+#[derive(Clone, Debug)]
+pub struct InlineFrame {
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl InlineFrame {
+    /// `file:line:column`, as far as it is known
+    pub fn location(&self) -> String {
+        let mut location = self.file.clone().unwrap_or_else(|| "??".to_string());
+        if let Some(line) = self.line {
+            location.push_str(&format!(":{}", line));
+            if let Some(column) = self.column.filter(|&column| column > 0) {
+                location.push_str(&format!(":{}", column));
+            }
+        }
+        location
+    }
+}
+
+/// Registers captured at a Cortex-M fault, as far as they are known
+#[derive(Clone, Debug, Default)]
+pub struct CrashRegisters {
+    /// r0-r15; SP is the address of the exception frame unless LR holds `EXC_RETURN`
+    pub core: [Option<u32>; 16],
+    pub msp: Option<u32>,
+    pub psp: Option<u32>,
+    pub xpsr: Option<u32>,
+    /// Configurable Fault Status Register (MMFSR, BFSR and UFSR)
+    pub cfsr: Option<u32>,
+    /// HardFault Status Register
+    pub hfsr: Option<u32>,
+    /// MemManage Fault Address Register
+    pub mmfar: Option<u32>,
+    /// BusFault Address Register
+    pub bfar: Option<u32>,
+    /// LR on entry to the fault handler, selects the stack and frame type
+    pub exc_return: Option<u32>,
+}
+
+/// Decoded fault status and symbolized backtrace of a crash
+#[derive(Clone, Debug)]
+pub struct CrashReport {
+    pub causes: Vec<String>,
+    /// Innermost first
+    pub frames: Vec<CrashFrame>,
+    /// Why unwinding ended
+    pub stop_reason: String,
+}
+
+/// One frame of an unwound call stack
+#[derive(Clone, Copy, Debug)]
+pub struct StackFrame {
+    pub pc: u64,
+    pub sp: Option<u64>,
+    /// Entered through an exception, so `pc` is the interrupted instruction rather
+    /// than a return address
+    pub exception: bool,
+}
+
+impl StackFrame {
+    /// Where to look up unwind info and symbols. A return address points after the
+    /// call, which may already be the next function.
+    pub fn lookup_address(&self) -> u64 {
+        if self.exception {
+            self.pc
+        } else {
+            self.pc.wrapping_sub(1)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CrashFrame {
+    pub frame: StackFrame,
+    /// Looked up at the call instruction for return addresses
+    pub info: AddressInfo,
+}
+
+/// Static call graph with per-function stack frames and worst-case stack depth
+#[derive(Clone, Debug, Default)]
+pub struct CallGraph {
+    /// Sorted by address
+    pub functions: Vec<CallGraphFunction>,
+    /// Entry points the worst case is computed from
+    pub roots: Vec<StackRoot>,
+    /// Inputs that could not be read, leaving the graph incomplete
+    pub warnings: Vec<String>,
+}
+
+impl CallGraph {
+    /// Index of the function starting at `address`
+    pub fn function_at(&self, address: u64) -> Option<usize> {
+        self.functions
+            .binary_search_by_key(&address, |function| function.address)
+            .ok()
+    }
+
+    /// Worst-case stack depth of a root, including the registers stacked on entry
+    pub fn root_depth(&self, root: &StackRoot) -> u64 {
+        root.exception_frame + self.functions[root.function].worst_case
+    }
+
+    /// Roots ordered by worst-case stack depth, deepest first
+    pub fn ranked_roots(&self) -> Vec<&StackRoot> {
+        let mut roots: Vec<&StackRoot> = self.roots.iter().collect();
+        roots.sort_by_key(|root| std::cmp::Reverse(self.root_depth(root)));
+        roots
+    }
+
+    /// The call chain below `index` that uses the most stack, starting with `index`
+    pub fn worst_path(&self, index: usize) -> Vec<usize> {
+        let mut path = vec![index];
+        let mut current = index;
+        while let Some(next) = self.functions[current].deepest_callee {
+            if path.contains(&next) {
+                break;
+            }
+            path.push(next);
+            current = next;
+        }
+        path
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CallGraphFunction {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    /// Stack the function itself uses, `None` if neither CFI nor its code tell
+    pub frame_size: Option<u64>,
+    /// The frame size was summed from PUSH/SUB SP instructions instead of CFI
+    pub frame_from_code: bool,
+    /// Directly called functions, including tail calls, as indices into `functions`
+    pub callees: Vec<usize>,
+    pub callers: Vec<usize>,
+    /// Calls through function pointers or vtables
+    pub indirect_calls: usize,
+    /// Part of a call cycle
+    pub recursive: bool,
+    /// Stack used by this function and its deepest call chain
+    pub worst_case: u64,
+    pub bound: StackBound,
+    /// Next function on the deepest call chain
+    pub deepest_callee: Option<usize>,
+}
+
+/// How far a worst-case stack depth can be trusted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StackBound {
+    Exact,
+    /// Indirect calls or functions without a known frame size are reachable
+    LowerBound,
+    /// Recursion is reachable
+    Unbounded,
+}
+
+/// Entry point of a call tree: reset, an exception or interrupt handler, or a function
+/// nothing calls directly, such as a task or callback reached through a pointer
+#[derive(Clone, Debug)]
+pub struct StackRoot {
+    pub name: String,
+    pub function: usize,
+    /// Vector table entry, `None` for functions nothing calls directly
+    pub vector_index: Option<usize>,
+    /// Most the core stacks on exception entry, with FPU context on cores with an FPU
+    /// and the realignment word; 0 for reset and uncalled functions
+    pub exception_frame: u64,
+}
+
+/// Machine code of a function with the source lines it was generated from
+#[derive(Clone, Debug, Default)]
+pub struct Disassembly {
+    pub name: String,
+    /// Instruction set the code was decoded as, e.g. "Thumb-2" or "RV32"
+    pub instruction_set: String,
+    pub lines: Vec<DisassemblyLine>,
+}
+
+#[derive(Clone, Debug)]
+pub enum DisassemblyLine {
+    /// Source line the following instructions belong to, `text` if the file was found
+    Source {
+        file: String,
+        line: u32,
+        text: Option<String>,
+    },
+    Instruction(DisassembledInstruction),
+}
+
+#[derive(Clone, Debug)]
+pub struct DisassembledInstruction {
+    pub address: u64,
+    pub bytes: Vec<u8>,
+    pub mnemonic: String,
+    pub operands: String,
+    /// Branch or call destination, or an address computed relative to the PC
+    pub target: Option<u64>,
+    /// Address a PC-relative load reads its constant from, or that a load or store
+    /// through a LUI/AUIPC base accesses
+    pub literal: Option<u64>,
+    /// Symbols of the target or the loaded constant
+    pub comment: Option<String>,
+    pub flow: Flow,
+    /// Bytes the instruction moves the stack pointer down by, e.g. PUSH or SUB SP
+    pub stack_push: u64,
+}
+
+/// Where execution goes after an instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    /// Call to a fixed address
+    Call(u64),
+    /// Call through a register, e.g. a function pointer or vtable
+    IndirectCall,
+    Branch(u64),
+    ConditionalBranch(u64),
+    /// TBB/TBH jump table inside the function
+    Switch,
+    /// Jump through a register or a load to PC from anything but LR or the stack
+    IndirectBranch,
+    Return,
+}
+
+/// Where symbols are used from: instructions that call, jump to, read, write or take
+/// the address of them, and pointers to them in initialized data
+#[derive(Clone, Debug, Default)]
+pub struct XrefIndex {
+    /// Sorted by `to`
+    pub xrefs: Vec<Xref>,
+}
+
+impl XrefIndex {
+    /// References into `address..address + size`, or to `address` itself for
+    /// zero-sized symbols
+    pub fn to_range(&self, address: u64, size: u64) -> &[Xref] {
+        let start = self.xrefs.partition_point(|xref| xref.to < address);
+        let end = self
+            .xrefs
+            .partition_point(|xref| xref.to < address + size.max(1));
+        &self.xrefs[start..end]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Xref {
+    /// Instruction or data word the reference is made from
+    pub from: u64,
+    /// Function or object containing `from` as `name+0xoff`, else its section
+    pub from_symbol: String,
+    /// Referenced address
+    pub to: u64,
+    pub kind: XrefKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrefKind {
+    Call,
+    /// Branch from another function, e.g. a tail call
+    Jump,
+    /// Load from the address
+    Read,
+    /// Store to the address
+    Write,
+    /// Address formed or loaded from a literal pool in code and used other than for
+    /// loads and stores, e.g. passed to a function
+    Address,
+    /// Pointer stored in data, e.g. a vtable, handler table or static initializer
+    Pointer,
+}
+
+impl XrefKind {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            XrefKind::Call => "call",
+            XrefKind::Jump => "jump",
+            XrefKind::Read => "read",
+            XrefKind::Write => "write",
+            XrefKind::Address => "address",
+            XrefKind::Pointer => "pointer",
+        }
+    }
+}
+
+/// A static that could use less RAM or startup time, found by `analyze_ram_usage`
+#[derive(Clone, Debug)]
+pub struct RamAdvice {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    /// Section the static lives in, e.g. `.data` or `.bss`
+    pub section: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub kind: RamAdviceKind,
+    /// Instructions in code accessing the static
+    pub accesses: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RamAdviceKind {
+    /// Writable, but code only ever reads it: a `const` or immutable static lives in flash
+    ReadOnly,
+    /// Large zero-initialized array: in `.uninit` it is not cleared at startup
+    Uninit,
+}
+
+impl RamAdvice {
+    /// RAM freed by following the advice
+    pub fn ram_saving(&self) -> u64 {
+        match self.kind {
+            RamAdviceKind::ReadOnly => self.size,
+            RamAdviceKind::Uninit => 0,
+        }
+    }
+
+    pub fn suggestion(&self) -> String {
+        match self.kind {
+            RamAdviceKind::ReadOnly if self.section.starts_with(".bss") => {
+                "only read and always zero: make it a `const`".to_string()
+            }
+            RamAdviceKind::ReadOnly => {
+                "only read: make it a `const` or immutable `static` to move it to flash"
+                    .to_string()
+            }
+            RamAdviceKind::Uninit => format!(
+                "zeroed at startup: move it to `.uninit` to skip clearing {} bytes, if nothing relies on the zeros",
+                self.size
+            ),
+        }
+    }
+}
+
+impl RamAdviceKind {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            RamAdviceKind::ReadOnly => "read-only",
+            RamAdviceKind::Uninit => "uninit",
+        }
+    }
+}
+
+/// Panic and `core::fmt` code in the image, and the functions outside the standard
+/// library that pull it in
+#[derive(Clone, Debug, Default)]
+pub struct PanicAudit {
+    /// Sorted by machinery, then function name
+    pub entries: Vec<PanicAuditEntry>,
+    /// `core::panic::Location`s found in read-only data
+    pub locations: Vec<PanicLocation>,
+    /// Functions named as panic machinery and their total size
+    pub panic_functions: usize,
+    pub panic_size: u64,
+    /// Functions named as `core::fmt` code, including `Debug`/`Display` impls
+    pub fmt_functions: usize,
+    pub fmt_size: u64,
+}
+
+/// A function outside the standard library calling into panic or fmt code
+#[derive(Clone, Debug)]
+pub struct PanicAuditEntry {
+    pub function: String,
+    pub address: u64,
+    pub machinery: Machinery,
+    /// Shortest call chain from `function` to the machinery, excluding `function`
+    pub path: Vec<String>,
+    /// Indices into `PanicAudit::locations` of the panic locations the function passes
+    pub locations: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Machinery {
+    /// `core::panicking`, `std::panicking` and `rust_begin_unwind`
+    Panic,
+    /// `core::fmt`
+    Fmt,
+}
+
+impl Machinery {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Machinery::Panic => "panic",
+            Machinery::Fmt => "fmt",
+        }
+    }
+}
+
+/// Source location of a panic, as compiled into a `core::panic::Location`
+#[derive(Clone, Debug)]
+pub struct PanicLocation {
+    pub address: u64,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// Everything worked out from the ELF file and the captures loaded with it, handed to
+/// the GUI in one piece
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    pub segments: Vec<MemorySegment>,
+    pub memory_regions: Vec<MemoryRegion>,
+    pub symbols: Vec<ElfSymbol>,
+    pub defmt_info: DefmtInfo,
+    pub rtt_info: RttInfo,
+    pub stack_info: StackInfo,
+    pub call_graph: CallGraph,
+    pub xrefs: XrefIndex,
+    pub ram_advice: Vec<RamAdvice>,
+    pub panic_audit: PanicAudit,
+    pub vector_table: VectorTable,
+    /// Interrupt names from the SVD file, by IRQ number
+    pub interrupt_names: std::collections::BTreeMap<u32, String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub dwarf_info: DwarfInfo,
+    pub line_table: LineTable,
+    pub capture: Option<Capture>,
+    pub snapshot: Option<MemorySnapshot>,
+    /// Statics decoded from `snapshot`, keyed by DIE offset
+    pub snapshot_values: std::collections::HashMap<u64, DecodedValue>,
+    /// Statics as initialized by the ELF image, keyed by DIE offset
+    pub initial_values: std::collections::HashMap<u64, DecodedValue>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DwarfType;
+
+    fn base(name: &str) -> DwarfType {
+        DwarfType::Base {
+            name: name.to_string(),
+        }
+    }
+
+    fn pointer(pointee: DwarfType) -> DwarfType {
+        DwarfType::Pointer {
+            sigil: "*",
+            name: None,
+            pointee: Box::new(pointee),
+        }
+    }
+
+    fn array(element: DwarfType, dims: &[Option<u64>]) -> DwarfType {
+        DwarfType::Array {
+            element: Box::new(element),
+            dims: dims.to_vec(),
+        }
+    }
+
+    fn function(ret: DwarfType, params: Vec<DwarfType>) -> DwarfType {
+        DwarfType::Subroutine {
+            ret: Box::new(ret),
+            params,
+            variadic: false,
+        }
+    }
+
+    #[test]
+    fn pointers_to_arrays() {
+        let int_array = array(base("int"), &[Some(4)]);
+        assert_eq!(pointer(int_array.clone()).c_name(), "int (*)[4]");
+        assert_eq!(array(pointer(base("int")), &[Some(4)]).c_name(), "int *[4]");
+        assert_eq!(pointer(pointer(int_array)).c_name(), "int (**)[4]");
+    }
+
+    #[test]
+    fn function_pointers() {
+        let handler = function(DwarfType::Void, vec![base("int"), pointer(base("char"))]);
+        assert_eq!(pointer(handler).c_name(), "void (*)(int, char *)");
+
+        let callback = pointer(function(DwarfType::Void, Vec::new()));
+        assert_eq!(array(callback, &[Some(8)]).c_name(), "void (*[8])(void)");
+
+        // A function returning a function pointer
+        let returns_pointer = function(pointer(function(base("int"), Vec::new())), Vec::new());
+        assert_eq!(pointer(returns_pointer).c_name(), "int (*(*)(void))(void)");
+
+        let variadic = DwarfType::Subroutine {
+            ret: Box::new(base("int")),
+            params: vec![pointer(base("char"))],
+            variadic: true,
+        };
+        assert_eq!(pointer(variadic).c_name(), "int (*)(char *, ...)");
+    }
+
+    #[test]
+    fn nested_arrays() {
+        // Both DWARF shapes: one array with two subranges, or an array of arrays
+        let flat = array(base("int"), &[Some(2), Some(3)]);
+        let nested = array(array(base("int"), &[Some(3)]), &[Some(2)]);
+        assert_eq!(flat.c_name(), "int [2][3]");
+        assert_eq!(nested.c_name(), "int [2][3]");
+        assert_eq!(array(base("char"), &[None]).c_name(), "char []");
+        assert_eq!(pointer(flat).c_name(), "int (*)[2][3]");
+    }
+
+    #[test]
+    fn qualified_pointers() {
+        let const_char = DwarfType::Qualified {
+            qualifier: "const",
+            inner: Box::new(base("char")),
+        };
+        assert_eq!(pointer(const_char).c_name(), "const char *");
+        let const_pointer = DwarfType::Qualified {
+            qualifier: "const",
+            inner: Box::new(pointer(base("char"))),
+        };
+        assert_eq!(const_pointer.c_name(), "char *const");
+    }
+}
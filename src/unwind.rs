@@ -0,0 +1,584 @@
+use anyhow::{Context, Result};
+use gimli::UnwindSection;
+use object::{Object, ObjectSection};
+use std::fs;
+use std::path::Path;
+
+use crate::types::{MemorySnapshot, StackFrame};
+
+/// Core registers r0-r15 of one frame, `None` where they could not be recovered
+pub type Registers = [Option<u32>; 16];
+
+pub const SP: usize = 13;
+pub const LR: usize = 14;
+pub const PC: usize = 15;
+
+/// A corrupted stack can loop or run on for a long time
+const MAX_FRAMES: usize = 64;
+
+/// On exception entry LR holds an `EXC_RETURN` value instead of a return address
+pub fn is_exc_return(value: u32) -> bool {
+    value >> 8 == 0x00ff_ffff && value != 0xffff_ffff
+}
+
+/// Bytes the core stacks on exception entry: r0-r3, r12, LR, PC and xPSR, s0-s15 and
+/// FPSCR on top with FPU context, and another word if it realigned the stack
+pub fn exception_frame_size(fpu_context: bool, realigned: bool) -> u32 {
+    let size = if fpu_context { 0x68 } else { 0x20 };
+    if realigned {
+        size + 4
+    } else {
+        size
+    }
+}
+
+/// Walks a Cortex-M call stack with the CFI in `.debug_frame`, falling back to the
+/// ARM EHABI tables in `.ARM.exidx`/`.ARM.extab`
+pub struct Unwinder {
+    debug_frame: Vec<u8>,
+    exidx: Vec<u8>,
+    exidx_address: u32,
+    extab: Vec<u8>,
+    extab_address: u32,
+    endian: gimli::RunTimeEndian,
+}
+
+impl Unwinder {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).context("Failed to read ELF file")?;
+        let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+        let section = |name: &str| -> (Vec<u8>, u32) {
+            obj.section_by_name(name)
+                .and_then(|section| {
+                    let data = section.uncompressed_data().ok()?;
+                    Some((data.into_owned(), section.address() as u32))
+                })
+                .unwrap_or_default()
+        };
+        let (debug_frame, _) = section(".debug_frame");
+        let (exidx, exidx_address) = section(".ARM.exidx");
+        let (extab, extab_address) = section(".ARM.extab");
+
+        Ok(Self {
+            debug_frame,
+            exidx,
+            exidx_address,
+            extab,
+            extab_address,
+            endian: if obj.is_little_endian() {
+                gimli::RunTimeEndian::Little
+            } else {
+                gimli::RunTimeEndian::Big
+            },
+        })
+    }
+
+    /// Frames from the innermost outwards, and why unwinding stopped. `registers` are
+    /// those of the innermost frame, whose PC is exact rather than a return address.
+    /// `psp` is needed to continue past an exception taken on the process stack.
+    pub fn unwind(
+        &self,
+        mut registers: Registers,
+        psp: Option<u32>,
+        memory: Option<&MemorySnapshot>,
+    ) -> (Vec<StackFrame>, String) {
+        let mut frames = Vec::new();
+        let mut exception = true;
+        loop {
+            let Some(pc) = registers[PC].map(|pc| pc & !1) else {
+                return (frames, "The return address is unknown".to_string());
+            };
+            let frame = StackFrame {
+                pc: pc as u64,
+                sp: registers[SP].map(u64::from),
+                exception,
+            };
+            frames.push(frame);
+            if frames.len() == MAX_FRAMES {
+                let reason = format!(
+                    "Stopped after {} frames, the stack may be corrupted",
+                    MAX_FRAMES
+                );
+                return (frames, reason);
+            }
+
+            let lookup = frame.lookup_address() as u32;
+            let step = self
+                .step_debug_frame(lookup, &registers, memory)
+                .or_else(|| self.step_exidx(lookup, &registers, memory))
+                .unwrap_or_else(|| Err(format!("No unwind info for 0x{:08x}", pc)));
+            let mut caller = match step {
+                Ok(caller) => caller,
+                Err(reason) => return (frames, reason),
+            };
+
+            exception = false;
+            match caller[PC] {
+                // Reset handlers mark the outermost frame with an invalid return address
+                Some(0) | Some(0xffff_ffff) => {
+                    return (frames, "Reached the outermost frame".to_string());
+                }
+                Some(exc_return) if is_exc_return(exc_return) => {
+                    // Returning from a handler into the code it interrupted, which the
+                    // core stacked on the stack selected by bit 2
+                    let sp = if exc_return & 0x4 != 0 {
+                        psp
+                    } else {
+                        caller[SP]
+                    };
+                    let Some(sp) = sp else {
+                        let reason = "The interrupted code used the process stack, give PSP to \
+                                      unwind past the exception";
+                        return (frames, reason.to_string());
+                    };
+                    let mut known = [None; 16];
+                    known[4..12].copy_from_slice(&caller[4..12]);
+                    caller = match self.unstack(sp, Some(exc_return), &known, None, memory) {
+                        Ok(caller) => caller,
+                        Err(reason) => return (frames, reason),
+                    };
+                    exception = true;
+                }
+                _ => {}
+            }
+
+            // Callers' frames lie above their callees', unless an exception switched stacks
+            if let (false, Some(sp), Some(caller_sp)) = (exception, registers[SP], caller[SP]) {
+                if caller_sp < sp || (caller_sp == sp && caller[PC].map(|pc| pc & !1) == Some(pc)) {
+                    return (
+                        frames,
+                        format!("Stack pointer did not advance at 0x{:08x}", pc),
+                    );
+                }
+            }
+            registers = caller;
+        }
+    }
+
+    /// Registers of the code an exception interrupted, from the frame the core stacked
+    /// at `sp`. `known` values, e.g. from a fault log, are preferred over the snapshot.
+    pub fn unstack(
+        &self,
+        sp: u32,
+        exc_return: Option<u32>,
+        known: &Registers,
+        known_xpsr: Option<u32>,
+        memory: Option<&MemorySnapshot>,
+    ) -> Result<Registers, String> {
+        let mut registers = [None; 16];
+        // Callee-saved registers are preserved by the handler
+        registers[4..12].copy_from_slice(&known[4..12]);
+        for (offset, index) in [
+            (0, 0),
+            (4, 1),
+            (8, 2),
+            (12, 3),
+            (16, 12),
+            (20, LR),
+            (24, PC),
+        ] {
+            registers[index] =
+                known[index].or_else(|| self.read_word(memory, sp.wrapping_add(offset)));
+        }
+        if registers[PC].is_none() {
+            return Err(format!(
+                "The exception frame at 0x{:08x} is not in the snapshot",
+                sp
+            ));
+        }
+
+        // Bit 4 clear: the frame includes s0-s15 and FPSCR. Bit 9 of the stacked xPSR:
+        // the core realigned the stack by another word.
+        let xpsr = known_xpsr.or_else(|| self.read_word(memory, sp.wrapping_add(28)));
+        let size = exception_frame_size(
+            exc_return.is_some_and(|value| value & 0x10 == 0),
+            xpsr.is_some_and(|xpsr| xpsr & (1 << 9) != 0),
+        );
+        registers[SP] = Some(sp.wrapping_add(size));
+        Ok(registers)
+    }
+
+    /// Caller registers from `.debug_frame`, `None` if it has no FDE for `pc`
+    fn step_debug_frame(
+        &self,
+        pc: u32,
+        registers: &Registers,
+        memory: Option<&MemorySnapshot>,
+    ) -> Option<Result<Registers, String>> {
+        let mut section = gimli::DebugFrame::new(&self.debug_frame, self.endian);
+        section.set_address_size(4);
+        let bases = gimli::BaseAddresses::default();
+        let fde = section
+            .fde_for_address(&bases, pc as u64, gimli::DebugFrame::cie_from_offset)
+            .ok()?;
+        let mut context = gimli::UnwindContext::new();
+        let row = match fde.unwind_info_for_address(&section, &bases, &mut context, pc as u64) {
+            Ok(row) => row,
+            Err(e) => return Some(Err(format!("Invalid CFI for 0x{:08x}: {}", pc, e))),
+        };
+
+        let cfa = match row.cfa() {
+            gimli::CfaRule::RegisterAndOffset { register, offset } => {
+                match registers.get(register.0 as usize).copied().flatten() {
+                    Some(base) => (base as i64 + offset) as u32,
+                    None => {
+                        return Some(Err(format!(
+                            "{} is unknown at 0x{:08x}",
+                            register_name(register.0),
+                            pc
+                        )));
+                    }
+                }
+            }
+            gimli::CfaRule::Expression(_) => {
+                return Some(Err(format!("Unsupported CFA expression at 0x{:08x}", pc)));
+            }
+        };
+
+        let mut caller = [None; 16];
+        for (index, value) in caller.iter_mut().enumerate() {
+            *value = match row.register(gimli::Register(index as u16)) {
+                // Without a rule callee-saved registers and LR, in leaf functions, still
+                // hold the caller's values
+                gimli::RegisterRule::Undefined => match index {
+                    4..=11 | LR => registers[index],
+                    _ => None,
+                },
+                gimli::RegisterRule::SameValue => registers[index],
+                gimli::RegisterRule::Offset(offset) => {
+                    let address = (cfa as i64 + offset) as u32;
+                    match self.read_word(memory, address) {
+                        Some(value) => Some(value),
+                        None if index as u16 == fde.cie().return_address_register().0 => {
+                            return Some(Err(format!(
+                                "Stack memory at 0x{:08x} is not in the snapshot",
+                                address
+                            )));
+                        }
+                        None => None,
+                    }
+                }
+                gimli::RegisterRule::ValOffset(offset) => Some((cfa as i64 + offset) as u32),
+                gimli::RegisterRule::Register(register) => {
+                    registers.get(register.0 as usize).copied().flatten()
+                }
+                _ => None,
+            };
+        }
+        let return_address = fde.cie().return_address_register().0 as usize;
+        caller[PC] = caller.get(return_address).copied().flatten();
+        caller[SP] = Some(cfa);
+        Some(Ok(caller))
+    }
+
+    /// Caller registers from `.ARM.exidx`, `None` if no entry covers `pc`
+    fn step_exidx(
+        &self,
+        pc: u32,
+        registers: &Registers,
+        memory: Option<&MemorySnapshot>,
+    ) -> Option<Result<Registers, String>> {
+        let entries = self.exidx.len() / 8;
+        let function = |index: usize| {
+            let place = self.exidx_address.wrapping_add(index as u32 * 8);
+            self.word(&self.exidx, index * 8)
+                .map(|word| prel31(word, place))
+        };
+
+        // Entries are sorted by function address, each covers up to the next one
+        let (mut low, mut high) = (0, entries);
+        while low < high {
+            let mid = (low + high) / 2;
+            if function(mid)? <= pc {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let entry = low.checked_sub(1)?;
+        let data = self.word(&self.exidx, entry * 8 + 4)?;
+
+        let instructions = if data == 1 {
+            Err(format!("0x{:08x} is marked as not unwindable", pc))
+        } else if data & 0x8000_0000 != 0 {
+            compact_instructions(data, &[])
+        } else {
+            let place = self.exidx_address.wrapping_add(entry as u32 * 8 + 4);
+            self.extab_instructions(prel31(data, place))
+        };
+        Some(instructions.and_then(|instructions| self.execute(&instructions, registers, memory)))
+    }
+
+    /// Unwind instructions of an `.ARM.extab` entry
+    fn extab_instructions(&self, address: u32) -> Result<Vec<u8>, String> {
+        let offset = address.wrapping_sub(self.extab_address) as usize;
+        let word = |index: usize| {
+            self.word(&self.extab, offset + index * 4)
+                .ok_or_else(|| format!("Truncated .ARM.extab entry at 0x{:08x}", address))
+        };
+
+        let header = word(0)?;
+        if header & 0x8000_0000 != 0 {
+            let count = match (header >> 24) & 0xf {
+                0 => 0,
+                _ => (header >> 16) & 0xff,
+            };
+            let extra = (1..=count as usize)
+                .map(word)
+                .collect::<Result<Vec<_>, _>>()?;
+            return compact_instructions(header, &extra);
+        }
+
+        // A generic personality routine such as __gxx_personality_v0, followed by
+        // instructions in the layout of the compact models
+        let first = word(1)?;
+        let extra = (2..2 + (first >> 24) as usize)
+            .map(word)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut instructions = first.to_be_bytes()[1..].to_vec();
+        instructions.extend(extra.iter().flat_map(|word| word.to_be_bytes()));
+        Ok(instructions)
+    }
+
+    /// Run EHABI unwind instructions against a virtual stack pointer
+    fn execute(
+        &self,
+        instructions: &[u8],
+        registers: &Registers,
+        memory: Option<&MemorySnapshot>,
+    ) -> Result<Registers, String> {
+        let mut caller = *registers;
+        for index in [0, 1, 2, 3, 12, PC] {
+            caller[index] = None;
+        }
+        let mut vsp = registers[SP].ok_or("SP is unknown")?;
+
+        let mut bytes = instructions.iter().copied();
+        let mut next = || {
+            bytes
+                .next()
+                .ok_or_else(|| "Truncated EHABI unwind instructions".to_string())
+        };
+        while let Ok(op) = next() {
+            match op {
+                0x00..=0x3f => vsp = vsp.wrapping_add(((op as u32 & 0x3f) << 2) + 4),
+                0x40..=0x7f => vsp = vsp.wrapping_sub(((op as u32 & 0x3f) << 2) + 4),
+                0x80..=0x8f => {
+                    let mask = ((op as u16 & 0xf) << 8 | next()? as u16) << 4;
+                    if mask == 0 {
+                        return Err("The function refuses to be unwound".to_string());
+                    }
+                    self.pop(&mut caller, &mut vsp, mask, memory)?;
+                }
+                0x90..=0x9f if op & 0xf != 13 && op & 0xf != 15 => {
+                    vsp = caller[op as usize & 0xf]
+                        .ok_or_else(|| format!("{} is unknown", register_name(op as u16 & 0xf)))?;
+                }
+                0xa0..=0xaf => {
+                    let mut mask = ((1u16 << ((op & 0x7) + 1)) - 1) << 4;
+                    if op & 0x8 != 0 {
+                        mask |= 1 << LR;
+                    }
+                    self.pop(&mut caller, &mut vsp, mask, memory)?;
+                }
+                0xb0 => break,
+                0xb1 => {
+                    let mask = next()?;
+                    if mask == 0 || mask & 0xf0 != 0 {
+                        return Err(format!("Invalid EHABI instruction 0xb1 0x{:02x}", mask));
+                    }
+                    self.pop(&mut caller, &mut vsp, mask as u16, memory)?;
+                }
+                0xb2 => {
+                    let mut value: u32 = 0;
+                    let mut shift = 0;
+                    loop {
+                        let byte = next()?;
+                        // A ULEB128 wider than 32 bits is no vsp increment
+                        if shift >= 32 {
+                            return Err("EHABI vsp increment out of range".to_string());
+                        }
+                        value |= ((byte & 0x7f) as u32).wrapping_shl(shift);
+                        shift += 7;
+                        if byte & 0x80 == 0 {
+                            break;
+                        }
+                    }
+                    vsp = vsp.wrapping_add(value.wrapping_shl(2).wrapping_add(0x204));
+                }
+                // VFP registers saved with FSTMFDX take an extra word
+                0xb3 => vsp = vsp.wrapping_add((next()? as u32 & 0xf) * 8 + 12),
+                0xb8..=0xbf => vsp = vsp.wrapping_add((op as u32 & 0x7) * 8 + 12),
+                0xc0..=0xc5 | 0xd0..=0xd7 => vsp = vsp.wrapping_add((op as u32 & 0x7) * 8 + 8),
+                0xc6 | 0xc8 | 0xc9 => vsp = vsp.wrapping_add((next()? as u32 & 0xf) * 8 + 8),
+                0xc7 => vsp = vsp.wrapping_add((next()? & 0xf).count_ones() * 4),
+                _ => return Err(format!("Unsupported EHABI instruction 0x{:02x}", op)),
+            }
+        }
+
+        caller[SP] = Some(vsp);
+        if caller[PC].is_none() {
+            caller[PC] = caller[LR];
+        }
+        Ok(caller)
+    }
+
+    /// Load the registers in `mask`, lowest first, from the virtual stack
+    fn pop(
+        &self,
+        caller: &mut Registers,
+        vsp: &mut u32,
+        mask: u16,
+        memory: Option<&MemorySnapshot>,
+    ) -> Result<(), String> {
+        let mut new_vsp = None;
+        for index in (0..16).filter(|index| mask & (1 << index) != 0) {
+            let value = self
+                .read_word(memory, *vsp)
+                .ok_or_else(|| format!("Stack memory at 0x{:08x} is not in the snapshot", *vsp))?;
+            *vsp = vsp.wrapping_add(4);
+            if index == SP {
+                new_vsp = Some(value);
+            } else {
+                caller[index] = Some(value);
+            }
+        }
+        if let Some(value) = new_vsp {
+            *vsp = value;
+        }
+        Ok(())
+    }
+
+    fn read_word(&self, memory: Option<&MemorySnapshot>, address: u32) -> Option<u32> {
+        let bytes = memory?.read(address as u64, 4)?;
+        self.word(bytes, 0)
+    }
+
+    fn word(&self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        Some(match self.endian {
+            gimli::RunTimeEndian::Little => u32::from_le_bytes(bytes),
+            gimli::RunTimeEndian::Big => u32::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// Resolve a 31-bit place-relative offset stored at `place`
+fn prel31(word: u32, place: u32) -> u32 {
+    let offset = ((word << 1) as i32) >> 1;
+    place.wrapping_add(offset as u32)
+}
+
+/// Instructions of an ARM compact model entry: personality 0 holds three bytes in
+/// `header`, personalities 1 and 2 two bytes plus `extra` words
+fn compact_instructions(header: u32, extra: &[u32]) -> Result<Vec<u8>, String> {
+    let bytes = header.to_be_bytes();
+    let mut instructions = match (header >> 24) & 0xf {
+        0 => bytes[1..].to_vec(),
+        1 | 2 => bytes[2..].to_vec(),
+        personality => return Err(format!("Unsupported EHABI personality {}", personality)),
+    };
+    instructions.extend(extra.iter().flat_map(|word| word.to_be_bytes()));
+    Ok(instructions)
+}
+
+fn register_name(register: u16) -> String {
+    match register as usize {
+        SP => "SP".to_string(),
+        LR => "LR".to_string(),
+        PC => "PC".to_string(),
+        register => format!("r{}", register),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact_instructions, Registers, Unwinder, LR, PC, SP};
+    use crate::types::{MemorySnapshot, SnapshotFormat, SnapshotRegion};
+
+    /// An unwinder without any tables, enough to run instructions directly
+    fn unwinder() -> Unwinder {
+        Unwinder {
+            debug_frame: Vec::new(),
+            exidx: Vec::new(),
+            exidx_address: 0,
+            extab: Vec::new(),
+            extab_address: 0,
+            endian: gimli::RunTimeEndian::Little,
+        }
+    }
+
+    fn registers(sp: u32) -> Registers {
+        let mut registers = [None; 16];
+        registers[SP] = Some(sp);
+        registers
+    }
+
+    fn vsp_after(sp: u32, instructions: &[u8]) -> Result<u32, String> {
+        unwinder()
+            .execute(instructions, &registers(sp), None)
+            .map(|caller| caller[SP].unwrap())
+    }
+
+    #[test]
+    fn large_vsp_increment() {
+        // vsp += 0x204 + (uleb128 << 2)
+        assert_eq!(vsp_after(0x2000_0000, &[0xb2, 0x00]), Ok(0x2000_0204));
+        assert_eq!(vsp_after(0x2000_0000, &[0xb2, 0x01]), Ok(0x2000_0208));
+        assert_eq!(vsp_after(0x2000_0000, &[0xb2, 0x81, 0x01]), Ok(0x2000_0408));
+        assert_eq!(vsp_after(0xffff_fff0, &[0xb2, 0x00]), Ok(0x0000_01f4));
+        assert!(vsp_after(0x2000_0000, &[0xb2, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]).is_err());
+        assert!(vsp_after(0x2000_0000, &[0xb2, 0x80]).is_err());
+    }
+
+    #[test]
+    fn short_vsp_adjustments() {
+        assert_eq!(vsp_after(0x2000_0000, &[0x03]), Ok(0x2000_0010));
+        assert_eq!(vsp_after(0x2000_0010, &[0x43]), Ok(0x2000_0000));
+        assert_eq!(vsp_after(0x2000_0000, &[0x00, 0xb0, 0x00]), Ok(0x2000_0004));
+    }
+
+    #[test]
+    fn pops_registers_from_the_snapshot() {
+        // push {r4, lr}, with LR becoming the caller's PC
+        let snapshot = MemorySnapshot {
+            file_name: "ram.bin".to_string(),
+            format: SnapshotFormat::Raw,
+            regions: vec![SnapshotRegion {
+                address: 0x2000_0000,
+                data: [0x1111_1111u32, 0x0800_0101]
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .collect(),
+            }],
+        };
+        let caller = unwinder()
+            .execute(&[0xa8, 0xb0], &registers(0x2000_0000), Some(&snapshot))
+            .unwrap();
+        assert_eq!(caller[4], Some(0x1111_1111));
+        assert_eq!(caller[LR], Some(0x0800_0101));
+        assert_eq!(caller[PC], Some(0x0800_0101));
+        assert_eq!(caller[SP], Some(0x2000_0008));
+
+        assert!(unwinder()
+            .execute(&[0xa8, 0xb0], &registers(0x2000_0000), None)
+            .is_err());
+        assert!(unwinder()
+            .execute(&[0x80, 0x00], &registers(0x2000_0000), Some(&snapshot))
+            .is_err());
+    }
+
+    #[test]
+    fn compact_model_instructions() {
+        assert_eq!(
+            compact_instructions(0x80a8_b0b0, &[]),
+            Ok(vec![0xa8, 0xb0, 0xb0])
+        );
+        assert_eq!(
+            compact_instructions(0x8101_b201, &[0xb0b0_b0b0]),
+            Ok(vec![0xb2, 0x01, 0xb0, 0xb0, 0xb0, 0xb0])
+        );
+        assert!(compact_instructions(0x8300_0000, &[]).is_err());
+    }
+}
@@ -1,4 +1,10 @@
+use crate::parser::demangle_name;
+use crate::types::{DwarfInfo, DwarfSymbol, DwarfTag, ElfSymbol, StackBound};
 use gpui::*;
+use object::elf::FileHeader32;
+use object::read::elf::AttributesSection;
+use object::{Endianness, Object, ObjectSection};
+use std::collections::HashSet;
 
 pub fn format_size(bytes: u64) -> String {
     if bytes < 1024 {
@@ -12,6 +18,163 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// Parse a hex number with or without `0x`, ignoring surrounding whitespace and `_`
+/// digit separators as in `0x2000_0000`
+pub fn parse_hex(text: &str) -> Option<u64> {
+    let digits = text.trim().replace('_', "");
+    let digits = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .unwrap_or(&digits);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Parse a number as written on the command line or in an SVD file: hex with a `0x`
+/// prefix, binary with `#`, decimal otherwise, ignoring `_` digit separators
+pub fn parse_number(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if text.starts_with("0x") || text.starts_with("0X") {
+        return parse_hex(text);
+    }
+    let digits = text.replace('_', "");
+    match digits.strip_prefix('#') {
+        Some(binary) => u64::from_str_radix(binary, 2).ok(),
+        None => digits.parse().ok(),
+    }
+}
+
+/// File-wide integer attribute `tag` of the `aeabi` build attributes in `.ARM.attributes`,
+/// e.g. 7 for `Tag_CPU_arch_profile` or 10 for `Tag_FP_arch`
+pub fn arm_attribute(obj: &object::File, tag: u64) -> Option<u64> {
+    let data = obj.section_by_name(".ARM.attributes")?.data().ok()?;
+    let section =
+        AttributesSection::<FileHeader32<Endianness>>::new(obj.endianness(), data).ok()?;
+    let mut subsections = section.subsections().ok()?;
+    while let Ok(Some(subsection)) = subsections.next() {
+        if subsection.vendor() != b"aeabi" {
+            continue;
+        }
+        let mut subsubsections = subsection.subsubsections();
+        while let Ok(Some(subsubsection)) = subsubsections.next() {
+            // Tag_File
+            if subsubsection.tag() != 1 {
+                continue;
+            }
+            let mut attributes = subsubsection.attributes();
+            while let Ok(Some(next)) = attributes.read_tag() {
+                // Strings are the CPU names and conformance, tag 32 is a number and a
+                // string, and above 32 odd tags are strings
+                match next {
+                    _ if next == tag => return attributes.read_integer().ok(),
+                    4 | 5 | 67 => {
+                        attributes.read_string().ok()?;
+                    }
+                    32 => {
+                        attributes.read_integer().ok()?;
+                        attributes.read_string().ok()?;
+                    }
+                    _ if next > 32 && next % 2 == 1 => {
+                        attributes.read_string().ok()?;
+                    }
+                    _ => {
+                        attributes.read_integer().ok()?;
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Worst-case stack depth in bytes, marked when it is only a lower bound
+pub fn format_stack_depth(bytes: u64, bound: StackBound) -> String {
+    match bound {
+        StackBound::Exact => format!("{} B", bytes),
+        StackBound::LowerBound => format!("≥ {} B", bytes),
+        StackBound::Unbounded => format!("unbounded (≥ {} B)", bytes),
+    }
+}
+
+/// Human readable size of a padding run, e.g. "3 bytes" or "5 bits"
+pub fn format_bits(bits: u64) -> String {
+    match (bits / 8, bits % 8) {
+        (bytes, 0) => format!("{} byte{}", bytes, if bytes == 1 { "" } else { "s" }),
+        (0, bits) => format!("{} bit{}", bits, if bits == 1 { "" } else { "s" }),
+        (bytes, bits) => format!("{} bytes {} bits", bytes, bits),
+    }
+}
+
+/// Resolve an address to the ELF symbol at or containing it, as `name` or `name+0xoff`.
+///
+/// Expects `symbols` sorted by address, as returned by `parse_elf_symbols`. ARM
+/// mapping symbols (`$t`, `$d`, ...) are ignored.
+pub fn symbolize_address(symbols: &[ElfSymbol], address: u64) -> Option<String> {
+    let end = symbols.partition_point(|s| s.address <= address);
+    let mut exact: Option<&ElfSymbol> = None;
+
+    for symbol in symbols[..end].iter().rev() {
+        if symbol.name.starts_with('$') {
+            continue;
+        }
+        if symbol.address == address {
+            // Prefer a sized symbol over zero-sized aliases at the same address
+            if exact.is_none_or(|e| e.size == 0 && symbol.size > 0) {
+                exact = Some(symbol);
+            }
+            continue;
+        }
+        if exact.is_some() {
+            break;
+        }
+        // Zero-sized labels below the address say nothing about containment
+        if symbol.size == 0 {
+            continue;
+        }
+        if address < symbol.address + symbol.size {
+            return Some(format!(
+                "{}+0x{:x}",
+                demangle_name(&symbol.name),
+                address - symbol.address
+            ));
+        }
+        break;
+    }
+
+    exact.map(|symbol| demangle_name(&symbol.name))
+}
+
+/// ELF symbols plus the statics only DWARF knows about, such as function-local
+/// statics missing from the symbol table, sorted by address
+pub fn with_dwarf_statics(symbols: &[ElfSymbol], dwarf_info: &DwarfInfo) -> Vec<ElfSymbol> {
+    fn walk(symbol: &DwarfSymbol, known: &HashSet<u64>, out: &mut Vec<ElfSymbol>) {
+        if symbol.tag == DwarfTag::Variable {
+            if let Some(address) = symbol.address.filter(|a| !known.contains(a)) {
+                out.push(ElfSymbol {
+                    name: symbol.name.clone(),
+                    address,
+                    size: symbol.size.unwrap_or(0),
+                });
+            }
+        }
+        for child in &symbol.children {
+            walk(child, known, out);
+        }
+    }
+
+    let known: HashSet<u64> = symbols
+        .iter()
+        .filter(|s| !s.name.starts_with('$'))
+        .map(|s| s.address)
+        .collect();
+    let mut merged = symbols.to_vec();
+    for unit in &dwarf_info.compile_units {
+        walk(unit, &known, &mut merged);
+    }
+    merged.sort_by_key(|s| s.address);
+    merged.dedup_by(|a, b| a.address == b.address && a.name == b.name);
+    merged
+}
+
 pub fn detail_row(label: impl Into<SharedString>, value: impl Into<SharedString>) -> Div {
     div()
         .flex()
@@ -38,3 +201,30 @@ pub fn generate_color(index: usize) -> Hsla {
     let hue = (index as f32 * 137.508) % 360.0;
     hsla(hue / 360.0, 0.75, 0.55, 1.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_hex, parse_number};
+
+    #[test]
+    fn hex_numbers() {
+        assert_eq!(parse_hex("0x2000_0000"), Some(0x2000_0000));
+        assert_eq!(parse_hex("0X1f"), Some(0x1f));
+        assert_eq!(parse_hex("08001234"), Some(0x0800_1234));
+        assert_eq!(parse_hex(" 0x1f\n"), Some(0x1f));
+        assert_eq!(parse_hex("0x"), None);
+        assert_eq!(parse_hex("0xg"), None);
+    }
+
+    #[test]
+    fn numbers_are_decimal_without_a_prefix() {
+        assert_eq!(parse_number("4096"), Some(4096));
+        assert_eq!(parse_number("1_000"), Some(1000));
+        assert_eq!(parse_number("0x4096"), Some(0x4096));
+        assert_eq!(parse_number("0X2000_0000"), Some(0x2000_0000));
+        assert_eq!(parse_number("#1010"), Some(10));
+        assert_eq!(parse_number("#12"), None);
+        assert_eq!(parse_number("2000000a"), None);
+        assert_eq!(parse_number(""), None);
+    }
+}
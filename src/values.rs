@@ -0,0 +1,1015 @@
+use anyhow::{Context as AnyhowContext, Result};
+use object::Object;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::parser::{
+    die_offset, get_string_attr, load_dwarf, resolve_type, static_address, uses_rust_syntax,
+};
+use crate::types::{DecodedValue, DwarfType, ElfSymbol, MemorySnapshot};
+use crate::utils::symbolize_address;
+
+/// Nesting limit for types, guards against malformed or cyclic type references
+const MAX_DEPTH: usize = 16;
+/// Array elements decoded before the rest is summarized
+const MAX_ELEMENTS: u64 = 64;
+/// Length of the one-line summary shown for structs and arrays
+const SUMMARY_LEN: usize = 60;
+/// Characters shown of a C string
+const MAX_STRING_LEN: usize = 128;
+
+/// Decode every static with a fixed address from `memory`, keyed by the DIE offset
+/// of its variable. Statics the memory does not cover are left out.
+pub fn parse_static_values(
+    path: &PathBuf,
+    memory: &MemorySnapshot,
+    symbols: &[ElfSymbol],
+) -> Result<HashMap<u64, DecodedValue>> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+    let dwarf = load_dwarf(&obj)?;
+
+    let mut values = HashMap::new();
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        let decoder = ValueDecoder {
+            dwarf: &dwarf,
+            unit: &unit,
+            memory,
+            symbols,
+            little_endian: obj.is_little_endian(),
+            rust_syntax: uses_rust_syntax(&unit),
+        };
+
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs()? {
+            if entry.tag() != gimli::DW_TAG_variable {
+                continue;
+            }
+            let (Some(address), Some(type_offset), Some(offset)) = (
+                static_address(&dwarf, &unit, entry),
+                type_ref(entry),
+                die_offset(&unit, entry),
+            ) else {
+                continue;
+            };
+            let Some(size) = type_size(&unit, type_offset) else {
+                continue;
+            };
+            let Some(bytes) = memory.read(address, size) else {
+                continue;
+            };
+
+            let name = get_string_attr(&dwarf, &unit, entry, gimli::DW_AT_name)
+                .unwrap_or_else(|| "<anonymous>".to_string());
+            values.insert(offset, decoder.decode(name, type_offset, bytes, address, 0));
+        }
+    }
+
+    Ok(values)
+}
+
+/// Type of a DIE, if it is a reference within the same unit
+pub fn type_ref<R: gimli::Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+) -> Option<gimli::UnitOffset<R::Offset>> {
+    match entry.attr_value(gimli::DW_AT_type).ok()?? {
+        gimli::AttributeValue::UnitRef(offset) => Some(offset),
+        _ => None,
+    }
+}
+
+pub fn udata_attr<R: gimli::Reader>(
+    entry: &gimli::DebuggingInformationEntry<R>,
+    attr_name: gimli::DwAt,
+) -> Option<u64> {
+    entry.attr_value(attr_name).ok()??.udata_value()
+}
+
+/// Size in bytes of a type, following typedefs and qualifiers
+pub fn type_size<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    type_offset: gimli::UnitOffset<R::Offset>,
+) -> Option<u64> {
+    type_size_at(unit, type_offset, 0)
+}
+
+fn type_size_at<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    type_offset: gimli::UnitOffset<R::Offset>,
+    depth: usize,
+) -> Option<u64> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let entry = unit.entry(type_offset).ok()?;
+    if let Some(size) = udata_attr(&entry, gimli::DW_AT_byte_size) {
+        return Some(size);
+    }
+
+    match entry.tag() {
+        gimli::DW_TAG_pointer_type
+        | gimli::DW_TAG_reference_type
+        | gimli::DW_TAG_rvalue_reference_type => Some(unit.encoding().address_size as u64),
+        gimli::DW_TAG_array_type => {
+            let element_size = type_size_at(unit, type_ref(&entry)?, depth + 1)?;
+            array_size(&array_dims(unit, type_offset), element_size)
+        }
+        gimli::DW_TAG_typedef
+        | gimli::DW_TAG_const_type
+        | gimli::DW_TAG_volatile_type
+        | gimli::DW_TAG_atomic_type
+        | gimli::DW_TAG_restrict_type => type_size_at(unit, type_ref(&entry)?, depth + 1),
+        _ => None,
+    }
+}
+
+/// Byte offset of a member, as a constant or a `DW_OP_plus_uconst` expression
+pub fn member_offset<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    member: &gimli::DebuggingInformationEntry<R>,
+) -> u64 {
+    match member.attr_value(gimli::DW_AT_data_member_location) {
+        Ok(Some(gimli::AttributeValue::Exprloc(expr))) => {
+            match expr.operations(unit.encoding()).next() {
+                Ok(Some(gimli::Operation::PlusConstant { value })) => value,
+                _ => 0,
+            }
+        }
+        Ok(Some(attr)) => attr.udata_value().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Bit offset of a bitfield from the start of its struct (little-endian targets)
+pub fn bitfield_start<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    member: &gimli::DebuggingInformationEntry<R>,
+    member_type: gimli::UnitOffset<R::Offset>,
+    member_offset: u64,
+    bit_size: u64,
+) -> Option<u64> {
+    // DWARF 4 counts bits from the start of the struct; DWARF 2/3 from the
+    // most significant bit of the storage unit at the member offset
+    udata_attr(member, gimli::DW_AT_data_bit_offset).or_else(|| {
+        let bit_offset = udata_attr(member, gimli::DW_AT_bit_offset)?;
+        let storage =
+            udata_attr(member, gimli::DW_AT_byte_size).or_else(|| type_size(unit, member_type))?;
+        (member_offset * 8 + storage * 8).checked_sub(bit_offset + bit_size)
+    })
+}
+
+/// Bytes taken by `dims` elements of `element_size`, `None` if that does not fit in a u64
+fn array_size(dims: &[u64], element_size: u64) -> Option<u64> {
+    dims.iter()
+        .try_fold(element_size, |size, &count| size.checked_mul(count))
+}
+
+/// Element counts of an array type, outermost first
+fn array_dims<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    type_offset: gimli::UnitOffset<R::Offset>,
+) -> Vec<u64> {
+    array_bounds(unit, type_offset)
+        .into_iter()
+        .map(|count| count.unwrap_or(0))
+        .collect()
+}
+
+/// Element counts of an array type, outermost first; `None` for unbounded dimensions
+pub fn array_bounds<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    type_offset: gimli::UnitOffset<R::Offset>,
+) -> Vec<Option<u64>> {
+    let mut dims = Vec::new();
+    for_each_child(unit, type_offset, |subrange| {
+        if subrange.tag() != gimli::DW_TAG_subrange_type {
+            return;
+        }
+        dims.push(udata_attr(subrange, gimli::DW_AT_count).or_else(|| {
+            let lower = udata_attr(subrange, gimli::DW_AT_lower_bound).unwrap_or(0);
+            let upper = udata_attr(subrange, gimli::DW_AT_upper_bound)?;
+            upper.checked_add(1)?.checked_sub(lower)
+        }));
+    });
+    dims
+}
+
+pub fn for_each_child<R: gimli::Reader>(
+    unit: &gimli::Unit<R>,
+    offset: gimli::UnitOffset<R::Offset>,
+    mut f: impl FnMut(&gimli::DebuggingInformationEntry<R>),
+) {
+    let Ok(mut tree) = unit.entries_tree(Some(offset)) else {
+        return;
+    };
+    let Ok(root) = tree.root() else {
+        return;
+    };
+    let mut children = root.children();
+    while let Ok(Some(child)) = children.next() {
+        f(child.entry());
+    }
+}
+
+/// Formats bytes through the types of one compile unit
+struct ValueDecoder<'a, R: gimli::Reader> {
+    dwarf: &'a gimli::Dwarf<R>,
+    unit: &'a gimli::Unit<R>,
+    /// Memory the values come from, used to follow string pointers
+    memory: &'a MemorySnapshot,
+    symbols: &'a [ElfSymbol],
+    little_endian: bool,
+    /// Name types in Rust rather than C syntax
+    rust_syntax: bool,
+}
+
+impl<R: gimli::Reader> ValueDecoder<'_, R> {
+    fn decode(
+        &self,
+        name: String,
+        type_offset: gimli::UnitOffset<R::Offset>,
+        bytes: &[u8],
+        address: u64,
+        depth: usize,
+    ) -> DecodedValue {
+        let mut value = DecodedValue {
+            name,
+            type_name: self.type_name(type_offset),
+            address,
+            value: hex_bytes(bytes),
+            children: Vec::new(),
+        };
+
+        let Ok(entry) = self.unit.entry(type_offset) else {
+            return value;
+        };
+        if depth > MAX_DEPTH {
+            value.value = "...".to_string();
+            return value;
+        }
+
+        match entry.tag() {
+            gimli::DW_TAG_base_type => {
+                if let Some(formatted) = self.format_base(&entry, bytes) {
+                    value.value = formatted;
+                }
+            }
+            gimli::DW_TAG_pointer_type
+            | gimli::DW_TAG_reference_type
+            | gimli::DW_TAG_rvalue_reference_type => {
+                value.value = self.format_pointer(&entry, bytes);
+            }
+            gimli::DW_TAG_enumeration_type => {
+                value.value = self.format_enum(type_offset, bytes);
+            }
+            gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type | gimli::DW_TAG_class_type => {
+                match self.decode_variant(type_offset, bytes, address, depth) {
+                    Some((variant, fields)) => {
+                        value.value = format_variant(&variant, &fields);
+                        value.children = fields;
+                    }
+                    None => {
+                        value.children = self.decode_members(type_offset, bytes, address, depth);
+                        value.value = summarize(&value.children, true);
+                    }
+                }
+            }
+            gimli::DW_TAG_array_type => {
+                if let Some(element_type) = type_ref(&entry) {
+                    let dims = array_dims(self.unit, type_offset);
+                    // C strings read better as text than as a list of characters
+                    if dims.len() == 1 && self.is_char_type(element_type) {
+                        value.value = format_c_string(bytes);
+                        return value;
+                    }
+                    value.children = self.decode_array(&dims, element_type, bytes, address, depth);
+                    value.value = summarize(&value.children, false);
+                }
+            }
+            gimli::DW_TAG_typedef
+            | gimli::DW_TAG_const_type
+            | gimli::DW_TAG_volatile_type
+            | gimli::DW_TAG_atomic_type
+            | gimli::DW_TAG_restrict_type => {
+                if let Some(inner_type) = type_ref(&entry) {
+                    let inner = self.decode(String::new(), inner_type, bytes, address, depth + 1);
+                    value.value = inner.value;
+                    value.children = inner.children;
+                }
+            }
+            _ => {}
+        }
+
+        value
+    }
+
+    fn decode_members(
+        &self,
+        type_offset: gimli::UnitOffset<R::Offset>,
+        bytes: &[u8],
+        address: u64,
+        depth: usize,
+    ) -> Vec<DecodedValue> {
+        let mut members = Vec::new();
+
+        for_each_child(self.unit, type_offset, |member| {
+            if member.tag() != gimli::DW_TAG_member {
+                return;
+            }
+            let Some(member_type) = type_ref(member) else {
+                return;
+            };
+            let name = get_string_attr(self.dwarf, self.unit, member, gimli::DW_AT_name)
+                .unwrap_or_else(|| "<anonymous>".to_string());
+            let member_offset = member_offset(self.unit, member);
+
+            if let Some(bit_size) = udata_attr(member, gimli::DW_AT_bit_size) {
+                members.push(self.decode_bitfield(
+                    name,
+                    member_type,
+                    member,
+                    member_offset,
+                    bit_size,
+                    bytes,
+                    address,
+                ));
+                return;
+            }
+
+            let size = type_size(self.unit, member_type).unwrap_or(0);
+            let start = member_offset as usize;
+            match bytes.get(start..start + size as usize) {
+                Some(member_bytes) => members.push(self.decode(
+                    name,
+                    member_type,
+                    member_bytes,
+                    address + member_offset,
+                    depth + 1,
+                )),
+                None => members.push(DecodedValue {
+                    name,
+                    type_name: self.type_name(member_type),
+                    address: address + member_offset,
+                    value: "<out of range>".to_string(),
+                    children: Vec::new(),
+                }),
+            }
+        });
+
+        members
+    }
+
+    /// The active variant of a Rust enum, a struct with a `DW_TAG_variant_part`, and
+    /// its fields. The discriminant member named by `DW_AT_discr` picks the variant
+    /// with that `DW_AT_discr_value`; the variant without one is the default, or
+    /// owns the niche the discriminant lives in.
+    fn decode_variant(
+        &self,
+        type_offset: gimli::UnitOffset<R::Offset>,
+        bytes: &[u8],
+        address: u64,
+        depth: usize,
+    ) -> Option<(String, Vec<DecodedValue>)> {
+        let mut variant_part = None;
+        for_each_child(self.unit, type_offset, |child| {
+            if variant_part.is_none() && child.tag() == gimli::DW_TAG_variant_part {
+                variant_part = Some(child.offset());
+            }
+        });
+        let variant_part = variant_part?;
+        let part_entry = self.unit.entry(variant_part).ok()?;
+
+        // Discriminant value and the mask of its width, `None` without `DW_AT_discr`
+        let discriminant = match part_entry.attr_value(gimli::DW_AT_discr) {
+            Ok(Some(gimli::AttributeValue::UnitRef(discr))) => {
+                let member = self.unit.entry(discr).ok()?;
+                let start = member_offset(self.unit, &member) as usize;
+                let size = type_size(self.unit, type_ref(&member)?)? as usize;
+                let discr_bytes = bytes
+                    .get(start..start + size)
+                    .filter(|discr_bytes| (1..=16).contains(&discr_bytes.len()))?;
+                let mask = u128::MAX >> (128 - size * 8);
+                Some((self.read_uint(discr_bytes), mask))
+            }
+            _ => None,
+        };
+
+        let mut matching = None;
+        let mut default = None;
+        for_each_child(self.unit, variant_part, |variant| {
+            if variant.tag() != gimli::DW_TAG_variant {
+                return;
+            }
+            let value = match variant.attr_value(gimli::DW_AT_discr_value) {
+                Ok(Some(gimli::AttributeValue::Sdata(value))) => Some(value as i128 as u128),
+                Ok(Some(attr)) => attr.udata_value().map(u128::from),
+                _ => None,
+            };
+            match (value, discriminant) {
+                (Some(value), Some((raw, mask))) if value & mask == raw => {
+                    matching.get_or_insert(variant.offset());
+                }
+                (None, _) => {
+                    default.get_or_insert(variant.offset());
+                }
+                _ => {}
+            }
+        });
+        let variant = matching.or(default)?;
+
+        // Each variant wraps one member, named after the variant, whose type is the payload
+        let mut payload = None;
+        for_each_child(self.unit, variant, |member| {
+            if payload.is_none() && member.tag() == gimli::DW_TAG_member {
+                payload = Some((
+                    get_string_attr(self.dwarf, self.unit, member, gimli::DW_AT_name),
+                    type_ref(member),
+                    member_offset(self.unit, member),
+                ));
+            }
+        });
+        let (name, payload_type, offset) = payload?;
+        let fields = match (payload_type, bytes.get(offset as usize..)) {
+            (Some(payload_type), Some(payload_bytes)) => {
+                self.decode_members(payload_type, payload_bytes, address + offset, depth + 1)
+            }
+            _ => Vec::new(),
+        };
+        Some((name.unwrap_or_else(|| "<anonymous>".to_string()), fields))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn decode_bitfield(
+        &self,
+        name: String,
+        member_type: gimli::UnitOffset<R::Offset>,
+        member: &gimli::DebuggingInformationEntry<R>,
+        member_offset: u64,
+        bit_size: u64,
+        bytes: &[u8],
+        address: u64,
+    ) -> DecodedValue {
+        let bit_start = bitfield_start(self.unit, member, member_type, member_offset, bit_size);
+
+        let value = bit_start
+            .filter(|_| self.little_endian && bit_size > 0 && bit_size <= 64)
+            .and_then(|bit_start| {
+                let first = (bit_start / 8) as usize;
+                let last = (bit_start + bit_size).div_ceil(8) as usize;
+                let raw = self.read_uint(bytes.get(first..last).filter(|b| b.len() <= 16)?);
+                Some((raw >> (bit_start % 8)) & ((1u128 << bit_size) - 1))
+            });
+
+        DecodedValue {
+            name,
+            type_name: format!("{}:{}", self.type_name(member_type), bit_size),
+            address: address + bit_start.unwrap_or(member_offset * 8) / 8,
+            value: value.map_or_else(|| "<unsupported bitfield>".to_string(), |v| v.to_string()),
+            children: Vec::new(),
+        }
+    }
+
+    fn decode_array(
+        &self,
+        dims: &[u64],
+        element_type: gimli::UnitOffset<R::Offset>,
+        bytes: &[u8],
+        address: u64,
+        depth: usize,
+    ) -> Vec<DecodedValue> {
+        let Some((&count, inner_dims)) = dims.split_first() else {
+            return Vec::new();
+        };
+        let element_size = type_size(self.unit, element_type).unwrap_or(0);
+        let stride = array_size(inner_dims, element_size).unwrap_or(0);
+        if stride == 0 {
+            return Vec::new();
+        }
+
+        let shown = count.min(MAX_ELEMENTS);
+        let mut elements: Vec<DecodedValue> = (0..shown)
+            .filter_map(|i| {
+                let start = usize::try_from(i.checked_mul(stride)?).ok()?;
+                let chunk = bytes.get(start..start.checked_add(stride as usize)?)?;
+                let name = format!("[{}]", i);
+                let element_address = address + i * stride;
+
+                if inner_dims.is_empty() {
+                    return Some(self.decode(
+                        name,
+                        element_type,
+                        chunk,
+                        element_address,
+                        depth + 1,
+                    ));
+                }
+                let children =
+                    self.decode_array(inner_dims, element_type, chunk, element_address, depth + 1);
+                Some(DecodedValue {
+                    name,
+                    type_name: DwarfType::Array {
+                        element: Box::new(resolve_type(self.dwarf, self.unit, element_type)),
+                        dims: inner_dims.iter().map(|&count| Some(count)).collect(),
+                    }
+                    .display(self.rust_syntax),
+                    address: element_address,
+                    value: summarize(&children, false),
+                    children,
+                })
+            })
+            .collect();
+
+        if count > shown {
+            elements.push(DecodedValue {
+                name: "…".to_string(),
+                type_name: String::new(),
+                address: address + shown * stride,
+                value: format!("{} more elements", count - shown),
+                children: Vec::new(),
+            });
+        }
+
+        elements
+    }
+
+    fn format_base(
+        &self,
+        entry: &gimli::DebuggingInformationEntry<R>,
+        bytes: &[u8],
+    ) -> Option<String> {
+        let gimli::AttributeValue::Encoding(encoding) =
+            entry.attr_value(gimli::DW_AT_encoding).ok()??
+        else {
+            return None;
+        };
+        if bytes.is_empty() {
+            return Some("()".to_string());
+        }
+        if bytes.len() > 16 {
+            return None;
+        }
+
+        let raw = self.read_uint(bytes);
+        let bits = bytes.len() as u32 * 8;
+
+        let formatted = match encoding {
+            gimli::DW_ATE_boolean => match raw {
+                0 => "false".to_string(),
+                1 => "true".to_string(),
+                other => format!("{} (invalid bool)", other),
+            },
+            gimli::DW_ATE_signed | gimli::DW_ATE_signed_char => {
+                let shift = 128 - bits;
+                (((raw << shift) as i128) >> shift).to_string()
+            }
+            gimli::DW_ATE_unsigned | gimli::DW_ATE_unsigned_char => raw.to_string(),
+            gimli::DW_ATE_float if bytes.len() == 4 => f32::from_bits(raw as u32).to_string(),
+            gimli::DW_ATE_float if bytes.len() == 8 => f64::from_bits(raw as u64).to_string(),
+            gimli::DW_ATE_UTF => char::from_u32(raw as u32)
+                .map(|c| format!("{:?}", c))
+                .unwrap_or_else(|| format!("{} (invalid char)", raw)),
+            _ => return None,
+        };
+
+        Some(formatted)
+    }
+
+    fn format_pointer(&self, entry: &gimli::DebuggingInformationEntry<R>, bytes: &[u8]) -> String {
+        if bytes.is_empty() || bytes.len() > 8 {
+            return hex_bytes(bytes);
+        }
+        let target = self.read_uint(bytes) as u64;
+
+        // `char *` pointing at memory we have: show the string
+        if target != 0 && type_ref(entry).is_some_and(|pointee| self.is_char_type(pointee)) {
+            if let Some(string) = self.read_c_string(target) {
+                return format!("0x{:08x} {}", target, string);
+            }
+        }
+
+        match symbolize_address(self.symbols, target) {
+            Some(symbol) if target != 0 => format!("0x{:08x} ({})", target, symbol),
+            _ => format!("0x{:08x}", target),
+        }
+    }
+
+    fn format_enum(&self, type_offset: gimli::UnitOffset<R::Offset>, bytes: &[u8]) -> String {
+        if bytes.is_empty() || bytes.len() > 16 {
+            return hex_bytes(bytes);
+        }
+        let raw = self.read_uint(bytes);
+        let mask = if bytes.len() == 16 {
+            u128::MAX
+        } else {
+            (1u128 << (bytes.len() * 8)) - 1
+        };
+
+        let mut variant = None;
+        for_each_child(self.unit, type_offset, |enumerator| {
+            if variant.is_some() || enumerator.tag() != gimli::DW_TAG_enumerator {
+                return;
+            }
+            let value = match enumerator.attr_value(gimli::DW_AT_const_value) {
+                Ok(Some(gimli::AttributeValue::Sdata(value))) => value as i128 as u128,
+                Ok(Some(attr)) => match attr.udata_value() {
+                    Some(value) => value as u128,
+                    None => return,
+                },
+                _ => return,
+            };
+            if value & mask == raw {
+                variant = get_string_attr(self.dwarf, self.unit, enumerator, gimli::DW_AT_name);
+            }
+        });
+
+        match variant {
+            Some(name) => format!("{} ({})", name, raw),
+            None => format!("{} (no matching variant)", raw),
+        }
+    }
+
+    /// Whether a type is a C `char`, possibly behind typedefs and qualifiers
+    fn is_char_type(&self, type_offset: gimli::UnitOffset<R::Offset>) -> bool {
+        let mut offset = type_offset;
+        for _ in 0..MAX_DEPTH {
+            let Ok(entry) = self.unit.entry(offset) else {
+                return false;
+            };
+            match entry.tag() {
+                gimli::DW_TAG_base_type => {
+                    let is_char = matches!(
+                        entry.attr_value(gimli::DW_AT_encoding),
+                        Ok(Some(gimli::AttributeValue::Encoding(
+                            gimli::DW_ATE_signed_char | gimli::DW_ATE_unsigned_char
+                        )))
+                    );
+                    return is_char && udata_attr(&entry, gimli::DW_AT_byte_size) == Some(1);
+                }
+                gimli::DW_TAG_typedef | gimli::DW_TAG_const_type | gimli::DW_TAG_volatile_type => {
+                    match type_ref(&entry) {
+                        Some(inner) => offset = inner,
+                        None => return false,
+                    }
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    /// NUL-terminated string at `address`, if the memory covers it
+    fn read_c_string(&self, address: u64) -> Option<String> {
+        let region = self.memory.regions.iter().find(|region| {
+            address >= region.address && address - region.address < region.data.len() as u64
+        })?;
+        let bytes = &region.data[(address - region.address) as usize..];
+        Some(format_c_string(
+            &bytes[..bytes.len().min(MAX_STRING_LEN + 1)],
+        ))
+    }
+
+    fn type_name(&self, type_offset: gimli::UnitOffset<R::Offset>) -> String {
+        resolve_type(self.dwarf, self.unit, type_offset).display(self.rust_syntax)
+    }
+
+    /// Unsigned integer of up to 16 bytes in target byte order
+    fn read_uint(&self, bytes: &[u8]) -> u128 {
+        let fold = |value: u128, &byte: &u8| (value << 8) | byte as u128;
+        if self.little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        }
+    }
+}
+
+/// One-line summary of struct members or array elements
+fn summarize(children: &[DecodedValue], named: bool) -> String {
+    if children.is_empty() {
+        return if named { "{}" } else { "[]" }.to_string();
+    }
+
+    let parts: Vec<String> = children
+        .iter()
+        .map(|child| {
+            if child.name == "…" {
+                "…".to_string()
+            } else if named {
+                format!("{}: {}", child.name, child.value)
+            } else {
+                child.value.clone()
+            }
+        })
+        .collect();
+
+    let mut summary = parts.join(", ");
+    if summary.chars().count() > SUMMARY_LEN {
+        summary = summary.chars().take(SUMMARY_LEN).collect::<String>() + "…";
+    }
+
+    if named {
+        format!("{{ {} }}", summary)
+    } else {
+        format!("[{}]", summary)
+    }
+}
+
+/// `None`, `Some(5)` or `Moving { speed: 3 }`; tuple variants name their fields `__0`, `__1`, ...
+fn format_variant(name: &str, fields: &[DecodedValue]) -> String {
+    if fields.is_empty() {
+        return name.to_string();
+    }
+    if fields.iter().all(|field| field.name.starts_with("__")) {
+        let summary = summarize(fields, false);
+        let inner = summary
+            .strip_prefix('[')
+            .and_then(|summary| summary.strip_suffix(']'))
+            .unwrap_or(&summary);
+        return format!("{}({})", name, inner);
+    }
+    format!("{} {}", name, summarize(fields, true))
+}
+
+/// Quoted text up to the first NUL, cut off after `MAX_STRING_LEN` bytes
+fn format_c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let text = String::from_utf8_lossy(&bytes[..end.min(MAX_STRING_LEN)]);
+    if end > MAX_STRING_LEN {
+        format!("{:?}…", text)
+    } else {
+        format!("{:?}", text)
+    }
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    let shown: Vec<String> = bytes
+        .iter()
+        .take(16)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if bytes.len() > 16 {
+        format!("{} … ({} bytes)", shown.join(" "), bytes.len())
+    } else {
+        shown.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{array_bounds, type_size, ValueDecoder};
+    use crate::types::{MemorySnapshot, SnapshotFormat};
+    use gimli::write::{AttributeValue, DwarfUnit, EndianVec, Sections, UnitEntryId};
+    use gimli::{Dwarf, EndianSlice, LittleEndian};
+
+    type Attributes<'a> = &'a [(gimli::DwAt, AttributeValue)];
+
+    /// A 32-bit Rust compile unit with a `u32` base type
+    fn rust_unit() -> (DwarfUnit, UnitEntryId) {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+        let root = dwarf.unit.root();
+        dwarf.unit.get_mut(root).set(
+            gimli::DW_AT_language,
+            AttributeValue::Language(gimli::DW_LANG_Rust),
+        );
+        let u32_type = add(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_base_type,
+            "u32",
+            &[
+                (gimli::DW_AT_byte_size, AttributeValue::Udata(4)),
+                (
+                    gimli::DW_AT_encoding,
+                    AttributeValue::Encoding(gimli::DW_ATE_unsigned),
+                ),
+            ],
+        );
+        (dwarf, u32_type)
+    }
+
+    fn add(
+        dwarf: &mut DwarfUnit,
+        parent: UnitEntryId,
+        tag: gimli::DwTag,
+        name: &str,
+        attrs: Attributes,
+    ) -> UnitEntryId {
+        let id = dwarf.unit.add(parent, tag);
+        let entry = dwarf.unit.get_mut(id);
+        if !name.is_empty() {
+            entry.set(gimli::DW_AT_name, AttributeValue::String(name.into()));
+        }
+        for (attr, value) in attrs.iter().cloned() {
+            entry.set(attr, value);
+        }
+        id
+    }
+
+    fn write(dwarf: &mut DwarfUnit) -> Sections<EndianVec<LittleEndian>> {
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        sections
+    }
+
+    fn load(sections: &Sections<EndianVec<LittleEndian>>) -> Dwarf<EndianSlice<'_, LittleEndian>> {
+        Dwarf::load(|id| -> gimli::Result<_> {
+            let data = sections.get(id).map_or(&[][..], |section| section.slice());
+            Ok(EndianSlice::new(data, LittleEndian))
+        })
+        .unwrap()
+    }
+
+    /// Offset of the first DIE tagged `tag`
+    fn find<R: gimli::Reader>(
+        unit: &gimli::Unit<R>,
+        tag: gimli::DwTag,
+    ) -> gimli::UnitOffset<R::Offset> {
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            if entry.tag() == tag {
+                return entry.offset();
+            }
+        }
+        panic!("no {} in the unit", tag);
+    }
+
+    /// `Option<u32>` as rustc describes it: a struct whose variant part picks the
+    /// variant by the `u32` tag at offset 0, with the payload at offset 4
+    fn option_u32(niche: bool) -> Sections<EndianVec<LittleEndian>> {
+        let (mut dwarf, u32_type) = rust_unit();
+        let root = dwarf.unit.root();
+        let size = || (gimli::DW_AT_byte_size, AttributeValue::Udata(8));
+        let option = add(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_structure_type,
+            "Option<u32>",
+            &[size()],
+        );
+        let none = add(
+            &mut dwarf,
+            option,
+            gimli::DW_TAG_structure_type,
+            "None",
+            &[size()],
+        );
+        let some = add(
+            &mut dwarf,
+            option,
+            gimli::DW_TAG_structure_type,
+            "Some",
+            &[size()],
+        );
+        add(
+            &mut dwarf,
+            some,
+            gimli::DW_TAG_member,
+            "__0",
+            &[
+                (gimli::DW_AT_type, AttributeValue::UnitRef(u32_type)),
+                (gimli::DW_AT_data_member_location, AttributeValue::Udata(4)),
+            ],
+        );
+
+        let part = add(&mut dwarf, option, gimli::DW_TAG_variant_part, "", &[]);
+        let tag = add(
+            &mut dwarf,
+            part,
+            gimli::DW_TAG_member,
+            "",
+            &[
+                (gimli::DW_AT_type, AttributeValue::UnitRef(u32_type)),
+                (gimli::DW_AT_data_member_location, AttributeValue::Udata(0)),
+            ],
+        );
+        dwarf
+            .unit
+            .get_mut(part)
+            .set(gimli::DW_AT_discr, AttributeValue::UnitRef(tag));
+        for (name, payload, discr_value) in [("None", none, 0), ("Some", some, 1)] {
+            // Niche-encoded enums leave the discriminant value off the owning variant
+            let attrs = if niche && name == "Some" {
+                Vec::new()
+            } else {
+                vec![(gimli::DW_AT_discr_value, AttributeValue::Udata(discr_value))]
+            };
+            let variant = add(&mut dwarf, part, gimli::DW_TAG_variant, "", &attrs);
+            add(
+                &mut dwarf,
+                variant,
+                gimli::DW_TAG_member,
+                name,
+                &[
+                    (gimli::DW_AT_type, AttributeValue::UnitRef(payload)),
+                    (gimli::DW_AT_data_member_location, AttributeValue::Udata(0)),
+                ],
+            );
+        }
+
+        write(&mut dwarf)
+    }
+
+    /// Decode `bytes` as the `Option<u32>` of `option_u32`
+    fn decode(niche: bool, bytes: &[u8]) -> String {
+        let sections = option_u32(niche);
+        let dwarf = load(&sections);
+        let unit = dwarf.unit(dwarf.units().next().unwrap().unwrap()).unwrap();
+        let memory = MemorySnapshot {
+            file_name: String::new(),
+            format: SnapshotFormat::Raw,
+            regions: Vec::new(),
+        };
+        let decoder = ValueDecoder {
+            dwarf: &dwarf,
+            unit: &unit,
+            memory: &memory,
+            symbols: &[],
+            little_endian: true,
+            rust_syntax: true,
+        };
+
+        let mut entries = unit.entries();
+        while let Some((_, entry)) = entries.next_dfs().unwrap() {
+            let name = entry.attr_value(gimli::DW_AT_name).unwrap();
+            if let Some(gimli::AttributeValue::String(name)) = name {
+                if name.slice() == b"Option<u32>" {
+                    return decoder
+                        .decode(String::new(), entry.offset(), bytes, 0, 0)
+                        .value;
+                }
+            }
+        }
+        panic!("Option<u32> not found");
+    }
+
+    #[test]
+    fn rust_enums_decode_by_variant() {
+        assert_eq!(decode(false, &[0, 0, 0, 0, 5, 0, 0, 0]), "None");
+        assert_eq!(decode(false, &[1, 0, 0, 0, 5, 0, 0, 0]), "Some(5)");
+        assert_eq!(decode(true, &[0, 0, 0, 0, 5, 0, 0, 0]), "None");
+        assert_eq!(decode(true, &[7, 0, 0, 0, 5, 0, 0, 0]), "Some(5)");
+    }
+
+    /// `u32` array with one subrange per entry of `subranges`
+    fn u32_array(subranges: &[Attributes]) -> Sections<EndianVec<LittleEndian>> {
+        let (mut dwarf, u32_type) = rust_unit();
+        let root = dwarf.unit.root();
+        let array = add(
+            &mut dwarf,
+            root,
+            gimli::DW_TAG_array_type,
+            "",
+            &[(gimli::DW_AT_type, AttributeValue::UnitRef(u32_type))],
+        );
+        for attrs in subranges {
+            add(&mut dwarf, array, gimli::DW_TAG_subrange_type, "", attrs);
+        }
+        write(&mut dwarf)
+    }
+
+    #[test]
+    fn array_bounds_from_count_or_bounds() {
+        let bound = |attr, value| (attr, AttributeValue::Udata(value));
+        let sections = u32_array(&[
+            &[bound(gimli::DW_AT_count, 3)],
+            &[
+                bound(gimli::DW_AT_lower_bound, 1),
+                bound(gimli::DW_AT_upper_bound, 4),
+            ],
+            &[bound(gimli::DW_AT_upper_bound, u64::MAX)],
+            &[],
+        ]);
+        let dwarf = load(&sections);
+        let unit = dwarf.unit(dwarf.units().next().unwrap().unwrap()).unwrap();
+        let array = find(&unit, gimli::DW_TAG_array_type);
+        assert_eq!(array_bounds(&unit, array), [Some(3), Some(4), None, None]);
+    }
+
+    #[test]
+    fn oversized_arrays_have_no_size() {
+        let count = |value| [(gimli::DW_AT_count, AttributeValue::Udata(value))];
+        let sizes = [
+            (u32_array(&[&count(3), &count(5)]), Some(60)),
+            (u32_array(&[&count(u64::MAX / 2)]), None),
+            (u32_array(&[&count(1 << 32), &count(1 << 32)]), None),
+        ];
+        for (sections, size) in sizes {
+            let dwarf = load(&sections);
+            let unit = dwarf.unit(dwarf.units().next().unwrap().unwrap()).unwrap();
+            assert_eq!(
+                type_size(&unit, find(&unit, gimli::DW_TAG_array_type)),
+                size
+            );
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{Context as AnyhowContext, Result};
+use object::{Architecture, Endianness, Object, ObjectSection};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::types::{ElfSymbol, MemoryKind, MemoryRegion, MemorySegment, VectorEntry, VectorTable};
+use crate::utils::{arm_attribute, parse_number, symbolize_address};
+
+/// Section names used for the vector table by cortex-m-rt, CMSIS and vendor startup files
+const VECTOR_TABLE_SECTIONS: &[&str] = &[".vector_table", ".isr_vector", ".vectors"];
+
+/// Symbols that unhandled exceptions and interrupts are routed to
+pub const DEFAULT_HANDLER_SYMBOLS: &[&str] =
+    &["DefaultHandler", "DefaultHandler_", "Default_Handler"];
+
+/// The 16 architectural entries at the start of every Cortex-M vector table.
+/// Empty names are reserved slots.
+const EXCEPTION_NAMES: [&str; 16] = [
+    "Initial SP",
+    "Reset",
+    "NMI",
+    "HardFault",
+    "MemManage",
+    "BusFault",
+    "UsageFault",
+    "SecureFault",
+    "",
+    "",
+    "",
+    "SVCall",
+    "DebugMonitor",
+    "",
+    "PendSV",
+    "SysTick",
+];
+
+/// Whether the `.ARM.attributes` build attributes describe an M-profile core:
+/// `Tag_CPU_arch_profile` is 'M', or `Tag_ARM_ISA_use` says the image has no
+/// ARM code at all. Stripped images without these attributes are taken to be
+/// Cortex-M if they are for ARM at all.
+fn is_cortex_m(obj: &object::File) -> bool {
+    const TAG_CPU_ARCH_PROFILE: u64 = 7;
+    const TAG_ARM_ISA_USE: u64 = 8;
+
+    match (
+        arm_attribute(obj, TAG_CPU_ARCH_PROFILE),
+        arm_attribute(obj, TAG_ARM_ISA_USE),
+    ) {
+        (None, None) => obj.architecture() == Architecture::Arm,
+        (profile, arm_isa) => profile == Some(b'M' as u64) || arm_isa == Some(0),
+    }
+}
+
+/// Decode the Cortex-M vector table from the ELF file contents.
+///
+/// Every entry is resolved to an ELF symbol and checked for a missing Thumb
+/// bit, a handler outside flash and fall-through to the default handler.
+/// `interrupt_names` maps IRQ numbers to names, usually from an SVD file.
+pub fn parse_vector_table(
+    path: &PathBuf,
+    symbols: &[ElfSymbol],
+    segments: &[MemorySegment],
+    memory_regions: &[MemoryRegion],
+    interrupt_names: &BTreeMap<u32, String>,
+) -> Result<VectorTable> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+
+    // Only the Cortex-M layout is understood. A- and R-profile cores start with
+    // branch instructions rather than addresses.
+    if !is_cortex_m(&obj) {
+        return Ok(VectorTable::default());
+    }
+
+    let Some(section) = VECTOR_TABLE_SECTIONS
+        .iter()
+        .find_map(|name| obj.section_by_name(name))
+    else {
+        return Ok(VectorTable::default());
+    };
+
+    let table_data = section.data().context("Failed to read vector table")?;
+    let words: Vec<u64> = table_data
+        .chunks_exact(4)
+        .map(|chunk| {
+            let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            match obj.endianness() {
+                Endianness::Little => u32::from_le_bytes(bytes) as u64,
+                Endianness::Big => u32::from_be_bytes(bytes) as u64,
+            }
+        })
+        .collect();
+
+    let default_handlers: Vec<u64> = symbols
+        .iter()
+        .filter(|s| DEFAULT_HANDLER_SYMBOLS.contains(&s.name.as_str()))
+        .map(|s| s.address)
+        .collect();
+
+    let flash_regions: Vec<&MemoryRegion> = memory_regions
+        .iter()
+        .filter(|r| r.kind == MemoryKind::Flash)
+        .collect();
+    let ram_regions: Vec<&MemoryRegion> = memory_regions
+        .iter()
+        .filter(|r| r.kind == MemoryKind::Ram)
+        .collect();
+
+    let mut entries = Vec::with_capacity(words.len());
+
+    for (index, &value) in words.iter().enumerate() {
+        let (name, irq_name) = match EXCEPTION_NAMES.get(index) {
+            Some(&"") => ("Reserved".to_string(), None),
+            Some(name) => (name.to_string(), None),
+            None => {
+                let irqn = (index - EXCEPTION_NAMES.len()) as u32;
+                (format!("IRQ {}", irqn), interrupt_names.get(&irqn).cloned())
+            }
+        };
+
+        let mut issues = Vec::new();
+
+        if index == 0 {
+            // Initial stack pointer rather than a handler
+            if !ram_regions.is_empty()
+                && !ram_regions
+                    .iter()
+                    .any(|r| r.contains(value.saturating_sub(1), 1))
+            {
+                issues.push("Initial SP is not inside RAM".to_string());
+            }
+            if value % 8 != 0 {
+                issues.push("Initial SP is not 8-byte aligned".to_string());
+            }
+            entries.push(VectorEntry {
+                index,
+                name,
+                irq_name,
+                value,
+                symbol: symbolize_address(symbols, value),
+                default_handler: false,
+                issues,
+            });
+            continue;
+        }
+
+        // Unused slots are left zero
+        if value == 0 {
+            entries.push(VectorEntry {
+                index,
+                name,
+                irq_name,
+                value,
+                symbol: None,
+                default_handler: false,
+                issues,
+            });
+            continue;
+        }
+
+        let target = value & !1;
+
+        if value & 1 == 0 {
+            issues.push("Thumb bit is cleared, entry will fault".to_string());
+        }
+
+        if !flash_regions.is_empty() {
+            if !flash_regions.iter().any(|r| r.contains(target, 2)) {
+                issues.push("Handler is outside flash".to_string());
+            }
+        } else if !segments
+            .iter()
+            .any(|s| s.flags.ends_with('X') && target >= s.address && target < s.address + s.size)
+        {
+            issues.push("Handler is not in an executable section".to_string());
+        }
+
+        let default_handler = default_handlers.contains(&target);
+        if default_handler && index == 1 {
+            issues.push("Reset points to the default handler".to_string());
+        }
+
+        entries.push(VectorEntry {
+            index,
+            name,
+            irq_name,
+            value,
+            symbol: symbolize_address(symbols, target),
+            default_handler,
+            issues,
+        });
+    }
+
+    Ok(VectorTable {
+        present: true,
+        section_name: section.name().ok().map(|n| n.to_string()),
+        address: Some(section.address()),
+        entries,
+    })
+}
+
+/// Read the device interrupt names and numbers from a CMSIS-SVD file
+pub fn parse_svd_interrupts(path: &PathBuf) -> Result<BTreeMap<u32, String>> {
+    let text = fs::read_to_string(path).context("Failed to read SVD file")?;
+    let doc = roxmltree::Document::parse(&text).context("Failed to parse SVD file")?;
+
+    let child_text = |node: roxmltree::Node, tag: &str| -> Option<String> {
+        node.children()
+            .find(|c| c.has_tag_name(tag))
+            .and_then(|c| c.text())
+            .map(|t| t.trim().to_string())
+    };
+
+    let mut interrupts = BTreeMap::new();
+
+    for interrupt in doc.descendants().filter(|n| n.has_tag_name("interrupt")) {
+        let (Some(name), Some(value)) = (
+            child_text(interrupt, "name"),
+            child_text(interrupt, "value"),
+        ) else {
+            continue;
+        };
+
+        let value = parse_number(&value).and_then(|value| u32::try_from(value).ok());
+
+        // The same interrupt is listed under every peripheral that shares it
+        if let Some(value) = value {
+            interrupts.entry(value).or_insert(name);
+        }
+    }
+
+    Ok(interrupts)
+}
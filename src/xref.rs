@@ -0,0 +1,557 @@
+use anyhow::{Context, Result};
+use object::{Object, ObjectSection, ObjectSymbol, SectionFlags, SectionKind};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::disasm::{decode_instructions, mapping_symbols, InstructionSet};
+use crate::parser::demangle_name;
+use crate::types::{DisassembledInstruction, ElfSymbol, Flow, Xref, XrefIndex, XrefKind};
+use crate::utils::symbolize_address;
+
+/// Values below this in data are far more likely counts or flags than pointers into
+/// a vector table at address 0
+const MIN_POINTER: u64 = 0x100;
+
+/// Instructions followed after an address is put in a register before giving up on
+/// telling how it is used
+const MAX_FOLLOW: usize = 64;
+
+struct AllocatedSection<'a> {
+    name: String,
+    address: u64,
+    size: u64,
+    /// `None` for NOBITS sections like `.bss`
+    data: Option<&'a [u8]>,
+    code: bool,
+}
+
+/// Index who references what: branches, literal pool loads and `adr`/`auipc`/`movw`
+/// address forms in every function, plus pointer-sized words in initialized data that
+/// point into a symbol. Code is only scanned for the instruction sets the disassembler
+/// knows. An address put in a register is followed to the loads and stores using it.
+pub fn parse_xrefs(path: &PathBuf, symbols: &[ElfSymbol]) -> Result<XrefIndex> {
+    let data = fs::read(path).context("Failed to read ELF file")?;
+    let obj = object::File::parse(&*data).context("Failed to parse ELF file")?;
+    let instruction_set = InstructionSet::from_architecture(obj.architecture());
+    let pointer_size = if obj.is_64() { 8 } else { 4 };
+
+    let sections: Vec<AllocatedSection> = obj
+        .sections()
+        .filter(|section| match section.flags() {
+            SectionFlags::Elf { sh_flags } => sh_flags & 0x2 != 0, // SHF_ALLOC
+            _ => false,
+        })
+        .map(|section| AllocatedSection {
+            name: section.name().unwrap_or("<unnamed>").to_string(),
+            address: section.address(),
+            size: section.size(),
+            data: section
+                .file_range()
+                .and_then(|_| section.data().ok())
+                .filter(|_| section.kind() != SectionKind::UninitializedData),
+            code: section.kind() == SectionKind::Text,
+        })
+        .collect();
+    let index = Index {
+        sections: &sections,
+        symbols,
+        thumb: instruction_set == Some(InstructionSet::Thumb),
+    };
+
+    let mut xrefs = Vec::new();
+    if let Some(instruction_set) = instruction_set {
+        let mapping = mapping_symbols(symbols);
+        let mut functions: Vec<(u64, u64)> = obj
+            .symbols()
+            .filter(|symbol| symbol.kind() == object::SymbolKind::Text && symbol.size() > 0)
+            .map(|symbol| (symbol.address() & !1, symbol.size()))
+            .collect();
+        functions.sort();
+        functions.dedup_by_key(|function| function.0);
+
+        for (start, size) in functions {
+            let Some(code) = index.bytes_at(start, size) else {
+                continue;
+            };
+            let instructions = decode_instructions(instruction_set, code, start, &mapping);
+            for position in 0..instructions.len() {
+                xrefs.extend(index.instruction_xref(
+                    instruction_set,
+                    &instructions,
+                    position,
+                    start,
+                    size,
+                ));
+            }
+        }
+    }
+
+    for section in &sections {
+        let Some(data) = section.data.filter(|_| !section.code) else {
+            continue;
+        };
+        // Pointers are aligned, whatever the section's start
+        let first = (section.address.next_multiple_of(pointer_size) - section.address) as usize;
+        for (word_index, word) in data
+            .get(first..)
+            .unwrap_or_default()
+            .chunks_exact(pointer_size as usize)
+            .enumerate()
+        {
+            let value = word
+                .iter()
+                .rev()
+                .fold(0u64, |value, &byte| value << 8 | byte as u64);
+            let Some(to) = index.resolve(value) else {
+                continue;
+            };
+            let from = section.address + (first + word_index * pointer_size as usize) as u64;
+            xrefs.push(Xref {
+                from,
+                from_symbol: symbolize_address(symbols, from)
+                    .unwrap_or_else(|| section.name.clone()),
+                to,
+                kind: XrefKind::Pointer,
+            });
+        }
+    }
+
+    xrefs.sort_by_key(|xref| (xref.to, xref.from));
+    Ok(XrefIndex { xrefs })
+}
+
+/// The references to the ELF symbols named `name`, raw or demangled, one per line
+pub fn describe_xrefs(index: &XrefIndex, symbols: &[ElfSymbol], name: &str) -> Vec<String> {
+    let mut matches: Vec<&ElfSymbol> = symbols
+        .iter()
+        .filter(|symbol| !symbol.name.starts_with('$'))
+        .filter(|symbol| symbol.name == name || demangle_name(&symbol.name) == name)
+        .collect();
+    matches.dedup_by_key(|symbol| symbol.address);
+    if matches.is_empty() {
+        return vec![format!("{}: no such symbol", name)];
+    }
+
+    let mut lines = Vec::new();
+    for symbol in matches {
+        let xrefs = index.to_range(symbol.address, symbol.size);
+        lines.push(format!(
+            "{} (0x{:08x}, {} bytes): {} references",
+            name,
+            symbol.address,
+            symbol.size,
+            xrefs.len()
+        ));
+        for xref in xrefs {
+            let offset = xref.to - symbol.address;
+            lines.push(format!(
+                "  {:<8} 0x{:08x}  {}{}",
+                xref.kind.display_name(),
+                xref.from,
+                xref.from_symbol,
+                if offset > 0 {
+                    format!("  (to +0x{:x})", offset)
+                } else {
+                    String::new()
+                }
+            ));
+        }
+    }
+    lines
+}
+
+struct Index<'a> {
+    sections: &'a [AllocatedSection<'a>],
+    symbols: &'a [ElfSymbol],
+    thumb: bool,
+}
+
+impl Index<'_> {
+    fn bytes_at(&self, address: u64, size: u64) -> Option<&[u8]> {
+        self.sections.iter().find_map(|section| {
+            let offset = address.checked_sub(section.address)? as usize;
+            section
+                .data?
+                .get(offset..offset.checked_add(size as usize)?)
+        })
+    }
+
+    /// The address `value` refers to if it points into a symbol of an allocated section.
+    /// Thumb function pointers lose their Thumb bit.
+    fn resolve(&self, value: u64) -> Option<u64> {
+        let in_section = |address: u64, code_only: bool| {
+            self.sections.iter().any(|section| {
+                (!code_only || section.code)
+                    && address
+                        .checked_sub(section.address)
+                        .is_some_and(|offset| offset < section.size)
+            })
+        };
+        let value = if self.thumb && value & 1 != 0 && in_section(value & !1, true) {
+            value & !1
+        } else {
+            value
+        };
+        (value >= MIN_POINTER
+            && in_section(value, false)
+            && symbolize_address(self.symbols, value).is_some())
+        .then_some(value)
+    }
+
+    /// What the instruction at `position` in the function at `start` refers to outside
+    /// that function
+    fn instruction_xref(
+        &self,
+        instruction_set: InstructionSet,
+        instructions: &[DisassembledInstruction],
+        position: usize,
+        start: u64,
+        size: u64,
+    ) -> Option<Xref> {
+        let instruction = &instructions[position];
+        let mnemonic = instruction.mnemonic.as_str();
+        // How an address loaded into a register is used
+        let follow = || follow_address(instruction_set, &instructions[position..]);
+
+        let (to, kind) = if let Some(target) = instruction.target {
+            match mnemonic {
+                "bl" | "blx" | "jal" | "jalr" | "call0" | "call4" | "call8" | "call12" => {
+                    (self.resolve(target)?, XrefKind::Call)
+                }
+                "adr" | "adr.w" | "addi" => (self.resolve(target)?, follow()),
+                _ => (self.resolve(target)?, XrefKind::Jump),
+            }
+        } else if let Some(literal) = instruction.literal {
+            match instruction_set {
+                // The literal pool holds the address, e.g. `ldr r0, =BUFFER`
+                InstructionSet::Thumb | InstructionSet::Xtensa => {
+                    if !matches!(mnemonic, "ldr" | "ldr.w" | "l32r") {
+                        return None;
+                    }
+                    let bytes = self.bytes_at(literal, 4)?;
+                    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    (self.resolve(value as u64)?, follow())
+                }
+                // A load or store through a `lui`/`auipc` base accesses the data itself
+                InstructionSet::Riscv(_) => {
+                    let kind = if is_riscv_store(mnemonic) {
+                        XrefKind::Write
+                    } else {
+                        XrefKind::Read
+                    };
+                    (self.resolve(literal)?, kind)
+                }
+            }
+        } else if mnemonic == "movt" {
+            let value = movw_movt(&instructions[..=position])?;
+            (self.resolve(value)?, follow())
+        } else {
+            return None;
+        };
+
+        // Branches and literal pools within the function are not references
+        if (start..start + size).contains(&to) {
+            return None;
+        }
+        Some(Xref {
+            from: instruction.address,
+            from_symbol: symbolize_address(self.symbols, instruction.address)
+                .unwrap_or_else(|| format!("0x{:08x}", instruction.address)),
+            to,
+            kind,
+        })
+    }
+}
+
+/// What an instruction does with a register holding an address
+enum RegisterUse {
+    Unused,
+    /// Loads through it, replacing it if `overwritten`
+    Load {
+        overwritten: bool,
+    },
+    Store,
+    /// Copied, offset, stored or passed on: later accesses can't be followed
+    Escape,
+    Overwrite,
+    /// Return or unconditional branch
+    End,
+}
+
+/// How the code after `instructions[0]`, which puts an address in its first operand,
+/// uses it: a write if anything stores through it, a read if it is only loaded from
+/// and an address use otherwise. Only the fall-through path is followed, so a
+/// conditional branch while the address is still live makes it an address use.
+fn follow_address(
+    instruction_set: InstructionSet,
+    instructions: &[DisassembledInstruction],
+) -> XrefKind {
+    let Some(register) = operand_tokens(&instructions[0].operands).first().copied() else {
+        return XrefKind::Address;
+    };
+    let mut read = false;
+    for (count, instruction) in instructions.iter().skip(1).enumerate() {
+        if count == MAX_FOLLOW || matches!(instruction.flow, Flow::ConditionalBranch(_)) {
+            return XrefKind::Address;
+        }
+        let register_use = match instruction_set {
+            InstructionSet::Thumb => thumb_register_use(instruction, register),
+            InstructionSet::Riscv(_) => riscv_register_use(instruction, register),
+            InstructionSet::Xtensa => xtensa_register_use(instruction, register),
+        };
+        match register_use {
+            RegisterUse::Unused => {}
+            RegisterUse::Load { overwritten } => {
+                read = true;
+                if overwritten {
+                    break;
+                }
+            }
+            RegisterUse::Store => return XrefKind::Write,
+            RegisterUse::Escape => return XrefKind::Address,
+            RegisterUse::Overwrite | RegisterUse::End => break,
+        }
+    }
+    if read {
+        XrefKind::Read
+    } else {
+        XrefKind::Address
+    }
+}
+
+/// Registers and immediates of an operand string, without brackets, braces and `!`
+fn operand_tokens(operands: &str) -> Vec<&str> {
+    operands
+        .split([',', ' ', '[', ']', '{', '}', '(', ')', '!'])
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+fn thumb_register_use(instruction: &DisassembledInstruction, register: &str) -> RegisterUse {
+    let mnemonic = instruction.mnemonic.as_str();
+    let operands = instruction.operands.as_str();
+    let tokens = operand_tokens(operands);
+    let uses = tokens.contains(&register);
+
+    if matches!(mnemonic, "bl" | "blx") {
+        // Arguments are passed in r0-r3, which the callee may clobber like r12
+        return match register {
+            "r0" | "r1" | "r2" | "r3" => RegisterUse::Escape,
+            "r12" => RegisterUse::Overwrite,
+            _ => RegisterUse::Unused,
+        };
+    }
+    if mnemonic == "bx" || matches!(mnemonic, "b" | "b.w") {
+        return RegisterUse::End;
+    }
+    let compare = ["cmp", "cmn", "tst", "teq"]
+        .iter()
+        .any(|prefix| mnemonic.starts_with(prefix));
+    if compare {
+        return RegisterUse::Unused;
+    }
+    if mnemonic.starts_with("push") {
+        return if uses {
+            RegisterUse::Escape
+        } else {
+            RegisterUse::Unused
+        };
+    }
+    if mnemonic.starts_with("pop") {
+        return if tokens.contains(&"pc") {
+            RegisterUse::End
+        } else if uses {
+            RegisterUse::Overwrite
+        } else {
+            RegisterUse::Unused
+        };
+    }
+
+    // `ldm r0!, {r1, r2}` and `stmia r0!, {r1}`
+    let multiple = ["ldm", "stm", "vldm", "vstm"]
+        .iter()
+        .any(|prefix| mnemonic.starts_with(prefix));
+    // `ldr r1, [r0, #4]` and `strd r1, r2, [r0]`
+    let single = operands.find('[');
+    if multiple || single.is_some() {
+        let (values, address) = match single {
+            Some(split) => (
+                operand_tokens(&operands[..split]),
+                operand_tokens(&operands[split..]),
+            ),
+            None => (tokens.get(1..).unwrap_or_default().to_vec(), tokens.clone()),
+        };
+        let store = mnemonic.starts_with("st") || mnemonic.starts_with("vst");
+        let in_values = values.contains(&register);
+        return if address.first() == Some(&register) {
+            if store {
+                RegisterUse::Store
+            } else {
+                RegisterUse::Load {
+                    overwritten: in_values,
+                }
+            }
+        } else if address.iter().skip(1).any(|&token| token == register) || (store && in_values) {
+            RegisterUse::Escape
+        } else if in_values {
+            RegisterUse::Overwrite
+        } else {
+            RegisterUse::Unused
+        };
+    }
+
+    match tokens.split_first() {
+        Some((_, sources)) if sources.contains(&register) => RegisterUse::Escape,
+        Some((&destination, sources)) if destination == register => {
+            // Two operand forms like `adds r0, #4` or `ands r0, r1` also read the destination
+            let replaces = ["mov", "mvn", "sxt", "uxt", "rev", "adr", "clz"]
+                .iter()
+                .any(|prefix| mnemonic.starts_with(prefix));
+            if sources.len() == 1 && !replaces {
+                RegisterUse::Escape
+            } else {
+                RegisterUse::Overwrite
+            }
+        }
+        _ => RegisterUse::Unused,
+    }
+}
+
+fn is_riscv_store(mnemonic: &str) -> bool {
+    matches!(mnemonic, "sb" | "sh" | "sw" | "sd" | "fsw" | "fsd")
+}
+
+fn riscv_register_use(instruction: &DisassembledInstruction, register: &str) -> RegisterUse {
+    let mnemonic = instruction.mnemonic.as_str();
+    let tokens = operand_tokens(&instruction.operands);
+
+    match mnemonic {
+        "jal" | "jalr" => {
+            return match register {
+                "a0" | "a1" | "a2" | "a3" | "a4" | "a5" | "a6" | "a7" => RegisterUse::Escape,
+                "ra" | "t0" | "t1" | "t2" | "t3" | "t4" | "t5" | "t6" => RegisterUse::Overwrite,
+                _ if tokens.contains(&register) => RegisterUse::Escape,
+                _ => RegisterUse::Unused,
+            };
+        }
+        "j" | "jr" | "ret" => return RegisterUse::End,
+        _ => {}
+    }
+
+    // `lw a1, 4(a0)` and `sw a1, 4(a0)`: value, offset, base
+    if let [value, _, base] = tokens[..] {
+        if instruction.operands.contains('(') {
+            return match (
+                is_riscv_store(mnemonic),
+                base == register,
+                value == register,
+            ) {
+                (false, true, overwritten) => RegisterUse::Load { overwritten },
+                (true, true, _) => RegisterUse::Store,
+                (true, false, true) => RegisterUse::Escape,
+                (false, false, true) => RegisterUse::Overwrite,
+                _ => RegisterUse::Unused,
+            };
+        }
+    }
+
+    match tokens.split_first() {
+        Some((_, sources)) if sources.contains(&register) => RegisterUse::Escape,
+        Some((&destination, _)) if destination == register => RegisterUse::Overwrite,
+        _ => RegisterUse::Unused,
+    }
+}
+
+fn xtensa_register_use(instruction: &DisassembledInstruction, register: &str) -> RegisterUse {
+    let mnemonic = instruction.mnemonic.as_str();
+    let tokens = operand_tokens(&instruction.operands);
+    let number = register
+        .strip_prefix('a')
+        .and_then(|number| number.parse::<u32>().ok());
+
+    if let Some(window) = mnemonic
+        .strip_prefix("callx")
+        .or_else(|| mnemonic.strip_prefix("call"))
+    {
+        // call0 passes arguments in a2-a7, callN in the registers it rotates out of view
+        let passed = match window.parse::<u32>() {
+            Ok(0) => number.is_some_and(|number| (2..=7).contains(&number)),
+            Ok(window) => number.is_some_and(|number| number >= window),
+            Err(_) => true,
+        };
+        return if passed || tokens.contains(&register) {
+            RegisterUse::Escape
+        } else {
+            RegisterUse::Unused
+        };
+    }
+    if mnemonic.starts_with("ret") || matches!(mnemonic, "j" | "jx") {
+        return RegisterUse::End;
+    }
+    let load = matches!(
+        mnemonic,
+        "l8ui" | "l16ui" | "l16si" | "l32i" | "l32i.n" | "l32ai"
+    );
+    let store = matches!(mnemonic, "s8i" | "s16i" | "s32i" | "s32i.n" | "s32ri");
+    if let (true, [value, base, ..]) = (load || store, &tokens[..]) {
+        return match (store, *base == register, *value == register) {
+            (false, true, overwritten) => RegisterUse::Load { overwritten },
+            (true, true, _) => RegisterUse::Store,
+            (true, false, true) => RegisterUse::Escape,
+            (false, false, true) => RegisterUse::Overwrite,
+            _ => RegisterUse::Unused,
+        };
+    }
+
+    match tokens.split_first() {
+        Some((_, sources)) if sources.contains(&register) => RegisterUse::Escape,
+        Some((&destination, _)) if destination == register => RegisterUse::Overwrite,
+        _ => RegisterUse::Unused,
+    }
+}
+
+/// The address a `movw`/`movt` pair ending with the last instruction puts together
+fn movw_movt(instructions: &[DisassembledInstruction]) -> Option<u64> {
+    let (movt, before) = instructions.split_last()?;
+    let register = operand_tokens(&movt.operands).first().copied()?;
+    let movw = before.iter().rev().take(8).find(|instruction| {
+        instruction.mnemonic == "movw"
+            && operand_tokens(&instruction.operands).first() == Some(&register)
+    })?;
+    let immediate = |instruction: &DisassembledInstruction| {
+        let token = operand_tokens(&instruction.operands)
+            .get(1)?
+            .strip_prefix('#')?;
+        match token.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => token.parse().ok(),
+        }
+    };
+    Some(immediate(movt)? << 16 | immediate(movw)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::follow_address;
+    use crate::disasm::{decode_instructions, InstructionSet};
+    use crate::types::XrefKind;
+
+    fn thumb_use(halfwords: &[u16]) -> XrefKind {
+        let code: Vec<u8> = halfwords.iter().flat_map(|hw| hw.to_le_bytes()).collect();
+        follow_address(
+            InstructionSet::Thumb,
+            &decode_instructions(InstructionSet::Thumb, &code, 0, &[]),
+        )
+    }
+
+    #[test]
+    fn stores_on_the_taken_path_are_not_missed() {
+        // ldr r0, [pc, #4]; ldr r2, [r0]; bx lr
+        assert_eq!(thumb_use(&[0x4801, 0x6802, 0x4770]), XrefKind::Read);
+        // ldr r0, [pc, #4]; cbz r1, 1f; ldr r2, [r0]; bx lr; 1: str r3, [r0]
+        assert_eq!(
+            thumb_use(&[0x4801, 0xb109, 0x6802, 0x4770, 0x6003]),
+            XrefKind::Address
+        );
+    }
+}